@@ -0,0 +1,19 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    compile_protos();
+}
+
+/// Generates the gRPC service from its schema, with protox in place of `protoc` so that building
+/// doesn't need it installed
+#[cfg(feature = "grpc")]
+fn compile_protos() {
+    println!("cargo:rerun-if-changed=proto");
+    let descriptors = protox::compile(["attestation.proto"], ["proto"])
+        .unwrap_or_else(|e| panic!("Failed to parse proto/attestation.proto: {e}"));
+    tonic_prost_build::configure()
+        // Subjects' extra fields are sorted, as in `Subject`
+        .btree_map(".")
+        .compile_fds(descriptors)
+        .unwrap_or_else(|e| panic!("Failed to generate the gRPC service: {e}"));
+}
@@ -0,0 +1,288 @@
+//! Signed statements of a chain's head, to publish outside of the chain (on a public blockchain,
+//! with a notary, in a newspaper) as evidence of what the chain held at the time.
+//!
+//! A chain only ever grows, so the block an anchor names has to still be in it, at the same
+//! height and with the same hash, and the chain has to validate up to its current tip. Anything
+//! else means blocks were rewritten or dropped since the anchor was made.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::blockchain::{Blockchain, BlockchainError, ChainPolicy};
+use crate::credential::{Issuer, verifying_key_serde};
+use crate::exchange::{EXCHANGE_VERSION, ExchangeError, from_versioned_json};
+use crate::hash::Hash;
+use crate::signature::Sig;
+use crate::signer::{BlockSigner, SignError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnchorError {
+    /// The file isn't an anchor this version can read
+    Exchange(ExchangeError),
+    /// The signature doesn't check against the key the anchor names
+    Signature,
+    /// The chain restricts block signers, and the issuer signing the anchor isn't one of them
+    NotAuthority,
+    /// The chain's authority set can't be followed through its blocks
+    Chain(BlockchainError),
+    /// The chain has no block to anchor yet
+    EmptyChain,
+    Sign(SignError),
+}
+
+impl Display for AnchorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exchange(error) => error.fmt(f),
+            Self::Signature => f.write_str(
+                "the anchor's signature doesn't match its signer's key, potential forgery",
+            ),
+            Self::NotAuthority => f.write_str("only a chain authority can anchor the chain"),
+            Self::Chain(error) => write!(f, "the chain's authorities can't be followed: {error}"),
+            Self::EmptyChain => f.write_str("the chain has no block to anchor yet"),
+            Self::Sign(error) => write!(f, "failed to sign the anchor: {error}"),
+        }
+    }
+}
+
+impl Error for AnchorError {}
+
+/// Why the chain no longer matches an anchor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnchorMismatch {
+    /// The anchor is another chain's
+    OtherChain { anchored: Option<Uuid>, local: Option<Uuid> },
+    /// The chain is shorter than when it was anchored
+    Truncated { height: u64, length: usize },
+    /// The block at the anchored height has another hash than the anchored one
+    Rewritten { height: u64, found: Hash },
+    /// The chain's blocks don't hold together anymore
+    Invalid(BlockchainError),
+}
+
+impl Display for AnchorMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OtherChain { anchored, local } => {
+                let id = |uuid: &Option<Uuid>| uuid.map_or("none".into(), |uuid| uuid.to_string());
+                write!(f, "the anchor is of chain {}, not {}", id(anchored), id(local))
+            },
+            Self::Truncated { height, length } => write!(
+                f,
+                "block #{height} was anchored but the chain only holds {length} blocks, blocks \
+                 were dropped since"
+            ),
+            Self::Rewritten { height, found } => write!(
+                f,
+                "block #{height} now has hash {}, the chain was rewritten at or before it",
+                found.short()
+            ),
+            Self::Invalid(error) => write!(f, "the chain doesn't validate: {error}"),
+        }
+    }
+}
+
+impl Error for AnchorMismatch {}
+
+/// Chain's head at a point in time, signed by a chain authority. Displayed as indented JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainAnchor {
+    /// Format version, [`EXCHANGE_VERSION`] for files written by this version
+    pub version: u32,
+    pub anchored_at: DateTime<Utc>,
+    /// UUID of the chain's genesis block, none for chains without one
+    pub chain: Option<Uuid>,
+    /// Height and hash of the chain's last block
+    pub height: u64,
+    pub head: Hash,
+    /// UUID and name of the issuer signing the anchor
+    pub signer: Uuid,
+    pub signer_name: String,
+    #[serde(with = "verifying_key_serde")]
+    pub signer_key: VerifyingKey,
+    pub signature: Sig,
+}
+
+/// The fields of a [`ChainAnchor`] its signature covers
+#[derive(Serialize)]
+struct Unsigned<'a> {
+    version: u32,
+    anchored_at: &'a DateTime<Utc>,
+    chain: &'a Option<Uuid>,
+    height: u64,
+    head: &'a Hash,
+    signer: Uuid,
+    signer_name: &'a str,
+    #[serde(with = "verifying_key_serde")]
+    signer_key: &'a VerifyingKey,
+}
+
+impl ChainAnchor {
+    /// Anchors the chain's last block, signed as the issuer
+    ///
+    /// # Errors
+    /// Returns [`AnchorError::EmptyChain`] if the chain has no block, [`AnchorError::NotAuthority`]
+    /// if it has authorities and the issuer isn't one, and [`AnchorError::Sign`] if the signer
+    /// fails or doesn't hold the issuer's key.
+    pub fn new(
+        blockchain: &Blockchain, issuer: &Issuer, signer: &dyn BlockSigner,
+    ) -> Result<Self, AnchorError> {
+        if let Some(authorities) = blockchain.authorities().map_err(AnchorError::Chain)?
+            && !authorities.iter().any(|authority| authority.verifying == issuer.verifying)
+        {
+            return Err(AnchorError::NotAuthority);
+        }
+        let tip = blockchain.tip().ok_or(AnchorError::EmptyChain)?;
+        let mut anchor = Self {
+            version: EXCHANGE_VERSION,
+            anchored_at: Utc::now(),
+            chain: blockchain.id(),
+            height: tip.height(),
+            head: tip.hash().clone(),
+            signer: issuer.uuid,
+            signer_name: issuer.name.clone(),
+            signer_key: signer.verifying_key(),
+            signature: Sig::default(),
+        };
+        let signature = signer.sign(&anchor.message()).map_err(AnchorError::Sign)?;
+        if anchor.signer_key.verify(&anchor.message(), &signature).is_err() {
+            return Err(AnchorError::Sign(SignError::InvalidSignature));
+        }
+        anchor.signature = signature.into();
+        Ok(anchor)
+    }
+
+    fn message(&self) -> Vec<u8> {
+        let unsigned = Unsigned {
+            version: self.version,
+            anchored_at: &self.anchored_at,
+            chain: &self.chain,
+            height: self.height,
+            head: &self.head,
+            signer: self.signer,
+            signer_name: &self.signer_name,
+            signer_key: &self.signer_key,
+        };
+        serde_json::to_vec(&unsigned).unwrap_or_default()
+    }
+
+    /// Reads an anchor as written by its [`Display`] implementation and checks its signature
+    /// against the key it names, which the caller still has to trust
+    ///
+    /// # Errors
+    /// Returns [`AnchorError::Exchange`] if the JSON isn't an anchor in this format version, and
+    /// [`AnchorError::Signature`] if the signature doesn't check.
+    pub fn from_json(json: &str) -> Result<Self, AnchorError> {
+        let anchor: Self = from_versioned_json(json).map_err(AnchorError::Exchange)?;
+        anchor.check_signature()?;
+        Ok(anchor)
+    }
+
+    /// Checks the signature against the key the anchor names, for anchors read as part of
+    /// another file
+    ///
+    /// # Errors
+    /// Returns [`AnchorError::Signature`] if the signature doesn't check.
+    pub fn check_signature(&self) -> Result<(), AnchorError> {
+        let signature = Signature::from(&self.signature);
+        self.signer_key.verify(&self.message(), &signature).map_err(|_| AnchorError::Signature)
+    }
+
+    /// Checks that the chain only grew since the anchor: it's the same chain, still holds the
+    /// anchored block at its height, and validates under the policy
+    ///
+    /// # Errors
+    /// Returns the first [`AnchorMismatch`] found.
+    pub fn check(
+        &self, blockchain: &Blockchain, policy: &ChainPolicy,
+    ) -> Result<(), AnchorMismatch> {
+        let local = blockchain.id();
+        if self.chain != local {
+            return Err(AnchorMismatch::OtherChain { anchored: self.chain, local });
+        }
+        let block = blockchain
+            .block(self.height)
+            .ok_or(AnchorMismatch::Truncated { height: self.height, length: blockchain.len() })?;
+        if block.hash() != &self.head {
+            let found = block.hash().clone();
+            return Err(AnchorMismatch::Rewritten { height: self.height, found });
+        }
+        blockchain.validate_with(policy).map_err(AnchorMismatch::Invalid)
+    }
+}
+
+impl Display for ChainAnchor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(&self).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::{Block, GenesisBlock};
+
+    fn chain(issuer: &Issuer, signing: &dyn BlockSigner, blocks: usize) -> Blockchain {
+        let genesis = GenesisBlock::new("Test".to_string(), None, None).unwrap();
+        let mut blockchain = Blockchain::with_genesis(genesis);
+        for _ in 0..blocks {
+            blockchain.add_block(Block::new(issuer.clone()), signing).unwrap();
+        }
+        blockchain
+    }
+
+    #[test]
+    fn test_anchor_holds_while_the_chain_grows() {
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let mut blockchain = chain(&issuer, &signing, 2);
+        let anchor = ChainAnchor::new(&blockchain, &issuer, &signing).unwrap();
+        assert_eq!(anchor.height, 1);
+        let anchor = ChainAnchor::from_json(&anchor.to_string()).unwrap();
+        let policy = ChainPolicy::default();
+        anchor.check(&blockchain, &policy).unwrap();
+
+        blockchain.add_block(Block::new(issuer.clone()), &signing).unwrap();
+        anchor.check(&blockchain, &policy).unwrap();
+    }
+
+    #[test]
+    fn test_anchor_detects_rewrites() {
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let blockchain = chain(&issuer, &signing, 2);
+        let anchor = ChainAnchor::new(&blockchain, &issuer, &signing).unwrap();
+        let policy = ChainPolicy::default();
+
+        // The same chain rebuilt from its genesis block by another signer
+        let (other_issuer, other_signing) = Issuer::new("Impostor".to_string());
+        let mut rebuilt = Blockchain::with_genesis(blockchain.genesis().unwrap().clone());
+        for _ in 0..3 {
+            rebuilt.add_block(Block::new(other_issuer.clone()), &other_signing).unwrap();
+        }
+        assert!(matches!(
+            anchor.check(&rebuilt, &policy).unwrap_err(),
+            AnchorMismatch::Rewritten { height: 1, .. }
+        ));
+        let mut json = serde_json::to_value(&blockchain).unwrap();
+        json["chain"].as_array_mut().unwrap().pop();
+        let truncated: Blockchain = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            anchor.check(&truncated, &policy).unwrap_err(),
+            AnchorMismatch::Truncated { height: 1, length: 1 }
+        );
+
+        let empty = Blockchain::with_genesis(GenesisBlock::new("Test".into(), None, None).unwrap());
+        assert!(matches!(
+            anchor.check(&empty, &policy).unwrap_err(),
+            AnchorMismatch::OtherChain { .. }
+        ));
+        assert_eq!(
+            ChainAnchor::new(&empty, &issuer, &signing).unwrap_err(),
+            AnchorError::EmptyChain
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::BuildHasher;
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::VerifyingKey;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::blockchain::Blockchain;
+use crate::credential::{Credential, Issuer};
+use crate::hash::Hash;
+use crate::import_policy::Violation;
+
+/// Place where an issuer's verifying key was seen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum KeySource {
+    Genesis,
+    Block { height: u64 },
+    Credential { uuid: Uuid },
+    Store { index: usize },
+}
+
+/// One of the keys an issuer UUID is bound to, with everywhere it was seen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyBinding {
+    pub key: String,
+    pub sources: Vec<KeySource>,
+}
+
+/// Issuer UUID bound to more than one verifying key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyConflict {
+    pub issuer: Uuid,
+    pub keys: Vec<KeyBinding>,
+}
+
+/// Collects issuer UUID to verifying key bindings to detect issuers whose keys diverge
+#[derive(Debug, Default)]
+pub struct KeyAudit {
+    seen: BTreeMap<Uuid, Vec<KeyBinding>>,
+    /// Keys the chain records their issuer rotating away from, which don't conflict with the
+    /// issuer's newer keys
+    rotated: HashSet<(Uuid, String)>,
+}
+
+impl KeyAudit {
+    /// Audits the issuers recorded on the chain, embedded in the credentials and kept in the store
+    #[must_use]
+    pub fn new<'a>(
+        blockchain: &Blockchain, credentials: impl IntoIterator<Item = &'a Credential>,
+        issuers: impl IntoIterator<Item = &'a Issuer>,
+    ) -> Self {
+        let mut audit = Self::default();
+        if let Some(genesis) = blockchain.genesis() {
+            let metadata = genesis.metadata();
+            for issuer in metadata.founder.iter().chain(metadata.authorities.iter().flatten()) {
+                audit.record(issuer, KeySource::Genesis);
+            }
+        }
+        for (height, block) in blockchain.blocks().enumerate() {
+            audit.record(block.signer(), KeySource::Block { height: height as u64 });
+        }
+        for rotation in blockchain.key_rotations() {
+            audit.rotated.insert((rotation.issuer, hex::encode(rotation.previous.as_bytes())));
+        }
+        for credential in credentials {
+            audit.record(&credential.issuer, KeySource::Credential { uuid: credential.uuid });
+        }
+        for (index, issuer) in issuers.into_iter().enumerate() {
+            audit.record(issuer, KeySource::Store { index });
+        }
+        audit
+    }
+
+    pub fn record(&mut self, issuer: &Issuer, source: KeySource) {
+        let key = hex::encode(issuer.verifying.as_bytes());
+        let bindings = self.seen.entry(issuer.uuid).or_default();
+        match bindings.iter_mut().find(|b| b.key == key) {
+            Some(binding) =>
+                if !binding.sources.contains(&source) {
+                    binding.sources.push(source);
+                },
+            None => bindings.push(KeyBinding { key, sources: vec![source] }),
+        }
+    }
+
+    /// Issuers seen with more than one verifying key that isn't explained by key rotations
+    #[must_use]
+    pub fn conflicts(&self) -> Vec<KeyConflict> {
+        self.seen
+            .iter()
+            .filter(|&(&issuer, keys)| {
+                let rotated = |b: &&KeyBinding| self.rotated.contains(&(issuer, b.key.clone()));
+                keys.iter().filter(|b| !rotated(b)).count() > 1
+            })
+            .map(|(&issuer, keys)| KeyConflict { issuer, keys: keys.clone() })
+            .collect()
+    }
+}
+
+/// Whether the credential's issuer key is none of the keys pinned for that issuer UUID, which
+/// hold the issuer's current key along with those it rotated away from
+#[must_use]
+pub fn conflicts_with_pinned<S: BuildHasher>(
+    credential: &Credential, pinned: &HashMap<Uuid, Vec<VerifyingKey>, S>,
+) -> bool {
+    pinned.get(&credential.issuer.uuid).is_some_and(|k| !k.contains(&credential.issuer.verifying))
+}
+
+/// Line of `audit.log`, recording a check overridden from the command line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+impl AuditRecord {
+    #[must_use]
+    pub fn new(event: AuditEvent) -> Self { Self { timestamp: Utc::now(), event } }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// Blocks breaking the import policy were imported with `--accept-violations`
+    ImportPolicyOverridden { command: String, source: String, violations: Vec<Violation> },
+    /// A local chain diverging from an imported one was replaced with `--force`, from the height
+    /// of the first differing block
+    DivergingChainReplaced {
+        command: String,
+        source: String,
+        height: u64,
+        local: Hash,
+        imported: Hash,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::blockchain::Block;
+    use crate::credential::{Attribute, Subject, ValidDuration};
+    use crate::hash::HashVersion;
+    use crate::import_policy::Rule;
+
+    #[test]
+    fn test_audit_detects_tampered_store_key() {
+        let (issuer, signing) = Issuer::new("Registry".to_string());
+        let credential = Credential::new(
+            vec![Attribute::new("degree".to_string(), "PhD".to_string())],
+            issuer.clone(),
+            Subject::new("Alice".to_string(), "Smith".to_string()),
+            ValidDuration::starting(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
+        );
+        let mut blockchain = Blockchain::new();
+        let mut block = Block::new(issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        blockchain.add_block(block, &signing).unwrap();
+
+        let audit = KeyAudit::new(&blockchain, [&credential], [&issuer]);
+        assert!(audit.conflicts().is_empty());
+
+        let mut tampered = issuer.clone();
+        tampered.verifying = Issuer::new("Other".to_string()).0.verifying;
+        let conflicts = KeyAudit::new(&blockchain, [&credential], [&tampered]).conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].issuer, issuer.uuid);
+        assert_eq!(conflicts[0].keys[0].sources, [
+            KeySource::Block { height: 0 },
+            KeySource::Credential { uuid: credential.uuid }
+        ]);
+        assert_eq!(conflicts[0].keys[1].sources, [KeySource::Store { index: 0 }]);
+    }
+
+    #[test]
+    fn test_audit_record_roundtrip() {
+        let violations = vec![Violation {
+            height: 3,
+            rule: Rule::TooManyCredentials { credentials: 12, limit: 10 },
+        }];
+        let event = AuditEvent::ImportPolicyOverridden {
+            command: "blockchain sync".into(),
+            source: "https://attest.example.org".into(),
+            violations,
+        };
+        let record = AuditRecord::new(event);
+        let json: serde_json::Value = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["event"], "import_policy_overridden");
+        assert_eq!(json["violations"][0]["rule"], "too_many_credentials");
+        assert_eq!(serde_json::from_value::<AuditRecord>(json).unwrap(), record);
+    }
+}
@@ -1,207 +1,4297 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 
-use chrono::{DateTime, Utc};
-use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
+use chrono::{DateTime, Datelike, NaiveDate, SecondsFormat, TimeDelta, Utc};
+use ed25519_dalek::{Verifier, VerifyingKey};
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha512};
+use uuid::Uuid;
 
-use crate::credential::{Credential, Issuer, SignedCredential};
-use crate::hash::Hash;
+use crate::audit::conflicts_with_pinned;
+use crate::credential::{
+    ConsentStatus, Credential, Issuer, SignedCredential, ValidDuration, verifying_key_serde,
+};
+use crate::hash::{
+    Algorithm, Hash, HashFormat, HashVersion, Hasher, PrefixError, domain, resolve_prefix,
+};
+use crate::keys::fingerprint;
+use crate::merkle::{self, MerkleProof};
+use crate::progress::ProgressSink;
+use crate::signature::Sig;
+use crate::signer::{BlockSigner, SignError};
+use crate::trust::TrustStore;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[cfg(test)]
+thread_local! {
+    /// Blocks scanned by credential checks on this thread, to test caches avoid scans
+    pub(crate) static BLOCK_VISITS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockError {
+    /// The stored hash doesn't match the hash recomputed from the block's contents
+    HashMismatch,
+    /// The block's signature doesn't verify under the signer's verifying key
+    InvalidSignature,
+    /// The block doesn't point at the expected previous block
+    PreviousHashMismatch,
+    /// The credential is already in the block's list
+    DuplicateCredential,
+    /// The stored Merkle root doesn't match the block's credential entries
+    MerkleRootMismatch,
+}
+
+impl Display for BlockError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::HashMismatch => "Block hash doesn't match its contents",
+            Self::InvalidSignature => "Block signature is invalid",
+            Self::PreviousHashMismatch => "Block doesn't link to the previous block",
+            Self::DuplicateCredential => "Credential is already in the block",
+            Self::MerkleRootMismatch => "Block's Merkle root doesn't match its credentials",
+        })
+    }
+}
+
+impl Error for BlockError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockchainError {
+    /// Block has already been finalized and can't be appended again
+    AlreadyFinalized,
+    /// The chains have different genesis blocks, `None` standing for a chain without one
+    ChainIdMismatch { local: Option<Uuid>, other: Option<Uuid> },
+    /// Block changes the authority set in a way that isn't allowed
+    AuthorityChangeRejected { height: u64 },
+    /// Block at the given index has an unexpected height
+    HeightMismatch { index: usize, height: u64 },
+    /// Block claims to be older than its predecessor by more than the allowed clock skew
+    NonMonotonicTimestamp { height: u64, previous_height: u64 },
+    /// Block at the given index failed verification
+    InvalidBlock { index: usize, error: BlockError },
+    /// Block holds a hash made with another algorithm than the chain's
+    MixedAlgorithms { height: u64 },
+    /// Genesis block failed verification
+    InvalidGenesis(BlockError),
+    /// The key used to sign the block doesn't belong to the block's signer
+    SignerKeyMismatch,
+    /// Block is signed by an issuer outside of the chain's authority set
+    UnauthorizedSigner { height: u64 },
+    /// Block records a key rotation that isn't signed by the issuer's current key
+    InvalidKeyRotation { height: u64 },
+    /// Block is signed with a key its issuer rotated away from in an earlier block
+    RetiredSignerKey { height: u64 },
+    /// Block deactivates an issuer without being signed by that issuer or a chain authority
+    UnauthorizedDeactivation { height: u64 },
+    /// Block records an accreditation that isn't signed by its accreditor
+    InvalidAccreditation { height: u64 },
+    /// The block's signer failed to sign it
+    Signing(SignError),
+}
+
+impl Display for BlockchainError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AlreadyFinalized => f.write_str("Block has already been finalized"),
+            Self::AuthorityChangeRejected { height } => write!(
+                f,
+                "Block {height} changes the authority set, but the chain doesn't use one or the \
+                 change would leave it empty"
+            ),
+            Self::ChainIdMismatch { local, other } => {
+                let id = |id: &Option<Uuid>| id.map_or("none".to_string(), |id| id.to_string());
+                write!(f, "Chain ID {} doesn't match the local chain ID {}", id(other), id(local))
+            },
+            Self::HeightMismatch { index, height } => {
+                write!(f, "Block {index}: height {height} doesn't match its position")
+            },
+            Self::InvalidBlock { index, error } => write!(f, "Block {index}: {error}"),
+            Self::MixedAlgorithms { height } => write!(
+                f,
+                "Block {height} holds a hash made with another algorithm than the chain's"
+            ),
+            Self::NonMonotonicTimestamp { height, previous_height } =>
+                write!(f, "Block {height} has a timestamp earlier than block {previous_height}"),
+            Self::InvalidGenesis(error) => write!(f, "Genesis block: {error}"),
+            Self::InvalidAccreditation { height } => write!(
+                f,
+                "Block {height} records an accreditation that isn't signed by its accreditor"
+            ),
+            Self::InvalidKeyRotation { height } => write!(
+                f,
+                "Block {height} records a key rotation that isn't signed by the issuer's current \
+                 key"
+            ),
+            Self::RetiredSignerKey { height } => write!(
+                f,
+                "Block {height} is signed with a key its issuer rotated away from in an earlier \
+                 block"
+            ),
+            Self::SignerKeyMismatch =>
+                f.write_str("Signing key doesn't match the block signer's verifying key"),
+            Self::UnauthorizedDeactivation { height } => write!(
+                f,
+                "Block {height} deactivates an issuer without being signed by it or a chain \
+                 authority"
+            ),
+            Self::Signing(error) => write!(f, "Failed to sign the block: {error}"),
+            Self::UnauthorizedSigner { height } =>
+                write!(f, "Block {height} is signed by an issuer that isn't a chain authority"),
+        }
+    }
+}
+
+impl Error for BlockchainError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidBlock { error, .. } | Self::InvalidGenesis(error) => Some(error),
+            Self::Signing(error) => Some(error),
+            Self::AlreadyFinalized
+            | Self::AuthorityChangeRejected { .. }
+            | Self::ChainIdMismatch { .. }
+            | Self::HeightMismatch { .. }
+            | Self::InvalidAccreditation { .. }
+            | Self::InvalidKeyRotation { .. }
+            | Self::MixedAlgorithms { .. }
+            | Self::NonMonotonicTimestamp { .. }
+            | Self::RetiredSignerKey { .. }
+            | Self::SignerKeyMismatch
+            | Self::UnauthorizedDeactivation { .. }
+            | Self::UnauthorizedSigner { .. } => None,
+        }
+    }
+}
+
+/// Amendment of the set of issuers allowed to sign blocks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorityChange {
+    Add(Issuer),
+    Remove(Issuer),
+}
+
+impl AuthorityChange {
+    fn update_hash(&self, hasher: &mut Hasher) {
+        let (tag, issuer) = match self {
+            Self::Add(issuer) => ("add", issuer),
+            Self::Remove(issuer) => ("remove", issuer),
+        };
+        hasher.domain(domain::AUTHORITY_CHANGE);
+        hasher.field(tag);
+        issuer.update_hash(hasher);
+    }
+
+    /// Applies the change to the authority set, failing if it would leave the set empty
+    fn apply(&self, authorities: &mut Vec<Issuer>) -> Result<(), ()> {
+        match self {
+            Self::Add(issuer) =>
+                if !authorities.iter().any(|a| a.verifying == issuer.verifying) {
+                    authorities.push(issuer.clone());
+                },
+            Self::Remove(issuer) => authorities.retain(|a| a.verifying != issuer.verifying),
+        }
+        if authorities.is_empty() { Err(()) } else { Ok(()) }
+    }
+}
+
+/// Why a credential was revoked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RevocationReason {
+    Expired,
+    Superseded,
+    Compromised,
+    IssuedInError,
+    Other(String),
+}
+
+impl RevocationReason {
+    fn update_hash(&self, hasher: &mut Hasher) {
+        let tag = match self {
+            Self::Expired => "expired",
+            Self::Superseded => "superseded",
+            Self::Compromised => "compromised",
+            Self::IssuedInError => "issued-in-error",
+            Self::Other(_) => "other",
+        };
+        hasher.field(tag);
+        if let Self::Other(reason) = self {
+            hasher.field(reason);
+        }
+    }
+}
+
+impl Display for RevocationReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Expired => f.write_str("expired"),
+            Self::Superseded => f.write_str("superseded"),
+            Self::Compromised => f.write_str("compromised"),
+            Self::IssuedInError => f.write_str("issued in error"),
+            Self::Other(reason) => f.write_str(reason),
+        }
+    }
+}
+
+/// Why and when the issuer revoked a credential
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevocationDetails {
+    pub reason: RevocationReason,
+    pub revoked_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl RevocationDetails {
+    #[must_use]
+    pub fn new(reason: RevocationReason, note: Option<String>) -> Self {
+        Self { reason, revoked_at: Utc::now(), note }
+    }
+
+    fn update_hash(&self, hasher: &mut Hasher) {
+        hasher.domain(domain::REVOCATION_DETAILS);
+        self.reason.update_hash(hasher);
+        hasher.field(self.revoked_at.to_string());
+        hasher.presence(self.note.is_some());
+        if let Some(note) = &self.note {
+            hasher.field(note);
+        }
+    }
+}
+
+impl Display for RevocationDetails {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.reason)?;
+        if let Some(note) = &self.note {
+            write!(f, " ({note})")?;
+        }
+        write!(f, ", revoked at {}", self.revoked_at.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+}
+
+/// Revocation listed in a block: the issuer's signed revocation hash, with why and when it was
+/// revoked. Revocations recorded before reasons were have no details, and are stored and hashed
+/// as the bare signed hash they used to be.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revocation {
+    #[serde(flatten)]
+    pub signed: SignedCredential,
+    #[serde(flatten)]
+    pub details: Option<RevocationDetails>,
+}
+
+impl Revocation {
+    #[must_use]
+    pub fn new(signed: SignedCredential, details: Option<RevocationDetails>) -> Self {
+        Self { signed, details }
+    }
+
+    fn update_hash(&self, hasher: &mut Hasher) {
+        self.signed.update_hash(hasher);
+        if let Some(details) = &self.details {
+            hasher.field("revocation_details");
+            details.update_hash(hasher);
+        }
+    }
+}
+
+/// Announcement that an issuer replaced its signing key, signed with the key being replaced so
+/// that verifiers can follow the issuer from one key to the next
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRotation {
+    pub issuer: Uuid,
+    #[serde(with = "verifying_key_serde")]
+    pub previous: VerifyingKey,
+    #[serde(with = "verifying_key_serde")]
+    pub new: VerifyingKey,
+    pub signature: Sig,
+}
+
+impl KeyRotation {
+    /// Announces `new` as the successor of `previous` for the issuer with the given UUID
+    ///
+    /// # Errors
+    /// Fails if the previous key's signer can't sign.
+    pub fn new(
+        issuer: Uuid, previous: &dyn BlockSigner, new: VerifyingKey, format: impl Into<HashFormat>,
+    ) -> Result<Self, SignError> {
+        let mut rotation =
+            Self { issuer, previous: previous.verifying_key(), new, signature: Sig::default() };
+        rotation.signature = previous.sign(&rotation.signed_hash(format).bytes)?.into();
+        Ok(rotation)
+    }
+
+    /// Hash of the announcement the previous key signs
+    fn signed_hash(&self, format: impl Into<HashFormat>) -> Hash {
+        let mut hasher = Hasher::new(format);
+        hasher.domain(domain::KEY_ROTATION);
+        hasher.update(self.issuer);
+        hasher.update(self.previous);
+        hasher.update(self.new);
+        hasher.finalize()
+    }
+
+    /// Whether the previous key signed the announcement
+    #[must_use]
+    pub fn verify(&self, format: impl Into<HashFormat>) -> bool {
+        let hash = self.signed_hash(format);
+        self.previous.verify(&hash.bytes, &(&self.signature).into()).is_ok()
+    }
+
+    fn update_hash(&self, hasher: &mut Hasher) {
+        hasher.domain(domain::KEY_ROTATION);
+        hasher.update(self.issuer);
+        hasher.update(self.previous);
+        hasher.update(self.new);
+        hasher.update(&self.signature);
+    }
+}
+
+/// Announcement that an issuer, e.g. an organization that shut down, can't be trusted with
+/// credentials issued in blocks dated after `effective`, while the earlier ones stay valid
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssuerDeactivation {
+    pub issuer: Uuid,
+    /// Key of the issuer when it was deactivated, the deactivation covering the keys it
+    /// rotated from or to
+    #[serde(with = "verifying_key_serde")]
+    pub verifying: VerifyingKey,
+    /// Last day the issuer could issue credentials
+    pub effective: NaiveDate,
+}
+
+impl IssuerDeactivation {
+    #[must_use]
+    pub fn new(issuer: &Issuer, effective: NaiveDate) -> Self {
+        Self { issuer: issuer.uuid, verifying: issuer.verifying, effective }
+    }
+
+    fn update_hash(&self, hasher: &mut Hasher) {
+        hasher.domain(domain::ISSUER_DEACTIVATION);
+        hasher.update(self.issuer);
+        hasher.update(self.verifying);
+        hasher.field(self.effective.format("%Y-%m-%d").to_string());
+    }
+}
+
+/// Statement of an issuer, e.g. a root registry, vouching for another issuer's key over a period,
+/// so that verifiers trusting the accreditor can trust the accredited issuer's credentials too
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Accreditation {
+    pub accreditor: Uuid,
+    #[serde(with = "verifying_key_serde")]
+    pub accreditor_key: VerifyingKey,
+    pub issuer: Uuid,
+    #[serde(with = "verifying_key_serde")]
+    pub verifying: VerifyingKey,
+    /// Days on which credentials issued by the accredited issuer are vouched for
+    pub valid_duration: ValidDuration,
+    pub signature: Sig,
+}
+
+impl Accreditation {
+    /// Accredits `issuer` over `valid_duration`, signed by `accreditor`
+    ///
+    /// # Errors
+    /// Fails if the accreditor's signer can't sign.
+    pub fn new(
+        accreditor: &Issuer, signer: &dyn BlockSigner, issuer: &Issuer,
+        valid_duration: ValidDuration, format: impl Into<HashFormat>,
+    ) -> Result<Self, SignError> {
+        let mut accreditation = Self {
+            accreditor: accreditor.uuid,
+            accreditor_key: signer.verifying_key(),
+            issuer: issuer.uuid,
+            verifying: issuer.verifying,
+            valid_duration,
+            signature: Sig::default(),
+        };
+        accreditation.signature = signer.sign(&accreditation.signed_hash(format).bytes)?.into();
+        Ok(accreditation)
+    }
+
+    /// Hash of the statement the accreditor signs
+    fn signed_hash(&self, format: impl Into<HashFormat>) -> Hash {
+        let mut hasher = Hasher::new(format);
+        self.hash_statement(&mut hasher);
+        hasher.finalize()
+    }
+
+    fn hash_statement(&self, hasher: &mut Hasher) {
+        hasher.domain(domain::ACCREDITATION);
+        hasher.update(self.accreditor);
+        hasher.update(self.accreditor_key);
+        hasher.update(self.issuer);
+        hasher.update(self.verifying);
+        self.valid_duration.hash(hasher);
+    }
+
+    /// Whether the accreditor's key signed the accreditation
+    #[must_use]
+    pub fn verify(&self, format: impl Into<HashFormat>) -> bool {
+        let hash = self.signed_hash(format);
+        self.accreditor_key.verify(&hash.bytes, &(&self.signature).into()).is_ok()
+    }
+
+    fn update_hash(&self, hasher: &mut Hasher) {
+        self.hash_statement(hasher);
+        hasher.update(&self.signature);
+    }
+}
+
+/// Issuer keys replaced by the key rotations recorded on a chain
+#[derive(Debug, Clone, Default)]
+struct KeyHistory {
+    /// Height of the block retiring each key
+    retired: HashMap<VerifyingKey, u64>,
+    /// Key each successor key replaced, with the height of the block recording the rotation
+    predecessors: HashMap<VerifyingKey, (u64, VerifyingKey)>,
+    /// Key the chain binds each issuer UUID to: the first one it records for the UUID, in the
+    /// genesis block or as a block signer, then each key the issuer rotated to
+    current: HashMap<Uuid, VerifyingKey>,
+}
+
+impl KeyHistory {
+    /// History of a chain with the genesis block, binding its founder and authorities' UUIDs to
+    /// their keys
+    fn new(genesis: Option<&GenesisBlock>) -> Self {
+        let mut history = Self::default();
+        if let Some(genesis) = genesis {
+            let metadata = &genesis.metadata;
+            for issuer in metadata.founder.iter().chain(metadata.authorities.iter().flatten()) {
+                history.current.entry(issuer.uuid).or_insert(issuer.verifying);
+            }
+        }
+        history
+    }
+
+    /// Records the block's rotations, failing if one isn't signed by the issuer's current key, is
+    /// recorded in a block another issuer signs, or brings back a retired key
+    fn record(&mut self, block: &Block, format: HashFormat) -> Result<(), BlockchainError> {
+        let height = block.height;
+        for rotation in &block.key_rotations {
+            let signer = &block.signer;
+            let signed_by_issuer = signer.uuid == rotation.issuer
+                && [rotation.previous, rotation.new].contains(&signer.verifying);
+            // The issuer's own block binds an issuer the chain doesn't know yet to the key it
+            // rotates from
+            let current = self.current.get(&rotation.issuer).unwrap_or(&rotation.previous);
+            if !rotation.verify(format)
+                || !signed_by_issuer
+                || current != &rotation.previous
+                || self.retired.contains_key(&rotation.previous)
+                || self.retired.contains_key(&rotation.new)
+            {
+                return Err(BlockchainError::InvalidKeyRotation { height });
+            }
+            self.retired.insert(rotation.previous, height);
+            self.predecessors.insert(rotation.new, (height, rotation.previous));
+            self.current.insert(rotation.issuer, rotation.new);
+        }
+        self.current.entry(block.signer.uuid).or_insert(block.signer.verifying);
+        Ok(())
+    }
+
+    /// Whether `key` was rotated away from in a block before `height`
+    fn retired_before(&self, key: &VerifyingKey, height: u64) -> bool {
+        self.retired.get(key).is_some_and(|&retired_in| retired_in < height)
+    }
+
+    /// `key` followed by the keys it replaced as of the block at `height`, newest first
+    fn lineage(&self, key: &VerifyingKey, height: u64) -> Vec<VerifyingKey> {
+        let mut lineage = vec![*key];
+        while let Some((rotated_in, predecessor)) =
+            self.predecessors.get(&lineage[lineage.len() - 1])
+            && *rotated_in <= height
+        {
+            lineage.push(*predecessor);
+        }
+        lineage
+    }
+
+    /// Whether `signer` is `key` or one of its successors as of the block at `height`
+    fn succeeds(&self, signer: &VerifyingKey, key: &VerifyingKey, height: u64) -> bool {
+        self.lineage(signer, height).contains(key)
+    }
+
+    /// Whether the block's revocation counts against credentials issued under `issuer`: it has to
+    /// be signed with that key, in a block signed with it or a later key. Revocations that don't
+    /// count for any key are warned about and never change a credential's status.
+    fn revocation_counts(
+        &self, block: &Block, signed: &SignedCredential, issuer: &VerifyingKey,
+    ) -> bool {
+        self.succeeds(&block.signer.verifying, issuer, block.height) && signed.verify(issuer)
+    }
+
+    /// Whether the block's revocation counts against credentials of any key its signer held
+    fn revocation_authorized(&self, block: &Block, signed: &SignedCredential) -> bool {
+        let keys = self.lineage(&block.signer.verifying, block.height);
+        keys.iter().any(|key| self.revocation_counts(block, signed, key))
+    }
+
+    /// Whether either key is the other or one of its successors, after all recorded rotations
+    fn related(&self, a: &VerifyingKey, b: &VerifyingKey) -> bool {
+        self.succeeds(a, b, u64::MAX) || self.succeeds(b, a, u64::MAX)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
+    #[serde(default)]
+    height: u64,
     timestamp: DateTime<Utc>,
     new_credentials: Vec<SignedCredential>,
-    revoked_credentials: Vec<SignedCredential>,
+    revoked_credentials: Vec<Revocation>,
+    /// Credentials of other issuers the block signer anchors without issuing them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    anchored_credentials: Vec<SignedCredential>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    authority_changes: Vec<AuthorityChange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    key_rotations: Vec<KeyRotation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    deactivations: Vec<IssuerDeactivation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    accreditations: Vec<Accreditation>,
+    /// Root of the Merkle tree over the credential entries, set when finalized from V3 on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    merkle_root: Option<Hash>,
     previous_hash: Hash,
     signer: Issuer,
     hash: Hash,
-    signature: Hash,
+    signature: Sig,
 }
 
 impl Block {
     #[must_use]
     pub fn new(signer: Issuer) -> Self {
         Self {
+            height: 0,
             timestamp: Utc::now(),
             new_credentials: Vec::new(),
             revoked_credentials: Vec::new(),
+            anchored_credentials: Vec::new(),
+            authority_changes: Vec::new(),
+            key_rotations: Vec::new(),
+            deactivations: Vec::new(),
+            accreditations: Vec::new(),
+            merkle_root: None,
             previous_hash: Hash::default(),
             signer,
             hash: Hash::default(),
-            signature: Hash::default(),
+            signature: Sig::default(),
         }
     }
 
-    pub fn add_credential(&mut self, signed_credential: SignedCredential, revoking: bool) {
+    /// Adds the credential to the new or revoked list, unless that list already holds it
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockError::DuplicateCredential`] if the credential's hash is already in the list
+    pub fn add_credential(
+        &mut self, signed_credential: SignedCredential, revoking: bool,
+    ) -> Result<(), BlockError> {
         if revoking {
-            self.revoked_credentials.push(signed_credential);
-        } else {
-            self.new_credentials.push(signed_credential);
+            return self.add_revocation(Revocation::new(signed_credential, None));
         }
+        if self.issues(&signed_credential.credential) {
+            return Err(BlockError::DuplicateCredential);
+        }
+        self.new_credentials.push(signed_credential);
+        Ok(())
     }
 
-    pub fn finalize(&mut self, previous_hash: Hash, signing: &SigningKey) {
-        self.timestamp = Utc::now();
-        self.previous_hash = previous_hash;
-        let mut hasher = Sha512::new();
-        hasher.update(self.timestamp.to_string());
-        self.new_credentials
-            .iter()
-            .chain(self.revoked_credentials.iter())
-            .for_each(|c| c.update_hash(&mut hasher));
-        hasher.update(self.previous_hash.0);
-        self.signer.update_hash(&mut hasher);
-        self.hash = hasher.finalize().into();
-        self.signature = signing.sign(&self.hash.0).into();
+    /// Adds the revocation, unless the block already revokes the same credential
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockError::DuplicateCredential`] if the revocation hash is already in the list
+    pub fn add_revocation(&mut self, revocation: Revocation) -> Result<(), BlockError> {
+        if self.revokes(&revocation.signed.credential) {
+            return Err(BlockError::DuplicateCredential);
+        }
+        self.revoked_credentials.push(revocation);
+        Ok(())
     }
 
-    fn find(
-        &self, new_hash: &Hash, revoking_hash: &Hash, verifying: &VerifyingKey,
-    ) -> (bool, bool) {
-        let new = self
-            .new_credentials
-            .iter()
-            .find(|s| &s.credential == new_hash)
-            .is_some_and(|c| c.verify(verifying));
-        let revoked = self
-            .revoked_credentials
-            .iter()
-            .find(|s| &s.credential == revoking_hash)
-            .is_some_and(|c| c.verify(verifying));
-        (new, revoked)
+    /// Revocation of the credential with the given revocation hash, if the block holds one
+    #[must_use]
+    pub fn revocation(&self, revoking_hash: &Hash) -> Option<&Revocation> {
+        self.revoked_credentials.iter().find(|r| &r.signed.credential == revoking_hash)
     }
-}
 
-impl Display for Block {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.write_str(&serde_json::to_string_pretty(&self).unwrap())
+    /// Whether the block revokes the credential with the given revocation hash
+    #[must_use]
+    pub fn revokes(&self, revoking_hash: &Hash) -> bool { self.revocation(revoking_hash).is_some() }
+
+    /// Anchors another issuer's credential, which counts as issued without the block signer
+    /// vouching for it
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockError::DuplicateCredential`] if the block already issues or anchors it
+    pub fn anchor_credential(
+        &mut self, signed_credential: SignedCredential,
+    ) -> Result<(), BlockError> {
+        if self.issues(&signed_credential.credential) {
+            return Err(BlockError::DuplicateCredential);
+        }
+        self.anchored_credentials.push(signed_credential);
+        Ok(())
     }
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Blockchain {
-    chain: Vec<Block>,
-}
+    /// Whether the block issues or anchors the credential with the given hash
+    #[must_use]
+    pub fn issues(&self, credential: &Hash) -> bool {
+        self.issued_credentials().any(|s| &s.credential == credential)
+    }
 
-impl Default for Blockchain {
-    fn default() -> Self { Self::new() }
-}
+    /// New and anchored credentials
+    pub fn issued_credentials(&self) -> impl Iterator<Item = &SignedCredential> {
+        self.new_credentials.iter().chain(&self.anchored_credentials)
+    }
 
-impl Blockchain {
-    #[must_use]
-    pub fn new() -> Self { Self { chain: Vec::new() } }
+    pub fn add_authority_change(&mut self, change: AuthorityChange) {
+        self.authority_changes.push(change);
+    }
 
-    pub fn add_block(&mut self, mut block: Block, signing: &SigningKey) {
-        block.finalize(self.chain.last().map_or(Hash::default(), |b| b.hash.clone()), signing);
-        self.chain.push(block);
+    pub fn add_key_rotation(&mut self, rotation: KeyRotation) { self.key_rotations.push(rotation); }
+
+    pub fn add_deactivation(&mut self, deactivation: IssuerDeactivation) {
+        self.deactivations.push(deactivation);
+    }
+
+    pub fn add_accreditation(&mut self, accreditation: Accreditation) {
+        self.accreditations.push(accreditation);
+    }
+
+    /// Replaces the signer of a block that isn't finalized yet, e.g. with its record after a key
+    /// rotation
+    pub fn set_signer(&mut self, signer: Issuer) { self.signer = signer; }
+
+    /// Links the block to `previous_hash`, then hashes and signs it
+    ///
+    /// # Errors
+    /// Fails if the signer can't sign.
+    pub fn finalize(
+        &mut self, previous_hash: Hash, signer: &dyn BlockSigner, format: impl Into<HashFormat>,
+    ) -> Result<(), SignError> {
+        let format = format.into();
+        self.timestamp = Utc::now();
+        self.previous_hash = previous_hash;
+        self.merkle_root =
+            (format.version >= HashVersion::V3).then(|| self.compute_merkle_root(format.algorithm));
+        self.hash = self.compute_hash(format);
+        self.signature = signer.sign(&self.hash.bytes)?.into();
+        Ok(())
     }
 
+    /// Recomputes the block's hash from its contents, ignoring the stored `hash`, `signature` and
+    /// `merkle_root`
     #[must_use]
-    pub fn check_credential(&self, credential: &Credential) -> bool {
-        let new_hash = credential.hash(false);
-        let revoking_hash = credential.hash(true);
-        let mut found = false;
-        for b in &self.chain {
-            let (f, r) = b.find(&new_hash, &revoking_hash, &credential.issuer.verifying);
-            if r {
-                return false;
+    pub fn compute_hash(&self, format: impl Into<HashFormat>) -> Hash {
+        let format = format.into();
+        if format.version >= HashVersion::V3 {
+            return self.header(format.algorithm).hash();
+        }
+        let mut hasher = Hasher::new(format);
+        hasher.domain(domain::BLOCK);
+        if hasher.version() >= HashVersion::V2 {
+            hasher.update(self.height.to_le_bytes());
+        }
+        hasher.field(self.timestamp.to_string());
+        hasher.length(self.new_credentials.len());
+        for credential in &self.new_credentials {
+            credential.update_hash(&mut hasher);
+        }
+        hasher.length(self.revoked_credentials.len());
+        for revocation in &self.revoked_credentials {
+            revocation.update_hash(&mut hasher);
+        }
+        if !self.anchored_credentials.is_empty() {
+            hasher.field("anchored");
+            hasher.length(self.anchored_credentials.len());
+            for credential in &self.anchored_credentials {
+                credential.update_hash(&mut hasher);
             }
-            found |= f;
         }
-        found
-    }
-}
-impl Display for Blockchain {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.write_str(&serde_json::to_string_pretty(&self).unwrap())
+        self.update_contents_hash(&mut hasher);
+        hasher.update(&self.previous_hash);
+        self.signer.update_hash(&mut hasher);
+        hasher.finalize()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use chrono::NaiveDate;
+    /// Hashes the block's contents besides its credential entries
+    fn update_contents_hash(&self, hasher: &mut Hasher) {
+        hasher.length(self.authority_changes.len());
+        for change in &self.authority_changes {
+            change.update_hash(hasher);
+        }
+        if !self.key_rotations.is_empty() {
+            hasher.field("key_rotations");
+            hasher.length(self.key_rotations.len());
+            for rotation in &self.key_rotations {
+                rotation.update_hash(hasher);
+            }
+        }
+        if !self.deactivations.is_empty() {
+            hasher.field("deactivations");
+            hasher.length(self.deactivations.len());
+            for deactivation in &self.deactivations {
+                deactivation.update_hash(hasher);
+            }
+        }
+        if !self.accreditations.is_empty() {
+            hasher.field("accreditations");
+            hasher.length(self.accreditations.len());
+            for accreditation in &self.accreditations {
+                accreditation.update_hash(hasher);
+            }
+        }
+    }
 
-    use super::*;
-    use crate::credential::{Attribute, Subject, ValidDuration};
+    /// What the block's hash commits to from V3 on, recomputed from its contents
+    #[must_use]
+    pub fn header(&self, algorithm: Algorithm) -> BlockHeader {
+        let format = HashFormat { version: HashVersion::V3, algorithm };
+        let mut contents = Hasher::new(format);
+        contents.domain(domain::BLOCK_CONTENTS);
+        self.update_contents_hash(&mut contents);
+        let mut signer = Hasher::new(format);
+        self.signer.update_hash(&mut signer);
+        BlockHeader {
+            height: self.height,
+            timestamp: self.timestamp,
+            merkle_root: self.compute_merkle_root(algorithm),
+            contents: contents.finalize(),
+            previous_hash: self.previous_hash.clone(),
+            signer: signer.finalize(),
+        }
+    }
 
-    fn sample_credential() -> (Credential, SigningKey) {
-        let (issuer, signing) = Issuer::new("Test Issuer".to_string());
-        let subject = Subject::new("Alice".to_string(), "Doe".to_string());
-        let attr =
-            Attribute::new("Driving Licence".to_string(), "Driving Licence Category B".to_string());
-        let valid = ValidDuration::new(
-            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
-            Some(NaiveDate::from_ymd_opt(2030, 1, 1).unwrap()),
-        );
-        let credential = Credential::new(attr, issuer, subject, valid);
-        (credential, signing)
+    /// Credential entries of every list with the hashes they're recorded under, as they are
+    /// committed to by the Merkle tree's leaves
+    fn merkle_leaves(&self, algorithm: Algorithm) -> Vec<(&Hash, Hash)> {
+        let revoked = self.revoked_credentials.iter();
+        let entries = (self.new_credentials.iter())
+            .map(|signed| (ListKind::New, signed, None))
+            .chain(revoked.map(|r| (ListKind::Revoked, &r.signed, r.details.as_ref())))
+            .chain(self.anchored_credentials.iter().map(|signed| (ListKind::Anchored, signed, None)));
+        entries
+            .map(|(kind, signed, details)| {
+                (&signed.credential, merkle_leaf(algorithm, kind, signed, details))
+            })
+            .collect()
     }
 
-    #[test]
-    fn test_block_add_credential_and_finalize() {
-        let (credential, signing) = sample_credential();
-        let signed = credential.sign(&signing, false);
-        let issuer = credential.issuer.clone();
-        let mut block = Block::new(issuer);
-        block.add_credential(signed, false);
-        block.finalize(Hash::default(), &signing);
-        assert_ne!(block.hash.0, [0u8; 64]);
-        assert_ne!(block.signature.0, [0u8; 64]);
+    /// List of the block holding the entry recorded under the given issuance or revocation hash
+    #[must_use]
+    pub fn list_of(&self, credential: &Hash) -> Option<ListKind> {
+        let holds = |list: &[SignedCredential]| list.iter().any(|s| &s.credential == credential);
+        if holds(&self.new_credentials) {
+            Some(ListKind::New)
+        } else if self.revokes(credential) {
+            Some(ListKind::Revoked)
+        } else if holds(&self.anchored_credentials) {
+            Some(ListKind::Anchored)
+        } else {
+            None
+        }
     }
 
-    #[test]
-    fn test_block_add_revoked_credential() {
-        let (credential, signing) = sample_credential();
-        let signed = credential.sign(&signing, true);
-        let issuer = credential.issuer.clone();
-        let mut block = Block::new(issuer);
-        block.add_credential(signed.clone(), true);
-        block.finalize(Hash::default(), &signing);
-        assert!(block.revoked_credentials.iter().any(|c| c.credential == signed.credential));
+    /// Root of the Merkle tree over the credential entries, recomputed from the block's contents
+    #[must_use]
+    pub fn compute_merkle_root(&self, algorithm: Algorithm) -> Hash {
+        let leaves = self.merkle_leaves(algorithm).into_iter().map(|(_, leaf)| leaf).collect();
+        merkle::root(leaves, algorithm)
     }
 
-    #[test]
-    fn test_blockchain_add_block_and_check_credential() {
-        let (credential, signing) = sample_credential();
-        let signed = credential.sign(&signing, false);
-        let issuer = credential.issuer.clone();
+    /// Root of the Merkle tree over the credential entries, for blocks finalized from V3 on
+    #[must_use]
+    pub fn merkle_root(&self) -> Option<&Hash> { self.merkle_root.as_ref() }
 
-        let mut block = Block::new(issuer);
-        block.add_credential(signed, false);
+    /// Leaf committing to the entry recorded under the given issuance or revocation hash, for
+    /// blocks finalized from V3 on
+    #[must_use]
+    pub fn merkle_leaf(&self, credential: &Hash) -> Option<Hash> {
+        let algorithm = self.merkle_root.as_ref()?.algorithm;
+        let leaves = self.merkle_leaves(algorithm);
+        leaves.into_iter().find(|(hash, _)| *hash == credential).map(|(_, leaf)| leaf)
+    }
 
-        let mut chain = Blockchain::new();
-        chain.add_block(block, &signing);
+    /// Path from the leaf of the entry recorded under the given issuance or revocation hash to
+    /// the block's Merkle root, for blocks finalized from V3 on
+    #[must_use]
+    pub fn merkle_proof(&self, credential: &Hash) -> Option<MerkleProof> {
+        let leaf = self.merkle_leaf(credential)?;
+        let algorithm = leaf.algorithm;
+        let leaves = self.merkle_leaves(algorithm).into_iter().map(|(_, leaf)| leaf).collect();
+        merkle::proof(leaves, &leaf)
+    }
 
-        assert!(chain.check_credential(&credential));
+    /// Checks that the block links to `previous_hash`, that its stored hash matches its contents
+    /// and that it's signed by its signer.
+    ///
+    /// # Errors
+    /// Returns the first check that failed.
+    pub fn verify(
+        &self, previous_hash: &Hash, format: impl Into<HashFormat>,
+    ) -> Result<(), BlockError> {
+        let format = format.into();
+        if &self.previous_hash != previous_hash {
+            return Err(BlockError::PreviousHashMismatch);
+        }
+        if self.compute_hash(format) != self.hash {
+            return Err(BlockError::HashMismatch);
+        }
+        // The hash commits to the root recomputed from the entries, not to the stored one
+        let merkle_root =
+            (format.version >= HashVersion::V3).then(|| self.compute_merkle_root(format.algorithm));
+        if self.merkle_root != merkle_root {
+            return Err(BlockError::MerkleRootMismatch);
+        }
+        self.signer
+            .verifying
+            .verify(&self.hash.bytes, &(&self.signature).into())
+            .map_err(|_| BlockError::InvalidSignature)
     }
 
-    #[test]
-    fn test_blockchain_revoked_credential_returns_false() {
-        let (credential, signing) = sample_credential();
-        let issuer = credential.issuer.clone();
-        let signed = credential.sign(&signing, false);
-        let revoked = credential.sign(&signing, true);
+    #[must_use]
+    pub fn height(&self) -> u64 { self.height }
 
-        let mut block = Block::new(issuer);
-        block.add_credential(signed, false);
-        block.add_credential(revoked, true);
+    #[must_use]
+    pub fn timestamp(&self) -> DateTime<Utc> { self.timestamp }
 
-        let mut chain = Blockchain::new();
-        chain.add_block(block, &signing);
+    #[must_use]
+    pub fn new_credentials(&self) -> &[SignedCredential] { &self.new_credentials }
+
+    #[must_use]
+    pub fn revoked_credentials(&self) -> &[Revocation] { &self.revoked_credentials }
 
-        assert!(!chain.check_credential(&credential));
+    #[must_use]
+    pub fn anchored_credentials(&self) -> &[SignedCredential] { &self.anchored_credentials }
+
+    #[must_use]
+    pub fn authority_changes(&self) -> &[AuthorityChange] { &self.authority_changes }
+
+    #[must_use]
+    pub fn key_rotations(&self) -> &[KeyRotation] { &self.key_rotations }
+
+    #[must_use]
+    pub fn deactivations(&self) -> &[IssuerDeactivation] { &self.deactivations }
+
+    #[must_use]
+    pub fn accreditations(&self) -> &[Accreditation] { &self.accreditations }
+
+    /// Whether the block is signed by the issuer with the given UUID or records something about
+    /// it, apart from the credentials it issued
+    #[must_use]
+    pub fn mentions_issuer(&self, uuid: Uuid) -> bool {
+        self.signer.uuid == uuid
+            || self.authority_changes.iter().any(|change| match change {
+                AuthorityChange::Add(issuer) | AuthorityChange::Remove(issuer) =>
+                    issuer.uuid == uuid,
+            })
+            || self.key_rotations.iter().any(|rotation| rotation.issuer == uuid)
+            || self.deactivations.iter().any(|deactivation| deactivation.issuer == uuid)
+            || self.accreditations.iter().any(|a| a.accreditor == uuid || a.issuer == uuid)
+    }
+
+    #[must_use]
+    pub fn previous_hash(&self) -> &Hash { &self.previous_hash }
+
+    #[must_use]
+    pub fn signer(&self) -> &Issuer { &self.signer }
+
+    #[must_use]
+    pub fn hash(&self) -> &Hash { &self.hash }
+
+    #[must_use]
+    pub fn signature(&self) -> &Sig { &self.signature }
+
+    #[must_use]
+    pub fn summary(&self) -> BlockSummary {
+        BlockSummary {
+            height: self.height,
+            hash: self.hash.clone(),
+            timestamp: self.timestamp,
+            signer: self.signer.name.clone(),
+            new_credentials: self.new_credentials.len(),
+            revoked_credentials: self.revoked_credentials.len(),
+        }
+    }
+
+    /// Looks the credential up in both lists, returning for each list whether its signature
+    /// verified, or `None` if the hash isn't in that list
+    fn find(
+        &self, new_hash: &Hash, revoking_hash: &Hash, verifying: &VerifyingKey,
+    ) -> (Option<bool>, Option<bool>) {
+        let verify = |c: &SignedCredential| c.verify(verifying);
+        let new = self.issued_credentials().find(|s| &s.credential == new_hash).map(verify);
+        let revoked = self.revocation(revoking_hash).map(|r| verify(&r.signed));
+        (new, revoked)
+    }
+}
+
+impl Display for Block {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(&self).unwrap())
+    }
+}
+
+/// Merkle leaf committing to a block's credential entry, with the details recorded with a
+/// revocation if it has any
+#[must_use]
+pub fn merkle_leaf(
+    algorithm: Algorithm, kind: ListKind, signed: &SignedCredential,
+    details: Option<&RevocationDetails>,
+) -> Hash {
+    merkle::leaf(algorithm, kind.tag(), |hasher| {
+        signed.update_hash(hasher);
+        if let Some(details) = details {
+            hasher.field("revocation_details");
+            details.update_hash(hasher);
+        }
+    })
+}
+
+/// What a block's hash commits to from V3 on, enough to check a block's hash and signature
+/// without its contents
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub height: u64,
+    pub timestamp: DateTime<Utc>,
+    /// Root of the Merkle tree over the block's credential entries
+    pub merkle_root: Hash,
+    /// Digest of the block's authority changes, key rotations, deactivations and accreditations
+    pub contents: Hash,
+    pub previous_hash: Hash,
+    /// Digest of the signer's issuer record
+    pub signer: Hash,
+}
+
+impl BlockHeader {
+    /// Hash of the block with this header, hashed with the algorithm of its Merkle root
+    #[must_use]
+    pub fn hash(&self) -> Hash {
+        let algorithm = self.merkle_root.algorithm;
+        let mut hasher = Hasher::new(HashFormat { version: HashVersion::V3, algorithm });
+        hasher.domain(domain::BLOCK_HEADER);
+        hasher.update(self.height.to_le_bytes());
+        hasher.field(self.timestamp.to_string());
+        for hash in [&self.merkle_root, &self.contents, &self.previous_hash, &self.signer] {
+            hasher.update(hash);
+        }
+        hasher.finalize()
+    }
+}
+
+/// Rules applied when appending and validating blocks
+#[derive(Debug, Clone, Default)]
+pub struct ChainPolicy {
+    /// How much earlier than its predecessor a block's timestamp may be
+    pub clock_skew: TimeDelta,
+}
+
+impl ChainPolicy {
+    fn check_timestamps(&self, previous: &Block, block: &Block) -> Result<(), BlockchainError> {
+        if block.timestamp + self.clock_skew < previous.timestamp {
+            return Err(BlockchainError::NonMonotonicTimestamp {
+                height: block.height,
+                previous_height: previous.height,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainMetadata {
+    pub uuid: Uuid,
+    pub name: String,
+    pub created: DateTime<Utc>,
+    pub version: HashVersion,
+    /// Digest algorithm of every hash on the chain, SHA-512 for chains created before it was
+    /// recorded
+    #[serde(default)]
+    pub algorithm: Algorithm,
+    pub founder: Option<Issuer>,
+    /// Issuers allowed to sign blocks, `None` if anyone may
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorities: Option<Vec<Issuer>>,
+}
+
+impl ChainMetadata {
+    fn update_hash(&self, hasher: &mut Hasher) {
+        hasher.domain(domain::CHAIN_METADATA);
+        hasher.update(self.uuid);
+        hasher.field(&self.name);
+        hasher.field(self.created.to_string());
+        hasher.update(u32::from(self.version).to_le_bytes());
+        if self.algorithm != Algorithm::Sha512 {
+            hasher.field(self.algorithm.name());
+        }
+        hasher.presence(self.founder.is_some());
+        if let Some(founder) = &self.founder {
+            founder.update_hash(hasher);
+        }
+        hasher.presence(self.authorities.is_some());
+        if let Some(authorities) = &self.authorities {
+            hasher.field("authorities");
+            hasher.length(authorities.len());
+            for authority in authorities {
+                authority.update_hash(hasher);
+            }
+        }
+    }
+}
+
+/// First block of a chain, carrying no credentials but identifying the chain itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenesisBlock {
+    metadata: ChainMetadata,
+    hash: Hash,
+    signature: Option<Sig>,
+}
+
+impl GenesisBlock {
+    /// Creates the genesis block, signed with `founder`'s key if a founding issuer is given.
+    /// Passing `authorities` restricts block signing to them (proof of authority).
+    ///
+    /// # Errors
+    /// Fails if the founder's signer can't sign.
+    pub fn new(
+        name: String, founder: Option<(Issuer, &dyn BlockSigner)>, authorities: Option<Vec<Issuer>>,
+    ) -> Result<Self, SignError> {
+        Self::with_algorithm(name, founder, authorities, Algorithm::default())
+    }
+
+    /// Like [`GenesisBlock::new`], for a chain hashing with the given algorithm
+    ///
+    /// # Errors
+    /// See [`GenesisBlock::new`].
+    pub fn with_algorithm(
+        name: String, founder: Option<(Issuer, &dyn BlockSigner)>,
+        authorities: Option<Vec<Issuer>>, algorithm: Algorithm,
+    ) -> Result<Self, SignError> {
+        let (founder, signing) = founder.unzip();
+        let metadata = ChainMetadata {
+            uuid: Uuid::new_v4(),
+            name,
+            created: Utc::now(),
+            version: HashVersion::LATEST,
+            algorithm,
+            founder,
+            authorities,
+        };
+        let mut genesis = Self { metadata, hash: Hash::default(), signature: None };
+        genesis.hash = genesis.compute_hash();
+        genesis.signature =
+            signing.map(|s| s.sign(&genesis.hash.bytes)).transpose()?.map(Sig::from);
+        Ok(genesis)
+    }
+
+    #[must_use]
+    pub fn compute_hash(&self) -> Hash {
+        let mut hasher = Hasher::new(HashFormat {
+            version: self.metadata.version,
+            algorithm: self.metadata.algorithm,
+        });
+        self.metadata.update_hash(&mut hasher);
+        hasher.finalize()
+    }
+
+    /// Checks that the stored hash matches the metadata and that the founder, if any, signed it
+    ///
+    /// # Errors
+    /// Returns the first check that failed.
+    pub fn verify(&self) -> Result<(), BlockError> {
+        if self.compute_hash() != self.hash {
+            return Err(BlockError::HashMismatch);
+        }
+        match (&self.metadata.founder, &self.signature) {
+            (None, None) => Ok(()),
+            (Some(founder), Some(signature)) => founder
+                .verifying
+                .verify(&self.hash.bytes, &signature.into())
+                .map_err(|_| BlockError::InvalidSignature),
+            _ => Err(BlockError::InvalidSignature),
+        }
+    }
+
+    #[must_use]
+    pub fn metadata(&self) -> &ChainMetadata { &self.metadata }
+
+    #[must_use]
+    pub fn hash(&self) -> &Hash { &self.hash }
+}
+
+/// Outcome of checking a credential against the chain, with heights of the blocks involved
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum VerificationStatus {
+    Valid { issued_in: u64 },
+    Revoked { issued_in: Option<u64>, revoked_in: u64 },
+    /// Issued, but only valid from a later date
+    NotYetValid { issued_in: u64, from: NaiveDate },
+    /// Issued, but only valid until an earlier date
+    Expired { issued_in: u64, to: NaiveDate },
+    NotFound,
+    /// Issued, but in a block timestamped after the date the credential was checked at
+    NotYetIssued { issued_in: u64 },
+    /// The credential's hash is in the block, but not signed with its issuer's key
+    SignatureInvalid { block: u64 },
+    /// The credential's issuer key differs from the key pinned for that issuer
+    IssuerKeyConflict,
+    /// Issued in a block dated after its issuer was deactivated
+    IssuerDeactivated { issued_in: u64, effective: NaiveDate },
+    /// Issued by an issuer the trust root didn't accredit on the day of the issuing block
+    NotAccredited { issued_in: u64 },
+    /// The credential's issuer key isn't in the verifier's trust store
+    UntrustedIssuer,
+}
+
+/// First blocks issuing, revoking and forging a credential
+#[derive(Debug, Clone, Copy, Default)]
+struct Sightings {
+    issued: Option<u64>,
+    revoked: Option<u64>,
+    forged: Option<u64>,
+}
+
+impl Sightings {
+    fn status(self) -> VerificationStatus {
+        VerificationStatus::from_heights(self.issued, self.revoked, self.forged)
+    }
+}
+
+impl VerificationStatus {
+    fn from_heights(
+        issued_in: Option<u64>, revoked_in: Option<u64>, forged_in: Option<u64>,
+    ) -> Self {
+        match (issued_in, revoked_in, forged_in) {
+            (.., Some(block)) => Self::SignatureInvalid { block },
+            (issued_in, Some(revoked_in), None) => Self::Revoked { issued_in, revoked_in },
+            (Some(issued_in), None, None) => Self::Valid { issued_in },
+            (None, None, None) => Self::NotFound,
+        }
+    }
+
+    /// Narrows a valid status down to `NotYetValid` or `Expired` if `date` falls outside
+    /// `duration`. Both ends are inclusive and a missing `to` means the duration never ends.
+    #[must_use]
+    pub fn within(self, duration: &ValidDuration, date: NaiveDate) -> Self {
+        match self {
+            Self::Valid { issued_in } if date < duration.from =>
+                Self::NotYetValid { issued_in, from: duration.from },
+            Self::Valid { issued_in } => match duration.to {
+                Some(to) if duration.is_expired(date) => Self::Expired { issued_in, to },
+                _ => self,
+            },
+            _ => self,
+        }
+    }
+
+    #[must_use]
+    pub fn is_valid(&self) -> bool { matches!(self, Self::Valid { .. }) }
+
+    /// Short name of the status, without the blocks and dates it refers to
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Valid { .. } => "valid",
+            Self::Revoked { .. } => "revoked",
+            Self::NotYetValid { .. } => "not yet valid",
+            Self::Expired { .. } => "expired",
+            Self::NotFound => "not found",
+            Self::NotYetIssued { .. } => "not yet issued",
+            Self::SignatureInvalid { .. } => "signature invalid",
+            Self::IssuerKeyConflict => "issuer key conflict",
+            Self::IssuerDeactivated { .. } => "issuer deactivated",
+            Self::NotAccredited { .. } => "not accredited",
+            Self::UntrustedIssuer => "untrusted issuer",
+        }
+    }
+
+    /// Height of the block that issued the credential, if it was found
+    #[must_use]
+    pub fn issued_in(&self) -> Option<u64> {
+        match *self {
+            Self::Valid { issued_in }
+            | Self::NotYetValid { issued_in, .. }
+            | Self::Expired { issued_in, .. }
+            | Self::NotYetIssued { issued_in }
+            | Self::IssuerDeactivated { issued_in, .. }
+            | Self::NotAccredited { issued_in } => Some(issued_in),
+            Self::Revoked { issued_in, .. } => issued_in,
+            Self::NotFound
+            | Self::SignatureInvalid { .. }
+            | Self::IssuerKeyConflict
+            | Self::UntrustedIssuer => None,
+        }
+    }
+
+    /// Height of the block that revoked the credential, if it was revoked
+    #[must_use]
+    pub fn revoked_in(&self) -> Option<u64> {
+        match *self {
+            Self::Revoked { revoked_in, .. } => Some(revoked_in),
+            _ => None,
+        }
+    }
+}
+
+impl Display for VerificationStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Valid { issued_in } => write!(f, "valid (issued in block {issued_in})"),
+            Self::Revoked { issued_in: Some(issued_in), revoked_in } => {
+                write!(f, "revoked (issued in block {issued_in}, revoked in block {revoked_in})")
+            },
+            Self::Revoked { issued_in: None, revoked_in } => {
+                write!(f, "revoked (revoked in block {revoked_in})")
+            },
+            Self::NotYetValid { issued_in, from } => {
+                write!(f, "not yet valid, valid from {from} (issued in block {issued_in})")
+            },
+            Self::Expired { issued_in, to } => {
+                write!(f, "expired, valid until {to} (issued in block {issued_in})")
+            },
+            Self::NotFound => f.write_str("not found"),
+            Self::NotYetIssued { issued_in } => {
+                write!(f, "not yet issued (issued later in block {issued_in})")
+            },
+            Self::SignatureInvalid { block } => {
+                write!(f, "signature invalid (in block {block})")
+            },
+            Self::IssuerKeyConflict => f.write_str("issuer key conflicts with the pinned key"),
+            Self::IssuerDeactivated { issued_in, effective } => write!(
+                f,
+                "issuer deactivated, no longer trusted after {effective} (issued in block \
+                 {issued_in})"
+            ),
+            Self::NotAccredited { issued_in } =>
+                write!(f, "issuer not accredited by the trust root (issued in block {issued_in})"),
+            Self::UntrustedIssuer => f.write_str("issuer not in the trust store"),
+        }
+    }
+}
+
+/// Suspicious content that doesn't make a chain invalid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainWarning {
+    /// The block revokes a credential without being signed by its issuer, so the revocation is
+    /// ignored
+    UnauthorizedRevocation { height: u64, credential: Hash },
+    /// The block issues a credential an earlier block already issued
+    DuplicateIssuance { height: u64, first_issued_in: u64, credential: Hash },
+}
+
+impl Display for ChainWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnauthorizedRevocation { height, credential } => write!(
+                f,
+                "Block {height} contains an unauthorized revocation attempt of credential \
+                 {credential}"
+            ),
+            Self::DuplicateIssuance { height, first_issued_in, credential } => write!(
+                f,
+                "Block {height} issues credential {credential} already issued in block \
+                 {first_issued_in}"
+            ),
+        }
+    }
+}
+
+/// Which of a block's credential lists holds a hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListKind {
+    /// Issued by the block signer, in `new_credentials`
+    New,
+    /// Another issuer's credential, in `anchored_credentials`
+    Anchored,
+    /// In `revoked_credentials`
+    Revoked,
+}
+
+impl ListKind {
+    /// Byte telling the Merkle leaves of the lists apart
+    fn tag(self) -> u8 {
+        match self {
+            Self::New => 0,
+            Self::Revoked => 1,
+            Self::Anchored => 2,
+        }
+    }
+}
+
+impl Display for ListKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::New => "issued",
+            Self::Anchored => "anchored",
+            Self::Revoked => "revoked",
+        })
+    }
+}
+
+/// Block a credential was found in, with what's needed to chase down who added it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockRef {
+    pub height: u64,
+    pub timestamp: DateTime<Utc>,
+    pub signer: String,
+    pub hash: Hash,
+}
+
+impl Display for BlockRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "block #{} ({}, {})", self.height, self.timestamp.date_naive(), self.signer)
+    }
+}
+
+/// Step of a credential check, reported to the callback of
+/// [`Blockchain::check_credential_traced`] to show how the status came about
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TraceEvent {
+    /// Hashes the credential is looked up by, computed before scanning the chain
+    Hashes { issuance: Hash, revocation: Hash },
+    /// Block left out for being dated after the day the credential is checked on
+    Skipped { height: u64, until: NaiveDate },
+    /// Block holding neither hash
+    NoMatch { height: u64 },
+    /// Block holding one of the hashes, with the outcome of checking the entry's signature
+    /// against the issuer key with the given fingerprint
+    Match { height: u64, entry: TraceEntry, outcome: EntryOutcome, key: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceEntry {
+    /// Issued or anchored by the block
+    Issuance,
+    Revocation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryOutcome {
+    /// The signature verified and the entry counts
+    SignatureOk,
+    /// The signature didn't verify, which fails the check
+    SignatureFailed,
+    /// The signature verified, but with a key rotated away before the block, which fails the check
+    RetiredKey,
+    /// The signature verified, but the revocation is in a block the issuer didn't sign and is
+    /// ignored
+    NotIssuerBlock,
+}
+
+impl Display for TraceEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hashes { issuance, revocation } => write!(
+                f,
+                "computed issuance hash {}…, revocation hash {}…",
+                issuance.short(),
+                revocation.short()
+            ),
+            Self::Skipped { height, until } =>
+                write!(f, "block #{height}: skipped, dated after {until}"),
+            Self::NoMatch { height } => write!(f, "block #{height}: no match"),
+            Self::Match { height, entry, outcome, key } => {
+                let entry = match entry {
+                    TraceEntry::Issuance => "issuance",
+                    TraceEntry::Revocation => "revocation",
+                };
+                write!(f, "block #{height}: {entry} match, ")?;
+                match outcome {
+                    EntryOutcome::SignatureOk => f.write_str("signature OK"),
+                    EntryOutcome::SignatureFailed =>
+                        write!(f, "signature FAILED (key fingerprint {key})"),
+                    EntryOutcome::RetiredKey =>
+                        write!(f, "signature OK, but key {key} was retired before the block"),
+                    EntryOutcome::NotIssuerBlock =>
+                        f.write_str("signature OK, ignored as the issuer didn't sign the block"),
+                }
+            },
+        }
+    }
+}
+
+/// What a block holds at a glance, one line when displayed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlockSummary {
+    pub height: u64,
+    pub hash: Hash,
+    pub timestamp: DateTime<Utc>,
+    pub signer: String,
+    pub new_credentials: usize,
+    pub revoked_credentials: usize,
+}
+
+impl Display for BlockSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} {} {} {}: {} new, {} revoked",
+            self.height,
+            self.hash.short(),
+            self.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            self.signer,
+            self.new_credentials,
+            self.revoked_credentials
+        )
+    }
+}
+
+/// Digest of a run of blocks, displayed as one line per block followed by the totals
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChainSummary {
+    pub blocks: Vec<BlockSummary>,
+    pub totals: ChainTotals,
+}
+
+/// What a run of blocks holds altogether
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ChainTotals {
+    pub blocks: usize,
+    pub issued: usize,
+    pub revoked: usize,
+    /// Issuers that signed any of the blocks
+    pub issuers: usize,
+    /// Timestamps of the first and last blocks, none without blocks
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+}
+
+impl ChainSummary {
+    #[must_use]
+    pub fn new<'a>(blocks: impl IntoIterator<Item = &'a Block>) -> Self {
+        let mut totals = ChainTotals::default();
+        let mut signers = HashSet::new();
+        let blocks: Vec<_> = blocks
+            .into_iter()
+            .inspect(|block| {
+                signers.insert(block.signer.uuid);
+                totals.first.get_or_insert(block.timestamp);
+                totals.last = Some(block.timestamp);
+            })
+            .map(Block::summary)
+            .collect();
+        totals.blocks = blocks.len();
+        totals.issued = blocks.iter().map(|block| block.new_credentials).sum();
+        totals.revoked = blocks.iter().map(|block| block.revoked_credentials).sum();
+        totals.issuers = signers.len();
+        Self { blocks, totals }
+    }
+}
+
+impl Display for ChainSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for block in &self.blocks {
+            writeln!(f, "{block}")?;
+        }
+        write!(f, "{}", self.totals)
+    }
+}
+
+impl Display for ChainTotals {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (Some(first), Some(last)) = (self.first, self.last) else {
+            return f.write_str("Totals: no blocks");
+        };
+        let format = "%Y-%m-%d %H:%M:%S";
+        write!(
+            f,
+            "Totals: {} blocks, {} credentials issued, {} revoked, {} distinct issuers, from {} \
+             to {}",
+            self.blocks,
+            self.issued,
+            self.revoked,
+            self.issuers,
+            first.format(format),
+            last.format(format)
+        )
+    }
+}
+
+/// Counts over a whole chain, altogether, per issuer and per month
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ChainStats {
+    pub blocks: usize,
+    pub issued: usize,
+    pub revoked: usize,
+    pub anchored: usize,
+    /// Issuers that signed any block, in the order they first did
+    pub issuers: Vec<IssuerStats>,
+    /// Every month from the first block's to the last block's, including those without blocks
+    pub months: Vec<MonthStats>,
+    /// Credentials issued, revoked and anchored per block
+    pub average_per_block: f64,
+    /// Block with the most credentials, the first of them if several have as many
+    pub largest_block: Option<LargestBlock>,
+}
+
+/// What the blocks signed by one issuer hold
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct IssuerStats {
+    pub uuid: Uuid,
+    /// Name the issuer had in its last block
+    pub name: String,
+    /// Fingerprint of the key that signed the issuer's last block, which still identifies it once
+    /// it's no longer among the local issuers
+    pub fingerprint: String,
+    pub blocks: usize,
+    pub issued: usize,
+    pub revoked: usize,
+    pub anchored: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MonthStats {
+    /// Year and month, e.g. `2024-01`
+    pub month: String,
+    pub blocks: usize,
+    pub issued: usize,
+    pub revoked: usize,
+    pub anchored: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LargestBlock {
+    pub height: u64,
+    pub hash: Hash,
+    pub credentials: usize,
+}
+
+impl ChainStats {
+    /// Gathers the stats in a single pass over the blocks
+    #[must_use]
+    pub fn new<'a>(blocks: impl IntoIterator<Item = &'a Block>) -> Self {
+        let mut stats = Self::default();
+        let mut issuers: HashMap<Uuid, usize> = HashMap::new();
+        let mut months: BTreeMap<(i32, u32), MonthStats> = BTreeMap::new();
+        for block in blocks {
+            let (issued, revoked, anchored) = (
+                block.new_credentials.len(),
+                block.revoked_credentials.len(),
+                block.anchored_credentials.len(),
+            );
+            stats.blocks += 1;
+            stats.issued += issued;
+            stats.revoked += revoked;
+            stats.anchored += anchored;
+
+            let signer = &block.signer;
+            let index = *issuers.entry(signer.uuid).or_insert_with(|| {
+                stats.issuers.push(IssuerStats { uuid: signer.uuid, ..IssuerStats::default() });
+                stats.issuers.len() - 1
+            });
+            let by_signer = &mut stats.issuers[index];
+            by_signer.name.clone_from(&signer.name);
+            by_signer.fingerprint = fingerprint(&signer.verifying);
+            by_signer.blocks += 1;
+            by_signer.issued += issued;
+            by_signer.revoked += revoked;
+            by_signer.anchored += anchored;
+
+            let date = block.timestamp.date_naive();
+            let month = months.entry((date.year(), date.month())).or_insert_with(|| MonthStats {
+                month: date.format("%Y-%m").to_string(),
+                ..MonthStats::default()
+            });
+            month.blocks += 1;
+            month.issued += issued;
+            month.revoked += revoked;
+            month.anchored += anchored;
+
+            let credentials = issued + revoked + anchored;
+            if stats.largest_block.as_ref().is_none_or(|largest| credentials > largest.credentials)
+            {
+                let (height, hash) = (block.height, block.hash.clone());
+                stats.largest_block = Some(LargestBlock { height, hash, credentials });
+            }
+        }
+        stats.months = Self::fill_months(months);
+        if stats.blocks > 0 {
+            #[expect(clippy::cast_precision_loss, reason = "counts are far below 2^52")]
+            let average =
+                (stats.issued + stats.revoked + stats.anchored) as f64 / stats.blocks as f64;
+            stats.average_per_block = average;
+        }
+        stats
+    }
+
+    /// Adds the months without blocks between the first and last months with some
+    fn fill_months(mut months: BTreeMap<(i32, u32), MonthStats>) -> Vec<MonthStats> {
+        let (Some(&first), Some(&last)) = (months.keys().next(), months.keys().next_back()) else {
+            return Vec::new();
+        };
+        let mut month = first;
+        while month < last {
+            month = if month.1 == 12 { (month.0 + 1, 1) } else { (month.0, month.1 + 1) };
+            months.entry(month).or_insert_with(|| MonthStats {
+                month: format!("{:04}-{:02}", month.0, month.1),
+                ..MonthStats::default()
+            });
+        }
+        months.into_values().collect()
+    }
+}
+
+/// Verification status together with the blocks that issued and revoked the credential
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationReport {
+    #[serde(flatten)]
+    pub status: VerificationStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issued: Option<BlockRef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked: Option<BlockRef>,
+    /// Why and when the credential was revoked, if the revoking block records it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revocation: Option<RevocationDetails>,
+    /// Blocks revoking the credential without being signed by its issuer
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unauthorized_revocations: Vec<BlockRef>,
+    /// Whether the issuing block holds the subject's consent to the credential
+    #[serde(default)]
+    pub consent: ConsentStatus,
+}
+
+/// Where two chains sharing a prefix start to differ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DivergencePoint {
+    /// Height of the last block both chains share, `None` if they differ from the first block
+    pub last_common: Option<u64>,
+    /// Height of the first differing block
+    pub height: u64,
+    /// Hash of the first differing block on this chain
+    pub local: Hash,
+    /// Hash of the first differing block on the other chain
+    pub other: Hash,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "StoredBlockchain")]
+pub struct Blockchain {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    genesis: Option<GenesisBlock>,
+    chain: Vec<Block>,
+    /// Position of each block by its hash, rebuilt when the chain is read
+    #[serde(skip)]
+    by_hash: HashMap<Hash, usize>,
+}
+
+/// Blockchain as stored, without the lookup index
+#[derive(Deserialize)]
+struct StoredBlockchain {
+    #[serde(default)]
+    genesis: Option<GenesisBlock>,
+    chain: Vec<Block>,
+}
+
+impl From<StoredBlockchain> for Blockchain {
+    fn from(stored: StoredBlockchain) -> Self {
+        let mut by_hash = HashMap::new();
+        for (position, block) in stored.chain.iter().enumerate() {
+            // A tampered chain may repeat a hash, the first block holding it is found
+            by_hash.entry(block.hash.clone()).or_insert(position);
+        }
+        Self { genesis: stored.genesis, chain: stored.chain, by_hash }
+    }
+}
+
+impl Default for Blockchain {
+    fn default() -> Self { Self::new() }
+}
+
+// Iterating by method is `Blockchain::blocks`
+#[expect(clippy::into_iter_without_iter)]
+impl<'a> IntoIterator for &'a Blockchain {
+    type IntoIter = std::slice::Iter<'a, Block>;
+    type Item = &'a Block;
+
+    fn into_iter(self) -> Self::IntoIter { self.chain.iter() }
+}
+
+impl Blockchain {
+    /// Creates an empty chain without a genesis block, as written by older versions
+    #[must_use]
+    pub fn new() -> Self { Self { genesis: None, chain: Vec::new(), by_hash: HashMap::new() } }
+
+    /// Creates an empty chain starting with the given genesis block
+    #[must_use]
+    pub fn with_genesis(genesis: GenesisBlock) -> Self {
+        Self { genesis: Some(genesis), ..Self::new() }
+    }
+
+    /// Chain made of blocks read one by one, e.g. from another node, which isn't validated
+    #[must_use]
+    pub fn from_blocks(genesis: Option<GenesisBlock>, blocks: Vec<Block>) -> Self {
+        StoredBlockchain { genesis, chain: blocks }.into()
+    }
+
+    #[must_use]
+    pub fn genesis(&self) -> Option<&GenesisBlock> { self.genesis.as_ref() }
+
+    /// Hash rules of the chain, chains without a genesis block using the original ones
+    #[must_use]
+    pub fn version(&self) -> HashVersion {
+        self.genesis.as_ref().map_or(HashVersion::V1, |g| g.metadata.version)
+    }
+
+    /// Digest algorithm of the chain, chains without a genesis block using SHA-512
+    #[must_use]
+    pub fn algorithm(&self) -> Algorithm {
+        self.genesis.as_ref().map_or(Algorithm::Sha512, |g| g.metadata.algorithm)
+    }
+
+    /// Hash version and algorithm credentials and blocks on this chain are hashed with
+    #[must_use]
+    pub fn format(&self) -> HashFormat {
+        HashFormat { version: self.version(), algorithm: self.algorithm() }
+    }
+
+    /// ID of the chain taken from its genesis block
+    #[must_use]
+    pub fn id(&self) -> Option<Uuid> { self.genesis.as_ref().map(|g| g.metadata.uuid) }
+
+    /// Issuers currently allowed to sign blocks, `None` if the chain doesn't restrict signers
+    ///
+    /// # Errors
+    /// Returns the error of the first block whose signer or authority changes the set doesn't
+    /// allow, which only a chain edited since its blocks were appended holds.
+    pub fn authorities(&self) -> Result<Option<Vec<Issuer>>, BlockchainError> {
+        let Some(genesis) = &self.genesis else {
+            return Ok(None);
+        };
+        let mut authorities = genesis.metadata.authorities.clone();
+        for block in &self.chain {
+            Self::check_authority(&mut authorities, block)?;
+        }
+        Ok(authorities)
+    }
+
+    /// Checks the block's signer against the authority set and applies its changes to it
+    fn check_authority(
+        authorities: &mut Option<Vec<Issuer>>, block: &Block,
+    ) -> Result<(), BlockchainError> {
+        let height = block.height;
+        let Some(authorities) = authorities else {
+            return if block.authority_changes.is_empty() {
+                Ok(())
+            } else {
+                Err(BlockchainError::AuthorityChangeRejected { height })
+            };
+        };
+        if !authorities.iter().any(|a| a.verifying == block.signer.verifying) {
+            return Err(BlockchainError::UnauthorizedSigner { height });
+        }
+        for change in &block.authority_changes {
+            change
+                .apply(authorities)
+                .map_err(|()| BlockchainError::AuthorityChangeRejected { height })?;
+        }
+        Ok(())
+    }
+
+    /// Authority set of the genesis block, `None` if the chain doesn't restrict signers
+    fn genesis_authorities(&self) -> Option<&Vec<Issuer>> {
+        self.genesis.as_ref()?.metadata.authorities.as_ref()
+    }
+
+    /// Checks that the block's deactivations are signed by a chain authority, which signs every
+    /// block of chains with authorities, or by the deactivated issuer itself
+    fn check_deactivations(
+        has_authorities: bool, history: &KeyHistory, block: &Block,
+    ) -> Result<(), BlockchainError> {
+        let authorized = |deactivation: &IssuerDeactivation| {
+            has_authorities
+                || (block.signer.uuid == deactivation.issuer
+                    && history
+                        .succeeds(&block.signer.verifying, &deactivation.verifying, block.height))
+        };
+        if block.deactivations.iter().all(authorized) {
+            Ok(())
+        } else {
+            Err(BlockchainError::UnauthorizedDeactivation { height: block.height })
+        }
+    }
+
+    /// Earliest deactivation of the issuer recorded on the chain, with the height of the block
+    /// recording it, considering only blocks dated on or before `until` if given
+    #[must_use]
+    pub fn deactivation(
+        &self, issuer: &Issuer, until: Option<NaiveDate>,
+    ) -> Option<(u64, &IssuerDeactivation)> {
+        let candidates: Vec<_> = self
+            .chain
+            .iter()
+            .filter(|block| until.is_none_or(|date| block.timestamp.date_naive() <= date))
+            .flat_map(|block| block.deactivations.iter().map(|d| (block.height, d)))
+            .filter(|(_, d)| d.issuer == issuer.uuid)
+            .collect();
+        // Following key rotations takes a pass over the chain, only needed once there's a match
+        if candidates.is_empty() {
+            return None;
+        }
+        let history = self.key_history();
+        candidates
+            .into_iter()
+            .filter(|(_, d)| history.related(&d.verifying, &issuer.verifying))
+            .min_by_key(|(_, d)| d.effective)
+    }
+
+    /// Narrows a valid status down to `IssuerDeactivated` if the credential was issued in a block
+    /// dated after the effective date of its issuer's deactivation. Credentials issued on that
+    /// date itself stay valid.
+    fn check_deactivation(
+        &self, status: VerificationStatus, credential: &Credential, until: Option<NaiveDate>,
+    ) -> VerificationStatus {
+        let VerificationStatus::Valid { issued_in } = status else {
+            return status;
+        };
+        let Some((_, deactivation)) = self.deactivation(&credential.issuer, until) else {
+            return status;
+        };
+        match self.block(issued_in) {
+            Some(block) if block.timestamp.date_naive() > deactivation.effective =>
+                VerificationStatus::IssuerDeactivated {
+                    issued_in,
+                    effective: deactivation.effective,
+                },
+            _ => status,
+        }
+    }
+
+    /// Checks that the block's accreditations are signed by their accreditors
+    fn check_accreditations(block: &Block, format: HashFormat) -> Result<(), BlockchainError> {
+        if block.accreditations.iter().all(|accreditation| accreditation.verify(format)) {
+            Ok(())
+        } else {
+            Err(BlockchainError::InvalidAccreditation { height: block.height })
+        }
+    }
+
+    /// Narrows a valid status down to `NotAccredited` unless the credential's issuer is `root`,
+    /// or was accredited by it directly or through accredited issuers on the day of the issuing
+    /// block. Only accreditations recorded up to the issuing block count.
+    #[must_use]
+    pub fn check_trust(
+        &self, status: VerificationStatus, credential: &Credential, root: &Issuer,
+    ) -> VerificationStatus {
+        let VerificationStatus::Valid { issued_in } = status else {
+            return status;
+        };
+        match self.block(issued_in) {
+            Some(block)
+                if !self.accredited(root, &credential.issuer, issued_in, block.timestamp) =>
+                VerificationStatus::NotAccredited { issued_in },
+            _ => status,
+        }
+    }
+
+    /// Whether `issuer` is `root` or accredited from it on the date of `at`, through
+    /// accreditations recorded in the blocks up to `height`
+    fn accredited(&self, root: &Issuer, issuer: &Issuer, height: u64, at: DateTime<Utc>) -> bool {
+        let date = at.date_naive();
+        let history = self.key_history();
+        let is = |(uuid, key): &(Uuid, VerifyingKey), other: Uuid, other_key: &VerifyingKey| {
+            *uuid == other && history.related(key, other_key)
+        };
+        let mut trusted = vec![(root.uuid, root.verifying)];
+        let mut pending: Vec<&Accreditation> = self
+            .chain
+            .iter()
+            .enumerate()
+            .take_while(|(h, _)| *h as u64 <= height)
+            .flat_map(|(_, block)| &block.accreditations)
+            .filter(|accreditation| accreditation.valid_duration.contains(date))
+            .collect();
+        // Every accreditation extends the trusted issuers at most once, so cycles end the loop
+        loop {
+            let (usable, rest): (Vec<_>, Vec<_>) = pending.into_iter().partition(|accreditation| {
+                trusted
+                    .iter()
+                    .any(|t| is(t, accreditation.accreditor, &accreditation.accreditor_key))
+            });
+            if usable.is_empty() {
+                break;
+            }
+            trusted.extend(
+                usable.iter().map(|accreditation| (accreditation.issuer, accreditation.verifying)),
+            );
+            pending = rest;
+        }
+        trusted.iter().any(|t| is(t, issuer.uuid, &issuer.verifying))
+    }
+
+    /// Key rotations recorded on the chain, oldest first
+    pub fn key_rotations(&self) -> impl Iterator<Item = &KeyRotation> {
+        self.chain.iter().flat_map(|block| &block.key_rotations)
+    }
+
+    /// Verifying keys the chain records for the issuer UUID, in the genesis block, as a block
+    /// signer or in key rotations, oldest first. Empty for issuers the chain doesn't know.
+    #[must_use]
+    pub fn issuer_keys(&self, issuer: Uuid) -> Vec<VerifyingKey> {
+        let mut keys = Vec::new();
+        let mut add = |key: &VerifyingKey| {
+            if !keys.contains(key) {
+                keys.push(*key);
+            }
+        };
+        if let Some(genesis) = &self.genesis {
+            let metadata = &genesis.metadata;
+            for founder in metadata.founder.iter().chain(metadata.authorities.iter().flatten()) {
+                if founder.uuid == issuer {
+                    add(&founder.verifying);
+                }
+            }
+        }
+        for block in &self.chain {
+            if block.signer.uuid == issuer {
+                add(&block.signer.verifying);
+            }
+            for rotation in block.key_rotations.iter().filter(|r| r.issuer == issuer) {
+                add(&rotation.previous);
+                add(&rotation.new);
+            }
+        }
+        keys
+    }
+
+    /// Revocations the chain honors, oldest first, with the block recording each: those signed
+    /// with the block signer's key or a key it replaced. Revocations slipped into another
+    /// issuer's block are left out, as verification ignores them.
+    #[must_use]
+    pub fn revocations(&self) -> Vec<(&Block, &Revocation)> {
+        let history = self.key_history();
+        let mut revocations = Vec::new();
+        for block in &self.chain {
+            for revocation in &block.revoked_credentials {
+                if history.revocation_authorized(block, &revocation.signed) {
+                    revocations.push((block, revocation));
+                }
+            }
+        }
+        revocations
+    }
+
+    /// Follows the key rotations of the stored blocks, which have already been checked when
+    /// appended
+    fn key_history(&self) -> KeyHistory {
+        let mut history = KeyHistory::new(self.genesis.as_ref());
+        for block in &self.chain {
+            let _ = history.record(block, self.format());
+        }
+        history
+    }
+
+    /// Hash of the last block, or of the genesis block if there are no blocks yet
+    #[must_use]
+    pub fn tip_hash(&self) -> Hash {
+        self.chain
+            .last()
+            .map(|b| &b.hash)
+            .or(self.genesis.as_ref().map(|g| &g.hash))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Whether the genesis block or any block mentions the issuer with the given UUID, see
+    /// [`Block::mentions_issuer`]
+    #[must_use]
+    pub fn mentions_issuer(&self, uuid: Uuid) -> bool {
+        let genesis = self.genesis.as_ref().map(|genesis| &genesis.metadata);
+        genesis.is_some_and(|metadata| {
+            metadata
+                .founder
+                .iter()
+                .chain(metadata.authorities.iter().flatten())
+                .any(|i| i.uuid == uuid)
+        }) || self.chain.iter().any(|block| block.mentions_issuer(uuid))
+    }
+
+    /// Checks that `other` is a copy of this chain rather than an unrelated one
+    ///
+    /// # Errors
+    /// Fails if the genesis chain IDs differ.
+    pub fn ensure_same_chain(&self, other: &Self) -> Result<(), BlockchainError> {
+        if self.id() == other.id() {
+            Ok(())
+        } else {
+            Err(BlockchainError::ChainIdMismatch { local: self.id(), other: other.id() })
+        }
+    }
+
+    /// Finalizes the block on top of the current tip and appends it to the chain
+    ///
+    /// # Errors
+    /// Fails if `signer` doesn't sign with the block signer's key or fails to sign, the block
+    /// was already finalized or the clock is behind the tip's timestamp.
+    pub fn add_block(
+        &mut self, block: Block, signer: &dyn BlockSigner,
+    ) -> Result<(), BlockchainError> {
+        self.add_block_with(block, signer, &ChainPolicy::default())
+    }
+
+    /// Like [`Blockchain::add_block`], using the given policy
+    ///
+    /// # Errors
+    /// See [`Blockchain::add_block`].
+    pub fn add_block_with(
+        &mut self, mut block: Block, signer: &dyn BlockSigner, policy: &ChainPolicy,
+    ) -> Result<(), BlockchainError> {
+        if signer.verifying_key() != block.signer.verifying {
+            return Err(BlockchainError::SignerKeyMismatch);
+        }
+        if block.previous_hash != Hash::default() || block.hash != Hash::default() {
+            return Err(BlockchainError::AlreadyFinalized);
+        }
+        block.height = self.chain.len() as u64;
+        Self::check_authority(&mut self.authorities()?, &block)?;
+        let mut history = self.key_history();
+        if history.retired.contains_key(&block.signer.verifying) {
+            return Err(BlockchainError::RetiredSignerKey { height: block.height });
+        }
+        history.record(&block, self.format())?;
+        Self::check_deactivations(self.genesis_authorities().is_some(), &history, &block)?;
+        Self::check_accreditations(&block, self.format())?;
+        block.finalize(self.tip_hash(), signer, self.format()).map_err(BlockchainError::Signing)?;
+        if let Some(tip) = self.chain.last() {
+            policy.check_timestamps(tip, &block)?;
+        }
+        self.by_hash.entry(block.hash.clone()).or_insert(self.chain.len());
+        self.chain.push(block);
+        Ok(())
+    }
+
+    /// Finds where the credential was issued and revoked, failing on the first block issuing its
+    /// hash with a signature that doesn't match the issuer key
+    #[must_use]
+    pub fn check_credential(&self, credential: &Credential) -> VerificationStatus {
+        self.scan_credential(credential, None, &mut |_| {})
+    }
+
+    /// Like [`Blockchain::check_credential`], passing each step of the check to `trace`
+    #[must_use]
+    pub fn check_credential_traced(
+        &self, credential: &Credential, trace: &mut dyn FnMut(TraceEvent),
+    ) -> VerificationStatus {
+        self.scan_credential(credential, None, trace)
+    }
+
+    /// Checks the credential as it stood on `date`: only blocks timestamped on or before that day
+    /// count, and the credential's valid duration must cover it
+    #[must_use]
+    pub fn check_credential_at(
+        &self, credential: &Credential, date: NaiveDate,
+    ) -> VerificationStatus {
+        self.check_credential_at_traced(credential, date, &mut |_| {})
+    }
+
+    /// Like [`Blockchain::check_credential_at`], passing each step of the check to `trace`
+    #[must_use]
+    pub fn check_credential_at_traced(
+        &self, credential: &Credential, date: NaiveDate, trace: &mut dyn FnMut(TraceEvent),
+    ) -> VerificationStatus {
+        let status = self.scan_credential(credential, Some(date), trace);
+        Self::status_at(status, || self.check_credential(credential), credential, date)
+    }
+
+    /// Narrows the status the chain gave the credential up to `date` down to its valid duration,
+    /// telling credentials that `whole`, the status over the whole chain, shows were issued later
+    /// apart from unknown ones
+    fn status_at(
+        status: VerificationStatus, whole: impl FnOnce() -> VerificationStatus,
+        credential: &Credential, date: NaiveDate,
+    ) -> VerificationStatus {
+        match status {
+            VerificationStatus::NotFound => match whole() {
+                VerificationStatus::Valid { issued_in }
+                | VerificationStatus::Revoked { issued_in: Some(issued_in), .. } =>
+                    VerificationStatus::NotYetIssued { issued_in },
+                _ => VerificationStatus::NotFound,
+            },
+            status => status.within(&credential.valid_duration, date),
+        }
+    }
+
+    fn scan_credential(
+        &self, credential: &Credential, until: Option<NaiveDate>, trace: &mut dyn FnMut(TraceEvent),
+    ) -> VerificationStatus {
+        let new_hash = credential.hash(false, self.format());
+        let revoking_hash = credential.hash(true, self.format());
+        trace(TraceEvent::Hashes { issuance: new_hash.clone(), revocation: revoking_hash.clone() });
+        let history = self.key_history();
+        let issuer = &credential.issuer.verifying;
+        let (mut issued_in, mut revoked_in) = (None, None);
+        for (height, block) in self.chain.iter().enumerate() {
+            let height = height as u64;
+            if let Some(until) = until
+                && block.timestamp.date_naive() > until
+            {
+                trace(TraceEvent::Skipped { height, until });
+                continue;
+            }
+            #[cfg(test)]
+            BLOCK_VISITS.with(|visits| visits.set(visits.get() + 1));
+            let found = block.find(&new_hash, &revoking_hash, issuer);
+            let outcomes = [
+                found.0.map(|verified| {
+                    let outcome = if !verified {
+                        EntryOutcome::SignatureFailed
+                    } else if history.retired_before(issuer, height) {
+                        EntryOutcome::RetiredKey
+                    } else {
+                        EntryOutcome::SignatureOk
+                    };
+                    (TraceEntry::Issuance, outcome)
+                }),
+                found.1.map(|verified| {
+                    let outcome = if !verified {
+                        EntryOutcome::SignatureFailed
+                    } else if history.succeeds(&block.signer.verifying, issuer, height) {
+                        EntryOutcome::SignatureOk
+                    } else {
+                        EntryOutcome::NotIssuerBlock
+                    };
+                    (TraceEntry::Revocation, outcome)
+                }),
+            ];
+            if outcomes.iter().all(Option::is_none) {
+                trace(TraceEvent::NoMatch { height });
+            }
+            for (entry, outcome) in outcomes.into_iter().flatten() {
+                trace(TraceEvent::Match { height, entry, outcome, key: fingerprint(issuer) });
+            }
+            // Only issuance entries can be forged: anyone can append a revocation, which then
+            // just doesn't count
+            match found.0 {
+                Some(false) => return VerificationStatus::SignatureInvalid { block: height },
+                // Keys only sign for their issuer until the block rotating them away
+                Some(true) if history.retired_before(issuer, height) =>
+                    return VerificationStatus::SignatureInvalid { block: height },
+                new => issued_in = issued_in.or(new.map(|_| height)),
+            }
+            if let Some(revocation) = block.revocation(&revoking_hash)
+                && history.revocation_counts(block, &revocation.signed, issuer)
+            {
+                revoked_in = revoked_in.or(Some(height));
+            }
+        }
+        let status = VerificationStatus::from_heights(issued_in, revoked_in, None);
+        self.check_deactivation(status, credential, until)
+    }
+
+    /// Whether any block issues the credential with the given hash
+    #[must_use]
+    pub fn has_issued(&self, credential: &Hash) -> bool { self.issued_in(credential).is_some() }
+
+    /// Height of the first block issuing the credential with the given hash
+    #[must_use]
+    pub fn issued_in(&self, credential: &Hash) -> Option<u64> {
+        self.chain.iter().position(|b| b.issues(credential)).map(|h| h as u64)
+    }
+
+    /// Checks many credentials at once, computing their hashes up front and scanning the chain a
+    /// single time
+    #[must_use]
+    pub fn check_credentials(&self, credentials: &[&Credential]) -> Vec<VerificationStatus> {
+        self.check_credentials_with_progress(credentials, &mut ())
+    }
+
+    /// Like [`Blockchain::check_credentials`], reporting each block scanned to `progress`
+    #[must_use]
+    pub fn check_credentials_with_progress(
+        &self, credentials: &[&Credential], progress: &mut dyn ProgressSink,
+    ) -> Vec<VerificationStatus> {
+        let sightings = self.scan_credentials(credentials, None, progress);
+        credentials
+            .iter()
+            .zip(sightings)
+            .map(|(credential, [whole, _])| {
+                self.check_deactivation(whole.status(), credential, None)
+            })
+            .collect()
+    }
+
+    /// Like [`Blockchain::check_credential_at`] for many credentials, scanning the chain a single
+    /// time
+    #[must_use]
+    pub fn check_credentials_at(
+        &self, credentials: &[&Credential], date: NaiveDate,
+    ) -> Vec<VerificationStatus> {
+        self.check_credentials_at_with_progress(credentials, date, &mut ())
+    }
+
+    /// Like [`Blockchain::check_credentials_at`], reporting each block scanned to `progress`
+    #[must_use]
+    pub fn check_credentials_at_with_progress(
+        &self, credentials: &[&Credential], date: NaiveDate, progress: &mut dyn ProgressSink,
+    ) -> Vec<VerificationStatus> {
+        let sightings = self.scan_credentials(credentials, Some(date), progress);
+        credentials
+            .iter()
+            .zip(sightings)
+            .map(|(&credential, [whole, dated])| {
+                let status = self.check_deactivation(dated.status(), credential, Some(date));
+                let whole = || self.check_deactivation(whole.status(), credential, None);
+                Self::status_at(status, whole, credential, date)
+            })
+            .collect()
+    }
+
+    /// Finds where each credential was issued, revoked and forged in a single pass over the
+    /// chain, over the whole of it and over the blocks timestamped on or before `until`
+    fn scan_credentials(
+        &self, credentials: &[&Credential], until: Option<NaiveDate>,
+        progress: &mut dyn ProgressSink,
+    ) -> Vec<[Sightings; 2]> {
+        let mut new_hashes: HashMap<Hash, Vec<usize>> = HashMap::new();
+        let mut revoking_hashes: HashMap<Hash, Vec<usize>> = HashMap::new();
+        for (i, credential) in credentials.iter().enumerate() {
+            new_hashes.entry(credential.hash(false, self.format())).or_default().push(i);
+            revoking_hashes.entry(credential.hash(true, self.format())).or_default().push(i);
+        }
+        let mut sightings = vec![[Sightings::default(); 2]; credentials.len()];
+        let history = self.key_history();
+        progress.start(self.chain.len() as u64);
+        for (height, block) in self.chain.iter().enumerate() {
+            let height = height as u64;
+            // Blocks after `until` only count towards the whole chain
+            let counted = if until.is_some_and(|until| block.timestamp.date_naive() > until) {
+                1
+            } else {
+                2
+            };
+            for signed in block.issued_credentials() {
+                for &i in new_hashes.get(&signed.credential).into_iter().flatten() {
+                    let issuer = &credentials[i].issuer.verifying;
+                    // Issuing with a key rotated away from counts as a forgery
+                    let forged = !signed.verify(issuer) || history.retired_before(issuer, height);
+                    for seen in &mut sightings[i][..counted] {
+                        if forged {
+                            seen.forged = seen.forged.or(Some(height));
+                        } else {
+                            seen.issued = seen.issued.or(Some(height));
+                        }
+                    }
+                }
+            }
+            for Revocation { signed, .. } in &block.revoked_credentials {
+                for &i in revoking_hashes.get(&signed.credential).into_iter().flatten() {
+                    if history.revocation_counts(block, signed, &credentials[i].issuer.verifying) {
+                        for seen in &mut sightings[i][..counted] {
+                            seen.revoked = seen.revoked.or(Some(height));
+                        }
+                    }
+                }
+            }
+            progress.tick();
+        }
+        progress.finish();
+        sightings
+    }
+
+    /// Like [`Blockchain::check_credentials`] for a single credential, failing closed if its
+    /// issuer's verifying key is none of those pinned for that issuer UUID
+    #[must_use]
+    pub fn check_credential_pinned(
+        &self, credential: &Credential, pinned: &HashMap<Uuid, Vec<VerifyingKey>>,
+    ) -> VerificationStatus {
+        if conflicts_with_pinned(credential, pinned) {
+            return VerificationStatus::IssuerKeyConflict;
+        }
+        self.check_credential(credential)
+    }
+
+    /// Like [`Blockchain::check_credential`], failing closed if the credential's issuer key isn't
+    /// in `store`, whether or not the chain holds the credential
+    #[must_use]
+    pub fn check_credential_trusted(
+        &self, credential: &Credential, store: &TrustStore,
+    ) -> VerificationStatus {
+        if !store.trusts(&credential.issuer.verifying) {
+            return VerificationStatus::UntrustedIssuer;
+        }
+        self.check_credential(credential)
+    }
+
+    /// Heights of the blocks signed with keys that aren't in `store`
+    #[must_use]
+    pub fn untrusted_blocks(&self, store: &TrustStore) -> Vec<u64> {
+        (0..)
+            .zip(&self.chain)
+            .filter(|(_, block)| !store.trusts(&block.signer.verifying))
+            .map(|(height, _)| height)
+            .collect()
+    }
+
+    #[must_use]
+    pub fn block(&self, height: u64) -> Option<&Block> {
+        usize::try_from(height).ok().and_then(|h| self.chain.get(h))
+    }
+
+    #[must_use]
+    pub fn blocks(&self) -> impl DoubleEndedIterator<Item = &Block> { self.chain.iter() }
+
+    /// The last block, none if no block was added yet
+    #[must_use]
+    pub fn tip(&self) -> Option<&Block> { self.chain.last() }
+
+    /// Digest of every block with the chain's totals
+    #[must_use]
+    pub fn summary(&self) -> ChainSummary { ChainSummary::new(&self.chain) }
+
+    #[must_use]
+    pub fn stats(&self) -> ChainStats { ChainStats::new(&self.chain) }
+
+    /// Finds the block with the given hash
+    ///
+    /// ```
+    /// use attributes_attestation::blockchain::{Block, Blockchain};
+    /// use attributes_attestation::credential::Issuer;
+    ///
+    /// let (issuer, signing) = Issuer::new("Registrar".to_string());
+    /// let mut chain = Blockchain::new();
+    /// chain.add_block(Block::new(issuer), &signing).unwrap();
+    /// let tip = chain.tip().unwrap();
+    /// assert_eq!(chain.block_by_hash(tip.hash()).unwrap().height(), 0);
+    /// for block in &chain {
+    ///     assert!(chain.block_by_hash(block.hash()).is_some());
+    /// }
+    /// ```
+    #[must_use]
+    pub fn block_by_hash(&self, hash: &Hash) -> Option<&Block> {
+        self.by_hash.get(hash).map(|&position| &self.chain[position])
+    }
+
+    /// Finds the only block whose hash starts with `prefix`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no block or more than one block matches.
+    pub fn block_by_hash_prefix(&self, prefix: &str) -> Result<&Block, PrefixError> {
+        resolve_prefix(prefix, self.chain.iter().map(|block| &block.hash))
+            .map(|position| &self.chain[position])
+    }
+
+    /// Heights of the blocks listing `hash` as a new, anchored or revoked credential, with the
+    /// list it's in
+    #[must_use]
+    pub fn blocks_containing(&self, hash: &Hash) -> Vec<(u64, ListKind)> {
+        let mut found = Vec::new();
+        for (height, block) in (0..).zip(&self.chain) {
+            let lists = [
+                (ListKind::New, block.new_credentials.iter().any(|s| &s.credential == hash)),
+                (
+                    ListKind::Anchored,
+                    block.anchored_credentials.iter().any(|s| &s.credential == hash),
+                ),
+                (ListKind::Revoked, block.revokes(hash)),
+            ];
+            found.extend(
+                lists.into_iter().filter(|(_, listed)| *listed).map(|(kind, _)| (height, kind)),
+            );
+        }
+        found
+    }
+
+    #[must_use]
+    pub fn block_ref(&self, height: u64) -> Option<BlockRef> {
+        self.block(height).map(|block| BlockRef {
+            height,
+            timestamp: block.timestamp,
+            signer: block.signer.name.clone(),
+            hash: block.hash.clone(),
+        })
+    }
+
+    /// Looks up the blocks the credential's status refers to
+    #[must_use]
+    pub fn report(
+        &self, credential: &Credential, status: VerificationStatus,
+    ) -> VerificationReport {
+        let revoking_hash = credential.hash(true, self.format());
+        let history = self.key_history();
+        let unauthorized_revocations = self
+            .chain
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| {
+                let issuer = &credential.issuer.verifying;
+                block.revocation(&revoking_hash).is_some_and(|revocation| {
+                    !history.revocation_counts(block, &revocation.signed, issuer)
+                })
+            })
+            .filter_map(|(h, _)| self.block_ref(h as u64))
+            .collect();
+        let revocation = status
+            .revoked_in()
+            .and_then(|h| self.block(h))
+            .and_then(|block| block.revocation(&revoking_hash))
+            .and_then(|revocation| revocation.details.clone());
+        let issuing_hash = credential.hash(false, self.format());
+        let consent = status
+            .issued_in()
+            .and_then(|h| self.block(h))
+            .and_then(|block| block.issued_credentials().find(|s| s.credential == issuing_hash))
+            .map_or(ConsentStatus::Absent, |signed| signed.consent_status(&credential.subject));
+        VerificationReport {
+            issued: status.issued_in().and_then(|h| self.block_ref(h)),
+            revoked: status.revoked_in().and_then(|h| self.block_ref(h)),
+            revocation,
+            unauthorized_revocations,
+            consent,
+            status,
+        }
+    }
+
+    /// Lists content that doesn't invalidate the chain but deserves a look
+    #[must_use]
+    pub fn warnings(&self) -> Vec<ChainWarning> {
+        let mut warnings = Vec::new();
+        let mut issued = HashMap::new();
+        let history = self.key_history();
+        for (height, block) in self.chain.iter().enumerate() {
+            let height = height as u64;
+            for signed in block.issued_credentials() {
+                if let Some(&first_issued_in) = issued.get(&signed.credential) {
+                    warnings.push(ChainWarning::DuplicateIssuance {
+                        height,
+                        first_issued_in,
+                        credential: signed.credential.clone(),
+                    });
+                } else {
+                    issued.insert(signed.credential.clone(), height);
+                }
+            }
+            for Revocation { signed, .. } in &block.revoked_credentials {
+                if !history.revocation_authorized(block, signed) {
+                    warnings.push(ChainWarning::UnauthorizedRevocation {
+                        height,
+                        credential: signed.credential.clone(),
+                    });
+                }
+            }
+        }
+        warnings
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize { self.chain.len() }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.chain.is_empty() }
+
+    /// Finds the first height at which the chains hold different blocks. Returns `None` if one
+    /// chain is a prefix of the other.
+    #[must_use]
+    pub fn diverges_from(&self, other: &Self) -> Option<DivergencePoint> {
+        let height = self.chain.iter().zip(&other.chain).position(|(a, b)| a.hash != b.hash)?;
+        Some(DivergencePoint {
+            last_common: height.checked_sub(1).map(|h| h as u64),
+            height: height as u64,
+            local: self.chain[height].hash.clone(),
+            other: other.chain[height].hash.clone(),
+        })
+    }
+
+    /// Verifies every block, the links between them and that their timestamps don't go back
+    ///
+    /// # Errors
+    /// Returns the index of the first invalid block along with the reason.
+    pub fn validate(&self) -> Result<(), BlockchainError> {
+        self.validate_with(&ChainPolicy::default())
+    }
+
+    /// Like [`Blockchain::validate`], using the given policy
+    ///
+    /// # Errors
+    /// See [`Blockchain::validate`].
+    pub fn validate_with(&self, policy: &ChainPolicy) -> Result<(), BlockchainError> {
+        self.validate_with_progress(policy, &mut ())
+    }
+
+    /// Like [`Blockchain::validate_with`], reporting each block validated to `progress`
+    ///
+    /// # Errors
+    /// See [`Blockchain::validate`].
+    pub fn validate_with_progress(
+        &self, policy: &ChainPolicy, progress: &mut dyn ProgressSink,
+    ) -> Result<(), BlockchainError> {
+        progress.start(self.chain.len() as u64);
+        let result = self.validate_blocks(policy, progress);
+        progress.finish();
+        result
+    }
+
+    fn validate_blocks(
+        &self, policy: &ChainPolicy, progress: &mut dyn ProgressSink,
+    ) -> Result<(), BlockchainError> {
+        let mut previous_hash = Hash::default();
+        if let Some(genesis) = &self.genesis {
+            genesis.verify().map_err(BlockchainError::InvalidGenesis)?;
+            previous_hash = genesis.hash.clone();
+        }
+        let format = self.format();
+        let mut authorities = self.genesis.as_ref().and_then(|g| g.metadata.authorities.clone());
+        let mut history = KeyHistory::new(self.genesis.as_ref());
+        for (index, block) in self.chain.iter().enumerate() {
+            if format.version >= HashVersion::V2 && block.height != index as u64 {
+                return Err(BlockchainError::HeightMismatch { index, height: block.height });
+            }
+            let mut hashes = [&block.hash, &block.previous_hash]
+                .into_iter()
+                .chain(&block.merkle_root)
+                .chain(block.issued_credentials().map(|c| &c.credential))
+                .chain(block.revoked_credentials.iter().map(|r| &r.signed.credential));
+            if hashes.any(|hash| hash.algorithm != format.algorithm) {
+                return Err(BlockchainError::MixedAlgorithms { height: block.height });
+            }
+            block
+                .verify(&previous_hash, format)
+                .map_err(|error| BlockchainError::InvalidBlock { index, error })?;
+            if let Some(previous) = index.checked_sub(1).map(|i| &self.chain[i]) {
+                policy.check_timestamps(previous, block)?;
+            }
+            Self::check_authority(&mut authorities, block)?;
+            if history.retired_before(&block.signer.verifying, block.height) {
+                return Err(BlockchainError::RetiredSignerKey { height: block.height });
+            }
+            history.record(block, format)?;
+            Self::check_deactivations(authorities.is_some(), &history, block)?;
+            Self::check_accreditations(block, format)?;
+            previous_hash = block.hash.clone();
+            progress.tick();
+        }
+        Ok(())
+    }
+}
+
+impl Display for Blockchain {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(&self).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::*;
+    use crate::credential::{Attribute, Subject, ValidDuration};
+    use crate::trust::TrustedIssuer;
+
+    fn sample_credential() -> (Credential, SigningKey) {
+        let (issuer, signing) = Issuer::new("Test Issuer".to_string());
+        let subject = Subject::new("Alice".to_string(), "Doe".to_string());
+        let attr =
+            Attribute::new("Driving Licence".to_string(), "Driving Licence Category B".to_string());
+        let valid = ValidDuration::new(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            Some(NaiveDate::from_ymd_opt(2030, 1, 1).unwrap()),
+        )
+        .unwrap();
+        let credential = Credential::new(vec![attr], issuer, subject, valid);
+        (credential, signing)
+    }
+
+    #[test]
+    fn test_block_add_credential_and_finalize() {
+        let (credential, signing) = sample_credential();
+        let signed = credential.sign(&signing, false, HashVersion::V1).unwrap();
+        let issuer = credential.issuer.clone();
+        let mut block = Block::new(issuer);
+        block.add_credential(signed, false).unwrap();
+        block.finalize(Hash::default(), &signing, HashVersion::LATEST).unwrap();
+        assert_ne!(block.hash.bytes, [0u8; 64]);
+        assert_ne!(block.signature, Sig::default());
+    }
+
+    #[test]
+    fn test_block_add_revoked_credential() {
+        let (credential, signing) = sample_credential();
+        let signed = credential.sign(&signing, true, HashVersion::V1).unwrap();
+        let issuer = credential.issuer.clone();
+        let mut block = Block::new(issuer);
+        block.add_credential(signed.clone(), true).unwrap();
+        block.finalize(Hash::default(), &signing, HashVersion::LATEST).unwrap();
+        assert!(block.revokes(&signed.credential));
+    }
+
+    #[test]
+    fn test_revocation_details() {
+        let (credential, signing) = sample_credential();
+        let issue = |revocation: Option<RevocationDetails>| {
+            let mut chain = Blockchain::new();
+            let mut block = Block::new(credential.issuer.clone());
+            block
+                .add_credential(credential.sign(&signing, false, chain.format()).unwrap(), false)
+                .unwrap();
+            chain.add_block(block, &signing).unwrap();
+            let mut block = Block::new(credential.issuer.clone());
+            let signed = credential.sign(&signing, true, chain.format()).unwrap();
+            block.add_revocation(Revocation::new(signed, revocation)).unwrap();
+            chain.add_block(block, &signing).unwrap();
+            chain
+        };
+        let details =
+            RevocationDetails::new(RevocationReason::Compromised, Some("key leak".to_string()));
+        let chain = issue(Some(details.clone()));
+        assert_eq!(chain.validate(), Ok(()));
+        let report = chain.report(&credential, chain.check_credential(&credential));
+        assert_eq!(report.revocation, Some(details.clone()));
+        assert!(report.revocation.unwrap().to_string().starts_with("compromised (key leak), "));
+
+        // Reasons and times are part of the block hash
+        let mut tampered = chain.clone();
+        tampered.chain[1].revoked_credentials[0].details.as_mut().unwrap().reason =
+            RevocationReason::Expired;
+        assert!(matches!(tampered.validate(), Err(BlockchainError::InvalidBlock { .. })));
+
+        let json = serde_json::to_value(&chain.chain[1].revoked_credentials[0]).unwrap();
+        assert_eq!(json["reason"], "compromised");
+        assert_eq!(json["note"], "key leak");
+        let other = serde_json::to_value(RevocationReason::Other("court order".into())).unwrap();
+        assert_eq!(other, serde_json::json!({ "other": "court order" }));
+
+        // Revocations without details are stored as the bare signed hash older versions wrote
+        let bare = issue(None);
+        let json = serde_json::to_value(&bare.chain[1].revoked_credentials[0]).unwrap();
+        assert_eq!(json.as_object().unwrap().keys().collect::<Vec<_>>(), [
+            "credential", "signature"
+        ]);
+        let restored: Revocation = serde_json::from_value(json).unwrap();
+        assert!(restored.details.is_none());
+        assert_eq!(bare.report(&credential, bare.check_credential(&credential)).revocation, None);
+    }
+
+    #[test]
+    fn test_blockchain_add_block_and_check_credential() {
+        let (credential, signing) = sample_credential();
+        let signed = credential.sign(&signing, false, HashVersion::V1).unwrap();
+        let issuer = credential.issuer.clone();
+
+        let mut block = Block::new(issuer);
+        block.add_credential(signed, false).unwrap();
+
+        let mut chain = Blockchain::new();
+        chain.add_block(block, &signing).unwrap();
+
+        assert!(chain.check_credential(&credential).is_valid());
+    }
+
+    #[test]
+    fn test_blockchain_add_block_rejects_foreign_key() {
+        let (credential, signing) = sample_credential();
+        let (_, other_signing) = Issuer::new("Other Issuer".to_string());
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+
+        let mut chain = Blockchain::new();
+        assert_eq!(chain.add_block(block, &other_signing), Err(BlockchainError::SignerKeyMismatch));
+        assert!(chain.chain.is_empty());
+    }
+
+    #[test]
+    fn test_blockchain_add_block_rejects_finalized_block() {
+        let (credential, signing) = sample_credential();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        block.finalize(Hash::default(), &signing, HashVersion::LATEST).unwrap();
+
+        let mut chain = Blockchain::new();
+        assert_eq!(chain.add_block(block, &signing), Err(BlockchainError::AlreadyFinalized));
+        assert!(chain.chain.is_empty());
+    }
+
+    #[test]
+    fn test_blockchain_revoked_credential_is_reported() {
+        let (credential, signing) = sample_credential();
+        let issuer = credential.issuer.clone();
+        let signed = credential.sign(&signing, false, HashVersion::V1).unwrap();
+        let revoked = credential.sign(&signing, true, HashVersion::V1).unwrap();
+
+        let mut block = Block::new(issuer);
+        block.add_credential(signed, false).unwrap();
+        block.add_credential(revoked, true).unwrap();
+
+        let mut chain = Blockchain::new();
+        chain.add_block(block, &signing).unwrap();
+
+        assert_eq!(chain.check_credential(&credential), VerificationStatus::Revoked {
+            issued_in: Some(0),
+            revoked_in: 0
+        });
+        let revocations = chain.revocations();
+        assert_eq!(revocations.len(), 1);
+        assert_eq!(revocations[0].1.signed.credential, credential.hash(true, HashVersion::V1));
+    }
+
+    #[test]
+    fn test_blockchain_missing_credential_is_not_found() {
+        let (credential, signing) = sample_credential();
+        let mut chain = Blockchain::new();
+        chain.add_block(Block::new(credential.issuer.clone()), &signing).unwrap();
+
+        assert_eq!(chain.check_credential(&credential), VerificationStatus::NotFound);
+        assert!(!chain.has_issued(&credential.hash(false, HashVersion::V1)));
+    }
+
+    #[test]
+    fn test_blockchain_forged_signature_is_reported() {
+        let (credential, signing) = sample_credential();
+        let (forger, forger_signing) = Issuer::new("Forger".to_string());
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+        let mut block = Block::new(forger);
+        block
+            .add_credential(credential.sign(&forger_signing, true, HashVersion::V1).unwrap(), true)
+            .unwrap();
+        chain.add_block(block, &forger_signing).unwrap();
+
+        // Anyone can append a revocation, which only counts when signed by the issuer
+        let valid = VerificationStatus::Valid { issued_in: 0 };
+        assert_eq!(chain.check_credential(&credential), valid);
+        assert_eq!(chain.check_credentials(&[&credential]), std::slice::from_ref(&valid));
+        assert_eq!(chain.report(&credential, valid).unauthorized_revocations.len(), 1);
+
+        // An issuance entry that doesn't verify makes the credential invalid
+        let mut block = Block::new(credential.issuer.clone());
+        let issuance = credential.sign(&forger_signing, false, HashVersion::V1).unwrap();
+        block.add_credential(issuance, false).unwrap();
+        chain.add_block(block, &signing).unwrap();
+        let status = VerificationStatus::SignatureInvalid { block: 2 };
+        assert_eq!(chain.check_credential(&credential), status);
+        assert_eq!(chain.check_credentials(&[&credential]), [status]);
+    }
+
+    #[test]
+    fn test_credential_trace() {
+        let (credential, signing) = sample_credential();
+        let (other, other_signing) = Issuer::new("Other".to_string());
+        let (forger, forger_signing) = Issuer::new("Forger".to_string());
+        let revoking = credential.sign(&signing, true, HashVersion::V1).unwrap();
+        let mut chain = Blockchain::new();
+        chain.add_block(Block::new(other.clone()), &other_signing).unwrap();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+        let mut block = Block::new(other);
+        block.add_credential(revoking.clone(), true).unwrap();
+        chain.add_block(block, &other_signing).unwrap();
+        let mut block = Block::new(credential.issuer.clone());
+        block.add_credential(revoking, true).unwrap();
+        chain.add_block(block, &signing).unwrap();
+
+        let key = fingerprint(&signing.verifying_key());
+        let matched =
+            |height, entry, outcome| TraceEvent::Match { height, entry, outcome, key: key.clone() };
+        let mut trace = Vec::new();
+        let status = chain.check_credential_traced(&credential, &mut |event| trace.push(event));
+        assert_eq!(status, chain.check_credential(&credential));
+        assert_eq!(status, VerificationStatus::Revoked { issued_in: Some(1), revoked_in: 3 });
+        assert_eq!(trace, [
+            TraceEvent::Hashes {
+                issuance: credential.hash(false, chain.format()),
+                revocation: credential.hash(true, chain.format()),
+            },
+            TraceEvent::NoMatch { height: 0 },
+            matched(1, TraceEntry::Issuance, EntryOutcome::SignatureOk),
+            matched(2, TraceEntry::Revocation, EntryOutcome::NotIssuerBlock),
+            matched(3, TraceEntry::Revocation, EntryOutcome::SignatureOk),
+        ]);
+        assert_eq!(trace[1].to_string(), "block #0: no match");
+        assert_eq!(trace[2].to_string(), "block #1: issuance match, signature OK");
+        assert!(trace[0].to_string().starts_with("computed issuance hash "));
+
+        // A forged revocation is traced but doesn't count
+        let mut block = Block::new(forger);
+        block
+            .add_credential(credential.sign(&forger_signing, true, HashVersion::V1).unwrap(), true)
+            .unwrap();
+        chain.add_block(block, &forger_signing).unwrap();
+        let mut trace = Vec::new();
+        let status = chain.check_credential_traced(&credential, &mut |event| trace.push(event));
+        assert_eq!(status, VerificationStatus::Revoked { issued_in: Some(1), revoked_in: 3 });
+        let failed = matched(4, TraceEntry::Revocation, EntryOutcome::SignatureFailed);
+        assert_eq!(trace.last(), Some(&failed));
+        assert_eq!(
+            failed.to_string(),
+            format!("block #4: revocation match, signature FAILED (key fingerprint {key})")
+        );
+
+        // Blocks dated after the day checked on are skipped
+        let today = Utc::now().date_naive();
+        for block in &mut chain.chain[3..] {
+            block.timestamp += TimeDelta::days(2);
+        }
+        let mut trace = Vec::new();
+        let status =
+            chain.check_credential_at_traced(&credential, today, &mut |event| trace.push(event));
+        assert_eq!(status.issued_in(), Some(1));
+        assert_eq!(&trace[4..], [
+            TraceEvent::Skipped { height: 3, until: today },
+            TraceEvent::Skipped { height: 4, until: today },
+        ]);
+    }
+
+    #[test]
+    fn test_block_verify() {
+        let (credential, signing) = sample_credential();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        block.finalize(Hash::default(), &signing, HashVersion::LATEST).unwrap();
+        assert_eq!(block.compute_hash(HashVersion::LATEST), *block.hash());
+        assert_eq!(block.verify(&Hash::default(), HashVersion::LATEST), Ok(()));
+        assert_eq!(
+            block.verify(&Hash::from([1; 64]), HashVersion::LATEST),
+            Err(BlockError::PreviousHashMismatch)
+        );
+    }
+
+    #[test]
+    fn test_block_merkle_root_and_proofs() {
+        let (credential, signing) = sample_credential();
+        let (other, _) = sample_credential();
+        let mut block = Block::new(credential.issuer.clone());
+        let issuance = credential.sign(&signing, false, HashVersion::V3).unwrap();
+        let revocation = other.sign(&signing, true, HashVersion::V3).unwrap();
+        block.add_credential(issuance.clone(), false).unwrap();
+        block.add_credential(revocation.clone(), true).unwrap();
+        block.finalize(Hash::default(), &signing, HashVersion::V2).unwrap();
+        assert_eq!(block.merkle_root(), None);
+        assert_eq!(block.merkle_proof(&issuance.credential), None);
+
+        block.finalize(Hash::default(), &signing, HashVersion::V3).unwrap();
+        let root = block.merkle_root().unwrap().clone();
+        assert_eq!(block.header(Algorithm::Sha512).hash(), *block.hash());
+        for signed in [&issuance, &revocation] {
+            let leaf = block.merkle_leaf(&signed.credential).unwrap();
+            assert!(block.merkle_proof(&signed.credential).unwrap().verify(&root, &leaf));
+        }
+        assert_eq!(block.merkle_proof(&credential.hash(true, HashVersion::V3)), None);
+
+        // Revocation details are committed to by the revocation's leaf
+        let leaf = block.merkle_leaf(&revocation.credential).unwrap();
+        let details = RevocationDetails::new(RevocationReason::Superseded, None);
+        block.revoked_credentials[0].details = Some(details);
+        assert_ne!(block.merkle_leaf(&revocation.credential).unwrap(), leaf);
+        assert_eq!(block.verify(&Hash::default(), HashVersion::V3), Err(BlockError::HashMismatch));
+        block.revoked_credentials[0].details = None;
+        block.merkle_root = Some(Hash::from([1; 64]));
+        assert_eq!(
+            block.verify(&Hash::default(), HashVersion::V3),
+            Err(BlockError::MerkleRootMismatch)
+        );
+    }
+
+    #[test]
+    fn test_block_verify_detects_tampering() {
+        let (credential, signing) = sample_credential();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        block.finalize(Hash::default(), &signing, HashVersion::LATEST).unwrap();
+
+        block
+            .revoked_credentials
+            .push(Revocation::new(credential.sign(&signing, true, HashVersion::V1).unwrap(), None));
+        assert_eq!(
+            block.verify(&Hash::default(), HashVersion::LATEST),
+            Err(BlockError::HashMismatch)
+        );
+
+        block.revoked_credentials.clear();
+        block.signature = Sig([1; 64]);
+        assert_eq!(
+            block.verify(&Hash::default(), HashVersion::LATEST),
+            Err(BlockError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_blockchain_validate() {
+        let (credential, signing) = sample_credential();
+        let mut chain = Blockchain::new();
+        for _ in 0..3 {
+            let mut block = Block::new(credential.issuer.clone());
+            block
+                .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+                .unwrap();
+            chain.add_block(block, &signing).unwrap();
+        }
+        assert_eq!(chain.validate(), Ok(()));
+
+        chain.chain[1].timestamp = Utc::now();
+        assert_eq!(
+            chain.validate(),
+            Err(BlockchainError::InvalidBlock { index: 1, error: BlockError::HashMismatch })
+        );
+    }
+
+    #[test]
+    fn test_genesis_links_first_block() {
+        let (credential, signing) = sample_credential();
+        let genesis = GenesisBlock::new(
+            "Test chain".to_string(),
+            Some((credential.issuer.clone(), &signing)),
+            None,
+        )
+        .unwrap();
+        let genesis_hash = genesis.hash().clone();
+        let mut chain = Blockchain::with_genesis(genesis);
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, chain.format()).unwrap(), false)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+
+        assert_eq!(chain.chain[0].previous_hash, genesis_hash);
+        assert_eq!(chain.validate(), Ok(()));
+        assert!(chain.check_credential(&credential).is_valid());
+    }
+
+    #[test]
+    fn test_blockchain_with_other_algorithms() {
+        let (credential, signing) = sample_credential();
+        for algorithm in [Algorithm::Sha3_512, Algorithm::Blake3] {
+            let genesis = GenesisBlock::with_algorithm(
+                "Test chain".to_string(),
+                Some((credential.issuer.clone(), &signing)),
+                None,
+                algorithm,
+            )
+            .unwrap();
+            let mut chain = Blockchain::with_genesis(genesis);
+            assert_eq!(chain.genesis().unwrap().hash().algorithm, algorithm);
+            let mut block = Block::new(credential.issuer.clone());
+            block
+                .add_credential(credential.sign(&signing, false, chain.format()).unwrap(), false)
+                .unwrap();
+            chain.add_block(block, &signing).unwrap();
+
+            assert_eq!(chain.tip_hash().algorithm, algorithm);
+            assert_eq!(chain.validate(), Ok(()));
+            assert!(chain.check_credential(&credential).is_valid());
+            let json = serde_json::to_string(&chain).unwrap();
+            let restored: Blockchain = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored.validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_blockchain_rejects_mixed_algorithms() {
+        let (credential, signing) = sample_credential();
+        let mut chain = Blockchain::with_genesis(
+            GenesisBlock::with_algorithm(
+                "Test chain".to_string(),
+                Some((credential.issuer.clone(), &signing)),
+                None,
+                Algorithm::Blake3,
+            )
+            .unwrap(),
+        );
+        let mut block = Block::new(credential.issuer.clone());
+        // Signed under SHA-512 rather than the chain's BLAKE3
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::LATEST).unwrap(), false)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+
+        assert_eq!(chain.validate(), Err(BlockchainError::MixedAlgorithms { height: 0 }));
+        assert_eq!(chain.check_credential(&credential), VerificationStatus::NotFound);
+    }
+
+    #[test]
+    fn test_genesis_without_founder_is_unsigned() {
+        let genesis = GenesisBlock::new("Unsigned".to_string(), None, None).unwrap();
+        assert!(genesis.signature.is_none());
+        assert_eq!(genesis.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_genesis_tampering_is_detected() {
+        let (credential, signing) = sample_credential();
+        let mut chain = Blockchain::with_genesis(
+            GenesisBlock::new(
+                "Test chain".to_string(),
+                Some((credential.issuer.clone(), &signing)),
+                None,
+            )
+            .unwrap(),
+        );
+        chain.genesis.as_mut().unwrap().metadata.name = "Other chain".to_string();
+        assert_eq!(
+            chain.validate(),
+            Err(BlockchainError::InvalidGenesis(BlockError::HashMismatch))
+        );
+    }
+
+    #[test]
+    fn test_blockchain_rejects_other_chain() {
+        let local =
+            Blockchain::with_genesis(GenesisBlock::new("Local".to_string(), None, None).unwrap());
+        let other =
+            Blockchain::with_genesis(GenesisBlock::new("Local".to_string(), None, None).unwrap());
+        let copy: Blockchain = serde_json::from_str(&local.to_string()).unwrap();
+        assert_eq!(local.ensure_same_chain(&copy), Ok(()));
+        assert_eq!(
+            local.ensure_same_chain(&other),
+            Err(BlockchainError::ChainIdMismatch { local: local.id(), other: other.id() })
+        );
+    }
+
+    #[test]
+    fn test_blockchain_heights() {
+        let (credential, signing) = sample_credential();
+        let mut chain =
+            Blockchain::with_genesis(GenesisBlock::new("Test".to_string(), None, None).unwrap());
+        for _ in 0..3 {
+            chain.add_block(Block::new(credential.issuer.clone()), &signing).unwrap();
+        }
+        let heights: Vec<_> = chain.chain.iter().map(Block::height).collect();
+        assert_eq!(heights, [0, 1, 2]);
+        assert_eq!(chain.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_blockchain_swapped_blocks_fail_height_check() {
+        let (credential, signing) = sample_credential();
+        let mut chain =
+            Blockchain::with_genesis(GenesisBlock::new("Test".to_string(), None, None).unwrap());
+        for _ in 0..3 {
+            chain.add_block(Block::new(credential.issuer.clone()), &signing).unwrap();
+        }
+        let mut json: serde_json::Value = serde_json::from_str(&chain.to_string()).unwrap();
+        json["chain"].as_array_mut().unwrap().swap(1, 2);
+        let swapped: Blockchain = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            swapped.validate(),
+            Err(BlockchainError::HeightMismatch { index: 1, height: 2 })
+        );
+    }
+
+    #[test]
+    fn test_v1_fixture_chain_keeps_verifying() {
+        let chain: Blockchain =
+            serde_json::from_str(include_str!("../tests/fixtures/v1/blockchain.json")).unwrap();
+        let credentials: Vec<(Credential, SignedCredential, SignedCredential)> =
+            serde_json::from_str(include_str!("../tests/fixtures/v1/credentials.json")).unwrap();
+
+        assert_eq!(chain.version(), HashVersion::V1);
+        assert_eq!(chain.validate(), Ok(()));
+        assert_eq!(chain.check_credential(&credentials[0].0), VerificationStatus::Valid {
+            issued_in: 0
+        });
+        assert_eq!(chain.check_credential(&credentials[1].0), VerificationStatus::Revoked {
+            issued_in: Some(0),
+            revoked_in: 1
+        });
+        assert_ne!(
+            credentials[0].0.hash(false, HashVersion::V1),
+            credentials[0].0.hash(false, HashVersion::V2)
+        );
+    }
+
+    #[test]
+    fn test_v1_fixture_signatures_round_trip() {
+        let fixture = include_str!("../tests/fixtures/v1/credentials.json");
+        let credentials: Vec<(Credential, SignedCredential, SignedCredential)> =
+            serde_json::from_str(fixture).unwrap();
+        let mut original: serde_json::Value = serde_json::from_str(fixture).unwrap();
+        // The single attribute of the time is written back as a list of one
+        for credential in original.as_array_mut().unwrap() {
+            let credential = credential[0].as_object_mut().unwrap();
+            let attribute = credential.remove("attribute").unwrap();
+            credential.insert("attributes".to_string(), serde_json::json!([attribute]));
+        }
+        assert_eq!(serde_json::to_value(&credentials).unwrap(), original);
+
+        let fixture = include_str!("../tests/fixtures/v1/blockchain.json");
+        let chain: Blockchain = serde_json::from_str(fixture).unwrap();
+        let original: serde_json::Value = serde_json::from_str(fixture).unwrap();
+        let written = serde_json::to_value(&chain).unwrap();
+        for (block, original) in
+            written["chain"].as_array().unwrap().iter().zip(original["chain"].as_array().unwrap())
+        {
+            for field in ["new_credentials", "revoked_credentials", "hash", "signature"] {
+                assert_eq!(block[field], original[field]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_block_hash_vectors() {
+        let issuer = Issuer {
+            uuid: Uuid::from_u128(2),
+            name: "Registry".to_string(),
+            verifying: SigningKey::from_bytes(&[7; 32]).verifying_key(),
+            previous_keys: Vec::new(),
+            url: None,
+            email: None,
+            country: None,
+            former_names: Vec::new(),
+            deleted: false,
+        };
+        let mut block = Block::new(issuer);
+        block.timestamp = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        block
+            .add_credential(SignedCredential::new(Hash::from([1; 64]), Sig([2; 64])), false)
+            .unwrap();
+        block
+            .add_credential(SignedCredential::new(Hash::from([3; 64]), Sig([4; 64])), true)
+            .unwrap();
+        block.previous_hash = Hash::from([5; 64]);
+        let hex = |version| hex::encode(block.compute_hash(version).bytes);
+        assert_eq!(
+            hex(HashVersion::V1),
+            "8a8af95999c69a8c6c2e5eb04b1a2430971f2bc4e2ec5762b3c36c35ad7ecba676e18572d450df1676540a22f32358a77d3ca6ea2ff7bb9b5c02c4267324e482"
+        );
+        assert_eq!(
+            hex(HashVersion::V2),
+            "8d941691f7ede0442d5bff07548e39e8e3cd765180d6a9a6a9cb1adceb7a6ca4332c8c4b1bdf3b974217700c23f3c609b9a1e5e4c566564b8d6cc49eb359c652"
+        );
+    }
+
+    #[test]
+    fn test_block_height_is_hashed_in_v2_only() {
+        let (credential, signing) = sample_credential();
+        let mut block = Block::new(credential.issuer.clone());
+        block.finalize(Hash::default(), &signing, HashVersion::V2).unwrap();
+        let (v1, v2) = (block.compute_hash(HashVersion::V1), block.compute_hash(HashVersion::V2));
+        block.height = 5;
+        assert_eq!(block.compute_hash(HashVersion::V1), v1);
+        assert_ne!(block.compute_hash(HashVersion::V2), v2);
+    }
+
+    #[test]
+    fn test_blockchain_rejects_block_older_than_tip() {
+        let (credential, signing) = sample_credential();
+        let mut chain = Blockchain::new();
+        chain.add_block(Block::new(credential.issuer.clone()), &signing).unwrap();
+        chain.chain[0].timestamp = Utc::now() + TimeDelta::hours(1);
+
+        assert_eq!(
+            chain.add_block(Block::new(credential.issuer.clone()), &signing),
+            Err(BlockchainError::NonMonotonicTimestamp { height: 1, previous_height: 0 })
+        );
+        let policy = ChainPolicy { clock_skew: TimeDelta::hours(2) };
+        assert_eq!(
+            chain.add_block_with(Block::new(credential.issuer.clone()), &signing, &policy),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_blockchain_validate_reports_backdated_block() {
+        let (credential, signing) = sample_credential();
+        let mut chain =
+            Blockchain::with_genesis(GenesisBlock::new("Test".to_string(), None, None).unwrap());
+        for _ in 0..3 {
+            chain.add_block(Block::new(credential.issuer.clone()), &signing).unwrap();
+        }
+        let version = chain.version();
+        let previous = chain.chain[1].timestamp;
+        let block = &mut chain.chain[2];
+        block.timestamp = previous - TimeDelta::minutes(5);
+        block.hash = block.compute_hash(version);
+        block.signature = Signer::sign(&signing, &block.hash.bytes).into();
+
+        assert_eq!(
+            chain.validate(),
+            Err(BlockchainError::NonMonotonicTimestamp { height: 2, previous_height: 1 })
+        );
+        let policy = ChainPolicy { clock_skew: TimeDelta::minutes(10) };
+        assert_eq!(chain.validate_with(&policy), Ok(()));
+    }
+
+    fn authority_chain() -> (Blockchain, Issuer, SigningKey) {
+        let (authority, signing) = Issuer::new("Registrar".to_string());
+        let genesis = GenesisBlock::new(
+            "Consortium".to_string(),
+            Some((authority.clone(), &signing)),
+            Some(vec![authority.clone()]),
+        )
+        .unwrap();
+        (Blockchain::with_genesis(genesis), authority, signing)
+    }
+
+    #[test]
+    fn test_blockchain_rejects_unauthorized_signer() {
+        let (mut chain, authority, signing) = authority_chain();
+        chain.add_block(Block::new(authority.clone()), &signing).unwrap();
+
+        let (outsider, outsider_signing) = Issuer::new("Outsider".to_string());
+        assert_eq!(
+            chain.add_block(Block::new(outsider.clone()), &outsider_signing),
+            Err(BlockchainError::UnauthorizedSigner { height: 1 })
+        );
+        assert_eq!(chain.chain.len(), 1);
+
+        // A chain edited to hold the outsider's block has no authority set to follow
+        let mut edited = Blockchain::new();
+        edited.add_block(Block::new(outsider), &outsider_signing).unwrap();
+        chain.chain.push(edited.chain.remove(0));
+        chain.chain[1].height = 1;
+        let error = BlockchainError::UnauthorizedSigner { height: 1 };
+        assert_eq!(chain.authorities().err(), Some(error.clone()));
+        assert_eq!(chain.add_block(Block::new(authority), &signing), Err(error));
+    }
+
+    #[test]
+    fn test_blockchain_authority_changes() {
+        let (mut chain, authority, signing) = authority_chain();
+        let (second, second_signing) = Issuer::new("Second Registrar".to_string());
+
+        let mut block = Block::new(authority.clone());
+        block.add_authority_change(AuthorityChange::Add(second.clone()));
+        chain.add_block(block, &signing).unwrap();
+        assert_eq!(chain.authorities().unwrap().unwrap().len(), 2);
+
+        let mut block = Block::new(second.clone());
+        block.add_authority_change(AuthorityChange::Remove(authority.clone()));
+        chain.add_block(block, &second_signing).unwrap();
+        assert_eq!(chain.authorities().unwrap().unwrap()[0].uuid, second.uuid);
+
+        assert_eq!(
+            chain.add_block(Block::new(authority), &signing),
+            Err(BlockchainError::UnauthorizedSigner { height: 2 })
+        );
+
+        let mut block = Block::new(second.clone());
+        block.add_authority_change(AuthorityChange::Remove(second));
+        assert_eq!(
+            chain.add_block(block, &second_signing),
+            Err(BlockchainError::AuthorityChangeRejected { height: 2 })
+        );
+        assert_eq!(chain.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_blockchain_validate_reports_unauthorized_signer_in_imported_chain() {
+        let (mut chain, authority, signing) = authority_chain();
+        chain.add_block(Block::new(authority), &signing).unwrap();
+
+        // Forge a block from an outsider, bypassing the checks of add_block
+        let (outsider, outsider_signing) = Issuer::new("Outsider".to_string());
+        let mut block = Block::new(outsider);
+        block.height = 1;
+        block.finalize(chain.tip_hash(), &outsider_signing, chain.version()).unwrap();
+        chain.chain.push(block);
+
+        let imported: Blockchain = serde_json::from_str(&chain.to_string()).unwrap();
+        assert_eq!(imported.validate(), Err(BlockchainError::UnauthorizedSigner { height: 1 }));
+    }
+
+    #[test]
+    fn test_issuer_deactivation_cutoff() {
+        let (early, signing) = sample_credential();
+        let late = Credential::new(
+            early.attributes.clone(),
+            early.issuer.clone(),
+            Subject::new("Bob".to_string(), "Doe".to_string()),
+            early.valid_duration.clone(),
+        );
+        let issuer = early.issuer.clone();
+        let day = |d| NaiveDate::from_ymd_opt(2025, 6, d).unwrap();
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(issuer.clone());
+        block.add_credential(early.sign(&signing, false, chain.format()).unwrap(), false).unwrap();
+        chain.add_block(block, &signing).unwrap();
+        let mut block = Block::new(issuer.clone());
+        block.add_deactivation(IssuerDeactivation::new(&issuer, day(30)));
+        chain.add_block(block, &signing).unwrap();
+        let mut block = Block::new(issuer.clone());
+        block.add_credential(late.sign(&signing, false, chain.format()).unwrap(), false).unwrap();
+        chain.add_block(block, &signing).unwrap();
+        assert_eq!(chain.validate(), Ok(()));
+        chain.chain[0].timestamp = day(29).and_hms_opt(12, 0, 0).unwrap().and_utc();
+        chain.chain[2].timestamp = day(30).and_hms_opt(12, 0, 0).unwrap().and_utc();
+
+        // Credentials issued on the effective date itself are still trusted
+        assert_eq!(chain.check_credential(&late), VerificationStatus::Valid { issued_in: 2 });
+        chain.chain[2].timestamp =
+            (day(30) + TimeDelta::days(1)).and_hms_opt(0, 0, 0).unwrap().and_utc();
+        assert_eq!(chain.check_credential(&early), VerificationStatus::Valid { issued_in: 0 });
+        let deactivated =
+            VerificationStatus::IssuerDeactivated { issued_in: 2, effective: day(30) };
+        assert_eq!(chain.check_credential(&late), deactivated);
+        assert_eq!(chain.check_credentials(&[&early, &late]), [
+            VerificationStatus::Valid { issued_in: 0 },
+            deactivated
+        ]);
+        assert_eq!(chain.deactivation(&issuer, None).map(|(height, _)| height), Some(1));
+        let (other, _) = Issuer::new("Other".to_string());
+        assert!(chain.deactivation(&other, None).is_none());
+    }
+
+    #[test]
+    fn test_issuer_deactivation_needs_issuer_or_authority() {
+        let (credential, _) = sample_credential();
+        let (outsider, outsider_signing) = Issuer::new("Outsider".to_string());
+        let effective = NaiveDate::from_ymd_opt(2025, 6, 30).unwrap();
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(outsider.clone());
+        block.add_deactivation(IssuerDeactivation::new(&credential.issuer, effective));
+        assert_eq!(
+            chain.add_block(block, &outsider_signing),
+            Err(BlockchainError::UnauthorizedDeactivation { height: 0 })
+        );
+        // An impostor reusing the issuer's UUID with its own key is no better
+        let impostor = Issuer { verifying: outsider.verifying, ..credential.issuer.clone() };
+        let mut block = Block::new(impostor.clone());
+        block.add_deactivation(IssuerDeactivation::new(&credential.issuer, effective));
+        assert_eq!(
+            chain.add_block(block, &outsider_signing),
+            Err(BlockchainError::UnauthorizedDeactivation { height: 0 })
+        );
+
+        let (mut chain, authority, authority_signing) = authority_chain();
+        let mut block = Block::new(authority);
+        block.add_deactivation(IssuerDeactivation::new(&credential.issuer, effective));
+        chain.add_block(block, &authority_signing).unwrap();
+        assert_eq!(chain.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_transitive_accreditation() {
+        let issuer = |name: &str| Issuer::new(name.to_string());
+        let (root, root_signing) = issuer("Root");
+        let (regional, regional_signing) = issuer("Regional");
+        let (local, local_signing) = issuer("Local");
+        let (lapsed, lapsed_signing) = issuer("Lapsed");
+        let (late, late_signing) = issuer("Late");
+        let (rogue, rogue_signing) = issuer("Rogue");
+        let (accomplice, accomplice_signing) = issuer("Accomplice");
+        let date = |year| NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+        let mut chain = Blockchain::new();
+        let accredit = |accreditor: &Issuer, signer: &SigningKey, issuer: &Issuer, to| {
+            let duration = ValidDuration::new(date(2020), to).unwrap();
+            Accreditation::new(accreditor, signer, issuer, duration, HashVersion::V1).unwrap()
+        };
+
+        let mut block = Block::new(root.clone());
+        for accreditation in [
+            accredit(&root, &root_signing, &regional, None),
+            accredit(&regional, &regional_signing, &local, Some(date(2099))),
+            // Lapsed before any of the blocks below
+            accredit(&root, &root_signing, &lapsed, Some(date(2021))),
+            // A cycle the root isn't part of, and one leading back to the root
+            accredit(&rogue, &rogue_signing, &accomplice, None),
+            accredit(&accomplice, &accomplice_signing, &rogue, None),
+            accredit(&local, &local_signing, &root, None),
+        ] {
+            block.add_accreditation(accreditation);
+        }
+        chain.add_block(block, &root_signing).unwrap();
+
+        let credentials: Vec<_> = [
+            (&root, &root_signing),
+            (&regional, &regional_signing),
+            (&local, &local_signing),
+            (&lapsed, &lapsed_signing),
+            (&late, &late_signing),
+            (&rogue, &rogue_signing),
+        ]
+        .into_iter()
+        .map(|(issuer, signing)| {
+            let subject = Subject::new("Alice".to_string(), "Doe".to_string());
+            let attribute = Attribute::new("degree".to_string(), "PhD".to_string());
+            let credential = Credential::new(
+                vec![attribute],
+                issuer.clone(),
+                subject,
+                ValidDuration::starting(date(2024)),
+            );
+            let mut block = Block::new(issuer.clone());
+            block
+                .add_credential(credential.sign(signing, false, chain.format()).unwrap(), false)
+                .unwrap();
+            chain.add_block(block, signing).unwrap();
+            credential
+        })
+        .collect();
+        // Accredited only after issuing its credential
+        let mut block = Block::new(root.clone());
+        block.add_accreditation(accredit(&root, &root_signing, &late, None));
+        chain.add_block(block, &root_signing).unwrap();
+        assert_eq!(chain.validate(), Ok(()));
+
+        let statuses: Vec<_> = credentials
+            .iter()
+            .map(|credential| {
+                chain.check_trust(chain.check_credential(credential), credential, &root)
+            })
+            .collect();
+        assert_eq!(statuses, [
+            VerificationStatus::Valid { issued_in: 1 },
+            VerificationStatus::Valid { issued_in: 2 },
+            VerificationStatus::Valid { issued_in: 3 },
+            VerificationStatus::NotAccredited { issued_in: 4 },
+            VerificationStatus::NotAccredited { issued_in: 5 },
+            VerificationStatus::NotAccredited { issued_in: 6 },
+        ]);
+        // Accreditations only vouch downwards, apart from the one back to the root
+        let from_regional = chain.check_trust(
+            VerificationStatus::Valid { issued_in: 1 },
+            &credentials[0],
+            &regional,
+        );
+        assert_eq!(from_regional, VerificationStatus::Valid { issued_in: 1 });
+        let (stranger, _) = issuer("Stranger");
+        let from_stranger = chain.check_trust(
+            VerificationStatus::Valid { issued_in: 2 },
+            &credentials[1],
+            &stranger,
+        );
+        assert_eq!(from_stranger, VerificationStatus::NotAccredited { issued_in: 2 });
+    }
+
+    #[test]
+    fn test_mentions_issuer() {
+        let (mut chain, authority, authority_signing) = authority_chain();
+        let (accredited, _) = Issuer::new("Accredited".to_string());
+        let (unrelated, _) = Issuer::new("Unrelated".to_string());
+        let duration = ValidDuration::starting(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap());
+        let accreditation = Accreditation::new(
+            &authority,
+            &authority_signing,
+            &accredited,
+            duration,
+            chain.format(),
+        )
+        .unwrap();
+        let mut block = Block::new(authority.clone());
+        block.add_accreditation(accreditation);
+        assert!(block.mentions_issuer(accredited.uuid));
+        chain.add_block(block, &authority_signing).unwrap();
+        assert!(chain.mentions_issuer(authority.uuid));
+        assert!(chain.mentions_issuer(accredited.uuid));
+        assert!(!chain.mentions_issuer(unrelated.uuid));
+    }
+
+    #[test]
+    fn test_block_by_hash_index() {
+        let (issuer, signing) = Issuer::new("Test Issuer".to_string());
+        let mut chain = Blockchain::new();
+        assert!(chain.tip().is_none());
+        for _ in 0..3 {
+            chain.add_block(Block::new(issuer.clone()), &signing).unwrap();
+            let tip = chain.tip().unwrap();
+            assert_eq!(chain.block_by_hash(tip.hash()).unwrap().height(), chain.len() as u64 - 1);
+        }
+        let hashes: Vec<_> = chain.into_iter().map(|block| block.hash().clone()).collect();
+        assert_eq!(hashes.len(), 3);
+        // The index is rebuilt when the chain is read back, and not written out
+        let json = serde_json::to_string(&chain).unwrap();
+        assert!(!json.contains("by_hash"));
+        let restored: Blockchain = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+        for (height, hash) in (0..).zip(&hashes) {
+            assert_eq!(chain.block_by_hash(hash).unwrap().height(), height);
+            assert_eq!(restored.block_by_hash(hash).unwrap().height(), height);
+        }
+        assert!(chain.block_by_hash(&Hash::default()).is_none());
+    }
+
+    #[test]
+    fn test_blocks_containing() {
+        let (credential, signing) = sample_credential();
+        let issued = credential.sign(&signing, false, HashVersion::V1).unwrap();
+        let revoking = credential.sign(&signing, true, HashVersion::V1).unwrap();
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(credential.issuer.clone());
+        block.add_credential(issued.clone(), false).unwrap();
+        chain.add_block(block, &signing).unwrap();
+        let (other, other_signing) = Issuer::new("Other".to_string());
+        let mut block = Block::new(other);
+        block.anchor_credential(issued.clone()).unwrap();
+        chain.add_block(block, &other_signing).unwrap();
+        let mut block = Block::new(credential.issuer.clone());
+        block.add_credential(revoking.clone(), true).unwrap();
+        chain.add_block(block, &signing).unwrap();
+
+        assert_eq!(chain.blocks_containing(&issued.credential), vec![
+            (0, ListKind::New),
+            (1, ListKind::Anchored)
+        ]);
+        assert_eq!(chain.blocks_containing(&revoking.credential), vec![(2, ListKind::Revoked)]);
+        assert!(chain.blocks_containing(&Hash::default()).is_empty());
+    }
+
+    #[test]
+    fn test_block_summary() {
+        let (credential, signing) = sample_credential();
+        let issued = credential.sign(&signing, false, HashVersion::V1).unwrap();
+        let revoking = credential.sign(&signing, true, HashVersion::V1).unwrap();
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(credential.issuer.clone());
+        block.add_credential(issued, false).unwrap();
+        block.add_credential(revoking, true).unwrap();
+        chain.add_block(block, &signing).unwrap();
+
+        let block = chain.tip().unwrap();
+        let summary = block.summary();
+        assert_eq!(
+            (summary.height, summary.new_credentials, summary.revoked_credentials),
+            (0, 1, 1)
+        );
+        assert_eq!(summary.signer, credential.issuer.name);
+        assert_eq!(
+            summary.to_string(),
+            format!(
+                "#0 {} {} {}: 1 new, 1 revoked",
+                block.hash().short(),
+                block.timestamp().format("%Y-%m-%d %H:%M:%S"),
+                credential.issuer.name
+            )
+        );
+    }
+
+    #[test]
+    fn test_chain_summary() {
+        assert_eq!(Blockchain::new().summary().totals, ChainTotals::default());
+        assert_eq!(Blockchain::new().summary().to_string(), "Totals: no blocks");
+
+        let (credential, signing) = sample_credential();
+        let (other, other_signing) = Issuer::new("Other".to_string());
+        let subject = credential.subject.disclosed().unwrap().clone();
+        let second = Credential::new(
+            credential.attributes.clone(),
+            credential.issuer.clone(),
+            subject,
+            credential.valid_duration.clone(),
+        );
+        let sign = |credential: &Credential, revoking| {
+            credential.sign(&signing, revoking, HashVersion::V1).unwrap()
+        };
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(credential.issuer.clone());
+        block.add_credential(sign(&credential, false), false).unwrap();
+        block.add_credential(sign(&second, false), false).unwrap();
+        chain.add_block(block, &signing).unwrap();
+        chain.add_block(Block::new(other), &other_signing).unwrap();
+        let mut block = Block::new(credential.issuer.clone());
+        block.add_credential(sign(&credential, true), true).unwrap();
+        chain.add_block(block, &signing).unwrap();
+
+        let summary = chain.summary();
+        assert_eq!(summary.blocks.len(), 3);
+        let counts: Vec<_> =
+            summary.blocks.iter().map(|b| (b.new_credentials, b.revoked_credentials)).collect();
+        assert_eq!(counts, [(2, 0), (0, 0), (0, 1)]);
+        let totals = &summary.totals;
+        assert_eq!((totals.blocks, totals.issued, totals.revoked, totals.issuers), (3, 2, 1, 2));
+        assert_eq!(totals.first, Some(chain.block(0).unwrap().timestamp()));
+        assert_eq!(totals.last, Some(chain.tip().unwrap().timestamp()));
+        let display = summary.to_string();
+        assert_eq!(display.lines().count(), 4);
+        assert!(display.lines().last().unwrap().starts_with(
+            "Totals: 3 blocks, 2 credentials issued, 1 revoked, 2 distinct issuers, from "
+        ));
+
+        // Any run of blocks can be summed up
+        let tail = ChainSummary::new(chain.blocks().skip(1));
+        assert_eq!((tail.totals.blocks, tail.totals.issued, tail.totals.revoked), (2, 0, 1));
+    }
+
+    #[test]
+    fn test_chain_stats() {
+        assert_eq!(Blockchain::new().stats(), ChainStats::default());
+
+        let (credential, signing) = sample_credential();
+        let (other, other_signing) = Issuer::new("Other".to_string());
+        let second = Credential::new(
+            credential.attributes.clone(),
+            credential.issuer.clone(),
+            credential.subject.disclosed().unwrap().clone(),
+            credential.valid_duration.clone(),
+        );
+        let sign = |credential: &Credential, revoking| {
+            credential.sign(&signing, revoking, HashVersion::V1).unwrap()
+        };
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(credential.issuer.clone());
+        block.add_credential(sign(&credential, false), false).unwrap();
+        block.add_credential(sign(&second, false), false).unwrap();
+        chain.add_block(block, &signing).unwrap();
+        let mut block = Block::new(other.clone());
+        block.anchor_credential(sign(&second, false)).unwrap();
+        chain.add_block(block, &other_signing).unwrap();
+        let mut block = Block::new(credential.issuer.clone());
+        block.add_credential(sign(&credential, true), true).unwrap();
+        chain.add_block(block, &signing).unwrap();
+        let at = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(9, 0, 0).unwrap();
+        chain.chain[0].timestamp = at(2023, 11, 15).and_utc();
+        chain.chain[1].timestamp = at(2024, 3, 2).and_utc();
+        chain.chain[2].timestamp = at(2024, 3, 20).and_utc();
+
+        let stats = chain.stats();
+        assert_eq!((stats.blocks, stats.issued, stats.revoked, stats.anchored), (3, 2, 1, 1));
+        assert_eq!(stats.issuers, [
+            IssuerStats {
+                uuid: credential.issuer.uuid,
+                name: "Test Issuer".into(),
+                fingerprint: fingerprint(&signing.verifying_key()),
+                blocks: 2,
+                issued: 2,
+                revoked: 1,
+                anchored: 0,
+            },
+            IssuerStats {
+                uuid: other.uuid,
+                name: "Other".into(),
+                fingerprint: fingerprint(&other_signing.verifying_key()),
+                blocks: 1,
+                issued: 0,
+                revoked: 0,
+                anchored: 1,
+            },
+        ]);
+        // Months without blocks are kept, across the turn of the year
+        let months: Vec<_> = stats
+            .months
+            .iter()
+            .map(|m| (m.month.as_str(), m.blocks, m.issued, m.revoked, m.anchored))
+            .collect();
+        assert_eq!(months, [
+            ("2023-11", 1, 2, 0, 0),
+            ("2023-12", 0, 0, 0, 0),
+            ("2024-01", 0, 0, 0, 0),
+            ("2024-02", 0, 0, 0, 0),
+            ("2024-03", 2, 0, 1, 1),
+        ]);
+        assert!((stats.average_per_block - 4.0 / 3.0).abs() < f64::EPSILON);
+        assert_eq!(
+            stats.largest_block,
+            Some(LargestBlock { height: 0, hash: chain.chain[0].hash.clone(), credentials: 2 })
+        );
+    }
+
+    #[derive(Default)]
+    struct CollectingSink {
+        total: Option<u64>,
+        ticks: u64,
+        finished: bool,
+    }
+
+    impl ProgressSink for CollectingSink {
+        fn start(&mut self, total: u64) { self.total = Some(total); }
+
+        fn tick(&mut self) { self.ticks += 1; }
+
+        fn finish(&mut self) { self.finished = true; }
+    }
+
+    #[test]
+    fn test_progress_ticks_once_per_block() {
+        let (credential, signing) = sample_credential();
+        let mut chain = Blockchain::new();
+        for _ in 0..4 {
+            chain.add_block(Block::new(credential.issuer.clone()), &signing).unwrap();
+        }
+
+        let mut sink = CollectingSink::default();
+        chain.validate_with_progress(&ChainPolicy::default(), &mut sink).unwrap();
+        assert_eq!((sink.total, sink.ticks, sink.finished), (Some(4), 4, true));
+
+        let mut sink = CollectingSink::default();
+        let statuses = chain.check_credentials_with_progress(&[&credential], &mut sink);
+        assert_eq!(statuses, [VerificationStatus::NotFound]);
+        assert_eq!((sink.total, sink.ticks, sink.finished), (Some(4), 4, true));
+
+        // A failed validation still finishes, after ticking the blocks before the invalid one
+        chain.chain[2].hash = Hash::default();
+        let mut sink = CollectingSink::default();
+        assert!(chain.validate_with_progress(&ChainPolicy::default(), &mut sink).is_err());
+        assert_eq!((sink.ticks, sink.finished), (2, true));
+    }
+
+    #[test]
+    fn test_forged_accreditation_is_rejected() {
+        let (root, _) = Issuer::new("Root".to_string());
+        let (rogue, rogue_signing) = Issuer::new("Rogue".to_string());
+        let duration = ValidDuration::starting(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap());
+        let mut accreditation =
+            Accreditation::new(&rogue, &rogue_signing, &rogue, duration, HashVersion::V1).unwrap();
+        // Claims to come from the root while signed by the rogue issuer
+        accreditation.accreditor = root.uuid;
+        accreditation.accreditor_key = root.verifying;
+        let mut block = Block::new(rogue.clone());
+        block.add_accreditation(accreditation);
+        assert_eq!(
+            Blockchain::new().add_block(block, &rogue_signing),
+            Err(BlockchainError::InvalidAccreditation { height: 0 })
+        );
+    }
+
+    #[test]
+    fn test_open_chain_rejects_authority_changes() {
+        let (credential, signing) = sample_credential();
+        let mut chain =
+            Blockchain::with_genesis(GenesisBlock::new("Test".to_string(), None, None).unwrap());
+        let mut block = Block::new(credential.issuer.clone());
+        block.add_authority_change(AuthorityChange::Add(credential.issuer.clone()));
+        assert_eq!(
+            chain.add_block(block, &signing),
+            Err(BlockchainError::AuthorityChangeRejected { height: 0 })
+        );
+    }
+
+    #[test]
+    fn test_blockchain_divergence() {
+        let (credential, signing) = sample_credential();
+        let mut local =
+            Blockchain::with_genesis(GenesisBlock::new("Test".to_string(), None, None).unwrap());
+        for _ in 0..2 {
+            local.add_block(Block::new(credential.issuer.clone()), &signing).unwrap();
+        }
+        let mut other = local.clone();
+        assert_eq!(local.diverges_from(&other), None);
+
+        other.add_block(Block::new(credential.issuer.clone()), &signing).unwrap();
+        assert_eq!(local.diverges_from(&other), None);
+        assert_eq!(other.diverges_from(&local), None);
+
+        local.add_block(Block::new(credential.issuer.clone()), &signing).unwrap();
+        assert_eq!(
+            local.diverges_from(&other),
+            Some(DivergencePoint {
+                last_common: Some(1),
+                height: 2,
+                local: local.chain[2].hash.clone(),
+                other: other.chain[2].hash.clone(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unrelated_chains_diverge_at_first_block() {
+        let (credential, signing) = sample_credential();
+        let mut local =
+            Blockchain::with_genesis(GenesisBlock::new("Test".to_string(), None, None).unwrap());
+        let mut other =
+            Blockchain::with_genesis(GenesisBlock::new("Test".to_string(), None, None).unwrap());
+        local.add_block(Block::new(credential.issuer.clone()), &signing).unwrap();
+        other.add_block(Block::new(credential.issuer.clone()), &signing).unwrap();
+        let divergence = local.diverges_from(&other).unwrap();
+        assert_eq!(divergence.last_common, None);
+        assert_eq!(divergence.height, 0);
+    }
+
+    #[test]
+    fn test_blockchain_without_genesis_deserializes() {
+        let chain: Blockchain = serde_json::from_str(r#"{"chain":[]}"#).unwrap();
+        assert!(chain.genesis().is_none());
+        assert_eq!(chain.version(), HashVersion::V1);
+        assert_eq!(chain.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_blockchain_check_credentials_batch() {
+        let (valid, signing) = sample_credential();
+        let revoked = Credential::new(
+            valid.attributes.clone(),
+            valid.issuer.clone(),
+            valid.subject.disclosed().unwrap().clone(),
+            valid.valid_duration.clone(),
+        );
+        let missing = Credential::new(
+            valid.attributes.clone(),
+            valid.issuer.clone(),
+            valid.subject.disclosed().unwrap().clone(),
+            valid.valid_duration.clone(),
+        );
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(valid.issuer.clone());
+        block.add_credential(valid.sign(&signing, false, HashVersion::V1).unwrap(), false).unwrap();
+        block
+            .add_credential(revoked.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+        let mut block = Block::new(valid.issuer.clone());
+        block.add_credential(revoked.sign(&signing, true, HashVersion::V1).unwrap(), true).unwrap();
+        chain.add_block(block, &signing).unwrap();
+
+        assert_eq!(chain.check_credentials(&[&valid, &revoked, &missing]), [
+            VerificationStatus::Valid { issued_in: 0 },
+            VerificationStatus::Revoked { issued_in: Some(0), revoked_in: 1 },
+            VerificationStatus::NotFound,
+        ]);
+        for credential in [&valid, &revoked, &missing] {
+            assert_eq!(
+                chain.check_credential(credential),
+                chain.check_credentials(&[credential]).remove(0)
+            );
+        }
+    }
+
+    #[test]
+    fn test_blockchain_check_credential_at_expiry_boundaries() {
+        let (credential, signing) = sample_credential();
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+        chain.chain[0].timestamp = DateTime::UNIX_EPOCH;
+
+        let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2030, 1, 1).unwrap();
+        let day = TimeDelta::days(1);
+        let valid = VerificationStatus::Valid { issued_in: 0 };
+        assert_eq!(
+            chain.check_credential_at(&credential, from - day),
+            VerificationStatus::NotYetValid { issued_in: 0, from }
+        );
+        assert_eq!(chain.check_credential_at(&credential, from), valid);
+        assert_eq!(chain.check_credential_at(&credential, to), valid);
+        assert_eq!(chain.check_credential_at(&credential, to + day), VerificationStatus::Expired {
+            issued_in: 0,
+            to
+        });
+
+        let indefinite = ValidDuration::starting(from);
+        let far_future = NaiveDate::from_ymd_opt(9999, 12, 31).unwrap();
+        assert_eq!(valid.clone().within(&indefinite, far_future), valid);
+
+        let revoked = VerificationStatus::Revoked { issued_in: Some(0), revoked_in: 1 };
+        assert_eq!(revoked.clone().within(&indefinite, from - day), revoked);
+    }
+
+    #[test]
+    fn test_blockchain_check_credential_at_past_dates() {
+        let (credential, signing) = sample_credential();
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, true, HashVersion::V1).unwrap(), true)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+
+        let date = |month, day| NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+        let at_noon = |date: NaiveDate| date.and_hms_opt(12, 0, 0).unwrap().and_utc();
+        chain.chain[0].timestamp = at_noon(date(3, 1));
+        chain.chain[1].timestamp = at_noon(date(7, 1));
+
+        assert_eq!(
+            chain.check_credential_at(&credential, date(2, 1)),
+            VerificationStatus::NotYetIssued { issued_in: 0 }
+        );
+        assert_eq!(chain.check_credential_at(&credential, date(3, 1)), VerificationStatus::Valid {
+            issued_in: 0
+        });
+        assert_eq!(chain.check_credential_at(&credential, date(6, 1)), VerificationStatus::Valid {
+            issued_in: 0
+        });
+        let revoked = VerificationStatus::Revoked { issued_in: Some(0), revoked_in: 1 };
+        assert_eq!(chain.check_credential_at(&credential, date(7, 1)), revoked);
+        assert_eq!(chain.check_credential_at(&credential, date(8, 1)), revoked);
+
+        // Checking many at once in a single pass agrees
+        let (unknown, _) = sample_credential();
+        for day in [date(2, 1), date(3, 1), date(6, 1), date(7, 1), date(8, 1)] {
+            let statuses = chain.check_credentials_at(&[&credential, &unknown], day);
+            assert_eq!(statuses, [
+                chain.check_credential_at(&credential, day),
+                VerificationStatus::NotFound
+            ]);
+        }
+    }
+
+    #[test]
+    fn test_blockchain_report_identifies_blocks() {
+        let (credential, signing) = sample_credential();
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, true, HashVersion::V1).unwrap(), true)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+
+        let report = chain.report(&credential, chain.check_credential(&credential));
+        let issued = report.issued.unwrap();
+        let revoked = report.revoked.unwrap();
+        assert_eq!((issued.height, &issued.hash), (0, &chain.chain[0].hash));
+        assert_eq!((revoked.height, &revoked.hash), (1, &chain.chain[1].hash));
+        assert_eq!(revoked.signer, "Test Issuer");
+        assert_eq!(
+            revoked.to_string(),
+            format!("block #1 ({}, Test Issuer)", chain.chain[1].timestamp.date_naive())
+        );
+        assert_eq!(chain.report(&credential, VerificationStatus::NotFound).issued, None);
+    }
+
+    #[test]
+    fn test_warned_revocations_leave_credentials_valid() {
+        let (credential, signing) = sample_credential();
+        let (_, forger_signing) = Issuer::new("Forger".to_string());
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+        // In the issuer's own block, but signed with another key
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&forger_signing, true, HashVersion::V1).unwrap(), true)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+
+        let revocation = credential.hash(true, HashVersion::V1);
+        assert_eq!(chain.warnings(), [ChainWarning::UnauthorizedRevocation {
+            height: 1,
+            credential: revocation
+        }]);
+        let valid = VerificationStatus::Valid { issued_in: 0 };
+        assert_eq!(chain.check_credential(&credential), valid);
+        assert_eq!(chain.check_credentials(&[&credential]), std::slice::from_ref(&valid));
+        assert!(chain.revocations().is_empty());
+        assert_eq!(chain.report(&credential, valid).unauthorized_revocations.len(), 1);
+    }
+
+    #[test]
+    fn test_blockchain_ignores_revocation_by_other_issuer() {
+        let (credential, signing) = sample_credential();
+        let (other, other_signing) = Issuer::new("Other Issuer".to_string());
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+        let mut block = Block::new(other);
+        block
+            .add_credential(credential.sign(&signing, true, HashVersion::V1).unwrap(), true)
+            .unwrap();
+        chain.add_block(block, &other_signing).unwrap();
+
+        let valid = VerificationStatus::Valid { issued_in: 0 };
+        assert_eq!(chain.check_credential(&credential), valid);
+        assert!(chain.has_issued(&credential.hash(false, HashVersion::V1)));
+        assert_eq!(chain.check_credentials(&[&credential])[0], valid);
+        let report = chain.report(&credential, valid);
+        assert_eq!(report.unauthorized_revocations.len(), 1);
+        assert_eq!(report.unauthorized_revocations[0].signer, "Other Issuer");
+        assert!(chain.revocations().is_empty());
+        assert_eq!(chain.validate(), Ok(()));
+        assert_eq!(chain.warnings(), [ChainWarning::UnauthorizedRevocation {
+            height: 1,
+            credential: credential.hash(true, HashVersion::V1)
+        }]);
+    }
+
+    #[test]
+    fn test_duplicate_issuance_is_rejected_and_flagged() {
+        let (credential, signing) = sample_credential();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        assert_eq!(
+            block.add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false),
+            Err(BlockError::DuplicateCredential)
+        );
+        block
+            .add_credential(credential.sign(&signing, true, HashVersion::V1).unwrap(), true)
+            .unwrap();
+        assert_eq!(block.new_credentials().len(), 1);
+
+        let mut chain = Blockchain::new();
+        chain.add_block(block, &signing).unwrap();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+
+        assert_eq!(chain.issued_in(&credential.hash(false, HashVersion::V1)), Some(0));
+        assert_eq!(chain.warnings(), [ChainWarning::DuplicateIssuance {
+            height: 1,
+            first_issued_in: 0,
+            credential: credential.hash(false, HashVersion::V1)
+        }]);
+    }
+
+    #[test]
+    fn test_anchored_credential_counts_as_issued() {
+        let (credential, signing) = sample_credential();
+        let (registry, registry_signing) = Issuer::new("Registry".to_string());
+        let mut block = Block::new(registry);
+        block
+            .anchor_credential(credential.sign(&signing, false, HashVersion::V1).unwrap())
+            .unwrap();
+        assert_eq!(
+            block.add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false),
+            Err(BlockError::DuplicateCredential)
+        );
+        let mut chain = Blockchain::new();
+        chain.add_block(block, &registry_signing).unwrap();
+
+        assert!(chain.chain[0].new_credentials().is_empty());
+        let mut tampered = chain.chain[0].clone();
+        tampered.anchored_credentials.clear();
+        assert_eq!(
+            tampered.verify(&Hash::default(), chain.version()),
+            Err(BlockError::HashMismatch)
+        );
+        assert_eq!(chain.check_credential(&credential), VerificationStatus::Valid { issued_in: 0 });
+        assert_eq!(chain.check_credentials(&[&credential])[0], VerificationStatus::Valid {
+            issued_in: 0
+        });
+        assert_eq!(chain.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_key_rotation_continuity() {
+        let (credential, first) = sample_credential();
+        let mut chain = Blockchain::new();
+        let format = chain.format();
+        let mut block = Block::new(credential.issuer.clone());
+        block.add_credential(credential.sign(&first, false, format).unwrap(), false).unwrap();
+        chain.add_block(block, &first).unwrap();
+
+        // The old key announces the new one
+        let second = SigningKey::from_bytes(&[11; 32]);
+        let mut rotated = credential.issuer.clone();
+        rotated.rotate_key(second.verifying_key());
+        let mut block = Block::new(credential.issuer.clone());
+        block.add_key_rotation(
+            KeyRotation::new(rotated.uuid, &first, second.verifying_key(), format).unwrap(),
+        );
+        chain.add_block(block, &first).unwrap();
+
+        // The new key revokes a credential issued under the old one
+        let mut block = Block::new(rotated.clone());
+        block.add_credential(credential.sign(&first, true, format).unwrap(), true).unwrap();
+        chain.add_block(block, &second).unwrap();
+        let revoked = VerificationStatus::Revoked { issued_in: Some(0), revoked_in: 2 };
+        assert_eq!(chain.check_credential(&credential), revoked);
+        assert_eq!(chain.check_credentials(&[&credential]), std::slice::from_ref(&revoked));
+        assert!(chain.report(&credential, revoked).unauthorized_revocations.is_empty());
+        assert_eq!(chain.warnings(), []);
+        assert_eq!(chain.validate(), Ok(()));
+
+        // The retired key can neither sign blocks nor issue credentials anymore
+        assert_eq!(
+            chain.add_block(Block::new(credential.issuer.clone()), &first),
+            Err(BlockchainError::RetiredSignerKey { height: 3 })
+        );
+        let late = Credential::new(
+            vec![Attribute::new("Late".to_string(), "Issued after the rotation".to_string())],
+            credential.issuer.clone(),
+            Subject::new("Bob".to_string(), "Doe".to_string()),
+            ValidDuration::starting(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
+        );
+        let mut block = Block::new(rotated);
+        block.add_credential(late.sign(&first, false, format).unwrap(), false).unwrap();
+        chain.add_block(block, &second).unwrap();
+        assert_eq!(chain.check_credential(&late), VerificationStatus::SignatureInvalid {
+            block: 3
+        });
+        assert_eq!(chain.check_credentials(&[&late]), [VerificationStatus::SignatureInvalid {
+            block: 3
+        }]);
+        assert_eq!(chain.issuer_keys(late.issuer.uuid), [
+            first.verifying_key(),
+            second.verifying_key()
+        ]);
+        assert_eq!(chain.issuer_keys(Uuid::new_v4()), []);
+    }
+
+    #[test]
+    fn test_key_rotation_must_be_signed_by_current_key() {
+        let (credential, first) = sample_credential();
+        let (_, stranger) = Issuer::new("Stranger".to_string());
+        let second = SigningKey::from_bytes(&[11; 32]);
+        let third = SigningKey::from_bytes(&[12; 32]);
+        let mut chain = Blockchain::new();
+        let format = chain.format();
+        let uuid = credential.issuer.uuid;
+
+        let mut forged = KeyRotation::new(uuid, &first, third.verifying_key(), format).unwrap();
+        forged.signature =
+            KeyRotation::new(uuid, &stranger, third.verifying_key(), format).unwrap().signature;
+        assert!(!forged.verify(format));
+        let mut block = Block::new(credential.issuer.clone());
+        block.add_key_rotation(forged);
+        assert_eq!(
+            chain.add_block(block, &first),
+            Err(BlockchainError::InvalidKeyRotation { height: 0 })
+        );
+
+        let mut block = Block::new(credential.issuer.clone());
+        block.add_key_rotation(
+            KeyRotation::new(uuid, &first, second.verifying_key(), format).unwrap(),
+        );
+        chain.add_block(block, &first).unwrap();
+        // The first key was already replaced, so it can't pick another successor
+        let mut rotated = credential.issuer.clone();
+        rotated.rotate_key(second.verifying_key());
+        let mut block = Block::new(rotated);
+        block.add_key_rotation(
+            KeyRotation::new(uuid, &first, third.verifying_key(), format).unwrap(),
+        );
+        assert_eq!(
+            chain.add_block(block, &second),
+            Err(BlockchainError::InvalidKeyRotation { height: 1 })
+        );
+
+        // Validation catches a rotation slipped into a stored block
+        let mut block = chain.chain[0].clone();
+        block.key_rotations[0].new = third.verifying_key();
+        block.hash = block.compute_hash(format);
+        block.signature = Signer::sign(&first, &block.hash.bytes).into();
+        chain.chain[0] = block;
+        assert_eq!(chain.validate(), Err(BlockchainError::InvalidKeyRotation { height: 0 }));
+    }
+
+    #[test]
+    fn test_key_rotation_of_another_issuer_is_rejected() {
+        let (credential, first) = sample_credential();
+        let (stranger, stranger_signing) = Issuer::new("Stranger".to_string());
+        let second = SigningKey::from_bytes(&[11; 32]);
+        let mut chain = Blockchain::new();
+        let format = chain.format();
+        let uuid = credential.issuer.uuid;
+        chain.add_block(Block::new(credential.issuer.clone()), &first).unwrap();
+
+        // Rotating the issuer's UUID away from a key of the stranger's, in the stranger's block
+        let mut block = Block::new(stranger.clone());
+        let takeover = KeyRotation::new(uuid, &stranger_signing, second.verifying_key(), format);
+        block.add_key_rotation(takeover.unwrap());
+        assert_eq!(
+            chain.add_block(block, &stranger_signing),
+            Err(BlockchainError::InvalidKeyRotation { height: 1 })
+        );
+        // Or in a block claiming to be the issuer's, signed with the stranger's key
+        let mut impostor = credential.issuer.clone();
+        impostor.verifying = stranger.verifying;
+        let mut block = Block::new(impostor);
+        let takeover = KeyRotation::new(uuid, &stranger_signing, second.verifying_key(), format);
+        block.add_key_rotation(takeover.unwrap());
+        assert_eq!(
+            chain.add_block(block, &stranger_signing),
+            Err(BlockchainError::InvalidKeyRotation { height: 1 })
+        );
+        // Even the issuer's key can't be rotated from someone else's block
+        let mut block = Block::new(stranger);
+        block.add_key_rotation(
+            KeyRotation::new(uuid, &first, second.verifying_key(), format).unwrap(),
+        );
+        assert_eq!(
+            chain.add_block(block, &stranger_signing),
+            Err(BlockchainError::InvalidKeyRotation { height: 1 })
+        );
+
+        assert_eq!(chain.issuer_keys(uuid), [first.verifying_key()]);
+        let mut block = Block::new(credential.issuer.clone());
+        block.add_key_rotation(
+            KeyRotation::new(uuid, &first, second.verifying_key(), format).unwrap(),
+        );
+        chain.add_block(block, &first).unwrap();
+        assert_eq!(chain.issuer_keys(uuid), [first.verifying_key(), second.verifying_key()]);
+    }
+
+    #[test]
+    fn test_blockchain_check_credential_pinned() {
+        let (credential, signing) = sample_credential();
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+
+        let mut pinned =
+            HashMap::from([(credential.issuer.uuid, vec![credential.issuer.verifying])]);
+        assert_eq!(
+            chain.check_credential_pinned(&credential, &pinned),
+            VerificationStatus::Valid { issued_in: 0 }
+        );
+        pinned.insert(credential.issuer.uuid, vec![Issuer::new("Other".to_string()).0.verifying]);
+        assert_eq!(
+            chain.check_credential_pinned(&credential, &pinned),
+            VerificationStatus::IssuerKeyConflict
+        );
+    }
+
+    #[test]
+    fn test_blockchain_check_credential_trusted() {
+        let (credential, signing) = sample_credential();
+        let mut chain = Blockchain::new();
+        let mut block = Block::new(credential.issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        chain.add_block(block, &signing).unwrap();
+
+        let mut store = TrustStore::default();
+        assert_eq!(
+            chain.check_credential_trusted(&credential, &store),
+            VerificationStatus::UntrustedIssuer
+        );
+        assert_eq!(chain.untrusted_blocks(&store), vec![0]);
+        store.trust(TrustedIssuer {
+            verifying: credential.issuer.verifying,
+            uuid: Some(credential.issuer.uuid),
+            name: None,
+        });
+        assert_eq!(
+            chain.check_credential_trusted(&credential, &store),
+            VerificationStatus::Valid { issued_in: 0 }
+        );
+        assert!(chain.untrusted_blocks(&store).is_empty());
+        // Trusting the key doesn't make a credential missing from the chain valid
+        let (mut other, _) = sample_credential();
+        other.issuer = credential.issuer.clone();
+        assert_eq!(chain.check_credential_trusted(&other, &store), VerificationStatus::NotFound);
     }
 
     #[test]
     fn test_block_display_serialization() {
         let (credential, signing) = sample_credential();
-        let signed = credential.sign(&signing, false);
+        let signed = credential.sign(&signing, false, HashVersion::V1).unwrap();
         let issuer = credential.issuer.clone();
         let mut block = Block::new(issuer);
-        block.add_credential(signed, false);
-        block.finalize(Hash::default(), &signing);
+        block.add_credential(signed, false).unwrap();
+        block.finalize(Hash::default(), &signing, HashVersion::LATEST).unwrap();
         let output = block.to_string();
         assert!(output.contains("new_credentials"));
         assert!(output.contains("timestamp"));
@@ -210,13 +4300,13 @@ mod tests {
     #[test]
     fn test_blockchain_display_serialization() {
         let (credential, signing) = sample_credential();
-        let signed = credential.sign(&signing, false);
+        let signed = credential.sign(&signing, false, HashVersion::V1).unwrap();
         let issuer = credential.issuer.clone();
         let mut block = Block::new(issuer);
-        block.add_credential(signed, false);
+        block.add_credential(signed, false).unwrap();
 
         let mut chain = Blockchain::new();
-        chain.add_block(block, &signing);
+        chain.add_block(block, &signing).unwrap();
         let output = chain.to_string();
         assert!(output.contains("chain"));
     }
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::blockchain::{Blockchain, VerificationReport};
+use crate::credential::Credential;
+use crate::hash::Hash;
+
+/// Verification reports of a chain, only valid while its tip hash stays the same
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VerificationCache {
+    tip: Hash,
+    reports: HashMap<Uuid, CachedReport>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedReport {
+    credential: Hash,
+    report: VerificationReport,
+}
+
+/// Answers repeated verifications of the same credentials without scanning the chain again
+pub struct CachedVerifier<'a> {
+    blockchain: &'a Blockchain,
+    cache: VerificationCache,
+}
+
+impl<'a> CachedVerifier<'a> {
+    #[must_use]
+    pub fn new(blockchain: &'a Blockchain) -> Self {
+        Self::with_cache(blockchain, VerificationCache::default())
+    }
+
+    /// Reuses the reports in `cache`, discarding all of them if the chain's tip has changed
+    #[must_use]
+    pub fn with_cache(blockchain: &'a Blockchain, mut cache: VerificationCache) -> Self {
+        let tip = blockchain.tip_hash();
+        if cache.tip != tip {
+            cache = VerificationCache { tip, reports: HashMap::new() };
+        }
+        Self { blockchain, cache }
+    }
+
+    /// Same as [`Blockchain::report`] on [`Blockchain::check_credential`], cached by the
+    /// credential's UUID
+    pub fn report(&mut self, credential: &Credential) -> VerificationReport {
+        let hash = credential.hash(false, self.blockchain.format());
+        if let Some(cached) = self.cache.reports.get(&credential.uuid)
+            && cached.credential == hash
+        {
+            return cached.report.clone();
+        }
+        let report =
+            self.blockchain.report(credential, self.blockchain.check_credential(credential));
+        self.cache
+            .reports
+            .insert(credential.uuid, CachedReport { credential: hash, report: report.clone() });
+        report
+    }
+
+    #[must_use]
+    pub fn into_cache(self) -> VerificationCache { self.cache }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::blockchain::{BLOCK_VISITS, Block, VerificationStatus};
+    use crate::credential::{Attribute, Issuer, Subject, ValidDuration};
+    use crate::hash::HashVersion;
+
+    #[test]
+    fn test_cache_hit_skips_scan_until_tip_changes() {
+        let (issuer, signing) = Issuer::new("Registry".to_string());
+        let credential = Credential::new(
+            vec![Attribute::new("degree".to_string(), "PhD".to_string())],
+            issuer.clone(),
+            Subject::new("Alice".to_string(), "Smith".to_string()),
+            ValidDuration::starting(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
+        );
+        let mut blockchain = Blockchain::new();
+        let mut block = Block::new(issuer.clone());
+        block
+            .add_credential(credential.sign(&signing, false, HashVersion::V1).unwrap(), false)
+            .unwrap();
+        blockchain.add_block(block, &signing).unwrap();
+        let visits = || BLOCK_VISITS.with(std::cell::Cell::get);
+
+        let mut verifier = CachedVerifier::new(&blockchain);
+        let before = visits();
+        let report = verifier.report(&credential);
+        assert_eq!(report.status, VerificationStatus::Valid { issued_in: 0 });
+        assert!(visits() > before);
+        let cache = verifier.into_cache();
+
+        let before = visits();
+        let mut verifier = CachedVerifier::with_cache(&blockchain, cache);
+        assert_eq!(verifier.report(&credential), report);
+        assert_eq!(visits(), before);
+        let cache = verifier.into_cache();
+
+        let mut block = Block::new(issuer);
+        block
+            .add_credential(credential.sign(&signing, true, HashVersion::V1).unwrap(), true)
+            .unwrap();
+        blockchain.add_block(block, &signing).unwrap();
+        let mut verifier = CachedVerifier::with_cache(&blockchain, cache);
+        assert_eq!(verifier.report(&credential).status, VerificationStatus::Revoked {
+            issued_in: Some(0),
+            revoked_in: 1
+        });
+        assert!(visits() > before);
+    }
+}
@@ -1,114 +1,596 @@
-use std::fs;
-use std::fs::File;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{self, AtomicBool};
+use std::time::Duration;
+use std::{fs, io, iter, thread};
 
-use chrono::NaiveDate;
-use clap::{Args, Parser, Subcommand};
-use ed25519_dalek::SigningKey;
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use clap::{ArgGroup, Args, ColorChoice, Parser, Subcommand, ValueEnum};
+use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+use ed25519_dalek::pkcs8::{DecodePublicKey, EncodePublicKey};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use indicatif::{ProgressBar, ProgressStyle};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zeroize::Zeroizing;
 
-use crate::blockchain::{Block, Blockchain};
-use crate::credential::{Attribute, Credential, Issuer, SignedCredential, Subject, ValidDuration};
+use crate::anchor::ChainAnchor;
+use crate::audit::{AuditEvent, AuditRecord, KeyAudit, KeySource, conflicts_with_pinned};
+use crate::blockchain::{
+    Accreditation, AuthorityChange, Block, Blockchain, ChainPolicy, ChainSummary, GenesisBlock,
+    IssuerDeactivation, KeyRotation, ListKind, MonthStats, Revocation, RevocationDetails,
+    RevocationReason, TraceEvent, VerificationReport, VerificationStatus,
+};
+use crate::cache::CachedVerifier;
+use crate::compact::{self, CompactCredential, VerificationReceipt};
+use crate::config::Config;
+use crate::credential::{
+    Attribute, Credential, CredentialSubject, Issuer, Salt, SignedCredential, Subject,
+    ValidDuration, lineage,
+};
+use crate::csv_import::{IssuanceReader, SubjectColumns, SubjectReader, SubjectRef};
+use crate::evidence::EvidenceRef;
+use crate::exchange::{ChainExport, CredentialExport};
+#[cfg(feature = "grpc")]
+use crate::grpc::GrpcServer;
+use crate::hash::{Algorithm, HashVersion, PrefixError, resolve_prefix};
+use crate::ids::{self, IdError};
+use crate::interop::w3c::VerifiableCredential;
+use crate::interop::{cose, jwt};
+use crate::keys::{KeyStore, KeyStoreError, SecretKeyMaterial, fingerprint, write_private};
+use crate::nonrevocation::{self, NonRevocationProof, RevocationRoot};
+use crate::presentation::{Presentation, PresentationError};
+use crate::progress::ProgressSink;
+use crate::proof::{self, InclusionProof};
+use crate::qr::{self, QrError};
+use crate::query::CredentialQuery;
+#[cfg(feature = "client")]
+use crate::remote::{ClientAuth, Pin, RemoteChain, RemoteError};
+use crate::requests::{IssuanceRequest, RequestStatus, RequestSubmission, RequestedSubject};
+use crate::revocation_list::RevocationList;
+use crate::schema::{AttributeSchema, ValueType};
+use crate::server::{ApiError, Backend, Server};
+use crate::signer::{BlockSigner, ExternalSigner};
+use crate::site::Site;
+use crate::status_list::{self, StatusList};
+use crate::sync::{self, ChainSource, Pull};
+use crate::table::Table;
+use crate::template::CredentialTemplate;
+use crate::trust::{TrustStore, TrustedIssuer};
+use crate::ui;
+#[cfg(feature = "client")]
+use crate::webhooks::{Notification, Webhooks};
 
-/// Custom serialization for `SigningKey`
-mod signing_key_serde {
-    use ed25519_dalek::SigningKey;
-    use serde::{Deserialize, Deserializer, Serializer, de};
+type CredentialFull = (Credential, SignedCredential, SignedCredential);
+
+/// Issuer or pending block record as read from disk, older versions storing the signing key
+/// next to it
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Stored<T> {
+    Public(T),
+    WithKey(T, Box<SecretKeyMaterial>),
+}
+
+impl<T> Stored<T> {
+    fn public(self) -> Result<T, String> {
+        match self {
+            Self::Public(value) => Ok(value),
+            Self::WithKey(..) => Err("Signing keys are stored next to the issuers, run \
+                                      `attributes_attestation migrate` to move them to keys/"
+                .into()),
+        }
+    }
+}
 
-    pub fn serialize<S>(key: &SigningKey, serializer: S) -> Result<S::Ok, S::Error>
-    where S: Serializer {
-        let hex_string = hex::encode(key.as_bytes());
-        serializer.serialize_str(&hex_string)
+fn key_store() -> KeyStore { KeyStore::new("keys") }
+
+/// Environment variable holding the passphrase of encrypted signing keys, so that it isn't
+/// prompted for
+const PASSPHRASE_VAR: &str = "ATTESTATION_KEY_PASSPHRASE";
+
+/// Reads the passphrase of the key of `owner`, e.g. "issuer 'Registry'"
+fn read_passphrase(owner: &str) -> Result<Zeroizing<String>, String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_VAR) {
+        return Ok(Zeroizing::new(passphrase));
     }
+    rpassword::prompt_password(format!("Passphrase for {owner}: "))
+        .map(Zeroizing::new)
+        .map_err(|e| e.to_string())
+}
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<SigningKey, D::Error>
-    where D: Deserializer<'de> {
-        let hex_str: String = Deserialize::deserialize(deserializer)?;
-        let bytes = hex::decode(hex_str).map_err(de::Error::custom)?;
-        let bytes =
-            bytes.try_into().map_err(|_| de::Error::custom("Verifying key must be 32 bytes"))?;
-        Ok(SigningKey::from_bytes(&bytes))
+/// Asks for a passphrase to encrypt a key with, twice to rule out typos
+fn new_passphrase(owner: &str) -> Result<Zeroizing<String>, String> {
+    let passphrase = read_passphrase(owner)?;
+    if std::env::var(PASSPHRASE_VAR).is_err() {
+        let repeated = rpassword::prompt_password("Repeat the passphrase: ")
+            .map(Zeroizing::new)
+            .map_err(|e| e.to_string())?;
+        if repeated != passphrase {
+            return Err("Passphrases don't match".into());
+        }
     }
+    if passphrase.is_empty() {
+        return Err("Passphrase can't be empty".into());
+    }
+    Ok(passphrase)
 }
 
-#[derive(Serialize, Deserialize)]
-struct BlockFull(Block, #[serde(with = "signing_key_serde")] SigningKey);
+/// Parses a hex-encoded signing key supplied by the user
+fn parse_signing_key(hex: &str) -> Result<SecretKeyMaterial, String> {
+    let key =
+        SecretKeyMaterial::from_hex(hex.trim()).ok_or("Signing key must be 32 bytes of hex")?;
+    if key.is_zero() {
+        return Err("Refusing to use the all-zero signing key".into());
+    }
+    Ok(key)
+}
 
-type CredentialFull = (Credential, SignedCredential, SignedCredential);
+/// Reads a signing key supplied by the user from a file holding hex or a PKCS#8 PEM document
+fn read_signing_key(path: &Path) -> Result<SecretKeyMaterial, String> {
+    let contents = Zeroizing::new(fs::read_to_string(path).map_err(|_| "Failed to open key file")?);
+    if contents.trim_start().starts_with("-----BEGIN") {
+        let key = SecretKeyMaterial::from_pkcs8_pem(&contents)
+            .ok_or("Key file doesn't hold a PKCS#8 PEM ed25519 private key")?;
+        if key.is_zero() {
+            return Err("Refusing to use the all-zero signing key".into());
+        }
+        Ok(key)
+    } else {
+        parse_signing_key(&contents)
+    }
+}
+
+/// Reads a verifying key shared by an issuer, as a `SubjectPublicKeyInfo` PEM document like
+/// `issuers export-pubkey` prints, or hex
+fn read_verifying_key(path: &Path) -> Result<VerifyingKey, String> {
+    let contents = fs::read_to_string(path).map_err(|_| "Failed to open key file")?;
+    if contents.trim_start().starts_with("-----BEGIN") {
+        return VerifyingKey::from_public_key_pem(&contents)
+            .map_err(|_| "Key file doesn't hold a PEM ed25519 public key".into());
+    }
+    let bytes: [u8; 32] = hex::decode(contents.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("Key file must hold a PEM document or 64 hex digits")?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| "Key file doesn't hold a valid ed25519 key".into())
+}
+
+/// Reads a verifying key given on the command line as hex, or as a file [`read_verifying_key`]
+/// reads
+fn parse_verifying_key(value: &str) -> Result<VerifyingKey, String> {
+    let path = Path::new(value);
+    if path.is_file() {
+        return read_verifying_key(path);
+    }
+    let bytes: [u8; 32] = hex::decode(value.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("Key must be 64 hex digits or a key file")?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| "Key isn't a valid ed25519 key".into())
+}
+
+/// Loads the issuer's signing key, for commands that sign
+fn open_key(issuer: &Issuer) -> Result<SecretKeyMaterial, String> {
+    let store = key_store();
+    let error = |e: KeyStoreError| format!("Can't sign as issuer '{}': {e}", issuer.name);
+    let mode = store.loose_permissions(issuer.uuid).map_err(error)?;
+    check_permissions(&store.path(issuer.uuid), mode)?;
+    store.load(issuer.uuid, || read_passphrase(&format!("issuer '{}'", issuer.name))).map_err(error)
+}
+
+/// Loads the subject's key, for consenting to its credentials or presenting them, as `action`
+/// says
+fn open_subject_key(subject: &Subject, action: &str) -> Result<SecretKeyMaterial, String> {
+    let owner = format!("subject '{} {}'", subject.name, subject.surname);
+    let verifying = subject.verifying.ok_or(format!(
+        "The {owner} has no key to {action} with, add subjects with `subjects add --with-key`"
+    ))?;
+    let store = key_store();
+    let error = |e: KeyStoreError| format!("Can't {action} as {owner}: {e}");
+    let mode = store.loose_permissions(subject.uuid).map_err(error)?;
+    check_permissions(&store.path(subject.uuid), mode)?;
+    let key = store.load(subject.uuid, || read_passphrase(&owner)).map_err(error)?;
+    if key.signing().verifying_key() != verifying {
+        return Err(format!("Stored key of {owner} doesn't match it"));
+    }
+    Ok(key)
+}
+
+/// Subject a credential is about, found by its key among the stored subjects if the credential
+/// only commits to it
+fn holder(subject: &CredentialSubject) -> Result<Subject, String> {
+    if let Some(subject) = subject.disclosed() {
+        return Ok(subject.clone());
+    }
+    let key = subject.verifying().ok_or("The private subject has no key")?;
+    open_subjects()?
+        .into_iter()
+        .find(|subject| subject.verifying.as_ref() == Some(key))
+        .ok_or("No stored subject holds the private subject's key".into())
+}
+
+/// Name and UUID of a credential's subject, or its commitment if the credential only commits to
+/// it
+fn subject_summary(subject: &CredentialSubject) -> String {
+    match subject {
+        CredentialSubject::Disclosed(subject) =>
+            format!("{} {} ({})", subject.name, subject.surname, subject.uuid),
+        CredentialSubject::Committed(commitment) =>
+            format!("private, committed to as {}", commitment.commitment),
+    }
+}
+
+/// Length of the longest bar in `blockchain stats` histograms
+const HISTOGRAM_WIDTH: usize = 30;
+
+/// How often `blockchain tail --follow` reads the chain again
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Set by `--quiet`, hiding progress bars
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Progress bar on stderr for operations over the whole chain, showing the blocks processed, the
+/// rate and the time left. It's hidden when stderr isn't a terminal, with `--quiet` or if `hidden`,
+/// which commands printing JSON set.
+struct ProgressBarSink(ProgressBar);
+
+impl ProgressBarSink {
+    fn new(message: &'static str, hidden: bool) -> Self {
+        if hidden || QUIET.load(atomic::Ordering::Relaxed) || !io::stderr().is_terminal() {
+            return Self(ProgressBar::hidden());
+        }
+        let style = ProgressStyle::with_template(
+            "{msg} [{bar:30}] {pos}/{len} blocks, {per_sec}, ETA {eta}",
+        )
+        .expect("progress template is valid")
+        .progress_chars("=> ");
+        Self(ProgressBar::new(0).with_style(style).with_message(message))
+    }
+}
+
+impl ProgressSink for ProgressBarSink {
+    fn start(&mut self, total: u64) { self.0.set_length(total); }
+
+    fn tick(&mut self) { self.0.inc(1); }
+
+    fn finish(&mut self) { self.0.finish_and_clear(); }
+}
+
+/// Set by `--strict-permissions`, turning warnings about key files other users can access into
+/// errors
+static STRICT_PERMISSIONS: AtomicBool = AtomicBool::new(false);
+
+/// Warns about a key file with the given loose mode, or refuses it with `--strict-permissions`
+fn check_permissions(path: &Path, mode: Option<u32>) -> Result<(), String> {
+    let Some(mode) = mode else {
+        return Ok(());
+    };
+    let problem = format!(
+        "signing key file {} is accessible by other users (mode {mode:o}), run `chmod 600` on it",
+        path.display()
+    );
+    if STRICT_PERMISSIONS.load(atomic::Ordering::Relaxed) {
+        return Err(format!("Refusing to sign: {problem}"));
+    }
+    eprintln!("WARNING: {problem}");
+    Ok(())
+}
+
+/// Signs as the issuer with the program `attestation.toml` configures for it, or else with its
+/// key file
+fn open_signer(issuer: &Issuer) -> Result<Box<dyn BlockSigner>, String> {
+    if let Some(config) = open_config()?.signers.remove(&issuer.uuid) {
+        return Ok(Box::new(ExternalSigner::new(config, issuer.verifying)));
+    }
+    Ok(Box::new(open_key(issuer)?))
+}
+
+fn open_config() -> Result<Config, String> {
+    match fs::read_to_string("attestation.toml") {
+        Ok(config) => toml::from_str(&config).map_err(|e| format!("Failed to parse config: {e}")),
+        Err(_) => Ok(Config::default()),
+    }
+}
+
+/// Chain policy from the config, with the clock skew overridden if given on the command line
+fn chain_policy(allow_clock_skew: Option<u64>) -> Result<ChainPolicy, String> {
+    let seconds = match allow_clock_skew {
+        Some(seconds) => seconds,
+        None => open_config()?.allow_clock_skew,
+    };
+    let seconds = i64::try_from(seconds).map_err(|_| "Clock skew is too large")?;
+    let clock_skew = TimeDelta::try_seconds(seconds).ok_or("Clock skew is too large")?;
+    Ok(ChainPolicy { clock_skew })
+}
+
+/// Import going ahead despite blocks breaking the import policy, with `--accept-violations`
+struct ImportOverride {
+    command: &'static str,
+    source: String,
+    dry_run: bool,
+}
+
+/// Checks the blocks brought in from the height on against the `[import]` policy of the config,
+/// refusing them if any breaks it unless overridden, which is recorded in audit.log
+fn check_import_policy(
+    blockchain: &Blockchain, from: u64, overriding: Option<ImportOverride>,
+) -> Result<(), String> {
+    let policy = open_config()?.import;
+    let violations = policy.check(blockchain, from, &open_trust()?, Utc::now());
+    if violations.is_empty() {
+        return Ok(());
+    }
+    for violation in &violations {
+        eprintln!("Import policy violation: {violation}");
+    }
+    let mut heights: Vec<u64> = violations.iter().map(|violation| violation.height).collect();
+    heights.dedup();
+    let Some(ImportOverride { command, source, dry_run }) = overriding else {
+        return Err(format!(
+            "Refusing to import {} blocks breaking the import policy, pass --accept-violations \
+             to import them anyway",
+            heights.len()
+        ));
+    };
+    if !dry_run {
+        let command = command.to_string();
+        append_audit(&AuditRecord::new(AuditEvent::ImportPolicyOverridden {
+            command,
+            source,
+            violations,
+        }))?;
+    }
+    eprintln!(
+        "Warning: accepting {} blocks breaking the import policy, recorded in audit.log",
+        heights.len()
+    );
+    Ok(())
+}
+
+fn append_audit(record: &AuditRecord) -> Result<(), String> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open("audit.log")
+        .map_err(|_| "Failed to open audit log")?;
+    let line = serde_json::to_string(record).map_err(|_| "Failed to write audit log")?;
+    writeln!(file, "{line}").map_err(|_| "Failed to write audit log".into())
+}
 
-#[derive(Serialize, Deserialize)]
-struct IssuerFull(Issuer, #[serde(with = "signing_key_serde")] SigningKey);
+/// Notifies the webhooks set in the config of the chain's last block. Failures are only
+/// reported, the block being on the chain already.
+fn notify_webhooks(blockchain: &Blockchain) {
+    let webhooks = match open_config() {
+        Ok(config) => config.webhooks,
+        Err(e) => {
+            eprintln!("Warning: not notifying webhooks: {e}");
+            return;
+        },
+    };
+    let Some(block) = blockchain.tip().filter(|_| !webhooks.is_empty()) else {
+        return;
+    };
+    #[cfg(feature = "client")]
+    {
+        let notifications = Notification::for_block(blockchain.id(), block);
+        let errors = match Webhooks::new() {
+            Ok(client) => client.deliver(&webhooks, &notifications).err().unwrap_or_default(),
+            Err(e) => {
+                eprintln!("Warning: not notifying webhooks: {e}");
+                return;
+            },
+        };
+        for error in errors {
+            for (attempt, reason) in error.attempts.iter().enumerate() {
+                eprintln!("Webhook {} attempt {} failed: {reason}", error.url, attempt + 1);
+            }
+            eprintln!("Warning: {error}");
+        }
+    }
+    #[cfg(not(feature = "client"))]
+    eprintln!("Warning: not notifying webhooks of block #{}: {NO_CLIENT}", block.height());
+}
 
-fn open_block() -> Result<BlockFull, &'static str> {
+fn open_block() -> Result<Block, String> {
     let reader = File::open_buffered("block.json").map_err(|_| "Failed to open block file")?;
-    let block: Option<BlockFull> =
+    let block: Option<Stored<Block>> =
         serde_json::from_reader(reader).map_err(|_| "Failed to parse block")?;
-    block.ok_or("Block is not initialized")
+    block.ok_or("Block is not initialized")?.public()
 }
 
-fn save_block(block: &BlockFull) -> Result<(), &'static str> {
+fn save_block(block: &Block) -> Result<(), String> {
     let writer = File::create_buffered("block.json").map_err(|_| "Failed to open block file")?;
-    serde_json::to_writer(writer, &block).map_err(|_| "Failed to write block")
+    serde_json::to_writer(writer, &block).map_err(|_| "Failed to write block".into())
 }
 
-fn open_blockchain() -> Result<Blockchain, &'static str> {
+fn open_blockchain() -> Result<Blockchain, String> {
     let reader =
         File::open_buffered("blockchain.json").map_err(|_| "Failed to open blockchain file")?;
-    serde_json::from_reader(reader).map_err(|_| "Failed to parse blockchain")
+    serde_json::from_reader(reader).map_err(|_| "Failed to parse blockchain".into())
 }
 
-fn save_blockchain(blockchain: &Blockchain) -> Result<(), &'static str> {
+fn save_blockchain(blockchain: &Blockchain) -> Result<(), String> {
     let writer =
         File::create_buffered("blockchain.json").map_err(|_| "Failed to open blockchain file")?;
-    serde_json::to_writer(writer, &blockchain).map_err(|_| "Failed to write blockchain")
+    serde_json::to_writer(writer, &blockchain).map_err(|_| "Failed to write blockchain".into())
 }
 
-fn open_credentials() -> Result<Vec<CredentialFull>, &'static str> {
+fn open_export(path: &Path) -> Result<CredentialExport, String> {
+    let contents = fs::read_to_string(path).map_err(|_| "Failed to open exported credential")?;
+    CredentialExport::from_json(&contents)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))
+}
+
+/// Replaces the chain in one step, so that readers see either the old chain or the new one
+fn replace_blockchain(blockchain: &Blockchain) -> Result<(), String> {
+    let temporary = Path::new("blockchain.json.tmp");
+    let write = || -> io::Result<()> {
+        let mut writer = File::create_buffered(temporary)?;
+        serde_json::to_writer(&mut writer, &blockchain)?;
+        writer.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()
+    };
+    write().map_err(|_| "Failed to write blockchain file")?;
+    fs::rename(temporary, "blockchain.json").map_err(|_| "Failed to replace blockchain file".into())
+}
+
+fn open_credentials() -> Result<Vec<CredentialFull>, String> {
     let reader =
         File::open_buffered("credentials.json").map_err(|_| "Failed to open credentials file")?;
-    serde_json::from_reader(reader).map_err(|_| "Failed to parse credentials")
+    serde_json::from_reader(reader).map_err(|_| "Failed to parse credentials".into())
 }
 
-fn save_credentials(credentials: &[CredentialFull]) -> Result<(), &'static str> {
+fn save_credentials(credentials: &[CredentialFull]) -> Result<(), String> {
     let writer =
         File::create_buffered("credentials.json").map_err(|_| "Failed to open credentials file")?;
-    serde_json::to_writer(writer, &credentials).map_err(|_| "Failed to write credentials")
+    serde_json::to_writer(writer, &credentials).map_err(|_| "Failed to write credentials".into())
 }
 
-fn open_issuers() -> Result<Vec<IssuerFull>, &'static str> {
+fn open_issuers() -> Result<Vec<Issuer>, String> {
     let reader = File::open_buffered("issuers.json").map_err(|_| "Failed to open issuers file")?;
-    serde_json::from_reader(reader)
+    let issuers: Vec<Stored<Issuer>> = serde_json::from_reader(reader)
         .inspect_err(|e| println!("{e}"))
-        .map_err(|_| "Failed to parse issuers")
+        .map_err(|_| "Failed to parse issuers")?;
+    issuers.into_iter().map(Stored::public).collect()
 }
 
-fn save_issuers(issuers: &[IssuerFull]) -> Result<(), &'static str> {
+fn save_issuers(issuers: &[Issuer]) -> Result<(), String> {
     let writer =
         File::create_buffered("issuers.json").map_err(|_| "Failed to open issuers file")?;
-    serde_json::to_writer(writer, &issuers).map_err(|_| "Failed to write issuers")
+    serde_json::to_writer(writer, &issuers).map_err(|_| "Failed to write issuers".into())
 }
 
-fn open_subjects() -> Result<Vec<Subject>, &'static str> {
+fn open_subjects() -> Result<Vec<Subject>, String> {
     let reader =
         File::open_buffered("subjects.json").map_err(|_| "Failed to open subjects file")?;
-    serde_json::from_reader(reader).map_err(|_| "Failed to parse subjects")
+    serde_json::from_reader(reader).map_err(|_| "Failed to parse subjects".into())
 }
 
-fn save_subjects(subjects: &[Subject]) -> Result<(), &'static str> {
+fn save_subjects(subjects: &[Subject]) -> Result<(), String> {
     let writer =
         File::create_buffered("subjects.json").map_err(|_| "Failed to open subjects file")?;
-    serde_json::to_writer(writer, &subjects).map_err(|_| "Failed to write subjects")
+    serde_json::to_writer(writer, &subjects).map_err(|_| "Failed to write subjects".into())
+}
+
+/// Attribute schemas, none if no schema was added yet
+fn open_schemas() -> Result<Vec<AttributeSchema>, String> {
+    let reader = match File::open_buffered("schemas.json") {
+        Ok(reader) => reader,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(_) => return Err("Failed to open schemas file".into()),
+    };
+    serde_json::from_reader(reader).map_err(|_| "Failed to parse schemas".into())
+}
+
+fn save_schemas(schemas: &[AttributeSchema]) -> Result<(), String> {
+    let writer =
+        File::create_buffered("schemas.json").map_err(|_| "Failed to open schemas file")?;
+    serde_json::to_writer(writer, &schemas).map_err(|_| "Failed to write schemas".into())
+}
+
+/// Issuer keys trusted by `--trusted-only`, none if no issuer was trusted yet
+fn open_trust() -> Result<TrustStore, String> {
+    let reader = match File::open_buffered("trust.json") {
+        Ok(reader) => reader,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(TrustStore::default()),
+        Err(_) => return Err("Failed to open trust store".into()),
+    };
+    serde_json::from_reader(reader).map_err(|_| "Failed to parse trust store".into())
+}
+
+fn save_trust(trust: &TrustStore) -> Result<(), String> {
+    let writer = File::create_buffered("trust.json").map_err(|_| "Failed to open trust store")?;
+    serde_json::to_writer(writer, trust).map_err(|_| "Failed to write trust store".into())
+}
+
+/// Anchors produced with `blockchain anchor`, oldest first, none if the chain wasn't anchored yet
+fn open_anchors() -> Result<Vec<ChainAnchor>, String> {
+    let reader = match File::open_buffered("anchors.json") {
+        Ok(reader) => reader,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(_) => return Err("Failed to open anchors file".into()),
+    };
+    serde_json::from_reader(reader).map_err(|_| "Failed to parse anchors".into())
+}
+
+fn save_anchors(anchors: &[ChainAnchor]) -> Result<(), String> {
+    let writer =
+        File::create_buffered("anchors.json").map_err(|_| "Failed to open anchors file")?;
+    serde_json::to_writer(writer, &anchors).map_err(|_| "Failed to write anchors".into())
+}
+
+/// Issuance requests submitted over the server, oldest first, none if none was submitted yet
+fn open_requests() -> Result<Vec<IssuanceRequest>, String> {
+    let reader = match File::open_buffered("requests.json") {
+        Ok(reader) => reader,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(_) => return Err("Failed to open requests file".into()),
+    };
+    serde_json::from_reader(reader).map_err(|_| "Failed to parse requests".into())
+}
+
+fn save_requests(requests: &[IssuanceRequest]) -> Result<(), String> {
+    let writer =
+        File::create_buffered("requests.json").map_err(|_| "Failed to open requests file")?;
+    serde_json::to_writer(writer, &requests).map_err(|_| "Failed to write requests".into())
+}
+
+/// Trust store for `--trusted-only`, which would reject everything if it was empty
+fn open_trust_for_check() -> Result<TrustStore, String> {
+    let trust = open_trust()?;
+    if trust.is_empty() {
+        return Err("No issuers are trusted, add them with `issuers trust` first".into());
+    }
+    Ok(trust)
+}
+
+/// Credential templates, none if no template was added yet
+fn open_templates() -> Result<Vec<CredentialTemplate>, String> {
+    let reader = match File::open_buffered("templates.json") {
+        Ok(reader) => reader,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(_) => return Err("Failed to open templates file".into()),
+    };
+    serde_json::from_reader(reader).map_err(|_| "Failed to parse templates".into())
+}
+
+fn save_templates(templates: &[CredentialTemplate]) -> Result<(), String> {
+    let writer =
+        File::create_buffered("templates.json").map_err(|_| "Failed to open templates file")?;
+    serde_json::to_writer(writer, &templates).map_err(|_| "Failed to write templates".into())
 }
 
 #[derive(Parser)]
 #[command()]
 pub struct Cli {
+    /// Refuse to use signing key files that other users can access instead of warning
+    #[arg(long, global = true)]
+    strict_permissions: bool,
+    /// Whether to color the output, `auto` only does on a terminal and unless `NO_COLOR` is set
+    #[arg(long, global = true, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+    /// Don't show progress bars
+    #[arg(short, long, global = true)]
+    quiet: bool,
     #[command(subcommand)]
     subcommand: Subcommands,
 }
 
 impl Cli {
     #[expect(clippy::missing_errors_doc)]
-    pub fn run(self) -> Result<(), &'static str> { self.subcommand.run() }
+    pub fn run(self) -> Result<(), String> {
+        STRICT_PERMISSIONS.store(self.strict_permissions, atomic::Ordering::Relaxed);
+        QUIET.store(self.quiet, atomic::Ordering::Relaxed);
+        ui::set_color_choice(match self.color {
+            ColorChoice::Auto => ui::ColorChoice::Auto,
+            ColorChoice::Always => ui::ColorChoice::Always,
+            ColorChoice::Never => ui::ColorChoice::Never,
+        });
+        self.subcommand.run()
+    }
 }
 
 #[derive(Subcommand)]
@@ -133,254 +615,4446 @@ enum Subcommands {
         #[command(subcommand)]
         subcommand: IssuerSubcommands,
     },
+    /// Move signing keys stored by older versions in issuers.json and block.json to keys/
+    Migrate,
+    /// List, approve or reject issuance requests submitted over the server
+    Requests {
+        #[command(subcommand)]
+        subcommand: RequestSubcommands,
+    },
+    /// Add or list attribute schemas credentials are validated against
+    Schemas {
+        #[command(subcommand)]
+        subcommand: SchemaSubcommands,
+    },
+    /// Serve the local stores over HTTP, see the README for the endpoints
+    Serve {
+        /// Address to listen on, port 0 picking a free one
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        bind: String,
+        /// Serve the gRPC service of proto/attestation.proto instead of HTTP
+        #[arg(long)]
+        grpc: bool,
+    },
     /// Add or list subjects
     Subjects {
         #[command(subcommand)]
         subcommand: SubjectSubcommands,
     },
+    /// Add or list templates credentials are issued from
+    Templates {
+        #[command(subcommand)]
+        subcommand: TemplateSubcommands,
+    },
 }
 
 impl Subcommands {
-    fn run(self) -> Result<(), &'static str> {
+    fn run(self) -> Result<(), String> {
         match self {
             Self::Block { subcommand } => subcommand.run(),
             Self::Blockchain { subcommand } => subcommand.run(),
             Self::Credentials { subcommand } => subcommand.run(),
             Self::Issuers { subcommand } => subcommand.run(),
+            Self::Migrate => migrate(),
+            Self::Requests { subcommand } => subcommand.run(),
+            Self::Schemas { subcommand } => subcommand.run(),
+            Self::Serve { bind, grpc: false } => serve(&bind),
+            Self::Serve { bind, grpc: true } => serve_grpc(&bind),
             Self::Subjects { subcommand } => subcommand.run(),
+            Self::Templates { subcommand } => subcommand.run(),
+        }
+    }
+}
+
+/// Backend of `serve`, over the stores in the working directory
+struct LocalBackend;
+
+impl Backend for LocalBackend {
+    fn blockchain(&self) -> Result<Blockchain, ApiError> {
+        open_blockchain().map_err(ApiError::Internal)
+    }
+
+    fn submit_request(&self, submission: RequestSubmission) -> Result<IssuanceRequest, ApiError> {
+        let issuers = open_issuers().map_err(ApiError::Internal)?;
+        if !issuers.iter().any(|issuer| issuer.uuid == submission.issuer && !issuer.deleted) {
+            return Err(ApiError::BadRequest(format!("no issuer {}", submission.issuer)));
+        }
+        if let RequestedSubject::Uuid(uuid) = submission.subject {
+            let subjects = open_subjects().map_err(ApiError::Internal)?;
+            if !subjects.iter().any(|subject| subject.uuid == uuid && !subject.deleted) {
+                return Err(ApiError::BadRequest(format!("no subject {uuid}")));
+            }
+        }
+        let request =
+            IssuanceRequest::new(submission).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        let mut requests = open_requests().map_err(ApiError::Internal)?;
+        requests.push(request.clone());
+        save_requests(&requests).map_err(ApiError::Internal)?;
+        Ok(request)
+    }
+}
+
+fn serve(bind: &str) -> Result<(), String> {
+    let config = open_config()?.server;
+    if config.token.is_none() {
+        println!("No server token set in attestation.toml, issuance requests will be refused");
+    }
+    let scheme = if config.tls.is_some() { "https" } else { "http" };
+    let server = Server::bind(bind, config).map_err(|e| e.to_string())?;
+    match server.local_addr() {
+        Some(address) => println!("Listening on {scheme}://{address}"),
+        None => println!("Listening on {bind}"),
+    }
+    server.run(&LocalBackend).map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "grpc")]
+fn serve_grpc(bind: &str) -> Result<(), String> {
+    let config = open_config()?.server;
+    let tls = if config.tls.is_some() { " over TLS" } else { "" };
+    let server = GrpcServer::bind(bind, config).map_err(|e| e.to_string())?;
+    match server.local_addr() {
+        Some(address) => println!("Listening for gRPC{tls} on {address}"),
+        None => println!("Listening for gRPC{tls} on {bind}"),
+    }
+    server.run(LocalBackend).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "grpc"))]
+fn serve_grpc(_: &str) -> Result<(), String> {
+    Err("This build can't serve gRPC, build with `--features grpc`".to_string())
+}
+
+/// Moves signing keys out of issuers.json and block.json into the key store
+fn migrate() -> Result<(), String> {
+    let store = key_store();
+    let mut moved = 0;
+    let mut keep = |issuer: &Issuer, key: &SecretKeyMaterial| -> Result<(), String> {
+        if key.signing().verifying_key() != issuer.verifying {
+            return Err(format!("Stored key of issuer '{}' doesn't match it", issuer.name));
         }
+        if !store.contains(issuer.uuid) {
+            store.save(issuer.uuid, key).map_err(|e| e.to_string())?;
+            moved += 1;
+        }
+        Ok(())
+    };
+
+    let reader = File::open_buffered("issuers.json").map_err(|_| "Failed to open issuers file")?;
+    let stored: Vec<Stored<Issuer>> =
+        serde_json::from_reader(reader).map_err(|_| "Failed to parse issuers")?;
+    let mut issuers = Vec::new();
+    for issuer in stored {
+        issuers.push(match issuer {
+            Stored::Public(issuer) => issuer,
+            Stored::WithKey(issuer, key) => {
+                keep(&issuer, &key)?;
+                issuer
+            },
+        });
     }
+
+    let reader = File::open_buffered("block.json").map_err(|_| "Failed to open block file")?;
+    let block: Option<Stored<Block>> =
+        serde_json::from_reader(reader).map_err(|_| "Failed to parse block")?;
+    let block = match block {
+        Some(Stored::WithKey(block, key)) => {
+            keep(block.signer(), &key)?;
+            Some(block)
+        },
+        Some(Stored::Public(block)) => Some(block),
+        None => None,
+    };
+
+    // Keys are saved before the files losing them are rewritten
+    save_issuers(&issuers)?;
+    match &block {
+        Some(block) => save_block(block)?,
+        None => fs::write("block.json", "null").map_err(|_| "Failed to open block file")?,
+    }
+    if moved == 0 {
+        println!("No signing keys to migrate");
+    } else {
+        println!("Moved {moved} signing keys to {}", store.dir().display());
+    }
+    Ok(())
 }
 
 #[derive(Subcommand)]
 enum BlockSubcommands {
     /// Add a credential to a block
-    Add { credential: usize },
+    Add {
+        /// Index, UUID or hash prefix of the credential
+        credential: String,
+        /// Anchor a credential signed by another issuer than the block's signer
+        #[arg(long)]
+        allow_foreign: bool,
+    },
     /// Display block
-    Display,
+    Display {
+        /// `table` lists the credentials the block holds, the others give the block as stored
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        /// Give the block as stored, same as `--format json-pretty`
+        #[arg(long, conflicts_with = "format")]
+        raw: bool,
+    },
     /// Finalize block and add to the blockchain
-    Finalize,
+    Finalize {
+        /// Seconds the block may be timestamped before the previous block, overrides the config
+        #[arg(long)]
+        allow_clock_skew: Option<u64>,
+        /// Finalize even if the block issues credentials already issued on the chain
+        #[arg(long)]
+        force: bool,
+        /// Don't notify the webhooks set in the config of the block
+        #[arg(long)]
+        no_webhooks: bool,
+    },
     /// Create new block
-    New { issuer: usize },
+    New {
+        /// Index, UUID or UUID prefix of the issuer signing the block
+        issuer: String,
+    },
     /// Add a credential to the block's revoking list
-    Revoke { credential: usize },
+    Revoke {
+        /// Index, UUID or hash prefix of the credential
+        credential: String,
+        /// Revoke even if the credential hasn't been issued on the chain
+        #[arg(long)]
+        force: bool,
+        /// Why the credential is revoked, recorded on chain with the time of revocation
+        #[arg(long, value_enum)]
+        reason: Option<ReasonArg>,
+        /// Free-form explanation of the reason, required for `--reason other`
+        #[arg(long, requires = "reason")]
+        note: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReasonArg {
+    Expired,
+    Superseded,
+    Compromised,
+    IssuedInError,
+    Other,
+}
+
+/// Details of a revocation given on the command line, the note of `--reason other` becoming the
+/// reason itself
+fn revocation_details(
+    reason: Option<ReasonArg>, note: Option<String>,
+) -> Result<Option<RevocationDetails>, String> {
+    let Some(reason) = reason else {
+        return Ok(None);
+    };
+    let (reason, note) = match reason {
+        ReasonArg::Expired => (RevocationReason::Expired, note),
+        ReasonArg::Superseded => (RevocationReason::Superseded, note),
+        ReasonArg::Compromised => (RevocationReason::Compromised, note),
+        ReasonArg::IssuedInError => (RevocationReason::IssuedInError, note),
+        ReasonArg::Other => {
+            let reason = note.ok_or("`--reason other` needs a --note explaining it")?;
+            (RevocationReason::Other(reason), None)
+        },
+    };
+    Ok(Some(RevocationDetails::new(reason, note)))
 }
 
 impl BlockSubcommands {
-    fn run(self) -> Result<(), &'static str> {
+    fn run(self) -> Result<(), String> {
         match self {
-            Self::Add { credential } => Self::add(credential),
-            Self::Display => Self::display(),
-            Self::Finalize => Self::finalize(),
-            Self::New { issuer } => Self::new(issuer),
-            Self::Revoke { credential } => Self::revoke(credential),
+            Self::Add { credential, allow_foreign } => Self::add(&credential, allow_foreign),
+            Self::Display { format, raw } =>
+                Self::display(if raw { OutputFormat::JsonPretty } else { format }),
+            Self::Finalize { allow_clock_skew, force, no_webhooks } =>
+                Self::finalize(allow_clock_skew, force, no_webhooks),
+            Self::New { issuer } => Self::new(&issuer),
+            Self::Revoke { credential, force, reason, note } =>
+                Self::revoke(&credential, force, revocation_details(reason, note)?),
         }
     }
 
-    fn add(credential: usize) -> Result<(), &'static str> {
+    fn add(credential: &str, allow_foreign: bool) -> Result<(), String> {
         let mut block = open_block()?;
         let mut credentials = open_credentials()?;
-        if credential >= credentials.len() {
-            return Err("No credential with given index");
+        let index = credential_index(&credentials, credential)?;
+        let (credential, signed, _) = credentials.swap_remove(index);
+        // Credentials signed before a rotation can still go in the block recording it
+        let retiring = block.key_rotations().iter().any(|r| {
+            r.issuer == credential.issuer.uuid && r.previous == credential.issuer.verifying
+        });
+        if signed.verify(&block.signer().verifying)
+            || (retiring
+                && block.signer().uuid == credential.issuer.uuid
+                && signed.verify(&credential.issuer.verifying))
+        {
+            block.add_credential(signed, false).map_err(|e| e.to_string())?;
+            println!("Added credential to the block");
+        } else if allow_foreign {
+            block.anchor_credential(signed).map_err(|e| e.to_string())?;
+            println!("Anchored foreign credential in the block");
+        } else {
+            return Err(foreign_credential_error(index, &credential, &block));
         }
-        let signed = credentials.swap_remove(credential).1;
-        block.0.add_credential(signed, false);
-        println!("Added credential to the block");
         save_block(&block)?;
         Ok(())
     }
 
-    fn display() -> Result<(), &'static str> {
-        let block = open_block()?.0;
-        println!("{block}");
+    fn display(format: OutputFormat) -> Result<(), String> {
+        let block = open_block()?;
+        if format != OutputFormat::Table {
+            return format.show(&block, || {});
+        }
+        // Blocks only hold hashes, which are looked up among the stored credentials' hashes
+        let credentials = open_credentials()?;
+        let hash_format = open_blockchain()?.format();
+        let mut stored = HashMap::new();
+        for (index, (credential, ..)) in credentials.iter().enumerate() {
+            for revoking in [false, true] {
+                stored.insert(credential.hash(revoking, hash_format), (index, credential));
+            }
+        }
+        let revoked = block.revoked_credentials().iter().map(|revocation| &revocation.signed);
+        let entries = (block.new_credentials().iter().map(|signed| (ListKind::New, signed)))
+            .chain(revoked.map(|signed| (ListKind::Revoked, signed)))
+            .chain(block.anchored_credentials().iter().map(|signed| (ListKind::Anchored, signed)));
+        let mut table = Table::new(["LIST", "HASH", "INDEX", "UUID", "SUBJECT", "ATTRIBUTES"]);
+        for (kind, signed) in entries {
+            let hash = &signed.credential;
+            let Some((index, credential)) = stored.get(hash) else {
+                let unknown = "unknown (not in local credential store)";
+                table.row([&kind.to_string(), &hash.short(), "-", "-", unknown]);
+                continue;
+            };
+            let subject = &credential.subject;
+            let attributes: Vec<_> = credential
+                .attributes
+                .iter()
+                .map(|attribute| format!("{}={}", attribute.name, attribute.value))
+                .collect();
+            table.row([
+                kind.to_string(),
+                hash.short(),
+                index.to_string(),
+                short_uuid(credential.uuid),
+                subject.full_name(),
+                attributes.join(", "),
+            ]);
+        }
+        println!("Block signed by {}", block.signer().name);
+        print_table(&table);
         Ok(())
     }
 
-    fn finalize() -> Result<(), &'static str> {
+    fn finalize(
+        allow_clock_skew: Option<u64>, force: bool, no_webhooks: bool,
+    ) -> Result<(), String> {
+        let policy = chain_policy(allow_clock_skew)?;
         let mut blockchain = open_blockchain()?;
         let block = open_block()?;
-        blockchain.add_block(block.0, &block.1);
+        if !force {
+            for signed in block.issued_credentials() {
+                if let Some(height) = blockchain.issued_in(&signed.credential) {
+                    return Err(format!(
+                        "Block issues a credential already issued in block {height}, use --force \
+                         to finalize anyway"
+                    ));
+                }
+            }
+        }
+        let signer = open_signer(block.signer())?;
+        blockchain.add_block_with(block, signer.as_ref(), &policy).map_err(|e| e.to_string())?;
+        drop(signer);
         fs::write("block.json", "null").map_err(|_| "Failed to open block file")?;
         save_blockchain(&blockchain)?;
         println!("Added block to blockchain");
+        if let Some(block) = blockchain.tip() {
+            promote_rotated_keys(block)?;
+        }
+        if !no_webhooks {
+            notify_webhooks(&blockchain);
+        }
         Ok(())
     }
 
     #[expect(clippy::new_ret_no_self)]
-    fn new(issuer: usize) -> Result<(), &'static str> {
-        let mut issuers = open_issuers()?;
-        if issuer >= issuers.len() {
-            return Err("No issuer with given index");
-        }
-        let issuer = issuers.swap_remove(issuer);
-        let block = BlockFull(Block::new(issuer.0), issuer.1);
-        save_block(&block)?;
+    fn new(issuer: &str) -> Result<(), String> {
+        let issuers = open_issuers()?;
+        let issuer = issuers[issuer_index(&issuers, issuer)?].clone();
+        // Fail now rather than when finalizing if the block couldn't be signed
+        drop(open_signer(&issuer)?);
+        save_block(&Block::new(issuer))?;
         println!("Created a new block with a given issuer");
         Ok(())
     }
 
-    fn revoke(credential: usize) -> Result<(), &'static str> {
+    fn revoke(
+        credential: &str, force: bool, details: Option<RevocationDetails>,
+    ) -> Result<(), String> {
         let mut block = open_block()?;
         let mut credentials = open_credentials()?;
-        if credential >= credentials.len() {
-            return Err("No credential with given index");
+        let index = credential_index(&credentials, credential)?;
+        let (credential, issued, signed) = credentials.swap_remove(index);
+        let block_signer = block.signer();
+        // The revocation is signed with the key the credential was issued under, which the
+        // issuer may have rotated away from since
+        if block_signer.uuid != credential.issuer.uuid
+            || !block_signer.has_used_key(&credential.issuer.verifying)
+            || !signed.verify(&credential.issuer.verifying)
+        {
+            return Err(foreign_credential_error(index, &credential, &block));
+        }
+        let pending = block.issues(&issued.credential);
+        if !force && !pending && !open_blockchain()?.has_issued(&issued.credential) {
+            return Err(format!(
+                "credential {} has not been issued on this chain",
+                credential.uuid
+            ));
         }
-        let signed = credentials.swap_remove(credential).2;
-        block.0.add_credential(signed, true);
+        block.add_revocation(Revocation::new(signed, details)).map_err(|e| e.to_string())?;
         save_block(&block)?;
         println!("Added credential to the block's revoking list");
         Ok(())
     }
 }
 
+/// Switches the issuers whose rotation the block records to the keys `issuers rotate-key` staged
+fn promote_rotated_keys(block: &Block) -> Result<(), String> {
+    if block.key_rotations().is_empty() {
+        return Ok(());
+    }
+    let mut issuers = open_issuers()?;
+    let store = key_store();
+    for rotation in block.key_rotations() {
+        let Some(issuer) = issuers
+            .iter_mut()
+            .find(|issuer| issuer.uuid == rotation.issuer && issuer.verifying == rotation.previous)
+        else {
+            continue;
+        };
+        let promoted = store
+            .promote(issuer.uuid)
+            .map_err(|e| format!("Failed to switch to the new key: {e}"))?;
+        if promoted {
+            issuer.rotate_key(rotation.new);
+            println!("Issuer '{}' now signs with its new key", issuer.name);
+        }
+    }
+    save_issuers(&issuers)
+}
+
+fn foreign_credential_error(index: usize, credential: &Credential, block: &Block) -> String {
+    format!(
+        "credential {index} was signed by '{}', but the pending block belongs to '{}'",
+        credential.issuer.name,
+        block.signer().name
+    )
+}
+
 #[derive(Subcommand)]
 enum BlockchainSubcommands {
+    /// Sign a statement of the chain's head to publish elsewhere, and log it to anchors.json
+    Anchor {
+        /// Index, UUID or UUID prefix of the issuer signing the anchor, a chain authority if the
+        /// chain has any
+        #[arg(long, value_name = "ISSUER")]
+        sign_with: String,
+        /// Write the anchor to a file rather than printing it
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Report issuers whose verifying key differs between the chain, credentials and issuers
+    AuditIssuers {
+        /// Print the conflicts as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List or amend the issuers allowed to sign blocks
+    Authorities {
+        #[command(subcommand)]
+        subcommand: AuthoritySubcommands,
+    },
+    /// Check that the chain only grew since an anchor exported with `blockchain anchor`, and
+    /// report the blocks rewritten or dropped otherwise
+    CheckAnchor {
+        file: PathBuf,
+        /// Seconds a block may be timestamped before the previous block, overrides the config
+        #[arg(long)]
+        allow_clock_skew: Option<u64>,
+    },
+    /// Compare the local blockchain with one stored in another file
+    Compare { file: PathBuf },
     /// Display blockchain
-    Display,
+    Display {
+        /// Display only the block with the given height or hash prefix
+        #[arg(long, value_name = "HEIGHT|HASH")]
+        block: Option<String>,
+        /// Display only the last block
+        #[arg(long, conflicts_with = "block")]
+        last: bool,
+        /// Display the blocks with heights in the range, as `FROM..TO` excluding `TO`,
+        /// `FROM..=TO` or `FROM..`
+        #[arg(long, value_parser = parse_height_range, conflicts_with_all = ["block", "last"])]
+        range: Option<HeightRange>,
+        /// `table` gives one summary line per block, the others the whole blocks, `json-pretty` if
+        /// not given
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+        /// Display a digest of the blocks followed by their totals, as a table if no format is
+        /// given
+        #[arg(long)]
+        summary: bool,
+        #[command(flatten)]
+        remote: RemoteArgs,
+    },
+    /// Export the whole chain, genesis block included, to move or publish it
+    Export {
+        /// Write the export to a file, conventionally named `*.export.json`, rather than
+        /// printing it
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Export the hashes of every revoked credential as a list signed by an issuer, for relying
+    /// parties to check cached credentials against without the chain
+    ExportRevocations {
+        /// Index, UUID or UUID prefix of the issuer signing the list
+        #[arg(long, value_name = "ISSUER")]
+        sign_with: String,
+        /// Write the list to a file rather than printing it
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Export the chain as files for a plain web host, laid out as `serve` answers so that
+    /// `blockchain sync` and `--remote` read it as they would a node
+    ExportStatic {
+        /// Directory to write the site to, where only the files that changed are written again
+        #[arg(long, value_name = "DIR")]
+        out_dir: PathBuf,
+        /// Index, UUID or UUID prefix of the issuer anchoring the head and signing the revocation
+        /// list, a chain authority if the chain has any
+        #[arg(long, value_name = "ISSUER")]
+        sign_with: String,
+        /// Also write an `index.html` listing the blocks
+        #[arg(long)]
+        html: bool,
+    },
+    /// Export the revocation status of every issued credential as a bitstring signed by a chain
+    /// authority, for relying parties to check a credential's bit without the chain
+    ExportStatusList {
+        /// Index, UUID or UUID prefix of the issuer signing the list, a chain authority if the
+        /// chain has any
+        #[arg(long, value_name = "ISSUER")]
+        sign_with: String,
+        /// Write the list to a file rather than printing it
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Import a chain exported with `blockchain export`, after validating it, as the local chain
+    /// or as an extension of it
+    Import {
+        file: PathBuf,
+        /// Seconds a block may be timestamped before the previous block, overrides the config
+        #[arg(long)]
+        allow_clock_skew: Option<u64>,
+        /// Only report what importing would do
+        #[arg(long)]
+        dry_run: bool,
+        /// Import blocks breaking the `[import]` policy of the config, recording it in audit.log
+        #[arg(long)]
+        accept_violations: bool,
+        /// Replace a local chain the import diverges from, recording it in audit.log
+        #[arg(long)]
+        force: bool,
+    },
     /// Initialize blockchain
-    Init,
+    Init(InitArgs),
+    /// Export a proof that the chain issued a credential, and revoked it if it did, which can be
+    /// checked with the block signers' keys alone
+    Prove {
+        /// Index, UUID or UUID prefix of the credential
+        credential: String,
+        /// Write the proof to a file rather than printing it
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Export a proof that the chain doesn't revoke a credential as of its head, against the root
+    /// of its revocation tree signed by a chain authority
+    ProveNonrevocation {
+        /// Index, UUID or UUID prefix of the credential
+        credential: String,
+        /// Index, UUID or UUID prefix of the issuer signing the root, a chain authority if the
+        /// chain has any
+        #[arg(long, value_name = "ISSUER")]
+        sign_with: String,
+        /// Write the proof to a file rather than printing it
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Print the root of the sparse Merkle tree of the chain's revocations, or export it signed
+    RevocationRoot {
+        /// Index, UUID or UUID prefix of the issuer signing the root, a chain authority if the
+        /// chain has any
+        #[arg(long, value_name = "ISSUER")]
+        sign_with: Option<String>,
+        /// Write the signed root to a file rather than printing it
+        #[arg(long, value_name = "PATH", requires = "sign_with")]
+        out: Option<PathBuf>,
+    },
+    /// Count the credentials issued, revoked and anchored per issuer and per month
+    Stats {
+        /// Print the stats as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Pull the blocks another node holds beyond the local chain, creating the chain if there is
+    /// none, and refuse chains diverging from the local one
+    Sync {
+        /// URL the node serves its chain at with `serve` or as a static site, or path of a site
+        /// exported with `blockchain export-static`, of a node's `blockchain.json` or the
+        /// directory holding it, e.g. on a shared drive
+        #[arg(long, value_name = "URL|PATH")]
+        from: String,
+        /// Seconds a block may be timestamped before the previous block, overrides the config
+        #[arg(long)]
+        allow_clock_skew: Option<u64>,
+        /// Only report how many blocks syncing would apply
+        #[arg(long)]
+        dry_run: bool,
+        /// Apply blocks breaking the `[import]` policy of the config, recording it in audit.log
+        #[arg(long)]
+        accept_violations: bool,
+        #[command(flatten)]
+        auth: ClientAuthArgs,
+    },
+    /// Summarize the newest blocks, newest first
+    Tail {
+        /// Number of blocks to summarize
+        #[arg(short, default_value_t = 5)]
+        n: usize,
+        /// Keep watching the chain and summarize blocks as they are finalized
+        #[arg(long)]
+        follow: bool,
+    },
+    /// Check every block's hash, signature, timestamp and link to the previous block
+    Validate {
+        /// Seconds a block may be timestamped before the previous block, overrides the config
+        #[arg(long)]
+        allow_clock_skew: Option<u64>,
+        /// Fail if any block is signed by a key that isn't in trust.json
+        #[arg(long)]
+        trusted_only: bool,
+        #[command(flatten)]
+        remote: RemoteArgs,
+    },
     /// Verify a credential is valid
-    Verify { credential: usize },
+    Verify(VerifyArgs),
+    /// Check a proof exported with `blockchain prove-nonrevocation`, without the local chain
+    VerifyNonrevocation {
+        file: PathBuf,
+        /// Check the path against this later root, exported with `blockchain revocation-root`,
+        /// rather than the proof's own
+        #[arg(long, value_name = "PATH")]
+        root: Option<PathBuf>,
+        /// Credential exported with `credentials export` the proof has to be about
+        #[arg(long, value_name = "PATH")]
+        credential: Option<PathBuf>,
+        /// Key the roots must be signed with, as a key file, 64 hex digits or the key's
+        /// fingerprint
+        #[arg(long, value_name = "KEY|FINGERPRINT")]
+        trusted_signer: Option<String>,
+    },
+    /// Check a proof exported with `blockchain prove`, without the local chain or credentials
+    VerifyProof {
+        file: PathBuf,
+        /// Key the blocks must be signed with, as a key file, 64 hex digits or the key's
+        /// fingerprint
+        #[arg(long, value_name = "KEY|FINGERPRINT")]
+        trusted_signer: Option<String>,
+    },
 }
 
 impl BlockchainSubcommands {
-    fn run(self) -> Result<(), &'static str> {
+    fn run(self) -> Result<(), String> {
         match self {
-            Self::Display => Self::display(),
-            Self::Init => Self::init(),
-            Self::Verify { credential } => Self::verify(credential),
+            Self::Anchor { sign_with, out } => Self::anchor(&sign_with, out.as_deref()),
+            Self::AuditIssuers { json } => Self::audit_issuers(json),
+            Self::Authorities { subcommand } => subcommand.run(),
+            Self::CheckAnchor { file, allow_clock_skew } =>
+                Self::check_anchor(&file, allow_clock_skew),
+            Self::Compare { file } => Self::compare(&file),
+            Self::Display { block, last, range, format, summary, remote } =>
+                Self::display(block.as_deref(), last, range, format, summary, &remote),
+            Self::Export { out } => Self::export(out.as_deref()),
+            Self::ExportRevocations { sign_with, out } =>
+                Self::export_revocations(&sign_with, out.as_deref()),
+            Self::ExportStatic { out_dir, sign_with, html } =>
+                Self::export_static(&out_dir, &sign_with, html),
+            Self::ExportStatusList { sign_with, out } =>
+                Self::export_status_list(&sign_with, out.as_deref()),
+            Self::Import { file, allow_clock_skew, dry_run, accept_violations, force } =>
+                Self::import(&file, allow_clock_skew, dry_run, accept_violations, force),
+            Self::Init(args) => args.run(),
+            Self::Prove { credential, out } => Self::prove(&credential, out.as_deref()),
+            Self::ProveNonrevocation { credential, sign_with, out } =>
+                Self::prove_nonrevocation(&credential, &sign_with, out.as_deref()),
+            Self::RevocationRoot { sign_with, out } =>
+                Self::revocation_root(sign_with.as_deref(), out.as_deref()),
+            Self::Stats { json } => Self::stats(json),
+            Self::Sync { from, allow_clock_skew, dry_run, accept_violations, auth } =>
+                Self::sync(&from, allow_clock_skew, dry_run, accept_violations, &auth),
+            Self::Tail { n, follow } => Self::tail(n, follow),
+            Self::Validate { allow_clock_skew, trusted_only, remote } =>
+                Self::validate(allow_clock_skew, trusted_only, &remote),
+            Self::Verify(args) => args.run(),
+            Self::VerifyNonrevocation { file, root, credential, trusted_signer } =>
+                Self::verify_nonrevocation(
+                    &file,
+                    root.as_deref(),
+                    credential.as_deref(),
+                    trusted_signer.as_deref(),
+                ),
+            Self::VerifyProof { file, trusted_signer } =>
+                Self::verify_proof(&file, trusted_signer.as_deref()),
         }
     }
 
-    fn display() -> Result<(), &'static str> {
+    fn anchor(sign_with: &str, out: Option<&Path>) -> Result<(), String> {
         let blockchain = open_blockchain()?;
-        println!("{blockchain}");
+        let issuers = open_issuers()?;
+        let issuer = &issuers[issuer_index(&issuers, sign_with)?];
+        let signer = open_signer(issuer)?;
+        let anchor =
+            ChainAnchor::new(&blockchain, issuer, signer.as_ref()).map_err(|e| e.to_string())?;
+        drop(signer);
+        let mut anchors = open_anchors()?;
+        anchors.push(anchor.clone());
+        save_anchors(&anchors)?;
+        let Some(path) = out else {
+            println!("{anchor}");
+            return Ok(());
+        };
+        fs::write(path, format!("{anchor}\n")).map_err(|_| "Failed to write anchor")?;
+        println!(
+            "Anchored block #{} with hash {} to {}",
+            anchor.height,
+            anchor.head.short(),
+            path.display()
+        );
         Ok(())
     }
 
-    fn init() -> Result<(), &'static str> {
-        let blockchain = Blockchain::new();
-        let mut writer = File::create_buffered("blockchain.json")
-            .map_err(|_| "Failed to create blockchain file")?;
-        serde_json::to_writer(&mut writer, &blockchain)
-            .map_err(|_| "Failed to write blockchain")?;
-        fs::write("block.json", "null").map_err(|_| "Failed to create block file")?;
-        fs::write("credentials.json", "[]").map_err(|_| "Failed to create credentials file")?;
-        fs::write("issuers.json", "[]").map_err(|_| "Failed to create issuers file")?;
-        fs::write("subjects.json", "[]").map_err(|_| "Failed to create subject file")?;
-        println!("Initialized new blockchain, created all the files");
+    fn check_anchor(file: &Path, allow_clock_skew: Option<u64>) -> Result<(), String> {
+        let policy = chain_policy(allow_clock_skew)?;
+        let contents = fs::read_to_string(file).map_err(|_| "Failed to open anchor")?;
+        let anchor = ChainAnchor::from_json(&contents)
+            .map_err(|e| format!("Failed to read {}: {e}", file.display()))?;
+        println!(
+            "Anchor of block #{} with hash {}, signed by '{}' with key {} on {}",
+            anchor.height,
+            anchor.head.short(),
+            anchor.signer_name,
+            fingerprint(&anchor.signer_key),
+            anchor.anchored_at.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+        let blockchain = open_blockchain()?;
+        anchor.check(&blockchain, &policy).map_err(|e| format!("Anchor check failed: {e}"))?;
+        let grown = blockchain.len() as u64 - anchor.height - 1;
+        println!("Chain holds the anchored block and grew by {grown} blocks since");
         Ok(())
     }
 
-    fn verify(credential: usize) -> Result<(), &'static str> {
+    fn audit_issuers(json: bool) -> Result<(), String> {
         let blockchain = open_blockchain()?;
         let credentials = open_credentials()?;
-        let credential = &credentials.get(credential).ok_or("No credential with given index")?.0;
-        let result = blockchain.check_credential(credential);
-        println!("Result: {result}");
-        Ok(())
+        let issuers = open_issuers()?;
+        let conflicts =
+            KeyAudit::new(&blockchain, credentials.iter().map(|c| &c.0), issuers.iter())
+                .conflicts();
+        if json {
+            println!("{}", serde_json::to_string_pretty(&conflicts).unwrap());
+        } else if conflicts.is_empty() {
+            println!("No conflicting issuer keys found");
+        }
+        if !json {
+            for conflict in &conflicts {
+                println!("Issuer {} is bound to conflicting keys:", conflict.issuer);
+                for binding in &conflict.keys {
+                    let sources: Vec<_> = binding
+                        .sources
+                        .iter()
+                        .map(|source| match source {
+                            KeySource::Genesis => "genesis block".to_string(),
+                            KeySource::Block { height } => format!("block {height}"),
+                            KeySource::Credential { uuid } => format!("credential {uuid}"),
+                            KeySource::Store { index } => format!("issuer {index} in issuers.json"),
+                        })
+                        .collect();
+                    println!("  {}: {}", binding.key, sources.join(", "));
+                }
+            }
+        }
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("{} issuers have conflicting keys", conflicts.len()))
+        }
     }
-}
 
-#[derive(Subcommand)]
-enum CredentialSubcommands {
-    /// Add a new credential
-    Add(NewCredentialArgs),
-    /// List existing credentials
-    List,
-}
+    fn export(out: Option<&Path>) -> Result<(), String> {
+        let export = ChainExport::new(open_blockchain()?);
+        let Some(path) = out else {
+            println!("{export}");
+            return Ok(());
+        };
+        fs::write(path, format!("{export}\n")).map_err(|_| "Failed to write chain export")?;
+        println!("Exported {} blocks to {}", export.blocks, path.display());
+        Ok(())
+    }
 
-impl CredentialSubcommands {
-    fn run(self) -> Result<(), &'static str> {
-        match self {
-            CredentialSubcommands::Add(args) => args.run(),
-            CredentialSubcommands::List => Self::list(),
-        }
+    fn export_revocations(sign_with: &str, out: Option<&Path>) -> Result<(), String> {
+        let issuers = open_issuers()?;
+        let issuer = &issuers[issuer_index(&issuers, sign_with)?];
+        let signer = open_signer(issuer)?;
+        let list = RevocationList::new(&open_blockchain()?, issuer, signer.as_ref())
+            .map_err(|e| e.to_string())?;
+        drop(signer);
+        let Some(path) = out else {
+            println!("{list}");
+            return Ok(());
+        };
+        fs::write(path, format!("{list}\n")).map_err(|_| "Failed to write revocation list")?;
+        println!("Exported {} revocations to {}", list.revocations.len(), path.display());
+        Ok(())
     }
 
-    fn list() -> Result<(), &'static str> {
-        let credentials = open_credentials()?;
-        for (i, c) in credentials.into_iter().enumerate() {
-            println!("{i}: {}", c.0);
+    fn export_static(out_dir: &Path, sign_with: &str, html: bool) -> Result<(), String> {
+        let blockchain = open_blockchain()?;
+        let issuers = open_issuers()?;
+        let issuer = &issuers[issuer_index(&issuers, sign_with)?];
+        let signer = open_signer(issuer)?;
+        let export = Site::new(out_dir)
+            .export(&blockchain, issuer, signer.as_ref(), html)
+            .map_err(|e| format!("Failed to export the site: {e}"))?;
+        drop(signer);
+        println!(
+            "Exported {} blocks to {}, {} files written and {} unchanged",
+            blockchain.len(),
+            out_dir.display(),
+            export.written,
+            export.unchanged
+        );
+        if export.removed > 0 {
+            println!("Removed {} blocks the chain no longer holds", export.removed);
         }
         Ok(())
     }
-}
-
-#[derive(Args)]
-struct NewCredentialArgs {
-    /// Index of the credential's issuer
-    issuer: usize,
-    /// Index of the credential's subject
-    subject: usize,
-    /// Name of the attribute
-    name: String,
-    /// Value of the attribute
-    value: String,
-    /// Date from which the attribute is valid
-    from: NaiveDate,
-    /// Date to which the attribute is valid, indefinite if not provided
-    to: Option<NaiveDate>,
-}
 
-impl NewCredentialArgs {
-    fn run(self) -> Result<(), &'static str> {
-        let mut issuers = open_issuers()?;
-        if self.issuer >= issuers.len() {
-            return Err("No issuer with given index");
-        }
-        let issuer = issuers.swap_remove(self.issuer);
-        let mut subjects = open_subjects()?;
-        if self.subject >= subjects.len() {
-            return Err("No subject with given index");
-        }
-        let subject = subjects.swap_remove(self.subject);
-        let credential = Credential::new(
-            Attribute::new(self.name, self.value),
-            issuer.0,
-            subject,
-            ValidDuration::new(self.from, self.to),
+    fn export_status_list(sign_with: &str, out: Option<&Path>) -> Result<(), String> {
+        let issuers = open_issuers()?;
+        let issuer = &issuers[issuer_index(&issuers, sign_with)?];
+        let credentials: Vec<_> = open_credentials()?
+            .into_iter()
+            .map(|(_, issuance, revocation)| (issuance.credential, revocation.credential))
+            .collect();
+        let signer = open_signer(issuer)?;
+        let list = StatusList::new(&open_blockchain()?, &credentials, issuer, signer.as_ref())
+            .map_err(|e| e.to_string())?;
+        drop(signer);
+        let Some(path) = out else {
+            println!("{list}");
+            return Ok(());
+        };
+        fs::write(path, format!("{list}\n")).map_err(|_| "Failed to write status list")?;
+        println!(
+            "Exported the status of {} credentials, {} revoked, to {}",
+            list.size,
+            list.revoked(),
+            path.display()
         );
-        let signed_regular = credential.sign(&issuer.1, false);
-        let signed_revoking = credential.sign(&issuer.1, true);
-        let mut credentials = open_credentials()?;
-        credentials.push((credential, signed_regular, signed_revoking));
-        save_credentials(&credentials)?;
-        println!("Created new credential");
         Ok(())
     }
-}
-
-#[derive(Subcommand)]
-enum IssuerSubcommands {
-    /// Add a new issuer
-    Add { name: String },
-    /// Display existing issuers
-    List,
-}
 
-impl IssuerSubcommands {
-    fn run(self) -> Result<(), &'static str> {
-        match self {
-            Self::Add { name } => Self::add(name),
-            Self::List => Self::list(),
+    fn import(
+        file: &Path, allow_clock_skew: Option<u64>, dry_run: bool, accept_violations: bool,
+        force: bool,
+    ) -> Result<(), String> {
+        let policy = chain_policy(allow_clock_skew)?;
+        let contents = fs::read_to_string(file).map_err(|_| "Failed to open chain export")?;
+        let incoming = ChainExport::from_json(&contents)
+            .map_err(|e| format!("Failed to read {}: {e}", file.display()))?
+            .blockchain;
+        let mut progress = ProgressBarSink::new("Validating", false);
+        incoming
+            .validate_with_progress(&policy, &mut progress)
+            .map_err(|e| format!("Refusing to import an invalid chain: {e}"))?;
+        // Without a local chain, the import becomes it
+        let local =
+            if Path::new("blockchain.json").exists() { Some(open_blockchain()?) } else { None };
+        let held = local.as_ref().map_or(0, Blockchain::len) as u64;
+        let mut divergence = None;
+        if let Some(local) = &local {
+            local.ensure_same_chain(&incoming).map_err(|e| format!("Refusing to import: {e}"))?;
+            divergence = local.diverges_from(&incoming);
+            match &divergence {
+                Some(divergence) if !force =>
+                    return Err(format!(
+                        "Refusing to import a chain that diverges from the local one at block {}: \
+                         local {}, imported {}, pass --force to replace the local chain",
+                        divergence.height, divergence.local, divergence.other
+                    )),
+                None if incoming.len() as u64 <= held => {
+                    println!("Local chain already holds all {} imported blocks", incoming.len());
+                    return Ok(());
+                },
+                _ => {},
+            }
+        }
+        // Blocks from the first differing one on replace the local ones
+        let from = divergence.as_ref().map_or(held, |divergence| divergence.height);
+        let new_blocks = incoming.len() as u64 - from;
+        let source = file.display().to_string();
+        let overriding =
+            ImportOverride { command: "blockchain import", source: source.clone(), dry_run };
+        check_import_policy(&incoming, from, accept_violations.then_some(overriding))?;
+        let action = match (&local, &divergence) {
+            (_, Some(divergence)) =>
+                format!("replace the local chain from block {}", divergence.height),
+            (Some(_), None) => "extend the local chain".to_string(),
+            (None, None) => "create the chain".to_string(),
+        };
+        if dry_run {
+            println!(
+                "Would import {new_blocks} blocks to {action}, leaving {} blocks",
+                incoming.len()
+            );
+            return Ok(());
         }
+        if let Some(divergence) = divergence {
+            append_audit(&AuditRecord::new(AuditEvent::DivergingChainReplaced {
+                command: "blockchain import".to_string(),
+                source,
+                height: divergence.height,
+                local: divergence.local,
+                imported: divergence.other,
+            }))?;
+            eprintln!(
+                "Warning: replacing the local chain from block {}, recorded in audit.log",
+                divergence.height
+            );
+        }
+        replace_blockchain(&incoming)?;
+        println!("Imported {new_blocks} blocks to {action}, now {} blocks", incoming.len());
+        Ok(())
     }
 
-    fn add(name: String) -> Result<(), &'static str> {
-        let (issuer, key) = Issuer::new(name);
+    fn prove(credential: &str, out: Option<&Path>) -> Result<(), String> {
+        let credentials = open_credentials()?;
+        let (credential, issuance, revocation) =
+            &credentials[credential_index(&credentials, credential)?];
+        let proof = InclusionProof::new(
+            &open_blockchain()?,
+            credential.uuid,
+            credential.issuer.verifying,
+            &issuance.credential,
+            &revocation.credential,
+        )
+        .map_err(|e| format!("Can't prove credential {}: {e}", credential.uuid))?;
+        let Some(path) = out else {
+            println!("{proof}");
+            return Ok(());
+        };
+        fs::write(path, format!("{proof}\n")).map_err(|_| "Failed to write proof")?;
+        println!(
+            "Exported proof of credential {} issued in block #{} to {}",
+            credential.uuid,
+            proof.issuance.block.height,
+            path.display()
+        );
+        if let Some(revocation) = &proof.revocation {
+            println!("The credential is revoked, in block #{}", revocation.block.height);
+        }
+        Ok(())
+    }
+
+    fn prove_nonrevocation(
+        credential: &str, sign_with: &str, out: Option<&Path>,
+    ) -> Result<(), String> {
+        let credentials = open_credentials()?;
+        let (credential, _, revocation) = &credentials[credential_index(&credentials, credential)?];
+        let issuers = open_issuers()?;
+        let issuer = &issuers[issuer_index(&issuers, sign_with)?];
+        let signer = open_signer(issuer)?;
+        let proof = NonRevocationProof::new(
+            &open_blockchain()?,
+            credential.uuid,
+            &revocation.credential,
+            issuer,
+            signer.as_ref(),
+        )
+        .map_err(|e| format!("Can't prove credential {} isn't revoked: {e}", credential.uuid))?;
+        drop(signer);
+        let Some(path) = out else {
+            println!("{proof}");
+            return Ok(());
+        };
+        fs::write(path, format!("{proof}\n")).map_err(|_| "Failed to write proof")?;
+        println!(
+            "Exported proof that credential {} isn't revoked as of revocation root {} to {}",
+            credential.uuid,
+            proof.root.root.short(),
+            path.display()
+        );
+        Ok(())
+    }
+
+    fn revocation_root(sign_with: Option<&str>, out: Option<&Path>) -> Result<(), String> {
+        let blockchain = open_blockchain()?;
+        let Some(sign_with) = sign_with else {
+            println!("{}", nonrevocation::revocation_root(&blockchain));
+            return Ok(());
+        };
+        let issuers = open_issuers()?;
+        let issuer = &issuers[issuer_index(&issuers, sign_with)?];
+        let signer = open_signer(issuer)?;
+        let root =
+            RevocationRoot::new(&blockchain, issuer, signer.as_ref()).map_err(|e| e.to_string())?;
+        drop(signer);
+        let Some(path) = out else {
+            println!("{root}");
+            return Ok(());
+        };
+        fs::write(path, format!("{root}\n")).map_err(|_| "Failed to write revocation root")?;
+        println!(
+            "Exported revocation root {} of {} revocations to {}",
+            root.root.short(),
+            root.revocations,
+            path.display()
+        );
+        Ok(())
+    }
+
+    fn compare(file: &Path) -> Result<(), String> {
+        let local = open_blockchain()?;
+        let reader = File::open_buffered(file).map_err(|_| "Failed to open blockchain file")?;
+        let other: Blockchain =
+            serde_json::from_reader(reader).map_err(|_| "Failed to parse blockchain")?;
+        local.ensure_same_chain(&other).map_err(|e| e.to_string())?;
+        if let Some(divergence) = local.diverges_from(&other) {
+            match divergence.last_common {
+                Some(height) => println!("Chains diverge after block {height}"),
+                None => println!("Chains diverge from the first block"),
+            }
+            println!("Local block {}: {}", divergence.height, divergence.local);
+            println!("Other block {}: {}", divergence.height, divergence.other);
+            return Ok(());
+        }
+        match other.len().cmp(&local.len()) {
+            Ordering::Equal => println!("Chains are identical"),
+            Ordering::Greater => println!("Other is ahead by {} blocks", other.len() - local.len()),
+            Ordering::Less => println!("Local is ahead by {} blocks", local.len() - other.len()),
+        }
+        Ok(())
+    }
+
+    fn display(
+        block: Option<&str>, last: bool, range: Option<HeightRange>, format: Option<OutputFormat>,
+        summary: bool, remote: &RemoteArgs,
+    ) -> Result<(), String> {
+        let blockchain = remote.open()?;
+        let blocks = match (block, range) {
+            (Some(block), _) => vec![find_block(&blockchain, block)?],
+            (None, _) if last => vec![blockchain.tip().ok_or("Blockchain has no blocks")?],
+            (None, Some(range)) => range.select(&blockchain)?,
+            (None, None) => blockchain.blocks().collect(),
+        };
+        if summary {
+            let summary = ChainSummary::new(blocks);
+            return format.unwrap_or(OutputFormat::Table).show(&summary, || {
+                for block in &summary.blocks {
+                    anstream::println!("{}", ui::summary(block));
+                }
+                println!("{}", summary.totals);
+            });
+        }
+        let format = format.unwrap_or(OutputFormat::JsonPretty);
+        let summaries = || {
+            for block in &blocks {
+                anstream::println!("{}", ui::summary(&block.summary()));
+            }
+        };
+        // A single block is given as is, a range as a list and the whole chain with its genesis
+        if block.is_some() || last {
+            format.show(blocks[0], summaries)
+        } else if range.is_some() {
+            format.show(&blocks, summaries)
+        } else {
+            format.show(&blockchain, summaries)
+        }
+    }
+
+    fn sync(
+        from: &str, allow_clock_skew: Option<u64>, dry_run: bool, accept_violations: bool,
+        auth: &ClientAuthArgs,
+    ) -> Result<(), String> {
+        let policy = chain_policy(allow_clock_skew)?;
+        let source = chain_source(from, auth)?;
+        // Without a local chain, the source's becomes it
+        let local =
+            if Path::new("blockchain.json").exists() { Some(open_blockchain()?) } else { None };
+        let pull = sync::pull(local.as_ref(), source.as_ref(), &policy)
+            .map_err(|e| format!("Failed to sync from {from}: {e}"))?;
+        let (blockchain, fetched) = match pull {
+            Pull::UpToDate { ahead: 0 } => {
+                println!("Local chain is up to date with {from}");
+                return Ok(());
+            },
+            Pull::UpToDate { ahead } => {
+                println!("Local chain is ahead of {from} by {ahead} blocks");
+                return Ok(());
+            },
+            Pull::Extended { blockchain, fetched } => (blockchain, fetched),
+        };
+        let held = blockchain.len() as u64 - fetched;
+        let source = from.to_string();
+        let overriding = ImportOverride { command: "blockchain sync", source, dry_run };
+        check_import_policy(&blockchain, held, accept_violations.then_some(overriding))?;
+        if dry_run {
+            let blocks = blockchain.len();
+            println!("Would apply {fetched} blocks from {from}, leaving {blocks} blocks");
+            return Ok(());
+        }
+        replace_blockchain(&blockchain)?;
+        println!("Applied {fetched} blocks from {from}, now {} blocks", blockchain.len());
+        Ok(())
+    }
+
+    fn stats(json: bool) -> Result<(), String> {
+        let stats = open_blockchain()?.stats();
+        if json {
+            println!("{}", serde_json::to_string_pretty(&stats).unwrap());
+            return Ok(());
+        }
+        let issuers = open_issuers()?;
+        println!(
+            "{} blocks, {} credentials issued, {} revoked, {} anchored",
+            stats.blocks, stats.issued, stats.revoked, stats.anchored
+        );
+        let Some(largest) = &stats.largest_block else {
+            return Ok(());
+        };
+        println!(
+            "{:.2} credentials per block on average, most in block #{} {} with {}",
+            stats.average_per_block,
+            largest.height,
+            largest.hash.short(),
+            largest.credentials
+        );
+
+        // Issuers removed since they signed are told apart by their key's fingerprint
+        let mut table = Table::new([
+            "ISSUER", "UUID", "FINGERPRINT", "BLOCKS", "ISSUED", "REVOKED", "ANCHORED",
+        ]);
+        for by_signer in &stats.issuers {
+            let name = match issuers.iter().find(|issuer| issuer.uuid == by_signer.uuid) {
+                Some(issuer) if issuer.deleted => format!("{} (deleted)", issuer.name),
+                Some(issuer) => issuer.name.clone(),
+                None => format!("{} (not in issuers.json)", by_signer.name),
+            };
+            table.row([
+                name,
+                short_uuid(by_signer.uuid),
+                by_signer.fingerprint.clone(),
+                by_signer.blocks.to_string(),
+                by_signer.issued.to_string(),
+                by_signer.revoked.to_string(),
+                by_signer.anchored.to_string(),
+            ]);
+        }
+        println!();
+        print_table(&table);
+
+        // The histogram shows the credentials issued and anchored each month
+        let volume = |month: &MonthStats| month.issued + month.anchored;
+        let busiest = stats.months.iter().map(volume).max().unwrap_or(0).max(1);
+        let mut table =
+            Table::new(["MONTH", "BLOCKS", "ISSUED", "REVOKED", "ANCHORED", "HISTOGRAM"]);
+        for month in &stats.months {
+            table.row([
+                month.month.clone(),
+                month.blocks.to_string(),
+                month.issued.to_string(),
+                month.revoked.to_string(),
+                month.anchored.to_string(),
+                "#".repeat((volume(month) * HISTOGRAM_WIDTH).div_ceil(busiest)),
+            ]);
+        }
+        println!();
+        print_table(&table);
+        Ok(())
+    }
+
+    fn tail(n: usize, follow: bool) -> Result<(), String> {
+        let blockchain = open_blockchain()?;
+        for block in blockchain.blocks().rev().take(n) {
+            anstream::println!("{}", ui::summary(&block.summary()));
+        }
+        if !follow {
+            return Ok(());
+        }
+        let mut tip = blockchain.tip().map(|block| block.hash().clone());
+        let mut len = blockchain.len();
+        loop {
+            thread::sleep(FOLLOW_POLL_INTERVAL);
+            // The file may be caught halfway through being written, it's read again next time
+            let Ok(blockchain) = open_blockchain() else { continue };
+            let kept = len.checked_sub(1).and_then(|height| blockchain.block(height as u64));
+            if blockchain.len() < len || kept.map(|block| block.hash().clone()) != tip {
+                eprintln!(
+                    "WARNING: blockchain was replaced, following it from its {} blocks",
+                    blockchain.len()
+                );
+            } else {
+                for block in blockchain.blocks().skip(len) {
+                    anstream::println!("{}", ui::summary(&block.summary()));
+                }
+            }
+            tip = blockchain.tip().map(|block| block.hash().clone());
+            len = blockchain.len();
+        }
+    }
+
+    fn validate(
+        allow_clock_skew: Option<u64>, trusted_only: bool, remote: &RemoteArgs,
+    ) -> Result<(), String> {
+        let policy = chain_policy(allow_clock_skew)?;
+        let trust = if trusted_only { Some(open_trust_for_check()?) } else { None };
+        let blockchain = match remote.fetch()? {
+            Some(blockchain) => blockchain,
+            None => open_blockchain()?,
+        };
+        let mut progress = ProgressBarSink::new("Validating", false);
+        match blockchain.validate_with_progress(&policy, &mut progress) {
+            Ok(()) => {
+                println!("Blockchain is valid");
+                for warning in blockchain.warnings() {
+                    println!("Warning: {warning}");
+                }
+            },
+            Err(e) => return Err(format!("Blockchain is invalid: {e}")),
+        }
+        let Some(trust) = trust else {
+            return Ok(());
+        };
+        let untrusted = blockchain.untrusted_blocks(&trust);
+        for block in untrusted.iter().filter_map(|&height| blockchain.block(height)) {
+            println!(
+                "Block #{} is signed by untrusted key {} of issuer '{}'",
+                block.height(),
+                fingerprint(&block.signer().verifying),
+                block.signer().name
+            );
+        }
+        if untrusted.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("{} blocks are signed by untrusted issuers", untrusted.len()))
+        }
+    }
+
+    fn verify_proof(file: &Path, trusted_signer: Option<&str>) -> Result<(), String> {
+        let contents = fs::read_to_string(file).map_err(|_| "Failed to open proof")?;
+        let proof = InclusionProof::from_json(&contents)
+            .map_err(|e| format!("Failed to read {}: {e}", file.display()))?;
+        let issuance = &proof.issuance;
+        println!("Credential {}", proof.credential);
+        println!("Credential hash: {}", issuance.entry.credential);
+        println!(
+            "Issued in block #{} at {}, signed with key {}",
+            issuance.block.height,
+            issuance.block.timestamp,
+            fingerprint(&issuance.signer_key)
+        );
+        if let Some(revocation) = &proof.revocation {
+            println!(
+                "Revoked in block #{} at {}, signed with key {}",
+                revocation.block.height,
+                revocation.block.timestamp,
+                fingerprint(&revocation.signer_key)
+            );
+        }
+        let trust = trusted_signer.map(|signer| Self::trusted_signer(&proof, signer)).transpose()?;
+        proof::verify(&proof, trust.as_ref()).map_err(|e| format!("INVALID: {e}"))?;
+        println!("VALID");
+        Ok(())
+    }
+
+    /// Key given with `--trusted-signer`, or the key of a block of the proof with the fingerprint
+    /// given instead
+    fn trusted_signer(proof: &InclusionProof, signer: &str) -> Result<VerifyingKey, String> {
+        if let Ok(key) = parse_verifying_key(signer) {
+            return Ok(key);
+        }
+        let query = signer.replace(' ', "").to_uppercase();
+        iter::once(&proof.issuance)
+            .chain(&proof.revocation)
+            .map(|entry| entry.signer_key)
+            .find(|key| fingerprint(key).replace(' ', "") == query)
+            .ok_or(format!("INVALID: no block is signed with the trusted key {signer}"))
+    }
+
+    fn verify_nonrevocation(
+        file: &Path, root: Option<&Path>, credential: Option<&Path>, trusted_signer: Option<&str>,
+    ) -> Result<(), String> {
+        let contents = fs::read_to_string(file).map_err(|_| "Failed to open proof")?;
+        let proof = NonRevocationProof::from_json(&contents)
+            .map_err(|e| format!("Failed to read {}: {e}", file.display()))?;
+        let later = root
+            .map(|path| {
+                let contents =
+                    fs::read_to_string(path).map_err(|_| "Failed to open revocation root")?;
+                RevocationRoot::from_json(&contents)
+                    .map_err(|e| format!("Failed to read {}: {e}", path.display()))
+            })
+            .transpose()?;
+        if let Some(path) = credential {
+            let export = open_export(path)?;
+            if export.credential.uuid != proof.credential
+                || &export.revocation.credential != proof.revocation_hash()
+            {
+                let path = path.display();
+                return Err(format!("INVALID: the proof isn't about the credential in {path}"));
+            }
+        }
+        let checked = later.as_ref().unwrap_or(&proof.root);
+        println!("Credential {}", proof.credential);
+        println!("Revocation hash: {}", proof.revocation_hash());
+        let head = checked.head.as_ref().map_or("none".into(), ToString::to_string);
+        println!(
+            "Revocation root {} of {} revocations at chain head {head}, signed with key {}",
+            checked.root.short(),
+            checked.revocations,
+            fingerprint(&checked.signer_key)
+        );
+        if let Some(signer) = trusted_signer {
+            let trusted = if let Ok(key) = parse_verifying_key(signer) {
+                key
+            } else {
+                let query = signer.replace(' ', "").to_uppercase();
+                iter::once(&proof.root)
+                    .chain(&later)
+                    .map(|root| root.signer_key)
+                    .find(|key| fingerprint(key).replace(' ', "") == query)
+                    .ok_or(format!("INVALID: no root is signed with the trusted key {signer}"))?
+            };
+            if iter::once(&proof.root).chain(&later).any(|root| root.signer_key != trusted) {
+                return Err("INVALID: a root isn't signed with the trusted key".into());
+            }
+        }
+        proof.verify(later.as_ref()).map_err(|e| format!("INVALID: {e}"))?;
+        println!("VALID");
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "client"))]
+const NO_CLIENT: &str = "This build can't read remote chains, build with `--features client`";
+
+/// Source `blockchain sync` reads from, a node's server or its chain on a shared drive
+fn chain_source(from: &str, auth: &ClientAuthArgs) -> Result<Box<dyn ChainSource>, String> {
+    if from.starts_with("http://") || from.starts_with("https://") {
+        #[cfg(feature = "client")]
+        return Ok(Box::new(
+            RemoteChain::with_auth(from, &auth.read()?).map_err(|e| e.to_string())?,
+        ));
+        #[cfg(not(feature = "client"))]
+        {
+            let _ = auth;
+            return Err(NO_CLIENT.to_string());
+        }
+    }
+    let mut path = PathBuf::from(from);
+    if Site::exists(&path) {
+        return Ok(Box::new(Site::new(path)));
+    }
+    if path.is_dir() {
+        path.push("blockchain.json");
+    }
+    let reader =
+        File::open_buffered(&path).map_err(|_| format!("Failed to open {}", path.display()))?;
+    let blockchain: Blockchain =
+        serde_json::from_reader(reader).map_err(|_| format!("Failed to parse {}", path.display()))?;
+    Ok(Box::new(blockchain))
+}
+
+/// Chain served by another node to read instead of the local one
+#[derive(Args)]
+struct RemoteArgs {
+    /// Read the chain served at this URL by `serve`, or as a static copy of its files, instead
+    /// of the local one
+    #[arg(long, value_name = "URL", value_parser = parse_url)]
+    remote: Option<String>,
+    /// Chain UUID, or fingerprint of the key the last block must be signed with, the remote
+    /// chain must match
+    #[arg(long, value_name = "UUID|FINGERPRINT", requires = "remote")]
+    pin: Option<String>,
+    #[command(flatten)]
+    auth: ClientAuthArgs,
+}
+
+/// Credentials for nodes requiring them
+#[derive(Args)]
+struct ClientAuthArgs {
+    /// Bearer token the node requires, sent with every request
+    #[arg(long)]
+    token: Option<String>,
+    /// PEM certificate to present to nodes requiring client certificates
+    #[arg(long, value_name = "PATH", requires = "client_key")]
+    client_cert: Option<PathBuf>,
+    /// PEM private key of the client certificate
+    #[arg(long, value_name = "PATH", requires = "client_cert")]
+    client_key: Option<PathBuf>,
+    /// PEM bundle of CAs the node's certificate may be issued by, besides the web's roots
+    #[arg(long, value_name = "PATH")]
+    ca_cert: Option<PathBuf>,
+}
+
+impl ClientAuthArgs {
+    #[cfg(feature = "client")]
+    fn read(&self) -> Result<ClientAuth, String> {
+        let read = |path: &PathBuf| {
+            fs::read(path).map_err(|_| format!("Failed to read {}", path.display()))
+        };
+        let identity = match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => Some([read(cert)?, read(key)?].concat()),
+            _ => None,
+        };
+        let ca = self.ca_cert.as_ref().map(read).transpose()?;
+        Ok(ClientAuth { token: self.token.clone(), identity, ca })
+    }
+}
+
+impl RemoteArgs {
+    /// The remote chain as served, without validating it, none without `--remote`
+    #[cfg(feature = "client")]
+    fn fetch(&self) -> Result<Option<Blockchain>, String> {
+        let Some(url) = &self.remote else {
+            return Ok(None);
+        };
+        let pin = self.pin.as_deref().map(str::parse::<Pin>).transpose()?;
+        let remote = RemoteChain::with_auth(url, &self.auth.read()?).map_err(|e| e.to_string())?;
+        match remote.fetch(pin.as_ref()) {
+            Ok(blockchain) => Ok(Some(blockchain)),
+            Err(e @ RemoteError::Network { .. }) => Err(format!("Network error: {e}")),
+            Err(e) => Err(format!("Remote blockchain rejected: {e}")),
+        }
+    }
+
+    #[cfg(not(feature = "client"))]
+    fn fetch(&self) -> Result<Option<Blockchain>, String> {
+        match self.remote {
+            Some(_) => Err(NO_CLIENT.to_string()),
+            None => Ok(None),
+        }
+    }
+
+    /// The remote chain once it validates under the config's policy, or the local chain
+    fn open(&self) -> Result<Blockchain, String> {
+        let Some(blockchain) = self.fetch()? else {
+            return open_blockchain();
+        };
+        let policy = chain_policy(None)?;
+        let invalid = |e| format!("Remote blockchain is invalid: {e}");
+        blockchain.validate_with(&policy).map_err(invalid)?;
+        Ok(blockchain)
+    }
+}
+
+#[derive(Args)]
+#[expect(clippy::struct_excessive_bools)]
+struct VerifyArgs {
+    /// Index, UUID or hash prefix of the credential to verify
+    #[arg(required_unless_present_any = ["all", "file"])]
+    credential: Option<String>,
+    /// Verify every stored credential, failing if any of them isn't valid
+    #[arg(long, conflicts_with = "credential")]
+    all: bool,
+    /// Verify the credential exported to this file instead of a stored one, checking its
+    /// embedded signatures and issuer key before the chain
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["credential", "all"])]
+    file: Option<PathBuf>,
+    /// Don't check the credentials' valid durations against today's date
+    #[arg(long)]
+    ignore_expiry: bool,
+    /// Verify as of the given date, ignoring blocks timestamped after it
+    #[arg(long, value_name = "YYYY-MM-DD", conflicts_with = "ignore_expiry")]
+    on_date: Option<NaiveDate>,
+    /// Answer from verify-cache.json while the chain's tip is unchanged, and update it
+    #[arg(long, conflicts_with_all = ["all", "on_date", "remote"])]
+    cache: bool,
+    /// Fail if the credential's issuer key differs from the key stored in issuers.json
+    #[arg(long, conflicts_with = "all")]
+    pin_keys: bool,
+    /// UUID or UUID prefix of the only issuer trusted, along with the issuers it accredited
+    /// directly or through other accredited issuers when the credentials were issued
+    #[arg(long, value_name = "ISSUER_UUID")]
+    trust_root: Option<String>,
+    /// Reject credentials whose issuer key isn't in trust.json, even if the chain holds them
+    #[arg(long)]
+    trusted_only: bool,
+    /// Print the results as JSON
+    #[arg(long)]
+    json: bool,
+    /// Also print a QR code of the result along with the chain's head hash, for the holder to
+    /// keep
+    #[arg(long, conflicts_with_all = ["all", "json"])]
+    qr_receipt: bool,
+    /// Also print the fingerprints of the keys the signatures were checked against, and trace
+    /// the blocks scanned and the entries matched, checking the chain even with `--cache`
+    #[arg(short, long, conflicts_with = "all")]
+    verbose: bool,
+    /// Only check the credential's bit in this status list, exported with
+    /// `blockchain export-status-list`, which doesn't need the chain for an exported credential
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["all", "on_date", "cache", "json", "qr_receipt", "remote"]
+    )]
+    status_list: Option<PathBuf>,
+    #[command(flatten)]
+    remote: RemoteArgs,
+}
+
+fn cached_report(
+    blockchain: &Blockchain, credential: &Credential,
+) -> Result<VerificationReport, String> {
+    // A missing or unreadable cache is simply rebuilt
+    let cache = File::open_buffered("verify-cache.json")
+        .ok()
+        .and_then(|reader| serde_json::from_reader(reader).ok())
+        .unwrap_or_default();
+    let mut verifier = CachedVerifier::with_cache(blockchain, cache);
+    let report = verifier.report(credential);
+    let writer =
+        File::create_buffered("verify-cache.json").map_err(|_| "Failed to open cache file")?;
+    serde_json::to_writer(writer, &verifier.into_cache()).map_err(|_| "Failed to write cache")?;
+    Ok(report)
+}
+
+/// Verification report along with the steps of the check, for `verify --json -v`
+#[derive(Serialize)]
+struct TracedReport {
+    #[serde(flatten)]
+    report: VerificationReport,
+    trace: Vec<TraceEvent>,
+}
+
+#[derive(Serialize)]
+struct VerificationRow {
+    index: usize,
+    uuid: Uuid,
+    subject: String,
+    attribute: String,
+    #[serde(flatten)]
+    report: VerificationReport,
+}
+
+impl VerifyArgs {
+    fn run(self) -> Result<(), String> {
+        if let Some(list) = &self.status_list {
+            return self.verify_status_list(list);
+        }
+        let blockchain = self.remote.open()?;
+        if let Some(path) = &self.file {
+            let export = open_export(path)?;
+            export.check(&blockchain).map_err(|e| format!("Exported credential rejected: {e}"))?;
+            return self.verify(&blockchain, &export.credential, Some(&export));
+        }
+        let credentials = open_credentials()?;
+        let Some(credential) = self.credential.clone() else {
+            return self.verify_all(&blockchain, &credentials);
+        };
+        let credential = &credentials[credential_index(&credentials, &credential)?].0;
+        self.verify(&blockchain, credential, None)
+    }
+
+    /// Verifies a single credential, stored or exported to the given file
+    fn verify(
+        &self, blockchain: &Blockchain, credential: &Credential, export: Option<&CredentialExport>,
+    ) -> Result<(), String> {
+        let pinned = if self.pin_keys {
+            open_issuers()?
+                .into_iter()
+                .map(|i| (i.uuid, [vec![i.verifying], i.previous_keys].concat()))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        let trust = if self.trusted_only { Some(open_trust_for_check()?) } else { None };
+        let mut trace = Vec::new();
+        let mut record = |event| trace.push(event);
+        let mut report = if conflicts_with_pinned(credential, &pinned) {
+            blockchain.report(credential, VerificationStatus::IssuerKeyConflict)
+        } else if let Some(trust) = &trust
+            && !trust.trusts(&credential.issuer.verifying)
+        {
+            blockchain.report(credential, VerificationStatus::UntrustedIssuer)
+        } else if let Some(date) = self.on_date {
+            let status = blockchain.check_credential_at_traced(credential, date, &mut record);
+            blockchain.report(credential, status)
+        } else {
+            let mut report = if self.cache && !self.verbose {
+                cached_report(blockchain, credential)?
+            } else {
+                let status = blockchain.check_credential_traced(credential, &mut record);
+                blockchain.report(credential, status)
+            };
+            if !self.ignore_expiry {
+                let today = Utc::now().date_naive();
+                report.status = report.status.within(&credential.valid_duration, today);
+            }
+            report
+        };
+        if let Some(root) = &self.trust_root()? {
+            report.status = blockchain.check_trust(report.status, credential, root);
+        }
+        if self.json && self.verbose {
+            let traced = TracedReport { report, trace };
+            println!("{}", serde_json::to_string_pretty(&traced).unwrap());
+            return Ok(());
+        }
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            return Ok(());
+        }
+        println!("Result: {}", report.status);
+        if let Some(export) = export
+            && report.status == VerificationStatus::NotFound
+        {
+            let head = export.head.as_ref().map_or("none".into(), ToString::to_string);
+            println!("Not anchored on this chain yet, exported at chain head {head}");
+        }
+        if let Some(block) = &report.issued {
+            println!("Issued in {block}");
+        }
+        if let Some(block) = &report.revoked {
+            println!("Revoked in {block}");
+        }
+        if let Some(revocation) = &report.revocation {
+            println!("Revocation reason: {revocation}");
+        }
+        for block in &report.unauthorized_revocations {
+            println!("Unauthorized revocation attempt in {block}, ignored");
+        }
+        if self.verbose {
+            println!(
+                "Credential signatures checked against key {} of issuer '{}'",
+                fingerprint(&credential.issuer.verifying),
+                credential.issuer.name
+            );
+            println!("Consent: {}", report.consent);
+            let blocks = [&report.issued, &report.revoked];
+            for block in blocks.into_iter().flatten().filter_map(|b| blockchain.block(b.height)) {
+                println!(
+                    "Block #{} signature checked against key {}",
+                    block.height(),
+                    fingerprint(&block.signer().verifying)
+                );
+            }
+            if !trace.is_empty() {
+                println!("Trace:");
+            }
+            for event in &trace {
+                println!("  {event}");
+            }
+        }
+        if self.qr_receipt {
+            let receipt = VerificationReceipt {
+                credential: credential.uuid,
+                status: report.status,
+                chain: blockchain.id(),
+                head: blockchain.tip().map(|block| block.hash().clone()),
+                verified_at: Utc::now(),
+            };
+            let payload = compact::to_cbor(&receipt).map_err(|e| e.to_string())?;
+            let code = qr::encode(&payload).map_err(|e| e.to_string())?;
+            println!("{}", qr::render_terminal(&code));
+        }
+        Ok(())
+    }
+
+    /// Checks the credential's bit in a status list. An exported credential brings its own index,
+    /// which is checked against the local chain if there is one; a stored credential's index is
+    /// looked up on the chain.
+    fn verify_status_list(&self, path: &Path) -> Result<(), String> {
+        let contents = fs::read_to_string(path).map_err(|_| "Failed to open status list")?;
+        let list = StatusList::from_json(&contents)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let blockchain =
+            if Path::new("blockchain.json").exists() { Some(open_blockchain()?) } else { None };
+        let (uuid, chain, issuance, exported) = if let Some(file) = &self.file {
+            let export = open_export(file)?;
+            (export.credential.uuid, export.chain, export.issuance.credential, export.status_index)
+        } else {
+            let credentials = open_credentials()?;
+            let query = self.credential.as_deref().unwrap_or_default();
+            let (credential, issuance, _) = &credentials[credential_index(&credentials, query)?];
+            let chain = blockchain.as_ref().and_then(Blockchain::id);
+            (credential.uuid, chain, issuance.credential.clone(), None)
+        };
+        if chain != list.chain {
+            return Err("The status list is for another chain".into());
+        }
+        let authorities = blockchain.as_ref().map(Blockchain::authorities).transpose();
+        if let Some(authorities) = authorities.map_err(|e| e.to_string())?.flatten()
+            && !authorities.iter().any(|authority| authority.verifying == list.signer_key)
+        {
+            return Err("The status list isn't signed by a chain authority".into());
+        }
+        let local = blockchain.as_ref().and_then(|b| status_list::status_index(b, &issuance));
+        let index = match (exported, local) {
+            (Some(exported), Some(local)) if exported != local =>
+                return Err(format!(
+                    "The export claims status index {exported}, but the chain has the credential \
+                     at {local}"
+                )),
+            (index, local) => index.or(local).ok_or("The credential has no status index")?,
+        };
+        let revoked = list.is_revoked(index).ok_or_else(|| {
+            format!("Status index {index} is past the end of the list, which predates the issuance")
+        })?;
+        println!("Credential: {uuid}");
+        println!(
+            "Status list signed by '{}' with key {}",
+            list.signer_name,
+            fingerprint(&list.signer_key)
+        );
+        let head = list.head.as_ref().map_or("none".into(), ToString::to_string);
+        println!("As of chain head {head}, generated at {}", list.generated_at);
+        println!("Status index: {index}");
+        println!("Result: {}", if revoked { "revoked" } else { "not revoked" });
+        Ok(())
+    }
+
+    /// Issuer record of the trust root, whose keys the accreditations are followed from
+    fn trust_root(&self) -> Result<Option<Issuer>, String> {
+        let Some(query) = &self.trust_root else {
+            return Ok(None);
+        };
+        let issuers = open_issuers()?;
+        let live = issuers.iter().filter(|issuer| !issuer.deleted);
+        let uuid =
+            ids::resolve_prefix(query, "issuer", live.map(|i| (i.uuid, &i.name))).map_err(|e| {
+                match e {
+                    IdError::NotFound { .. } => format!("No issuer with UUID {query} to trust"),
+                    e => e.to_string(),
+                }
+            })?;
+        Ok(issuers.into_iter().find(|issuer| issuer.uuid == uuid))
+    }
+
+    fn verify_all(
+        self, blockchain: &Blockchain, credentials: &[CredentialFull],
+    ) -> Result<(), String> {
+        let mut statuses = {
+            let credentials: Vec<_> = credentials.iter().map(|c| &c.0).collect();
+            let mut progress = ProgressBarSink::new("Verifying", self.json);
+            match self.on_date {
+                Some(date) => blockchain.check_credentials_at_with_progress(
+                    &credentials,
+                    date,
+                    &mut progress,
+                ),
+                None => blockchain.check_credentials_with_progress(&credentials, &mut progress),
+            }
+        };
+        if !self.ignore_expiry && self.on_date.is_none() {
+            let today = Utc::now().date_naive();
+            statuses = statuses
+                .into_iter()
+                .zip(credentials)
+                .map(|(status, (credential, ..))| status.within(&credential.valid_duration, today))
+                .collect();
+        }
+        if let Some(root) = &self.trust_root()? {
+            statuses = statuses
+                .into_iter()
+                .zip(credentials)
+                .map(|(status, (credential, ..))| blockchain.check_trust(status, credential, root))
+                .collect();
+        }
+        if self.trusted_only {
+            let trust = open_trust_for_check()?;
+            statuses = statuses
+                .into_iter()
+                .zip(credentials)
+                .map(|(status, (credential, ..))| {
+                    if trust.trusts(&credential.issuer.verifying) {
+                        status
+                    } else {
+                        VerificationStatus::UntrustedIssuer
+                    }
+                })
+                .collect();
+        }
+        let invalid = statuses.iter().filter(|s| !s.is_valid()).count();
+        let total = statuses.len();
+        let rows: Vec<_> = credentials
+            .iter()
+            .zip(statuses)
+            .enumerate()
+            .map(|(index, ((credential, ..), status))| VerificationRow {
+                index,
+                uuid: credential.uuid,
+                subject: credential.subject.full_name(),
+                attribute: credential
+                    .attributes
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                report: blockchain.report(credential, status),
+            })
+            .collect();
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&rows).unwrap());
+        } else {
+            println!("{:<6} {:<24} {:<32} STATUS", "INDEX", "SUBJECT", "ATTRIBUTE");
+            for row in &rows {
+                println!(
+                    "{:<6} {:<24} {:<32} {}",
+                    row.index, row.subject, row.attribute, row.report.status
+                );
+            }
+        }
+        if invalid > 0 {
+            return Err(format!("{invalid} of {total} credentials are not valid"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct InitArgs {
+    /// Human-readable name of the chain
+    #[arg(long, default_value = "Attributes attestation")]
+    name: String,
+    /// Name of the founding issuer, created together with the chain to sign its genesis block
+    #[arg(long)]
+    founder: Option<String>,
+    /// Only allow chain authorities to sign blocks, starting with the founder
+    #[arg(long, requires = "founder")]
+    proof_of_authority: bool,
+    /// Digest algorithm of the chain's hashes: sha512, sha3-512 or blake3
+    #[arg(long, default_value_t = Algorithm::Sha512)]
+    algorithm: Algorithm,
+}
+
+impl InitArgs {
+    fn run(self) -> Result<(), String> {
+        let founder = self.founder.map(Issuer::new);
+        let authorities = founder
+            .as_ref()
+            .filter(|_| self.proof_of_authority)
+            .map(|(issuer, _)| vec![issuer.clone()]);
+        let genesis = GenesisBlock::with_algorithm(
+            self.name,
+            founder.as_ref().map(|(issuer, key)| (issuer.clone(), key as &dyn BlockSigner)),
+            authorities,
+            self.algorithm,
+        )
+        .map_err(|e| e.to_string())?;
+        let blockchain = Blockchain::with_genesis(genesis);
+        let mut writer = File::create_buffered("blockchain.json")
+            .map_err(|_| "Failed to create blockchain file")?;
+        serde_json::to_writer(&mut writer, &blockchain)
+            .map_err(|_| "Failed to write blockchain")?;
+        fs::write("block.json", "null").map_err(|_| "Failed to create block file")?;
+        fs::write("credentials.json", "[]").map_err(|_| "Failed to create credentials file")?;
+        let mut issuers = Vec::new();
+        if let Some((issuer, key)) = founder {
+            key_store().save(issuer.uuid, &key.into()).map_err(|e| e.to_string())?;
+            issuers.push(issuer);
+        }
+        save_issuers(&issuers).map_err(|_| "Failed to create issuers file")?;
+        fs::write("subjects.json", "[]").map_err(|_| "Failed to create subject file")?;
+        println!("Initialized new blockchain, created all the files");
+        Ok(())
+    }
+}
+
+#[derive(Subcommand)]
+enum AuthoritySubcommands {
+    /// Add an issuer to the authorities in the pending block
+    Add {
+        /// Index, UUID or UUID prefix of the issuer
+        issuer: String,
+    },
+    /// List the issuers currently allowed to sign blocks
+    List,
+    /// Remove an issuer from the authorities in the pending block
+    Remove {
+        /// Index, UUID or UUID prefix of the issuer
+        issuer: String,
+    },
+}
+
+impl AuthoritySubcommands {
+    fn run(self) -> Result<(), String> {
+        match self {
+            Self::Add { issuer } => Self::stage(&issuer, AuthorityChange::Add),
+            Self::List => Self::list(),
+            Self::Remove { issuer } => Self::stage(&issuer, AuthorityChange::Remove),
+        }
+    }
+
+    fn stage(issuer: &str, change: fn(Issuer) -> AuthorityChange) -> Result<(), String> {
+        let mut block = open_block()?;
+        let issuers = open_issuers()?;
+        let issuer = issuers[issuer_index(&issuers, issuer)?].clone();
+        block.add_authority_change(change(issuer));
+        save_block(&block)?;
+        println!("Added authority change to the block");
+        Ok(())
+    }
+
+    fn list() -> Result<(), String> {
+        let blockchain = open_blockchain()?;
+        let authorities =
+            blockchain
+                .authorities()
+                .map_err(|e| e.to_string())?
+                .ok_or("Blockchain doesn't use proof of authority")?;
+        for (i, issuer) in authorities.into_iter().enumerate() {
+            println!("{i}: {issuer}");
+        }
+        Ok(())
+    }
+}
+
+/// What the pending block does with a credential
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum PendingAction {
+    Issuance,
+    Revocation,
+}
+
+/// First eight hex digits of a UUID, enough to tell credentials of a store apart
+fn short_uuid(uuid: Uuid) -> String { uuid.simple().to_string()[..8].to_string() }
+
+fn has_no_renewals(lineage: &[Uuid]) -> bool { lineage.len() < 2 }
+
+#[derive(Serialize)]
+struct CredentialStatus {
+    uuid: Uuid,
+    /// UUIDs of the credentials it renews and of its renewals, oldest first, when it has any
+    #[serde(skip_serializing_if = "has_no_renewals")]
+    lineage: Vec<Uuid>,
+    state: String,
+    signed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pending: Option<PendingAction>,
+    valid_duration: ValidDuration,
+    #[serde(flatten)]
+    report: VerificationReport,
+}
+
+#[derive(Subcommand)]
+enum CredentialSubcommands {
+    /// Add a new credential
+    Add(Box<NewCredentialArgs>),
+    /// List existing credentials, all of them unless filtered
+    List(ListCredentialsArgs),
+    /// Check whether a credential is on a revocation list exported with
+    /// `blockchain export-revocations`, once the list's signature checks against its signer's key
+    CheckAgainstRevocationList {
+        /// Index, UUID or hash prefix of the credential
+        credential: String,
+        /// Revocation list
+        list: PathBuf,
+    },
+    /// Check that a document is one of the credential's evidence files
+    CheckEvidence {
+        /// Index, UUID or hash prefix of the credential
+        credential: String,
+        /// Document presented as evidence
+        #[arg(long)]
+        file: PathBuf,
+    },
+    /// Export a credential with its signatures for others to verify
+    #[command(group(
+        ArgGroup::new("export_to")
+            .args(["out", "format", "qr", "qr_png"])
+            .required(true)
+            .multiple(true)
+    ))]
+    Export {
+        /// Index, UUID or hash prefix of the credential
+        credential: String,
+        /// Write the export to a file rather than printing it. In the default format it's a
+        /// standalone file with the credential, its signatures, the issuer key and the chain's
+        /// head, conventionally named `*.attest.json`
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+        /// Format of the export, printed unless written with `--out`
+        #[arg(long, value_enum)]
+        format: Option<ExportFormat>,
+        /// Print a QR code of the credential in its compact CBOR encoding
+        #[arg(long, conflicts_with = "format")]
+        qr: bool,
+        /// Write the QR code as a PNG image
+        #[arg(long, value_name = "PATH", conflicts_with = "format")]
+        qr_png: Option<PathBuf>,
+    },
+    /// Find the stored credential an issuance or revocation hash seen on chain belongs to
+    FindHash {
+        /// Hash or hash prefix, as in `blockchain display`
+        hash: String,
+    },
+    /// Present a credential revealing only some of its attributes, the others withheld as salted
+    /// commitments that still let verifiers check it against the chain
+    Present {
+        /// Index, UUID or hash prefix of the credential
+        credential: String,
+        /// Name of an attribute to reveal, all others are withheld
+        #[arg(long, value_name = "ATTRIBUTE")]
+        reveal: Vec<String>,
+        /// Verifier's challenge to answer with the subject's key, proving the presentation comes
+        /// from the holder
+        #[arg(long, value_name = "HEX", value_parser = parse_challenge)]
+        challenge: Option<Challenge>,
+        /// Write the presentation to a file rather than printing it
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Check that a credential issued with `--private-subject` commits to the given subject,
+    /// opening its commitment with the salt printed when it was issued
+    RevealSubject {
+        /// Index, UUID or hash prefix of the credential
+        credential: String,
+        /// Index, UUID or UUID prefix of the subject the commitment should open to
+        #[arg(long)]
+        subject: String,
+        /// Salt of the commitment, as 64 hex digits
+        #[arg(long, value_name = "HEX")]
+        salt: Salt,
+    },
+    /// Search credentials for attributes whose name or value matches a pattern
+    Search {
+        /// Glob matching a whole name or value, e.g. `*Licence*`, or a regex with `--regex`
+        pattern: String,
+        /// Take the pattern as a regular expression found anywhere in the name or value
+        #[arg(long)]
+        regex: bool,
+        /// Tell upper and lower case apart
+        #[arg(long)]
+        case_sensitive: bool,
+        /// Only credentials of the issuer with the given index or UUID
+        #[arg(long)]
+        issuer: Option<String>,
+    },
+    /// Show the credentials a credential renews and its renewals, with their chain events
+    History {
+        /// Index, UUID or hash prefix of the credential
+        credential: String,
+    },
+    /// Issue credentials in bulk from a CSV file, signing them all as one issuer
+    Import(ImportCredentialsArgs),
+    /// Show a single credential with the hashes its issuance and revocation are recorded under
+    Show {
+        /// Index, UUID or hash prefix of the credential
+        credential: String,
+        /// `table` describes the credential, the others give it with its signed copies as stored
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        /// Same as `--format json-pretty`
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+    },
+    /// Show a credential's signature, pending block, chain and validity state
+    Status {
+        /// Index, UUID or hash prefix of the credential
+        credential: String,
+        /// Print the status as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check a presentation made with `credentials present` against the issuance hash it carries,
+    /// then against the chain
+    VerifyPresentation {
+        file: PathBuf,
+        /// Challenge the holder was given, which the presentation must answer with the subject's
+        /// key
+        #[arg(long, value_name = "HEX", value_parser = parse_challenge)]
+        expected_challenge: Option<Challenge>,
+        /// How old the answer to the challenge may be, in seconds, minutes, hours or days, e.g.
+        /// `90s` or `5m`
+        #[arg(long, value_name = "AGE", value_parser = parse_max_age, default_value = "5m")]
+        #[arg(requires = "expected_challenge")]
+        max_age: TimeDelta,
+    },
+    /// Check a credential exported with `--format cose` offline, against the issuer's key
+    VerifyCose {
+        file: PathBuf,
+        /// Issuer's verifying key, as hex or a file holding what `issuers export-pubkey` prints
+        #[arg(long, value_name = "HEX|FILE")]
+        issuer_key: String,
+    },
+    /// Check a credential exported with `--format jwt` offline: its signature against the
+    /// issuer's key and its validity period against today's date
+    VerifyJwt {
+        token: String,
+        /// Issuer's verifying key, as hex or a file holding what `issuers export-pubkey` prints
+        #[arg(long, value_name = "HEX|FILE")]
+        issuer_key: String,
+    },
+}
+
+impl CredentialSubcommands {
+    fn run(self) -> Result<(), String> {
+        match self {
+            CredentialSubcommands::Add(args) => args.run(),
+            CredentialSubcommands::CheckAgainstRevocationList { credential, list } =>
+                Self::check_against_revocation_list(&credential, &list),
+            CredentialSubcommands::CheckEvidence { credential, file } =>
+                Self::check_evidence(&credential, &file),
+            CredentialSubcommands::Export { credential, out, format, qr, qr_png } =>
+                Self::export(&credential, out.as_deref(), format, qr, qr_png.as_deref()),
+            CredentialSubcommands::FindHash { hash } => Self::find_hash(&hash),
+            CredentialSubcommands::History { credential } => Self::history(&credential),
+            CredentialSubcommands::Import(args) => args.run(),
+            CredentialSubcommands::List(args) => args.run(),
+            CredentialSubcommands::Present { credential, reveal, challenge, out } =>
+                Self::present(&credential, &reveal, challenge.as_ref(), out.as_deref()),
+            CredentialSubcommands::RevealSubject { credential, subject, salt } =>
+                Self::reveal_subject(&credential, &subject, &salt),
+            CredentialSubcommands::Search { pattern, regex, case_sensitive, issuer } =>
+                Self::search(&pattern, regex, case_sensitive, issuer.as_deref()),
+            CredentialSubcommands::Show { credential, format, json } =>
+                Self::show(&credential, if json { OutputFormat::JsonPretty } else { format }),
+            CredentialSubcommands::Status { credential, json } => Self::status(&credential, json),
+            CredentialSubcommands::VerifyCose { file, issuer_key } =>
+                Self::verify_cose(&file, &issuer_key),
+            CredentialSubcommands::VerifyJwt { token, issuer_key } =>
+                Self::verify_jwt(&token, &issuer_key),
+            CredentialSubcommands::VerifyPresentation { file, expected_challenge, max_age } =>
+                Self::verify_presentation(&file, expected_challenge.as_ref(), max_age),
+        }
+    }
+
+    fn export(
+        credential: &str, out: Option<&Path>, format: Option<ExportFormat>, qr: bool,
+        qr_png: Option<&Path>,
+    ) -> Result<(), String> {
+        let mut credentials = open_credentials()?;
+        let index = credential_index(&credentials, credential)?;
+        let (credential, issuance, revocation) = credentials.swap_remove(index);
+        let export = CredentialExport::new(credential, issuance, revocation, &open_blockchain()?);
+        let Some(format) = format else {
+            if let Some(path) = out {
+                Self::write_export(index, path, format!("{export}\n").as_bytes())?;
+            }
+            if !qr && qr_png.is_none() {
+                return Ok(());
+            }
+            return Self::export_qr(export, qr, qr_png);
+        };
+        // Text formats end with a newline, binary ones are written as they are
+        let document = match format {
+            ExportFormat::Attest => format!("{export}\n").into_bytes(),
+            ExportFormat::W3cVc => {
+                let vc = VerifiableCredential::new(&export.credential, &export.issuance);
+                format!("{vc}\n").into_bytes()
+            },
+            ExportFormat::Jwt => {
+                let signer = Self::current_signer(&export.credential)?;
+                let key = signer.verifying_key();
+                let claims = jwt::Claims::new(&export.credential, &export.issuance, &key);
+                let token = jwt::encode(&claims, signer.as_ref()).map_err(|e| e.to_string())?;
+                format!("{token}\n").into_bytes()
+            },
+            ExportFormat::Cbor => {
+                let CredentialExport { credential, issuance, revocation, .. } = export;
+                let compact = CompactCredential { credential, issuance, revocation };
+                compact::to_cbor(&compact).map_err(|e| e.to_string())?
+            },
+            ExportFormat::Cose => {
+                let signer = Self::current_signer(&export.credential)?;
+                cose::sign(&export.credential, signer.as_ref()).map_err(|e| e.to_string())?
+            },
+        };
+        match out {
+            Some(path) => Self::write_export(index, path, &document),
+            None => io::stdout()
+                .write_all(&document)
+                .map_err(|_| "Failed to write exported credential".into()),
+        }
+    }
+
+    fn write_export(index: usize, path: &Path, document: &[u8]) -> Result<(), String> {
+        fs::write(path, document).map_err(|_| "Failed to write exported credential")?;
+        println!("Exported credential {index} to {}", path.display());
+        Ok(())
+    }
+
+    /// Prints or writes a QR code of the credential in its compact encoding
+    fn export_qr(export: CredentialExport, qr: bool, qr_png: Option<&Path>) -> Result<(), String> {
+        let CredentialExport { credential, issuance, revocation, .. } = export;
+        let compact = CompactCredential { credential, issuance, revocation };
+        let payload = compact::to_cbor(&compact).map_err(|e| e.to_string())?;
+        let code = qr::encode(&payload).map_err(|e| match e {
+            QrError::TooLarge { .. } =>
+                format!("Credential is too large for a QR code even in its compact encoding: {e}"),
+            e => e.to_string(),
+        })?;
+        if qr {
+            println!("{}", qr::render_terminal(&code));
+        }
+        if let Some(path) = qr_png {
+            let writer = File::create_buffered(path).map_err(|_| "Failed to create PNG file")?;
+            qr::write_png(&code, writer).map_err(|e| e.to_string())?;
+            println!("Wrote the QR code to {}", path.display());
+        }
+        Ok(())
+    }
+
+    /// Signer of the credential's issuer with its current key, which may have replaced the key the
+    /// credential was issued with
+    fn current_signer(credential: &Credential) -> Result<Box<dyn BlockSigner>, String> {
+        let issuer = open_issuers()?
+            .into_iter()
+            .find(|issuer| issuer.uuid == credential.issuer.uuid && !issuer.deleted)
+            .ok_or("The credential's issuer isn't in issuers.json to sign the export")?;
+        open_signer(&issuer)
+    }
+
+    fn verify_cose(file: &Path, issuer_key: &str) -> Result<(), String> {
+        let key = parse_verifying_key(issuer_key)?;
+        let bytes = fs::read(file).map_err(|_| "Failed to open COSE file")?;
+        let credential =
+            cose::verify(&bytes, &key).map_err(|e| format!("Invalid COSE_Sign1: {e}"))?;
+        println!("Valid COSE_Sign1 for credential {}", credential.uuid);
+        println!("Issuer: {} ({})", credential.issuer.name, credential.issuer.uuid);
+        println!("Subject: {}", subject_summary(&credential.subject));
+        for attribute in &credential.attributes {
+            println!("Attribute: {attribute}");
+        }
+        Ok(())
+    }
+
+    fn verify_jwt(token: &str, issuer_key: &str) -> Result<(), String> {
+        let key = parse_verifying_key(issuer_key)?;
+        let claims =
+            jwt::verify(token, &key, Utc::now()).map_err(|e| format!("Invalid token: {e}"))?;
+        println!("Valid token for credential {}", claims.jti);
+        println!("Issuer: {} ({})", claims.vc.issuer, claims.iss);
+        println!("Subject: {}", claims.sub);
+        for attribute in &claims.vc.attributes {
+            println!("Attribute: {}: {}", attribute.name, attribute.value);
+        }
+        println!("Credential hash: {}", claims.vc.credential_hash);
+        Ok(())
+    }
+
+    fn present(
+        credential: &str, reveal: &[String], challenge: Option<&Challenge>, out: Option<&Path>,
+    ) -> Result<(), String> {
+        let credentials = open_credentials()?;
+        let (credential, issuance, _) = &credentials[credential_index(&credentials, credential)?];
+        let format = open_blockchain()?.format();
+        let error =
+            |e: PresentationError| format!("Can't present credential {}: {e}", credential.uuid);
+        let mut presentation =
+            Presentation::new(credential, issuance, reveal, format).map_err(error)?;
+        if let Some(challenge) = challenge {
+            let key = open_subject_key(&holder(&credential.subject)?, "answer challenges")?;
+            presentation.answer(&challenge.0, Utc::now(), &key).map_err(error)?;
+        }
+        let Some(path) = out else {
+            println!("{presentation}");
+            return Ok(());
+        };
+        fs::write(path, format!("{presentation}\n")).map_err(|_| "Failed to write presentation")?;
+        println!(
+            "Presented credential {} to {}, revealing {} and withholding {} attributes",
+            credential.uuid,
+            path.display(),
+            presentation.credential.attributes.len(),
+            presentation.credential.withheld.len()
+        );
+        Ok(())
+    }
+
+    fn verify_presentation(
+        file: &Path, expected_challenge: Option<&Challenge>, max_age: TimeDelta,
+    ) -> Result<(), String> {
+        let contents = fs::read_to_string(file).map_err(|_| "Failed to open presentation")?;
+        let presentation = Presentation::from_json(&contents)
+            .map_err(|e| format!("Invalid presentation {}: {e}", file.display()))?;
+        // The holder is checked first, a replayed presentation being rejected whatever the chain
+        if let Some(challenge) = expected_challenge {
+            presentation
+                .check_holder(&challenge.0, max_age, Utc::now())
+                .map_err(|status| format!("Presentation rejected: {status}"))?;
+        }
+        let blockchain = open_blockchain()?;
+        if presentation.format() != blockchain.format() {
+            return Err(format!(
+                "The presentation is hashed with version {} and {}, the chain with version {} and \
+                 {}",
+                u32::from(presentation.hash_version),
+                presentation.algorithm,
+                u32::from(blockchain.version()),
+                blockchain.format().algorithm
+            ));
+        }
+        let credential = &presentation.credential;
+        println!("Credential {} issued by '{}'", credential.uuid, credential.issuer.name);
+        for attribute in &credential.attributes {
+            println!("Revealed: {attribute}");
+        }
+        println!("Withheld: {} attributes", credential.withheld.len());
+        if let Some(holder) = &presentation.holder {
+            let checked = if expected_challenge.is_some() { "" } else { ", not checked" };
+            let (challenge, timestamp) = (&holder.challenge, holder.timestamp);
+            println!("Holder: answered challenge {challenge} at {timestamp}{checked}");
+        }
+        let today = Utc::now().date_naive();
+        let status = blockchain.check_credential(credential);
+        let status = status.within(&credential.valid_duration, today);
+        let report = blockchain.report(credential, status);
+        println!("Result: {}", report.status);
+        if let Some(block) = &report.issued {
+            println!("Issued in {block}");
+        }
+        if let Some(block) = &report.revoked {
+            println!("Revoked in {block}");
+        }
+        Ok(())
+    }
+
+    fn find_hash(hash: &str) -> Result<(), String> {
+        // `blockchain display` tags hashes of other algorithms than SHA-512, e.g. `blake3:`
+        let prefix = hash.rsplit(':').next().unwrap_or_default();
+        if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Expected a hex hash or hash prefix, got '{hash}'"));
+        }
+        let credentials = open_credentials()?;
+        let blockchain = open_blockchain()?;
+        let format = blockchain.format();
+        let mut found = false;
+        for (index, (credential, ..)) in credentials.iter().enumerate() {
+            for (form, revoking) in [("Issuance", false), ("Revocation", true)] {
+                let hash = credential.hash(revoking, format);
+                if !hash.matches_prefix(prefix) {
+                    continue;
+                }
+                found = true;
+                let attributes: Vec<_> =
+                    credential.attributes.iter().map(ToString::to_string).collect();
+                println!(
+                    "Credential {index} ({}): {}, {}",
+                    credential.uuid,
+                    credential.subject.full_name(),
+                    attributes.join(", ")
+                );
+                println!("  {form} hash {hash}");
+                let blocks = blockchain.blocks_containing(&hash);
+                if blocks.is_empty() {
+                    println!("  Not in any block");
+                }
+                for (height, kind) in blocks {
+                    if let Some(block) = blockchain.block_ref(height) {
+                        println!("  Found in {block} as {kind}");
+                    }
+                }
+            }
+        }
+        if found { Ok(()) } else { Err(format!("No stored credential hashes to '{hash}'")) }
+    }
+
+    fn reveal_subject(credential: &str, subject: &str, salt: &Salt) -> Result<(), String> {
+        let credentials = open_credentials()?;
+        let credential = &credentials[credential_index(&credentials, credential)?].0;
+        let CredentialSubject::Committed(commitment) = &credential.subject else {
+            return Err(format!("Credential {} doesn't commit to its subject", credential.uuid));
+        };
+        let subjects = open_subjects()?;
+        let subject = subject_at(&subjects, subject_index(&subjects, subject)?)?;
+        if !commitment.opens_to(subject, salt) {
+            return Err(format!(
+                "The subject commitment of credential {} doesn't open to subject '{} {}' with this \
+                 salt",
+                credential.uuid, subject.name, subject.surname
+            ));
+        }
+        println!(
+            "Credential {} commits to subject '{} {}' ({})",
+            credential.uuid, subject.name, subject.surname, subject.uuid
+        );
+        Ok(())
+    }
+
+    fn search(
+        pattern: &str, regex: bool, case_sensitive: bool, issuer: Option<&str>,
+    ) -> Result<(), String> {
+        let query = if regex {
+            CredentialQuery::regex(pattern, case_sensitive)
+        } else {
+            CredentialQuery::glob(pattern, case_sensitive)
+        };
+        let mut query = query.map_err(|e| format!("Can't search for '{pattern}': {e}"))?;
+        if let Some(issuer) = issuer {
+            let issuers = open_issuers()?;
+            query = query.issuer(issuers[issuer_index(&issuers, issuer)?].uuid);
+        }
+        let credentials = open_credentials()?;
+        let matched: Vec<_> = credentials
+            .iter()
+            .map(|(credential, ..)| credential)
+            .filter(|credential| query.matches(credential))
+            .collect();
+        if matched.is_empty() {
+            return Err(format!("No credential has an attribute matching '{pattern}'"));
+        }
+        let blockchain = open_blockchain()?;
+        let today = Utc::now().date_naive();
+        for (credential, status) in matched.iter().zip(blockchain.check_credentials(&matched)) {
+            let status = status.within(&credential.valid_duration, today);
+            let subject = &credential.subject;
+            for attribute in query.matching_attributes(credential) {
+                println!(
+                    "{} {}: {}={} ({status})",
+                    credential.uuid,
+                    subject.full_name(),
+                    attribute.name,
+                    attribute.value
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn show(credential: &str, format: OutputFormat) -> Result<(), String> {
+        let credentials = open_credentials()?;
+        let stored = &credentials[credential_index(&credentials, credential)?];
+        if format != OutputFormat::Table {
+            return format.show(stored, || {});
+        }
+        let (credential, issued, revoking) = stored;
+        // The hashes the chain records, computed under the chain's rules as when signing
+        let format = open_blockchain()?.format();
+        let issuance = credential.hash(false, format);
+        let revocation = credential.hash(true, format);
+        println!("Credential {}", credential.uuid);
+        for attribute in &credential.attributes {
+            println!("Attribute: {attribute}");
+        }
+        println!("Subject: {}", subject_summary(&credential.subject));
+        println!("Issuer: {} ({})", credential.issuer.name, credential.issuer.fingerprint());
+        match credential.valid_duration.to {
+            Some(to) => println!("Valid from {} to {to}", credential.valid_duration.from),
+            None => println!("Valid from {} indefinitely", credential.valid_duration.from),
+        }
+        println!("Issuance hash: {issuance}");
+        println!("Revocation hash: {revocation}");
+        for (copy, signed, hash) in
+            [("issuance", issued, &issuance), ("revocation", revoking, &revocation)]
+        {
+            let state = if signed.credential != *hash {
+                "doesn't match the credential"
+            } else if signed.verify(&credential.issuer.verifying) {
+                "matches, signed by the issuer"
+            } else {
+                "matches, but the signature is invalid"
+            };
+            println!("Signed {copy} copy: {state}");
+        }
+        Ok(())
+    }
+
+    fn check_against_revocation_list(credential: &str, list: &Path) -> Result<(), String> {
+        let contents = fs::read_to_string(list).map_err(|_| "Failed to open revocation list")?;
+        let list = RevocationList::from_json(&contents)
+            .map_err(|e| format!("Invalid revocation list {}: {e}", list.display()))?;
+        // The list names its signer's key, which has to be one the signer is known by here
+        let known = open_issuers()?
+            .iter()
+            .any(|issuer| issuer.uuid == list.signer && issuer.has_used_key(&list.signer_key));
+        if !known {
+            return Err(format!(
+                "Revocation list is signed by '{}' with key {}, which isn't a key of a known \
+                 issuer",
+                list.signer_name,
+                fingerprint(&list.signer_key)
+            ));
+        }
+        let credentials = open_credentials()?;
+        let (credential, _, revocation) = &credentials[credential_index(&credentials, credential)?];
+        let head = list.head.as_ref().map_or("none".into(), ToString::to_string);
+        println!(
+            "Revocation list signed by '{}', generated at {} for chain head {head}",
+            list.signer_name, list.generated_at
+        );
+        match list.find(&revocation.credential) {
+            Some(entry) => println!(
+                "Credential {} is revoked, in block #{} at {}",
+                credential.uuid, entry.height, entry.timestamp
+            ),
+            None => println!("Credential {} is not on the list", credential.uuid),
+        }
+        Ok(())
+    }
+
+    fn check_evidence(credential: &str, file: &Path) -> Result<(), String> {
+        let credentials = open_credentials()?;
+        let credential = &credentials[credential_index(&credentials, credential)?].0;
+        if credential.evidence.is_empty() {
+            return Err("The credential has no evidence attached".into());
+        }
+        let read_error = |e: io::Error| format!("Can't read {}: {e}", file.display());
+        for evidence in &credential.evidence {
+            let mut document = File::open_buffered(file).map_err(read_error)?;
+            if evidence.matches(&mut document).map_err(read_error)? {
+                println!(
+                    "Evidence matches '{}' ({}, {} bytes)",
+                    evidence.filename, evidence.media_type, evidence.length
+                );
+                return Ok(());
+            }
+        }
+        Err(format!("{} doesn't match any evidence of the credential", file.display()))
+    }
+
+    fn history(credential: &str) -> Result<(), String> {
+        let credentials = open_credentials()?;
+        let credential = &credentials[credential_index(&credentials, credential)?].0;
+        let all: Vec<_> = credentials.iter().map(|c| &c.0).collect();
+        let generations = lineage(credential, &all).map_err(|e| e.to_string())?;
+        let blockchain = open_blockchain()?;
+        for generation in generations {
+            let report = blockchain.report(generation, blockchain.check_credential(generation));
+            let renewal = generation
+                .supersedes
+                .map(|previous| format!(" (renewal of {})", short_uuid(previous)))
+                .unwrap_or_default();
+            println!("Credential {}{renewal}: {}", generation.uuid, report.status);
+            if let Some(block) = &report.issued {
+                println!("  Issued in {block}");
+            }
+            if let Some(block) = &report.revoked {
+                println!("  Revoked in {block}");
+            }
+        }
+        Ok(())
+    }
+
+    fn status(credential: &str, json: bool) -> Result<(), String> {
+        let credentials = open_credentials()?;
+        let (credential, issued, revoking) =
+            &credentials[credential_index(&credentials, credential)?];
+        let signed = issued.verify(&credential.issuer.verifying)
+            && revoking.verify(&credential.issuer.verifying);
+        let pending = open_block().ok().and_then(|block| {
+            if block.issues(&issued.credential) {
+                Some(PendingAction::Issuance)
+            } else if block.revokes(&revoking.credential) {
+                Some(PendingAction::Revocation)
+            } else {
+                None
+            }
+        });
+        let blockchain = open_blockchain()?;
+        let today = Utc::now().date_naive();
+        let status =
+            blockchain.check_credential(credential).within(&credential.valid_duration, today);
+        let style = if signed { ui::status_style(&status) } else { ui::BAD };
+        let state = match (&status, pending) {
+            _ if !signed => "signature invalid".to_string(),
+            (VerificationStatus::NotFound, Some(PendingAction::Issuance)) =>
+                "staged for issuance in the pending block".to_string(),
+            (VerificationStatus::NotFound, _) => "not issued".to_string(),
+            (status, Some(PendingAction::Revocation)) =>
+                format!("{status}, staged for revocation in the pending block"),
+            (status, _) => status.to_string(),
+        };
+        let all: Vec<_> = credentials.iter().map(|c| &c.0).collect();
+        let lineage = lineage(credential, &all).map_err(|e| e.to_string())?;
+        let mut status = CredentialStatus {
+            uuid: credential.uuid,
+            lineage: lineage.iter().map(|c| c.uuid).collect(),
+            state,
+            signed,
+            pending,
+            valid_duration: credential.valid_duration.clone(),
+            report: blockchain.report(credential, status),
+        };
+        // Until it's issued, the consent is the one stored with the credential
+        if status.report.issued.is_none() {
+            status.report.consent = issued.consent_status(&credential.subject);
+        }
+        if json {
+            println!("{}", serde_json::to_string_pretty(&status).unwrap());
+            return Ok(());
+        }
+        anstream::println!("{}", ui::heading(format_args!("Credential {}", status.uuid)));
+        anstream::println!("{} {}", ui::heading("Status:"), ui::paint(style, &status.state));
+        let signature = if signed { "valid" } else { "invalid" };
+        anstream::println!(
+            "{} {} ({signature} signature)",
+            ui::heading("Signed by:"),
+            credential.issuer.name
+        );
+        match status.valid_duration.to {
+            Some(to) => println!("Valid from {} to {to}", status.valid_duration.from),
+            None => println!("Valid from {} indefinitely", status.valid_duration.from),
+        }
+        if let Some(block) = &status.report.issued {
+            println!("Issued in {block}");
+        }
+        if let Some(block) = &status.report.revoked {
+            println!("Revoked in {block}");
+        }
+        if let Some(revocation) = &status.report.revocation {
+            anstream::println!("{} {revocation}", ui::heading("Revocation reason:"));
+        }
+        anstream::println!("{} {}", ui::heading("Consent:"), status.report.consent);
+        if !has_no_renewals(&status.lineage) {
+            let generations: Vec<_> = status
+                .lineage
+                .iter()
+                .map(|&uuid| {
+                    let marker = if uuid == status.uuid { " (this)" } else { "" };
+                    format!("{}{marker}", short_uuid(uuid))
+                })
+                .collect();
+            println!("Lineage: {}", generations.join(" -> "));
+        }
+        Ok(())
+    }
+}
+
+/// Position of an entry of a store given by `query`, as an index, then as a UUID or a UUID
+/// prefix. `uuid` gives none for removed entries, which are never matched.
+fn lookup<T>(
+    kind: &'static str, entries: &[T], query: &str, uuid: impl Fn(&T) -> Option<Uuid>,
+    name: impl Fn(&T) -> String,
+) -> Result<usize, IdError> {
+    if let Ok(index) = query.parse::<usize>()
+        && entries.get(index).and_then(&uuid).is_some()
+    {
+        return Ok(index);
+    }
+    let live = entries.iter().filter_map(|entry| uuid(entry).map(|u| (u, name(entry))));
+    let found = ids::resolve_prefix(query, kind, live)?;
+    Ok(entries.iter().position(|entry| uuid(entry) == Some(found)).expect("UUID of an entry"))
+}
+
+/// Error for a query [`lookup`] failed on, which was most likely meant as an index if it's a
+/// number
+fn lookup_error(kind: &str, query: &str, error: &IdError) -> String {
+    match error {
+        IdError::Ambiguous { .. } => error.to_string(),
+        _ if query.parse::<usize>().is_ok() => format!("No {kind} with given index"),
+        _ => error.to_string(),
+    }
+}
+
+/// Finds a credential by its index, UUID, UUID prefix or hash prefix
+fn credential_index(credentials: &[CredentialFull], credential: &str) -> Result<usize, String> {
+    let uuid = |c: &CredentialFull| Some(c.0.uuid);
+    let name = |c: &CredentialFull| c.0.subject.full_name();
+    match lookup("credential", credentials, credential, uuid, name) {
+        Ok(index) => Ok(index),
+        Err(IdError::NotFound { .. } | IdError::NotHex { .. })
+            if credential.parse::<usize>().is_err() =>
+            resolve_prefix(credential, credentials.iter().map(|c| &c.1.credential)).map_err(|e| {
+                format!("Credential must be an index, a UUID or a UUID or hash prefix: {e}")
+            }),
+        Err(e) => Err(lookup_error("credential", credential, &e)),
+    }
+}
+
+/// Looks an issuer up by index, UUID or UUID prefix
+fn issuer_index(issuers: &[Issuer], issuer: &str) -> Result<usize, String> {
+    let uuid = |i: &Issuer| (!i.deleted).then_some(i.uuid);
+    lookup("issuer", issuers, issuer, uuid, |i| i.name.clone())
+        .map_err(|e| lookup_error("issuer", issuer, &e))
+}
+
+/// Looks a subject up by index, UUID or UUID prefix
+fn subject_index(subjects: &[Subject], subject: &str) -> Result<usize, String> {
+    let uuid = |s: &Subject| (!s.deleted).then_some(s.uuid);
+    let name = |s: &Subject| format!("{} {}", s.name, s.surname);
+    lookup("subject", subjects, subject, uuid, name)
+        .map_err(|e| lookup_error("subject", subject, &e))
+}
+
+/// Indexes of the subjects matching `query`, see [`Subject::matches`]
+fn matching_subjects(subjects: &[Subject], query: &str, exact: bool) -> Vec<usize> {
+    subjects
+        .iter()
+        .enumerate()
+        .filter(|(_, subject)| !subject.deleted && subject.matches(query, exact))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Index of the only subject matching `query`, failing with the candidates if there are several
+fn find_subject(subjects: &[Subject], query: &str) -> Result<usize, String> {
+    match matching_subjects(subjects, query, false).as_slice() {
+        [index] => Ok(*index),
+        [] => Err(format!("No subject matches '{query}'")),
+        indexes => {
+            let candidates: Vec<_> =
+                indexes.iter().map(|&i| format!("  {}", subject_line(i, &subjects[i]))).collect();
+            Err(format!(
+                "Subject '{query}' is ambiguous, give its index or UUID instead:\n{}",
+                candidates.join("\n")
+            ))
+        },
+    }
+}
+
+/// Subject that looks like `other`, with its number of credentials and the extra fields both
+/// have the same value for
+fn duplicate_line(
+    index: usize, subject: &Subject, other: &Subject, credentials: &[CredentialFull],
+) -> String {
+    let count = credentials.iter().filter(|c| c.0.subject.is(subject)).count();
+    let (same, _) = subject.compare_extra(other);
+    let same = if same.is_empty() { String::new() } else { format!(" (same {})", same.join(", ")) };
+    format!("{}, {count} credential(s){same}", subject_line(index, subject))
+}
+
+/// Index, UUID and full name of a subject on a single line
+fn subject_line(index: usize, subject: &Subject) -> String {
+    format!("{index}: {} {} {}", subject.uuid, subject.name, subject.surname)
+}
+
+/// Subject at the given index, removed subjects' slots holding none
+fn subject_at(subjects: &[Subject], index: usize) -> Result<&Subject, String> {
+    subjects.get(index).filter(|s| !s.deleted).ok_or_else(|| "No subject with given index".into())
+}
+
+/// Order and slice of the entries list commands print
+#[derive(Args)]
+struct PageArgs {
+    /// Field to sort by, store order if not given
+    #[arg(long, value_enum)]
+    sort: Option<SortField>,
+    /// Reverse the order
+    #[arg(long)]
+    reverse: bool,
+    /// Print at most this many entries
+    #[arg(long, value_name = "N")]
+    limit: Option<usize>,
+    /// Skip this many entries first
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    offset: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SortField {
+    /// Name, the subject's full name for credentials, ignoring case
+    Name,
+    /// Issuance time for credentials, store order for issuers and subjects
+    Created,
+    /// Last day of the validity period, open-ended credentials last
+    ValidityEnd,
+    Uuid,
+}
+
+/// What a listed entry is sorted by, along with its store index, which is what's printed
+struct SortKey {
+    index: usize,
+    name: String,
+    uuid: Uuid,
+    /// None when entries are only known to be created in store order
+    created: Option<DateTime<Utc>>,
+    /// None for entries without a validity period
+    validity: Option<ValidDuration>,
+}
+
+impl SortKey {
+    fn new(index: usize, name: &str, uuid: Uuid) -> Self {
+        Self { index, name: name.to_lowercase(), uuid, created: None, validity: None }
+    }
+}
+
+impl PageArgs {
+    /// Store indexes of the entries on the requested page, in the requested order
+    fn apply(&self, mut keys: Vec<SortKey>) -> Result<Vec<usize>, String> {
+        // Sorts are stable, so entries with the same key stay in store order
+        match self.sort {
+            Some(SortField::Name) => keys.sort_by(|a, b| a.name.cmp(&b.name)),
+            Some(SortField::Created) => keys.sort_by_key(|key| key.created),
+            Some(SortField::ValidityEnd) => {
+                if keys.iter().any(|key| key.validity.is_none()) {
+                    return Err("Only credentials can be sorted by validity end".into());
+                }
+                // Open-ended validity goes past any end date
+                keys.sort_by_key(|key| key.validity.as_ref().map(|v| (v.to.is_none(), v.to)));
+            },
+            Some(SortField::Uuid) => keys.sort_by_key(|key| key.uuid),
+            None => {},
+        }
+        if self.reverse {
+            keys.reverse();
+        }
+        let page = keys.into_iter().skip(self.offset).take(self.limit.unwrap_or(usize::MAX));
+        Ok(page.map(|key| key.index).collect())
+    }
+}
+
+#[derive(Args)]
+struct ListCredentialsArgs {
+    /// Only credentials of the issuer with the given index, UUID or UUID prefix
+    #[arg(long)]
+    issuer: Option<String>,
+    /// Only credentials of the subject with the given index, UUID or UUID prefix
+    #[arg(long)]
+    subject: Option<String>,
+    /// Only credentials with an attribute of the given name, ignoring case
+    #[arg(long, value_name = "NAME")]
+    attribute: Option<String>,
+    /// Only credentials whose `--attribute` has the given value
+    #[arg(long, requires = "attribute")]
+    value: Option<String>,
+    /// Only credentials in the given state on the chain today
+    #[arg(long, value_enum)]
+    status: Option<StatusFilter>,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+    /// Same as `--format json`
+    #[arg(long, conflicts_with = "format")]
+    json: bool,
+    #[command(flatten)]
+    page: PageArgs,
+}
+
+/// Chain states `credentials list --status` selects
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum StatusFilter {
+    Valid,
+    Revoked,
+    Expired,
+    NotYetValid,
+    /// In no block yet
+    NotIssued,
+    /// Issued by being anchored in another issuer's block
+    Anchored,
+}
+
+impl StatusFilter {
+    fn matches(self, status: &VerificationStatus, anchored: bool) -> bool {
+        match self {
+            Self::Valid => status.is_valid(),
+            Self::Revoked => matches!(status, VerificationStatus::Revoked { .. }),
+            Self::Expired => matches!(status, VerificationStatus::Expired { .. }),
+            Self::NotYetValid => matches!(status, VerificationStatus::NotYetValid { .. }),
+            Self::NotIssued => matches!(status, VerificationStatus::NotFound),
+            Self::Anchored => anchored,
+        }
+    }
+}
+
+/// Formats credentials are exported in for others to verify
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    /// This crate's own standalone file, verified with `blockchain verify --file`
+    Attest,
+    /// W3C verifiable credential, with the issuer as a `did:key`
+    #[value(name = "w3c-vc")]
+    W3cVc,
+    /// Compact JWS signed with the issuer's current key, checked with `credentials verify-jwt`
+    Jwt,
+    /// The credential and its signatures in CBOR, as QR codes hold them
+    Cbor,
+    /// `COSE_Sign1` of the credential in CBOR, signed with the issuer's current key and checked
+    /// with `credentials verify-cose`
+    Cose,
+}
+
+/// How commands print what they show
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Aligned columns or lines for people to read
+    Table,
+    /// JSON on a single line, list commands giving each entry its index
+    Json,
+    /// Indented JSON, list commands printing each entry's index followed by the entry
+    JsonPretty,
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Prints the view serialized in the format, or has `table` lay it out for people
+    fn show<T: Serialize + ?Sized>(self, view: &T, table: impl FnOnce()) -> Result<(), String> {
+        let serialized = match self {
+            Self::Table => {
+                table();
+                return Ok(());
+            },
+            Self::Json => serde_json::to_string(view).map_err(|e| e.to_string()),
+            Self::JsonPretty => serde_json::to_string_pretty(view).map_err(|e| e.to_string()),
+            // YAML documents end with a line break already
+            Self::Yaml => serde_yaml::to_string(view)
+                .map(|yaml| yaml.trim_end().to_string())
+                .map_err(|e| e.to_string()),
+        };
+        println!("{}", serialized.map_err(|e| format!("Failed to serialize the output: {e}"))?);
+        Ok(())
+    }
+}
+
+/// Entry of a list command's JSON or YAML output
+#[derive(Serialize)]
+struct Listed<'a, T> {
+    index: usize,
+    #[serde(flatten)]
+    entry: &'a T,
+}
+
+fn listed<'a, T>(entries: &'a [T], selected: &[usize]) -> Vec<Listed<'a, T>> {
+    selected.iter().map(|&index| Listed { index, entry: &entries[index] }).collect()
+}
+
+/// Prints the table fitted to the terminal, or whole if the output isn't a terminal, nothing at
+/// all if it has no rows
+fn print_table(table: &Table) {
+    if !table.is_empty() {
+        print!("{}", table.render(terminal_width()));
+    }
+}
+
+/// Width of the terminal the output goes to, as the shell exports it
+fn terminal_width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    std::env::var("COLUMNS").ok()?.parse().ok()
+}
+
+impl ListCredentialsArgs {
+    fn run(self) -> Result<(), String> {
+        let credentials = open_credentials()?;
+        let issuer = match &self.issuer {
+            Some(issuer) => {
+                let issuers = open_issuers()?;
+                Some(issuers[issuer_index(&issuers, issuer)?].uuid)
+            },
+            None => None,
+        };
+        let subject = match &self.subject {
+            Some(subject) => {
+                let subjects = open_subjects()?;
+                Some(subjects[subject_index(&subjects, subject)?].uuid)
+            },
+            None => None,
+        };
+        let mut selected: Vec<_> = (0..credentials.len())
+            .filter(|&i| {
+                let credential = &credentials[i].0;
+                issuer.is_none_or(|uuid| credential.issuer.uuid == uuid)
+                    && subject.is_none_or(|uuid| {
+                        credential.subject.disclosed().is_some_and(|subject| subject.uuid == uuid)
+                    })
+                    && self.attribute.as_ref().is_none_or(|name| {
+                        credential.attributes.iter().any(|attribute| {
+                            attribute.name.eq_ignore_ascii_case(name)
+                                && self.value.as_ref().is_none_or(|value| &attribute.value == value)
+                        })
+                    })
+            })
+            .collect();
+        if let Some(filter) = self.status {
+            selected = Self::with_status(&credentials, selected, filter)?;
+        }
+        let keys = selected
+            .into_iter()
+            .map(|i| {
+                let credential = &credentials[i].0;
+                let name = credential.subject.full_name();
+                SortKey {
+                    created: Some(credential.issued_at),
+                    validity: Some(credential.valid_duration.clone()),
+                    ..SortKey::new(i, &name, credential.uuid)
+                }
+            })
+            .collect();
+        let selected = self.page.apply(keys)?;
+        let format = if self.json { OutputFormat::Json } else { self.format };
+        if matches!(format, OutputFormat::Json | OutputFormat::Yaml) {
+            let listed: Vec<_> = selected
+                .iter()
+                .map(|&index| Listed { index, entry: &credentials[index].0 })
+                .collect();
+            return format.show(&listed, || {});
+        }
+        for &i in &selected {
+            if !credentials[i].0.has_issuance_time() {
+                eprintln!("WARNING: credential {i} was stored without its issuance time");
+            }
+        }
+        if format == OutputFormat::Table {
+            print_table(&Self::table(&credentials, &selected));
+            return Ok(());
+        }
+        for i in selected {
+            let credential = &credentials[i].0;
+            match credential.supersedes {
+                Some(previous) =>
+                    println!("{i} (renewal of {}): {credential}", short_uuid(previous)),
+                None => println!("{i}: {credential}"),
+            }
+        }
+        Ok(())
+    }
+
+    fn table(credentials: &[CredentialFull], selected: &[usize]) -> Table {
+        // Credentials can be listed before the blockchain exists, none has a status then
+        let blockchain = open_blockchain().ok();
+        let checked: Vec<_> = selected.iter().map(|&i| &credentials[i].0).collect();
+        let statuses = blockchain.map(|chain| chain.check_credentials(&checked));
+        let today = Utc::now().date_naive();
+        let mut table =
+            Table::new(["INDEX", "UUID", "SUBJECT", "ATTRIBUTES", "ISSUER", "VALIDITY", "STATUS"]);
+        for (n, (&i, credential)) in selected.iter().zip(checked).enumerate() {
+            let attributes: Vec<_> = credential
+                .attributes
+                .iter()
+                .map(|attribute| format!("{}={}", attribute.name, attribute.value))
+                .collect();
+            let duration = &credential.valid_duration;
+            let validity = match duration.to {
+                Some(to) => format!("{} to {to}", duration.from),
+                None => format!("from {}", duration.from),
+            };
+            let status = statuses
+                .as_ref()
+                .map_or("-", |statuses| statuses[n].clone().within(duration, today).label());
+            table.row([
+                i.to_string(),
+                short_uuid(credential.uuid),
+                credential.subject.full_name(),
+                attributes.join(", "),
+                credential.issuer.name.clone(),
+                validity,
+                status.to_string(),
+            ]);
+        }
+        table
+    }
+
+    /// Narrows the selected credentials down to those in the given state, reading the chain a
+    /// single time for all of them
+    fn with_status(
+        credentials: &[CredentialFull], selected: Vec<usize>, filter: StatusFilter,
+    ) -> Result<Vec<usize>, String> {
+        let blockchain = open_blockchain()?;
+        let format = blockchain.format();
+        let anchored: HashSet<_> = blockchain
+            .blocks()
+            .flat_map(Block::anchored_credentials)
+            .map(|signed| &signed.credential)
+            .collect();
+        let checked: Vec<_> = selected.iter().map(|&i| &credentials[i].0).collect();
+        let today = Utc::now().date_naive();
+        let statuses = blockchain.check_credentials(&checked);
+        Ok(selected
+            .into_iter()
+            .zip(statuses)
+            .filter(|&(i, ref status)| {
+                let credential = &credentials[i].0;
+                let status = status.clone().within(&credential.valid_duration, today);
+                filter.matches(&status, anchored.contains(&credential.hash(false, format)))
+            })
+            .map(|(i, _)| i)
+            .collect())
+    }
+}
+
+/// Looks a block up by height, or by hash prefix if no block has that height
+fn find_block<'a>(blockchain: &'a Blockchain, block: &str) -> Result<&'a Block, String> {
+    let height = block.parse::<u64>().ok();
+    if let Some(found) = height.and_then(|height| blockchain.block(height)) {
+        return Ok(found);
+    }
+    blockchain.block_by_hash_prefix(block).map_err(|e| match (height, e) {
+        (Some(height), PrefixError::NotFound) => format!(
+            "No block at height {height} and no block hash starts with it, {}",
+            chain_extent(blockchain)
+        ),
+        (_, e) => format!("No block {block}: {e}"),
+    })
+}
+
+/// Heights of a chain's blocks, for errors about heights outside them
+fn chain_extent(blockchain: &Blockchain) -> String {
+    match blockchain.len() {
+        0 => "the chain has no blocks".to_string(),
+        len => format!("the chain's blocks go from height 0 to {}", len - 1),
+    }
+}
+
+/// Heights selected by `blockchain display --range`, `to` excluded
+#[derive(Clone, Copy)]
+struct HeightRange {
+    from: u64,
+    to: Option<u64>,
+}
+
+impl HeightRange {
+    fn select(self, blockchain: &Blockchain) -> Result<Vec<&Block>, String> {
+        let len = blockchain.len() as u64;
+        let to = self.to.unwrap_or(len);
+        if self.from >= len || to > len {
+            return Err(format!(
+                "Range {}..{to} goes past the last block, {}",
+                self.from,
+                chain_extent(blockchain)
+            ));
+        }
+        Ok((self.from..to).filter_map(|height| blockchain.block(height)).collect())
+    }
+}
+
+fn parse_height_range(range: &str) -> Result<HeightRange, String> {
+    let error = || format!("Expected FROM..TO, FROM..=TO or FROM.., got '{range}'");
+    let (from, to) = range.split_once("..").ok_or_else(error)?;
+    let from = if from.is_empty() { 0 } else { from.parse().map_err(|_| error())? };
+    let to = match to.strip_prefix('=') {
+        Some(to) => Some(to.parse::<u64>().map_err(|_| error())?.saturating_add(1)),
+        None if to.is_empty() => None,
+        None => Some(to.parse().map_err(|_| error())?),
+    };
+    match to {
+        Some(to) if to <= from => Err(format!("Range '{range}' holds no blocks")),
+        to => Ok(HeightRange { from, to }),
+    }
+}
+
+/// Parses an `--attr` value of the form `name=value`
+/// Verifier's challenge to a holder presenting a credential
+#[derive(Clone)]
+struct Challenge(Vec<u8>);
+
+fn parse_challenge(challenge: &str) -> Result<Challenge, String> {
+    match hex::decode(challenge) {
+        Ok(bytes) if !bytes.is_empty() => Ok(Challenge(bytes)),
+        _ => Err(format!("Expected the challenge as hex digits, got '{challenge}'")),
+    }
+}
+
+fn parse_max_age(age: &str) -> Result<TimeDelta, String> {
+    let error = || format!("Expected a number followed by s, m, h or d, got '{age}'");
+    let unit = age.chars().last().ok_or_else(error)?;
+    let count: i64 = age[..age.len() - unit.len_utf8()].parse().map_err(|_| error())?;
+    match unit {
+        's' => TimeDelta::try_seconds(count),
+        'm' => TimeDelta::try_minutes(count),
+        'h' => TimeDelta::try_hours(count),
+        'd' => TimeDelta::try_days(count),
+        _ => return Err(error()),
+    }
+    .filter(|age| *age >= TimeDelta::zero())
+    .ok_or_else(error)
+}
+
+fn parse_attribute(attribute: &str) -> Result<Attribute, String> {
+    match attribute.split_once('=') {
+        Some((name, value)) if !name.is_empty() =>
+            Ok(Attribute::new(name.to_string(), value.to_string())),
+        _ => Err(format!("Expected NAME=VALUE, got '{attribute}'")),
+    }
+}
+
+#[derive(Args)]
+// Without the positional arguments, the dates are given with `--from` and `--to`
+#[command(group(ArgGroup::new("dated_by_flags").args(["template", "subject_name"])))]
+struct NewCredentialArgs {
+    /// Index, UUID or UUID prefix of the credential's issuer
+    #[arg(required_unless_present = "template")]
+    issuer: Option<String>,
+    /// Index, UUID or UUID prefix of the credential's subject
+    #[arg(required_unless_present_any = ["template", "subject_name"])]
+    subject: Option<String>,
+    /// Name and value of the attribute unless given with `--attr`, then the date from which the
+    /// credential is valid and the date to which it is, indefinitely if not provided
+    #[arg(value_name = "[NAME VALUE] FROM [TO]", num_args = 1..=4)]
+    #[arg(required_unless_present_any = ["template", "subject_name"])]
+    arguments: Vec<String>,
+    /// Name, surname or UUID prefix of the subject instead of its index, as matched by
+    /// `subjects find`, the attributes and dates then given with `--attr`, `--from` and `--to`
+    #[arg(long, value_name = "QUERY", conflicts_with_all = ["subject", "arguments"])]
+    subject_name: Option<String>,
+    /// Attribute of the credential, repeatable for credentials carrying several claims. With a
+    /// template, replaces the template's attribute of the same name.
+    #[arg(long = "attr", value_name = "NAME=VALUE", value_parser = parse_attribute)]
+    attributes: Vec<Attribute>,
+    /// Template to issue the credential from instead of giving the issuer, subject, attributes
+    /// and dates as arguments
+    #[arg(long, conflicts_with_all = ["issuer", "subject", "arguments"])]
+    #[arg(requires = "subject_index", requires = "from")]
+    template: Option<String>,
+    /// Index, UUID or UUID prefix of the issuer instead of the template's
+    #[arg(long = "issuer", value_name = "ISSUER", requires = "template")]
+    issuer_index: Option<String>,
+    /// Index, UUID or UUID prefix of the subject of a credential issued from a template
+    #[arg(long = "subject", value_name = "SUBJECT", requires = "template")]
+    subject_index: Option<String>,
+    /// Date from which a credential issued from a template or with `--subject-name` is valid
+    #[arg(long, value_name = "YYYY-MM-DD", requires = "dated_by_flags")]
+    from: Option<NaiveDate>,
+    /// Date to which a credential issued from a template or with `--subject-name` is valid,
+    /// instead of the end of the template's validity period or indefinitely
+    #[arg(long, value_name = "YYYY-MM-DD", requires = "dated_by_flags")]
+    to: Option<NaiveDate>,
+    /// Years a credential issued from a template is valid for instead of the template's
+    #[arg(long, requires = "template", conflicts_with = "to")]
+    valid_years: Option<u32>,
+    /// RFC 3339 time the credential was issued at instead of now, to backfill historical records
+    #[arg(long, value_name = "RFC3339")]
+    issued_at: Option<DateTime<Utc>>,
+    /// UUID or UUID prefix of the credential this one renews
+    #[arg(long, value_name = "UUID")]
+    supersedes: Option<String>,
+    /// Supporting document to bind to the credential by its digest, repeatable
+    #[arg(long, value_name = "FILE")]
+    evidence: Vec<PathBuf>,
+    /// Name of a schema to validate the attribute of the same name against, repeatable
+    #[arg(long = "schema", value_name = "NAME")]
+    schemas: Vec<String>,
+    /// Have the subject countersign the credential with its key, as evidence it agreed
+    #[arg(long)]
+    require_consent: bool,
+    /// Commit to the subject by a salted hash instead of naming it, printing the salt to open the
+    /// commitment with. Needs a chain hashing with version 4 or later.
+    #[arg(long)]
+    private_subject: bool,
+}
+
+impl NewCredentialArgs {
+    /// UUID of the stored credential given with `--supersedes`
+    fn supersedes(&self, credentials: &[CredentialFull]) -> Result<Option<Uuid>, String> {
+        let Some(query) = &self.supersedes else {
+            return Ok(None);
+        };
+        let stored = credentials.iter().map(|c| (c.0.uuid, c.0.subject.full_name()));
+        let uuid = ids::resolve_prefix(query, "credential", stored).map_err(|e| match e {
+            IdError::NotFound { .. } => format!("No credential {query} to supersede"),
+            e => e.to_string(),
+        })?;
+        Ok(Some(uuid))
+    }
+
+    /// Attributes and validity period of a credential issued from a template
+    fn template_claims(
+        &self, template: &CredentialTemplate,
+    ) -> Result<(Vec<Attribute>, ValidDuration), String> {
+        let from = self.from.ok_or("Missing the date from which the credential is valid")?;
+        let duration = match (self.to, self.valid_years.or(template.valid_years)) {
+            (Some(to), _) => ValidDuration::new(from, Some(to)),
+            (None, Some(years)) => ValidDuration::for_years(from, years),
+            (None, None) => Ok(ValidDuration::starting(from)),
+        }
+        .map_err(|e| e.to_string())?;
+        Ok((template.attributes_with(&self.attributes), duration))
+    }
+
+    /// Splits the positional arguments into the attributes and the validity period
+    fn claims(&self) -> Result<(Vec<Attribute>, ValidDuration), String> {
+        let (attributes, dates) = match (self.attributes.as_slice(), self.arguments.as_slice()) {
+            ([], [name, value, dates @ ..]) =>
+                (vec![Attribute::new(name.clone(), value.clone())], dates),
+            ([], _) => return Err("Give the attribute's name and value, or --attr".into()),
+            (attributes, dates) => (attributes.to_vec(), dates),
+        };
+        let date = |date: &String| {
+            date.parse::<NaiveDate>()
+                .map_err(|_| format!("Invalid date '{date}', expected YYYY-MM-DD"))
+        };
+        let duration = match dates {
+            [] if let Some(from) = self.from =>
+                ValidDuration::new(from, self.to).map_err(|e| e.to_string())?,
+            [from] => ValidDuration::starting(date(from)?),
+            [from, to] =>
+                ValidDuration::new(date(from)?, Some(date(to)?)).map_err(|e| e.to_string())?,
+            [] => return Err("Missing the date from which the credential is valid".into()),
+            _ => return Err("Too many arguments, the attributes were given with --attr".into()),
+        };
+        Ok((attributes, duration))
+    }
+
+    fn run(self) -> Result<(), String> {
+        let template = match &self.template {
+            Some(name) => Some(
+                open_templates()?
+                    .into_iter()
+                    .find(|template| &template.name == name)
+                    .ok_or(format!("No template named '{name}', see `templates list`"))?,
+            ),
+            None => None,
+        };
+        let (mut attributes, valid_duration) = match &template {
+            Some(template) => self.template_claims(template)?,
+            None => self.claims()?,
+        };
+        let mut schema_names = template.as_ref().map(|t| t.schemas.clone()).unwrap_or_default();
+        for name in &self.schemas {
+            if !schema_names.contains(name) {
+                schema_names.push(name.clone());
+            }
+        }
+        if !schema_names.is_empty() {
+            let schemas = open_schemas()?;
+            for name in &schema_names {
+                let schema = schemas
+                    .iter()
+                    .find(|schema| &schema.name == name)
+                    .ok_or(format!("No schema named '{name}', see `schemas list`"))?;
+                let attribute = attributes.iter_mut().find(|a| a.name == schema.name).ok_or(
+                    format!("Schema '{name}' given but the credential has no attribute '{name}'"),
+                )?;
+                schema
+                    .validate(attribute)
+                    .map_err(|e| format!("Attribute '{name}' doesn't follow its schema: {e}"))?;
+                attribute.schema = Some(schema.uri.clone());
+            }
+        }
+        let evidence = self
+            .evidence
+            .iter()
+            .map(|path| {
+                EvidenceRef::from_file(path)
+                    .map_err(|e| format!("Can't read evidence file {}: {e}", path.display()))
+            })
+            .collect::<Result<_, _>>()?;
+        let issuers = open_issuers()?;
+        let issuer = match (self.issuer.as_ref().or(self.issuer_index.as_ref()), &template) {
+            (Some(issuer), _) => issuers[issuer_index(&issuers, issuer)?].clone(),
+            (None, Some(template)) => issuers
+                .into_iter()
+                .find(|issuer| issuer.uuid == template.issuer && !issuer.deleted)
+                .ok_or(format!("Issuer of template '{}' no longer exists", template.name))?,
+            (None, None) => return Err("Missing the credential's issuer".into()),
+        };
+        let subjects = open_subjects()?;
+        let subject =
+            match (self.subject.as_ref().or(self.subject_index.as_ref()), &self.subject_name) {
+                (Some(subject), _) => subject_index(&subjects, subject)?,
+                (None, Some(query)) => find_subject(&subjects, query)?,
+                (None, None) => return Err("Missing the credential's subject".into()),
+            };
+        let subject = subject_at(&subjects, subject)?.clone();
+        let consent =
+            self.require_consent.then(|| open_subject_key(&subject, "consent")).transpose()?;
+        let mut credentials = open_credentials()?;
+        let supersedes = self.supersedes(&credentials)?;
+        // Credentials are signed under the rules of the chain they'll be issued on
+        let format = open_blockchain()?.format();
+        if self.private_subject && format.version < HashVersion::V4 {
+            return Err("Private subjects need a chain hashing with version 4 or later".into());
+        }
+        let signer = open_signer(&issuer)?;
+        let mut credential = Credential::new(attributes, issuer, subject, valid_duration);
+        if let Some(issued_at) = self.issued_at {
+            credential.issued_at = issued_at;
+        }
+        credential.supersedes = supersedes;
+        credential.evidence = evidence;
+        let salt = if self.private_subject { credential.commit_subject() } else { None };
+        let sign = |revoking| {
+            credential
+                .sign(signer.as_ref(), revoking, format)
+                .map_err(|e| format!("Can't sign as issuer '{}': {e}", credential.issuer.name))
+        };
+        let mut signed_regular = sign(false)?;
+        let signed_revoking = sign(true)?;
+        drop(signer);
+        if let Some(key) = consent {
+            signed_regular.add_consent(&key).map_err(|e| e.to_string())?;
+        }
+        credentials.push((credential, signed_regular, signed_revoking));
+        save_credentials(&credentials)?;
+        println!("Created new credential");
+        if let Some(salt) = salt {
+            println!("Subject commitment salt: {salt}");
+            println!("Keep it and hand it to the subject, it's stored nowhere else");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct ImportCredentialsArgs {
+    /// CSV file with a header row and a row per credential, see the README for its columns
+    #[arg(long, value_name = "PATH")]
+    csv: PathBuf,
+    /// Index, UUID or UUID prefix of the issuer signing every credential
+    #[arg(long)]
+    issuer: String,
+    /// Add the credentials to the pending block too, which the issuer has to sign
+    #[arg(long)]
+    stage: bool,
+    /// Issue nothing if any row fails instead of skipping it
+    #[arg(long)]
+    strict: bool,
+}
+
+impl ImportCredentialsArgs {
+    /// Subject a row names, by index or UUID, or by a name and surname only one subject has
+    fn resolve(subjects: &[Subject], subject: &SubjectRef) -> Result<usize, String> {
+        let (name, surname) = match subject {
+            SubjectRef::Id(query) => return subject_index(subjects, query),
+            SubjectRef::Name { name, surname } => (name, surname),
+        };
+        let wanted = Subject::new(name.clone(), surname.clone()).normalized_name();
+        let named: Vec<_> = (subjects.iter().enumerate())
+            .filter(|(_, s)| !s.deleted && s.normalized_name() == wanted)
+            .map(|(index, _)| index)
+            .collect();
+        match named.as_slice() {
+            [index] => Ok(*index),
+            [] => Err(format!("no subject named '{name} {surname}'")),
+            _ => Err(format!("{} subjects named '{name} {surname}', give a UUID", named.len())),
+        }
+    }
+
+    fn run(self) -> Result<(), String> {
+        let issuers = open_issuers()?;
+        let issuer = issuers[issuer_index(&issuers, &self.issuer)?].clone();
+        let mut block = self.stage.then(open_block).transpose()?;
+        if let Some(block) = &block
+            && block.signer().uuid != issuer.uuid
+        {
+            return Err(format!(
+                "The pending block belongs to '{}', it can't stage credentials signed by '{}'",
+                block.signer().name,
+                issuer.name
+            ));
+        }
+        let subjects = open_subjects()?;
+        let mut credentials = open_credentials()?;
+        let file = File::open_buffered(&self.csv).map_err(|_| "Failed to open CSV file")?;
+        let csv = self.csv.display();
+        let rows = IssuanceReader::new(file).map_err(|e| format!("{csv}: {e}"))?;
+        let signer = open_signer(&issuer)?;
+        // Credentials are signed under the rules of the chain they'll be issued on
+        let format = open_blockchain()?.format();
+        let before = credentials.len();
+        let mut failed = Vec::new();
+        for row in rows {
+            let row = row.map_err(|e| format!("{csv}: {e}, nothing issued"))?;
+            let issuance = row.issuance.and_then(|issuance| {
+                let subject = Self::resolve(&subjects, &issuance.subject)?;
+                Ok((issuance, subjects[subject].clone()))
+            });
+            let (issuance, subject) = match issuance {
+                Ok(issuance) => issuance,
+                Err(reason) if self.strict =>
+                    return Err(format!("{csv}: line {}: {reason}, nothing issued", row.line)),
+                Err(reason) => {
+                    failed.push((row.line, reason));
+                    continue;
+                },
+            };
+            let credential = Credential::new(
+                vec![issuance.attribute],
+                issuer.clone(),
+                subject,
+                issuance.valid_duration,
+            );
+            let sign = |revoking| {
+                credential
+                    .sign(signer.as_ref(), revoking, format)
+                    .map_err(|e| format!("Can't sign as issuer '{}': {e}", issuer.name))
+            };
+            let (regular, revoking) = (sign(false)?, sign(true)?);
+            if let Some(block) = &mut block {
+                block.add_credential(regular.clone(), false).map_err(|e| e.to_string())?;
+            }
+            credentials.push((credential, regular, revoking));
+        }
+        drop(signer);
+        let created = credentials.len() - before;
+        save_credentials(&credentials)?;
+        if let Some(block) = &block {
+            save_block(block)?;
+            println!(
+                "Created {created} credentials and added them to the block, {} rows failed",
+                failed.len()
+            );
+        } else {
+            println!("Created {created} credentials, {} rows failed", failed.len());
+        }
+        for (line, reason) in &failed {
+            println!("  line {line}: {reason}");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Subcommand)]
+enum IssuerSubcommands {
+    /// Add a new issuer
+    Add {
+        name: String,
+        /// Encrypt the signing key with a passphrase, read from `ATTESTATION_KEY_PASSPHRASE` or
+        /// prompted for
+        #[arg(long)]
+        encrypt: bool,
+        /// Hex-encoded 32-byte signing key to use instead of a random one, for tests and demos
+        #[arg(long, hide = true, conflicts_with_all = ["key_hex", "key_file"])]
+        seed: Option<String>,
+        /// Hex-encoded 32-byte signing key to issue with, e.g. from a key ceremony
+        #[arg(long, conflicts_with = "key_file")]
+        key_hex: Option<String>,
+        /// File holding the signing key to issue with, as hex or a PKCS#8 PEM document
+        #[arg(long)]
+        key_file: Option<PathBuf>,
+        #[command(flatten)]
+        metadata: IssuerMetadataArgs,
+    },
+    /// Change an issuer's URL, contact email or country, an empty value clearing it
+    Set {
+        /// Index, UUID or UUID prefix of the issuer
+        issuer: String,
+        #[command(flatten)]
+        metadata: IssuerMetadataArgs,
+    },
+    /// Encrypt an issuer's plaintext signing key with a passphrase
+    EncryptKey {
+        /// Index, UUID or UUID prefix of the issuer
+        issuer: String,
+    },
+    /// Write an issuer's private signing key to a file
+    ExportKey {
+        /// Index, UUID or UUID prefix of the issuer
+        issuer: String,
+        #[arg(long, value_enum, default_value_t = KeyFormat::Pem)]
+        format: KeyFormat,
+        #[arg(long)]
+        out: PathBuf,
+        /// Don't ask for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Print the fingerprint of an issuer's verifying key
+    Fingerprint {
+        /// Index, UUID or UUID prefix of the issuer
+        issuer: String,
+    },
+    /// Print an issuer's verifying key as a `SubjectPublicKeyInfo` PEM document
+    ExportPubkey {
+        /// Index, UUID or UUID prefix of the issuer
+        issuer: String,
+    },
+    /// Replace an issuer's signing key, recording the rotation in the pending block
+    RotateKey {
+        /// Index, UUID or UUID prefix of the issuer
+        issuer: String,
+    },
+    /// Give an issuer a new name, e.g. after a merger, keeping its UUID and key
+    Rename {
+        /// Index, UUID or UUID prefix of the issuer
+        issuer: String,
+        name: String,
+    },
+    /// Remove an issuer nothing refers to, keeping the other issuers' indexes
+    Remove {
+        /// Index, UUID or UUID prefix of the issuer
+        issuer: String,
+        /// Remove it even if credentials, templates or the pending block refer to it
+        #[arg(long)]
+        force: bool,
+    },
+    /// Stop trusting an issuer's credentials issued after a date, recording it in the pending
+    /// block, which the issuer itself or a chain authority has to sign
+    Deactivate {
+        /// Index, UUID or UUID prefix of the issuer
+        issuer: String,
+        /// Last day on which credentials of the issuer are still trusted
+        #[arg(long)]
+        effective: NaiveDate,
+    },
+    /// Vouch for another issuer's credentials issued over a period, recording the accreditation
+    /// in the pending block
+    Accredit {
+        /// Index, UUID or UUID prefix of the issuer vouching
+        accreditor: String,
+        /// Index, UUID or UUID prefix of the issuer vouched for
+        issuer: String,
+        /// First day the accreditation covers
+        #[arg(long)]
+        from: NaiveDate,
+        /// Last day the accreditation covers, indefinitely if not given
+        #[arg(long)]
+        to: Option<NaiveDate>,
+    },
+    /// Add a new issuer signing with an externally generated key, like `issuers add --key-file`
+    ImportKey {
+        name: String,
+        /// File holding the private key, as a PKCS#8 PEM document or hex
+        #[arg(long)]
+        key: PathBuf,
+        /// Encrypt the signing key with a passphrase, like `issuers add --encrypt`
+        #[arg(long)]
+        encrypt: bool,
+    },
+    /// Accept an issuer's credentials and blocks under `--trusted-only`
+    Trust {
+        /// UUID of a known issuer, or a file holding its verifying key as a PEM document or hex
+        #[arg(value_name = "UUID|KEY_FILE")]
+        issuer: String,
+        /// Name to list the key under, the issuer's name by default
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Stop trusting an issuer
+    Untrust {
+        /// UUID of the issuer or fingerprint of its key
+        #[arg(value_name = "UUID|FINGERPRINT")]
+        issuer: String,
+    },
+    /// Display the trusted issuer keys
+    Trusted,
+    /// Display existing issuers
+    List {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        #[command(flatten)]
+        page: PageArgs,
+    },
+}
+
+/// Where verifiers can find out about and contact an issuer
+#[derive(Args, Default)]
+struct IssuerMetadataArgs {
+    /// Website verifiers can find out about the issuer on
+    #[arg(long, value_parser = parse_url)]
+    url: Option<String>,
+    /// Address verifiers can contact the issuer at
+    #[arg(long, value_parser = parse_email)]
+    email: Option<String>,
+    /// ISO 3166-1 alpha-2 code of the country whose law the issuer operates under, e.g. PL
+    #[arg(long, value_parser = parse_country)]
+    country: Option<String>,
+}
+
+impl IssuerMetadataArgs {
+    fn is_empty(&self) -> bool {
+        self.url.is_none() && self.email.is_none() && self.country.is_none()
+    }
+
+    /// Sets the given fields on the issuer, clearing those given empty
+    fn apply(self, issuer: &mut Issuer) {
+        for (value, field) in [
+            (self.url, &mut issuer.url),
+            (self.email, &mut issuer.email),
+            (self.country, &mut issuer.country),
+        ] {
+            if let Some(value) = value {
+                *field = (!value.is_empty()).then_some(value);
+            }
+        }
+    }
+}
+
+fn parse_url(url: &str) -> Result<String, String> {
+    match url.split_once("://") {
+        _ if url.is_empty() => Ok(String::new()),
+        Some(("http" | "https", rest)) if !rest.is_empty() => Ok(url.to_string()),
+        _ => Err(format!("Expected an http:// or https:// URL, got '{url}'")),
+    }
+}
+
+fn parse_email(email: &str) -> Result<String, String> {
+    match email.split_once('@') {
+        _ if email.is_empty() => Ok(String::new()),
+        Some((user, domain)) if !user.is_empty() && domain.contains('.') => Ok(email.to_string()),
+        _ => Err(format!("Expected an email address, got '{email}'")),
+    }
+}
+
+fn parse_country(country: &str) -> Result<String, String> {
+    if country.is_empty()
+        || (country.len() == 2 && country.chars().all(|c| c.is_ascii_alphabetic()))
+    {
+        Ok(country.to_ascii_uppercase())
+    } else {
+        Err(format!("Expected a two-letter ISO 3166-1 country code, got '{country}'"))
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum KeyFormat {
+    /// PKCS#8 `PRIVATE KEY` PEM document
+    Pem,
+    /// Hex-encoded 32-byte key, as stored in keys/
+    Hex,
+}
+
+impl IssuerSubcommands {
+    fn run(self) -> Result<(), String> {
+        match self {
+            Self::Add { name, encrypt, seed, key_hex, key_file, metadata } => {
+                let key = match (key_hex, key_file) {
+                    (Some(hex), _) => Some(parse_signing_key(&Zeroizing::new(hex))?),
+                    (None, Some(path)) => Some(read_signing_key(&path)?),
+                    (None, None) => None,
+                };
+                Self::add(name, encrypt, seed.as_deref(), key, metadata)
+            },
+            Self::Set { issuer, metadata } => Self::set(&issuer, metadata),
+            Self::EncryptKey { issuer } => Self::encrypt_key(&issuer),
+            Self::ExportKey { issuer, format, out, yes } =>
+                Self::export_key(&issuer, format, &out, yes),
+            Self::ExportPubkey { issuer } => Self::export_pubkey(&issuer),
+            Self::Fingerprint { issuer } => Self::fingerprint(&issuer),
+            Self::ImportKey { name, key, encrypt } => Self::import_key(name, &key, encrypt),
+            Self::RotateKey { issuer } => Self::rotate_key(&issuer),
+            Self::Rename { issuer, name } => Self::rename(&issuer, name),
+            Self::Remove { issuer, force } => Self::remove(&issuer, force),
+            Self::Deactivate { issuer, effective } => Self::deactivate(&issuer, effective),
+            Self::Accredit { accreditor, issuer, from, to } =>
+                Self::accredit(&accreditor, &issuer, from, to),
+            Self::Trust { issuer, name } => Self::trust(&issuer, name),
+            Self::Untrust { issuer } => Self::untrust(&issuer),
+            Self::Trusted => Self::trusted(),
+            Self::List { format, page } => Self::list(format, &page),
+        }
+    }
+
+    fn trust(issuer: &str, name: Option<String>) -> Result<(), String> {
+        let issuers = open_issuers()?;
+        let verifying = if let Ok(uuid) = Uuid::parse_str(issuer) {
+            let known = issuers.iter().find(|i| i.uuid == uuid && !i.deleted);
+            known.ok_or_else(|| format!("No issuer with UUID {uuid}"))?.verifying
+        } else {
+            read_verifying_key(Path::new(issuer))?
+        };
+        let known = issuers.iter().find(|i| i.verifying == verifying && !i.deleted);
+        let name = name.or_else(|| known.map(|i| i.name.clone()));
+        let label = name.clone().unwrap_or_else(|| fingerprint(&verifying));
+        let mut trust = open_trust()?;
+        if !trust.trust(TrustedIssuer { verifying, uuid: known.map(|i| i.uuid), name }) {
+            return Err(format!("Issuer '{label}' is already trusted"));
+        }
+        save_trust(&trust)?;
+        println!("Trusted issuer '{label}' with key {}", fingerprint(&verifying));
+        Ok(())
+    }
+
+    fn untrust(issuer: &str) -> Result<(), String> {
+        let mut trust = open_trust()?;
+        if trust.untrust(issuer) == 0 {
+            return Err(format!("No trusted issuer matches '{issuer}'"));
+        }
+        save_trust(&trust)?;
+        println!("Stopped trusting '{issuer}'");
+        Ok(())
+    }
+
+    fn trusted() -> Result<(), String> {
+        let trust = open_trust()?;
+        if trust.is_empty() {
+            println!("No trusted issuers");
+        }
+        for issuer in trust.issuers() {
+            let uuid = issuer.uuid.map(|uuid| format!(" ({uuid})")).unwrap_or_default();
+            let name = issuer.name.as_deref().unwrap_or("unnamed");
+            println!("{}: {name}{uuid}", fingerprint(&issuer.verifying));
+        }
+        Ok(())
+    }
+
+    fn add(
+        name: String, encrypt: bool, seed: Option<&str>, key: Option<SecretKeyMaterial>,
+        metadata: IssuerMetadataArgs,
+    ) -> Result<(), String> {
+        if let Some(key) = key {
+            let mut issuer = Issuer::from_signing_key(name, key.signing());
+            metadata.apply(&mut issuer);
+            println!("Verifying key: {}", hex::encode(issuer.verifying.as_bytes()));
+            println!("Fingerprint: {}", issuer.fingerprint());
+            return Self::create(issuer, &key, encrypt);
+        }
+        let (mut issuer, key) = match seed {
+            Some(seed) => {
+                let mut bytes = Zeroizing::new([0; 32]);
+                hex::decode_to_slice(seed, bytes.as_mut_slice())
+                    .map_err(|_| "Seed must be 32 bytes of hex")?;
+                eprintln!(
+                    "WARNING: the issuer's signing key is derived from a seed given on the \
+                     command line, use it only for tests and demos"
+                );
+                let key = SigningKey::from_bytes(&bytes);
+                (Issuer::from_signing_key(name, &key), key)
+            },
+            None => Issuer::new(name),
+        };
+        metadata.apply(&mut issuer);
+        Self::create(issuer, &key.into(), encrypt)
+    }
+
+    /// Stores the new issuer and its signing key
+    fn create(issuer: Issuer, key: &SecretKeyMaterial, encrypt: bool) -> Result<(), String> {
         let mut issuers = open_issuers()?;
-        issuers.push(IssuerFull(issuer, key));
+        if let Some(other) = issuers.iter().find(|other| other.verifying == issuer.verifying) {
+            eprintln!(
+                "WARNING: issuer '{}' ({}) already signs with this key",
+                other.name, other.uuid
+            );
+        }
+        if encrypt {
+            let passphrase = new_passphrase(&format!("issuer '{}'", issuer.name))?;
+            key_store().save_encrypted(issuer.uuid, key, &passphrase)
+        } else {
+            key_store().save(issuer.uuid, key)
+        }
+        .map_err(|e| e.to_string())?;
+        issuers.push(issuer);
         save_issuers(&issuers)?;
         println!("Created new issuer");
         Ok(())
     }
 
-    fn list() -> Result<(), &'static str> {
+    fn encrypt_key(issuer: &str) -> Result<(), String> {
+        let issuers = open_issuers()?;
+        let issuer = &issuers[issuer_index(&issuers, issuer)?];
+        let store = key_store();
+        if store.is_encrypted(issuer.uuid).map_err(|e| e.to_string())? {
+            return Err(format!("Signing key of issuer '{}' is already encrypted", issuer.name));
+        }
+        let key = open_key(issuer)?;
+        let passphrase = new_passphrase(&format!("issuer '{}'", issuer.name))?;
+        store.save_encrypted(issuer.uuid, &key, &passphrase).map_err(|e| e.to_string())?;
+        println!("Encrypted the signing key of issuer '{}'", issuer.name);
+        Ok(())
+    }
+
+    fn export_key(issuer: &str, format: KeyFormat, out: &Path, yes: bool) -> Result<(), String> {
+        let issuers = open_issuers()?;
+        let issuer = &issuers[issuer_index(&issuers, issuer)?];
+        if !yes {
+            print!(
+                "Write the private signing key of issuer '{}' to {}? [y/N] ",
+                issuer.name,
+                out.display()
+            );
+            io::stdout().flush().map_err(|e| e.to_string())?;
+            let mut answer = String::new();
+            io::stdin().read_line(&mut answer).map_err(|e| e.to_string())?;
+            if !answer.trim().eq_ignore_ascii_case("y") {
+                return Err("Export cancelled".into());
+            }
+        }
+        let key = open_key(issuer)?;
+        let contents = match format {
+            KeyFormat::Pem => key.to_pkcs8_pem()?,
+            KeyFormat::Hex => {
+                let mut hex = key.to_hex();
+                hex.push('\n');
+                hex
+            },
+        };
+        write_private(out, &contents).map_err(|e| format!("Failed to write key file: {e}"))?;
+        println!("Exported the signing key of issuer '{}'", issuer.name);
+        Ok(())
+    }
+
+    fn fingerprint(issuer: &str) -> Result<(), String> {
+        let issuers = open_issuers()?;
+        println!("{}", issuers[issuer_index(&issuers, issuer)?].fingerprint());
+        Ok(())
+    }
+
+    fn export_pubkey(issuer: &str) -> Result<(), String> {
+        let issuers = open_issuers()?;
+        let issuer = &issuers[issuer_index(&issuers, issuer)?];
+        let pem = issuer.verifying.to_public_key_pem(LineEnding::LF).map_err(|e| e.to_string())?;
+        print!("{pem}");
+        Ok(())
+    }
+
+    fn import_key(name: String, path: &Path, encrypt: bool) -> Result<(), String> {
+        Self::add(name, encrypt, None, Some(read_signing_key(path)?), IssuerMetadataArgs::default())
+    }
+
+    fn rotate_key(issuer: &str) -> Result<(), String> {
+        let issuers = open_issuers()?;
+        let issuer = &issuers[issuer_index(&issuers, issuer)?];
+        let mut block = open_block()
+            .map_err(|e| format!("{e}, create one with `block new` to record the rotation in"))?;
+        // Only the issuer's own block can record its rotation
+        if block.signer().uuid != issuer.uuid {
+            return Err(format!(
+                "The pending block belongs to '{}', create one for '{}' with `block new` to record \
+                 the rotation in",
+                block.signer().name,
+                issuer.name
+            ));
+        }
+        if block.key_rotations().iter().any(|rotation| rotation.issuer == issuer.uuid) {
+            return Err(format!("The pending block already rotates the key of '{}'", issuer.name));
+        }
+        let store = key_store();
+        let encrypted = store.is_encrypted(issuer.uuid).map_err(|e| e.to_string())?;
+        let previous = open_signer(issuer)?;
+        let key = SecretKeyMaterial::from(SigningKey::generate(&mut rand::thread_rng()));
+        let format = open_blockchain()?.format();
+        let rotation =
+            KeyRotation::new(issuer.uuid, previous.as_ref(), key.signing().verifying_key(), format)
+                .map_err(|e| format!("Can't sign as issuer '{}': {e}", issuer.name))?;
+        drop(previous);
+        // The issuer keeps signing with its current key until the block is on the chain
+        let passphrase = if encrypted {
+            Some(new_passphrase(&format!("issuer '{}'", issuer.name))?)
+        } else {
+            None
+        };
+        store
+            .stage(issuer.uuid, &key, passphrase.as_ref().map(|p| p.as_str()))
+            .map_err(|e| e.to_string())?;
+        block.add_key_rotation(rotation);
+        save_block(&block)?;
+        println!(
+            "Recorded the rotation of the signing key of issuer '{}' in the pending block, the new \
+             key takes over once the block is finalized",
+            issuer.name
+        );
+        Ok(())
+    }
+
+    fn set(issuer: &str, metadata: IssuerMetadataArgs) -> Result<(), String> {
+        if metadata.is_empty() {
+            return Err("Give at least one of --url, --email and --country".into());
+        }
+        let mut issuers = open_issuers()?;
+        let index = issuer_index(&issuers, issuer)?;
+        let issuer = &mut issuers[index];
+        metadata.apply(issuer);
+        let issuer = issuer.clone();
+        save_issuers(&issuers)?;
+        // Like a rename, only credentials and blocks created from now on carry the change
+        if let Ok(mut block) = open_block()
+            && block.signer().uuid == issuer.uuid
+        {
+            block.set_signer(issuer.clone());
+            save_block(&block)?;
+        }
+        println!("Updated issuer '{}'", issuer.name);
+        Ok(())
+    }
+
+    fn rename(issuer: &str, name: String) -> Result<(), String> {
+        let mut issuers = open_issuers()?;
+        let index = issuer_index(&issuers, issuer)?;
+        let issuer = &mut issuers[index];
+        if issuer.name == name {
+            return Err(format!("Issuer is already named '{name}'"));
+        }
+        issuer.rename(name);
+        let issuer = issuer.clone();
+        save_issuers(&issuers)?;
+        // The pending block is only hashed when finalized, so it can still sign with the new name
+        if let Ok(mut block) = open_block()
+            && block.signer().uuid == issuer.uuid
+        {
+            block.set_signer(issuer.clone());
+            save_block(&block)?;
+        }
+        let former = issuer.former_names.last().map_or("", String::as_str);
+        println!("Renamed issuer '{former}' to '{}'", issuer.name);
+        // Credentials are hashed with the issuer's name, so those already created keep the old one
+        let blockchain = open_blockchain().ok();
+        let pending = open_credentials()
+            .unwrap_or_default()
+            .iter()
+            .filter(|(credential, ..)| credential.issuer.uuid == issuer.uuid)
+            .filter(|(_, signed, _)| {
+                blockchain.as_ref().is_none_or(|chain| !chain.has_issued(&signed.credential))
+            })
+            .count();
+        if pending > 0 {
+            eprintln!(
+                "WARNING: {pending} credential(s) of the issuer created but not yet in a block \
+                 still show the old name '{former}', create them again to carry the new one"
+            );
+        }
+        Ok(())
+    }
+
+    fn remove(issuer: &str, force: bool) -> Result<(), String> {
+        let mut issuers = open_issuers()?;
+        let index = issuer_index(&issuers, issuer)?;
+        let issuer = &issuers[index];
+        let blockchain = open_blockchain()?;
+        let credentials: Vec<_> =
+            open_credentials()?.into_iter().filter(|c| c.0.issuer.uuid == issuer.uuid).collect();
+        // Removing an issuer the chain refers to would leave its blocks without their issuer
+        if blockchain.mentions_issuer(issuer.uuid)
+            || credentials.iter().any(|(_, signed, _)| blockchain.has_issued(&signed.credential))
+        {
+            return Err(format!(
+                "Issuer '{}' is referred to by the blockchain and can't be removed, deactivate it \
+                 with `issuers deactivate` instead",
+                issuer.name
+            ));
+        }
+        let mut references = Vec::new();
+        if !credentials.is_empty() {
+            references.push(format!("{} credential(s)", credentials.len()));
+        }
+        references.extend(
+            open_templates()?
+                .into_iter()
+                .filter(|template| template.issuer == issuer.uuid)
+                .map(|template| format!("template '{}'", template.name)),
+        );
+        if open_block().is_ok_and(|block| block.mentions_issuer(issuer.uuid)) {
+            references.push("the pending block".into());
+        }
+        if !references.is_empty() && !force {
+            return Err(format!(
+                "Issuer '{}' is referred to by {}, pass --force to remove it anyway",
+                issuer.name,
+                references.join(", ")
+            ));
+        }
+        let name = issuer.name.clone();
+        issuers[index].deleted = true;
+        save_issuers(&issuers)?;
+        println!("Removed issuer '{name}'");
+        Ok(())
+    }
+
+    fn deactivate(issuer: &str, effective: NaiveDate) -> Result<(), String> {
+        let issuers = open_issuers()?;
+        let issuer = &issuers[issuer_index(&issuers, issuer)?];
+        let mut block = open_block().map_err(|e| {
+            format!("{e}, create one with `block new` to record the deactivation in")
+        })?;
+        let blockchain = open_blockchain()?;
+        let authorities = blockchain.authorities().map_err(|e| e.to_string())?;
+        if block.signer().uuid != issuer.uuid && authorities.is_none() {
+            return Err(format!(
+                "Only issuer '{}' itself or a chain authority can deactivate it, the pending \
+                 block is signed by '{}'",
+                issuer.name,
+                block.signer().name
+            ));
+        }
+        block.add_deactivation(IssuerDeactivation::new(issuer, effective));
+        save_block(&block)?;
+        println!(
+            "Staged the deactivation of issuer '{}' from {effective} in the pending block",
+            issuer.name
+        );
+        Ok(())
+    }
+
+    fn accredit(
+        accreditor: &str, issuer: &str, from: NaiveDate, to: Option<NaiveDate>,
+    ) -> Result<(), String> {
+        let issuers = open_issuers()?;
+        let accreditor = &issuers[issuer_index(&issuers, accreditor)?];
+        let issuer = &issuers[issuer_index(&issuers, issuer)?];
+        if accreditor.uuid == issuer.uuid {
+            return Err(format!("Issuer '{}' can't accredit itself", issuer.name));
+        }
+        let duration = ValidDuration::new(from, to).map_err(|e| e.to_string())?;
+        let mut block = open_block().map_err(|e| {
+            format!("{e}, create one with `block new` to record the accreditation in")
+        })?;
+        let format = open_blockchain()?.format();
+        let signer = open_signer(accreditor)?;
+        let accreditation =
+            Accreditation::new(accreditor, signer.as_ref(), issuer, duration, format)
+                .map_err(|e| format!("Can't sign as issuer '{}': {e}", accreditor.name))?;
+        block.add_accreditation(accreditation);
+        save_block(&block)?;
+        println!(
+            "Staged the accreditation of issuer '{}' by '{}' in the pending block",
+            issuer.name, accreditor.name
+        );
+        Ok(())
+    }
+
+    fn list(format: OutputFormat, page: &PageArgs) -> Result<(), String> {
+        let issuers = open_issuers()?;
+        // Issuers can be listed before the blockchain exists, they're all active then
+        let blockchain = open_blockchain().ok();
+        let keys = (0..issuers.len())
+            .filter(|&i| !issuers[i].deleted)
+            .map(|i| SortKey::new(i, &issuers[i].name, issuers[i].uuid))
+            .collect();
+        let selected = page.apply(keys)?;
+        let deactivation =
+            |issuer| blockchain.as_ref().and_then(|chain| chain.deactivation(issuer, None));
+        if format == OutputFormat::JsonPretty {
+            for &i in &selected {
+                let issuer = &issuers[i];
+                println!("{i}: {issuer}");
+                match deactivation(issuer) {
+                    Some((height, deactivation)) => println!(
+                        "Status: deactivated after {} (recorded in block #{height})",
+                        deactivation.effective
+                    ),
+                    None => println!("Status: active"),
+                }
+            }
+            return Ok(());
+        }
+        format.show(&listed(&issuers, &selected), || {
+            let mut table = Table::new(["INDEX", "UUID", "NAME", "FINGERPRINT", "STATUS"]);
+            for &i in &selected {
+                let issuer = &issuers[i];
+                let status = match deactivation(issuer) {
+                    Some((_, deactivation)) =>
+                        format!("deactivated after {}", deactivation.effective),
+                    None => "active".to_string(),
+                };
+                table.row([
+                    i.to_string(),
+                    short_uuid(issuer.uuid),
+                    issuer.name.clone(),
+                    issuer.fingerprint(),
+                    status,
+                ]);
+            }
+            print_table(&table);
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum RequestStatusArg {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Subcommand)]
+enum RequestSubcommands {
+    /// Approve a pending request: create and sign its credential, and add it to the pending
+    /// block like `credentials add` and `block add` would
+    Approve {
+        /// UUID or UUID prefix of the request
+        request: String,
+    },
+    /// List the issuance requests, oldest first
+    List {
+        /// Only list the requests with this status
+        #[arg(long, value_enum)]
+        status: Option<RequestStatusArg>,
+    },
+    /// Reject a pending request, which then never produces a credential
+    Reject {
+        /// UUID or UUID prefix of the request
+        request: String,
+        /// Why the request is rejected
+        #[arg(long)]
+        reason: String,
+    },
+}
+
+impl RequestSubcommands {
+    fn run(self) -> Result<(), String> {
+        match self {
+            Self::Approve { request } => Self::approve(&request),
+            Self::List { status } => Self::list(status),
+            Self::Reject { request, reason } => Self::reject(&request, reason),
+        }
+    }
+
+    /// Index of the pending request with the given UUID or UUID prefix
+    fn pending_index(requests: &[IssuanceRequest], query: &str) -> Result<usize, String> {
+        let stored = requests.iter().map(|request| (request.id, request.subject.to_string()));
+        let uuid = ids::resolve_prefix(query, "request", stored).map_err(|e| e.to_string())?;
+        let index = requests.iter().position(|request| request.id == uuid).unwrap_or_default();
+        let status = &requests[index].status;
+        if *status != RequestStatus::Pending {
+            return Err(format!("Request {} was already {status}", short_uuid(uuid)));
+        }
+        Ok(index)
+    }
+
+    /// Subject a request is about, added to the subjects if it names one that isn't stored
+    fn subject(
+        subjects: &mut Vec<Subject>, requested: &RequestedSubject,
+    ) -> Result<Subject, String> {
+        let (name, surname) = match requested {
+            RequestedSubject::Uuid(uuid) => {
+                let subject = subjects.iter().find(|s| s.uuid == *uuid && !s.deleted);
+                return subject
+                    .cloned()
+                    .ok_or(format!("Subject {uuid} of the request no longer exists"));
+            },
+            RequestedSubject::Named { name, surname } => (name, surname),
+        };
+        let subject = Subject::new(name.clone(), surname.clone());
+        let wanted = subject.normalized_name();
+        let named: Vec<_> =
+            subjects.iter().filter(|s| !s.deleted && s.normalized_name() == wanted).collect();
+        match named.as_slice() {
+            [] => {
+                subjects.push(subject.clone());
+                Ok(subject)
+            },
+            [subject] => Ok((*subject).clone()),
+            _ => Err(format!(
+                "{} subjects named '{requested}', reject the request and submit it with the \
+                 subject's UUID",
+                named.len()
+            )),
+        }
+    }
+
+    fn approve(query: &str) -> Result<(), String> {
+        let mut requests = open_requests()?;
+        let index = Self::pending_index(&requests, query)?;
+        let request = &requests[index];
+        let issuer = open_issuers()?
+            .into_iter()
+            .find(|issuer| issuer.uuid == request.issuer && !issuer.deleted)
+            .ok_or(format!("Issuer {} of the request no longer exists", request.issuer))?;
+        let mut block = open_block()?;
+        if block.signer().uuid != issuer.uuid {
+            return Err(format!(
+                "The pending block belongs to '{}', it can't stage credentials signed by '{}'",
+                block.signer().name,
+                issuer.name
+            ));
+        }
+        let mut subjects = open_subjects()?;
+        let known = subjects.len();
+        let subject = Self::subject(&mut subjects, &request.subject)?;
+        let mut credentials = open_credentials()?;
+        // Credentials are signed under the rules of the chain they'll be issued on
+        let format = open_blockchain()?.format();
+        let signer = open_signer(&issuer)?;
+        let attributes = request.credential_attributes();
+        let credential =
+            Credential::new(attributes, issuer, subject, request.valid_duration.clone());
+        let sign = |revoking| {
+            credential
+                .sign(signer.as_ref(), revoking, format)
+                .map_err(|e| format!("Can't sign as issuer '{}': {e}", credential.issuer.name))
+        };
+        let (regular, revoking) = (sign(false)?, sign(true)?);
+        drop(signer);
+        block.add_credential(regular.clone(), false).map_err(|e| e.to_string())?;
+        if subjects.len() > known {
+            save_subjects(&subjects)?;
+        }
+        let uuid = credential.uuid;
+        credentials.push((credential, regular, revoking));
+        save_credentials(&credentials)?;
+        save_block(&block)?;
+        requests[index].status = RequestStatus::Approved { at: Utc::now(), credential: uuid };
+        save_requests(&requests)?;
+        println!(
+            "Approved request {}, created credential {} and added it to the block",
+            short_uuid(requests[index].id),
+            short_uuid(uuid)
+        );
+        Ok(())
+    }
+
+    fn list(status: Option<RequestStatusArg>) -> Result<(), String> {
+        let requests = open_requests()?;
+        let issuers = open_issuers()?;
+        let mut table =
+            Table::new(["ID", "SUBMITTED", "ISSUER", "SUBJECT", "ATTRIBUTES", "STATUS"]);
+        for request in &requests {
+            let (listed, detail) = match &request.status {
+                RequestStatus::Pending => (RequestStatusArg::Pending, String::new()),
+                RequestStatus::Approved { credential, .. } => {
+                    let detail = format!(", credential {}", short_uuid(*credential));
+                    (RequestStatusArg::Approved, detail)
+                },
+                RequestStatus::Rejected { reason, .. } =>
+                    (RequestStatusArg::Rejected, format!(": {reason}")),
+            };
+            if status.is_some_and(|status| status != listed) {
+                continue;
+            }
+            let issuer = issuers.iter().find(|issuer| issuer.uuid == request.issuer);
+            let attributes: Vec<_> =
+                request.attributes.iter().map(|a| format!("{}={}", a.name, a.value)).collect();
+            table.row([
+                short_uuid(request.id),
+                request.submitted_at.format("%Y-%m-%d %H:%M").to_string(),
+                issuer.map_or_else(|| short_uuid(request.issuer), |issuer| issuer.name.clone()),
+                request.subject.to_string(),
+                attributes.join(", "),
+                format!("{}{detail}", request.status),
+            ]);
+        }
+        print_table(&table);
+        Ok(())
+    }
+
+    fn reject(query: &str, reason: String) -> Result<(), String> {
+        let mut requests = open_requests()?;
+        let index = Self::pending_index(&requests, query)?;
+        requests[index].status = RequestStatus::Rejected { at: Utc::now(), reason };
+        save_requests(&requests)?;
+        println!("Rejected request {}", short_uuid(requests[index].id));
+        Ok(())
+    }
+}
+
+#[derive(Subcommand)]
+enum SchemaSubcommands {
+    /// Add a new attribute schema
+    Add(NewSchemaArgs),
+    /// List existing attribute schemas
+    List,
+}
+
+impl SchemaSubcommands {
+    fn run(self) -> Result<(), String> {
+        match self {
+            Self::Add(args) => args.run(),
+            Self::List => Self::list(),
+        }
+    }
+
+    fn list() -> Result<(), String> {
+        let schemas = open_schemas()?;
+        for (i, schema) in schemas.into_iter().enumerate() {
+            println!("{i}: {schema}");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct NewSchemaArgs {
+    /// Name of the attributes the schema applies to
+    name: String,
+    /// Identifier of the schema, recorded in the credentials validated against it
+    #[arg(long)]
+    uri: String,
+    /// Kind of value the attribute holds
+    #[arg(long = "type", value_enum, default_value_t = ValueTypeArg::String)]
+    value_type: ValueTypeArg,
+    /// Regular expression the whole value must match
+    #[arg(long)]
+    pattern: Option<String>,
+    /// Value the attribute may take, repeatable, any if not given
+    #[arg(long, value_name = "VALUE")]
+    allowed: Vec<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ValueTypeArg {
+    String,
+    Integer,
+    Decimal,
+    Boolean,
+    Date,
+}
+
+impl From<ValueTypeArg> for ValueType {
+    fn from(value_type: ValueTypeArg) -> Self {
+        match value_type {
+            ValueTypeArg::String => Self::String,
+            ValueTypeArg::Integer => Self::Integer,
+            ValueTypeArg::Decimal => Self::Decimal,
+            ValueTypeArg::Boolean => Self::Boolean,
+            ValueTypeArg::Date => Self::Date,
+        }
+    }
+}
+
+impl NewSchemaArgs {
+    fn run(self) -> Result<(), String> {
+        let mut schemas = open_schemas()?;
+        if schemas.iter().any(|schema| schema.name == self.name) {
+            return Err(format!("A schema named '{}' already exists", self.name));
+        }
+        let mut schema = AttributeSchema::new(self.name, self.uri, self.value_type.into());
+        schema.pattern = self.pattern;
+        schema.allowed = self.allowed;
+        schema.check().map_err(|e| format!("Can't add schema '{}': {e}", schema.name))?;
+        schemas.push(schema);
+        save_schemas(&schemas)?;
+        println!("Created new schema");
+        Ok(())
+    }
+}
+
+#[derive(Subcommand)]
+enum TemplateSubcommands {
+    /// Add a new credential template
+    Add(NewTemplateArgs),
+    /// List existing credential templates
+    List,
+}
+
+impl TemplateSubcommands {
+    fn run(self) -> Result<(), String> {
+        match self {
+            Self::Add(args) => args.run(),
+            Self::List => Self::list(),
+        }
+    }
+
+    fn list() -> Result<(), String> {
+        let templates = open_templates()?;
+        for (i, template) in templates.into_iter().enumerate() {
+            println!("{i}: {template}");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct NewTemplateArgs {
+    /// Name the template is used by
+    name: String,
+    /// Index, UUID or UUID prefix of the issuer issuing the credentials
+    #[arg(long)]
+    issuer: String,
+    /// Attribute of the credentials, repeatable
+    #[arg(long = "attr", value_name = "NAME=VALUE", value_parser = parse_attribute)]
+    #[arg(required = true)]
+    attributes: Vec<Attribute>,
+    /// Years the credentials are valid for, indefinitely if not given
+    #[arg(long)]
+    valid_years: Option<u32>,
+    /// Name of a schema to validate the attribute of the same name against, repeatable
+    #[arg(long = "schema", value_name = "NAME")]
+    schemas: Vec<String>,
+}
+
+impl NewTemplateArgs {
+    fn run(self) -> Result<(), String> {
+        let mut templates = open_templates()?;
+        if templates.iter().any(|template| template.name == self.name) {
+            return Err(format!("A template named '{}' already exists", self.name));
+        }
         let issuers = open_issuers()?;
-        for (i, issuer) in issuers.into_iter().enumerate() {
-            println!("{i}: {}", issuer.0);
+        let issuer = issuers[issuer_index(&issuers, &self.issuer)?].clone();
+        let schemas = open_schemas()?;
+        if let Some(name) =
+            self.schemas.iter().find(|name| !schemas.iter().any(|s| &s.name == *name))
+        {
+            return Err(format!("No schema named '{name}', see `schemas list`"));
         }
+        let mut template = CredentialTemplate::new(self.name, issuer.uuid, self.attributes);
+        template.valid_years = self.valid_years;
+        template.schemas = self.schemas;
+        templates.push(template);
+        save_templates(&templates)?;
+        println!("Created new template");
         Ok(())
     }
 }
@@ -390,22 +5064,151 @@ enum SubjectSubcommands {
     /// Add a new subject
     Add(NewSubjectArgs),
     /// List existing subjects
-    List,
+    List {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        #[command(flatten)]
+        page: PageArgs,
+    },
+    /// Find subjects by name, surname or UUID prefix, ignoring case
+    Find {
+        query: String,
+        /// Only match the full name, or the full UUID, exactly
+        #[arg(long)]
+        exact: bool,
+    },
+    /// List groups of subjects with the same name, likely records of the same person
+    Duplicates,
+    /// Add subjects in bulk from a CSV file with a header row
+    Import(ImportSubjectsArgs),
+    /// Remove a subject without credentials, keeping the other subjects' indexes
+    Remove {
+        /// Index or UUID of the subject
+        subject: String,
+        /// Remove it even if credentials refer to it
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 impl SubjectSubcommands {
-    fn run(self) -> Result<(), &'static str> {
+    fn run(self) -> Result<(), String> {
         match self {
             Self::Add(args) => args.run(),
-            Self::List => Self::list(),
+            Self::List { format, page } => Self::list(format, &page),
+            Self::Find { query, exact } => Self::find(&query, exact),
+            Self::Duplicates => Self::duplicates(),
+            Self::Import(args) => args.run(),
+            Self::Remove { subject, force } => Self::remove(&subject, force),
+        }
+    }
+
+    fn find(query: &str, exact: bool) -> Result<(), String> {
+        let subjects = open_subjects()?;
+        let matches = matching_subjects(&subjects, query, exact);
+        if matches.is_empty() {
+            println!("No subject matches '{query}'");
+        }
+        for index in matches {
+            println!("{}", subject_line(index, &subjects[index]));
+        }
+        Ok(())
+    }
+
+    fn list(format: OutputFormat, page: &PageArgs) -> Result<(), String> {
+        let subjects = open_subjects()?;
+        let keys = (0..subjects.len())
+            .filter(|&i| !subjects[i].deleted)
+            .map(|i| {
+                let subject = &subjects[i];
+                SortKey::new(i, &format!("{} {}", subject.name, subject.surname), subject.uuid)
+            })
+            .collect();
+        let selected = page.apply(keys)?;
+        if format == OutputFormat::JsonPretty {
+            for i in selected {
+                println!("{i}: {}", subjects[i]);
+            }
+            return Ok(());
         }
+        format.show(&listed(&subjects, &selected), || {
+            let mut table = Table::new(["INDEX", "UUID", "NAME", "SURNAME"]);
+            for &i in &selected {
+                let subject = &subjects[i];
+                table.row([
+                    i.to_string(),
+                    short_uuid(subject.uuid),
+                    subject.name.clone(),
+                    subject.surname.clone(),
+                ]);
+            }
+            print_table(&table);
+        })
     }
 
-    fn list() -> Result<(), &'static str> {
+    fn duplicates() -> Result<(), String> {
         let subjects = open_subjects()?;
-        for (i, s) in subjects.into_iter().enumerate() {
-            println!("{i}: {s}");
+        let credentials = open_credentials().unwrap_or_default();
+        let mut groups: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (index, subject) in subjects.iter().enumerate().filter(|(_, s)| !s.deleted) {
+            groups.entry(subject.normalized_name()).or_default().push(index);
+        }
+        groups.retain(|_, group| group.len() > 1);
+        if groups.is_empty() {
+            println!("No likely duplicate subjects found");
+        }
+        for (name, group) in groups {
+            // Fields telling whether they're really the same person, or namesakes
+            let (mut same, mut different) = (BTreeSet::new(), BTreeSet::new());
+            for (i, &a) in group.iter().enumerate() {
+                for &b in &group[i + 1..] {
+                    let (agreeing, differing) = subjects[a].compare_extra(&subjects[b]);
+                    same.extend(agreeing);
+                    different.extend(differing);
+                }
+            }
+            let mut header = vec![format!("'{name}'"), format!("{} subjects", group.len())];
+            for (label, fields) in [("same", same), ("different", different)] {
+                if !fields.is_empty() {
+                    let fields: Vec<_> = fields.into_iter().collect();
+                    header.push(format!("{label} {}", fields.join(", ")));
+                }
+            }
+            println!("{}:", header.join(", "));
+            for index in group {
+                let count =
+                    credentials.iter().filter(|c| c.0.subject.is(&subjects[index])).count();
+                println!("  {}, {count} credential(s)", subject_line(index, &subjects[index]));
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(subject: &str, force: bool) -> Result<(), String> {
+        let mut subjects = open_subjects()?;
+        let index = subject_index(&subjects, subject)?;
+        let subject = &subjects[index];
+        let credentials: Vec<_> =
+            open_credentials()?.into_iter().filter(|c| c.0.subject.is(subject)).collect();
+        let issued = open_blockchain().map_or(0, |blockchain| {
+            credentials.iter().filter(|c| blockchain.has_issued(&c.1.credential)).count()
+        });
+        let pending = open_block().map_or(0, |block| {
+            credentials.iter().filter(|c| block.issues(&c.1.credential)).count()
+        });
+        if !credentials.is_empty() && !force {
+            return Err(format!(
+                "Subject '{} {}' has {} credential(s), {issued} of them on the blockchain and \
+                 {pending} in the pending block, pass --force to remove it anyway",
+                subject.name,
+                subject.surname,
+                credentials.len()
+            ));
         }
+        let name = format!("{} {}", subject.name, subject.surname);
+        subjects[index].deleted = true;
+        save_subjects(&subjects)?;
+        println!("Removed subject '{name}'");
         Ok(())
     }
 }
@@ -414,12 +5217,177 @@ impl SubjectSubcommands {
 struct NewSubjectArgs {
     name: String,
     surname: String,
+    /// Give the subject a key pair to consent to its credentials with
+    #[arg(long)]
+    with_key: bool,
+    /// Encrypt the subject's key with a passphrase, read from `ATTESTATION_KEY_PASSPHRASE` or
+    /// prompted for
+    #[arg(long, requires = "with_key")]
+    encrypt: bool,
+    /// Further field telling the subject apart, e.g. `dob=1990-02-03`, repeatable
+    #[arg(long = "field", value_name = "KEY=VALUE", value_parser = parse_field)]
+    fields: Vec<(String, String)>,
+    /// Add the subject even if one with the same name already exists
+    #[arg(long)]
+    allow_duplicate: bool,
+}
+
+/// What `subjects import` does with rows naming a likely duplicate of an existing subject or of
+/// an earlier row's
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DuplicateHandling {
+    /// Leave the row out, listing it in the summary
+    Skip,
+    /// Add the subject anyway
+    Create,
+    /// Import nothing
+    Error,
+}
+
+#[derive(Args)]
+struct ImportSubjectsArgs {
+    /// CSV file with a header row naming its columns
+    #[arg(long, value_name = "PATH")]
+    csv: PathBuf,
+    /// Column holding the subjects' names
+    #[arg(long, value_name = "COLUMN")]
+    name_column: String,
+    /// Column holding the subjects' surnames
+    #[arg(long, value_name = "COLUMN")]
+    surname_column: String,
+    /// Column holding a further field telling subjects apart, keyed by its header, repeatable
+    #[arg(long = "field-column", value_name = "COLUMN")]
+    field_columns: Vec<String>,
+    #[arg(long, value_enum, default_value_t = DuplicateHandling::Skip)]
+    duplicates: DuplicateHandling,
+    /// Only report what importing would do, showing the first rows read
+    #[arg(long)]
+    dry_run: bool,
+    /// Rows `--dry-run` shows
+    #[arg(long, value_name = "N", default_value_t = 10, requires = "dry_run")]
+    preview: usize,
+}
+
+impl ImportSubjectsArgs {
+    fn run(self) -> Result<(), String> {
+        let mut subjects = open_subjects()?;
+        let columns = SubjectColumns {
+            name: self.name_column,
+            surname: self.surname_column,
+            fields: self.field_columns,
+        };
+        let file = File::open_buffered(&self.csv).map_err(|_| "Failed to open CSV file")?;
+        let csv = self.csv.display();
+        let rows = SubjectReader::new(file, &columns).map_err(|e| format!("{csv}: {e}"))?;
+        // Likely duplicates share a normalized name, so only those need comparing
+        let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, subject) in subjects.iter().enumerate().filter(|(_, s)| !s.deleted) {
+            by_name.entry(subject.normalized_name()).or_default().push(index);
+        }
+        let mut created = 0;
+        let mut skipped = Vec::new();
+        for row in rows {
+            let row = row.map_err(|e| format!("{csv}: {e}, nothing imported"))?;
+            let subject = match row.subject {
+                Ok(subject) => subject,
+                Err(reason) => {
+                    skipped.push((row.line, reason));
+                    continue;
+                },
+            };
+            let same_name = by_name.entry(subject.normalized_name()).or_default();
+            let duplicate = same_name.iter().find(|&&i| subjects[i].is_likely_duplicate(&subject));
+            match (duplicate, self.duplicates) {
+                (Some(index), DuplicateHandling::Skip) => {
+                    skipped.push((row.line, format!("likely duplicate of subject {index}")));
+                    continue;
+                },
+                (Some(index), DuplicateHandling::Error) =>
+                    return Err(format!(
+                        "{csv}: line {}: subject '{} {}' is a likely duplicate of subject \
+                         {index}, nothing imported",
+                        row.line, subject.name, subject.surname
+                    )),
+                _ => {},
+            }
+            if self.dry_run && created < self.preview {
+                println!("Line {}: {}", row.line, subject_line(subjects.len(), &subject));
+            }
+            same_name.push(subjects.len());
+            subjects.push(subject);
+            created += 1;
+        }
+        if self.dry_run {
+            println!("Would create {created} subjects, skipping {} rows", skipped.len());
+        } else {
+            save_subjects(&subjects)?;
+            println!("Created {created} subjects, skipped {} rows", skipped.len());
+        }
+        for (line, reason) in &skipped {
+            println!("  line {line}: {reason}");
+        }
+        Ok(())
+    }
+}
+
+/// Parses a `--field` value of the form `key=value`
+fn parse_field(field: &str) -> Result<(String, String), String> {
+    match field.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("Expected KEY=VALUE, got '{field}'")),
+    }
 }
 
 impl NewSubjectArgs {
-    fn run(self) -> Result<(), &'static str> {
-        let subject = Subject::new(self.name, self.surname);
+    fn run(self) -> Result<(), String> {
+        let (mut subject, key) = if self.with_key {
+            let (subject, key) = Subject::with_key(self.name, self.surname);
+            (subject, Some(SecretKeyMaterial::from(key)))
+        } else {
+            (Subject::new(self.name, self.surname), None)
+        };
+        for (key, value) in self.fields {
+            if subject.extra.insert(key.clone(), value).is_some() {
+                return Err(format!("Field '{key}' given more than once"));
+            }
+        }
         let mut subjects = open_subjects()?;
+        let duplicates: Vec<_> = subjects
+            .iter()
+            .enumerate()
+            .filter(|(_, other)| !other.deleted && other.is_likely_duplicate(&subject))
+            .collect();
+        if !duplicates.is_empty() {
+            let credentials = open_credentials().unwrap_or_default();
+            let existing: Vec<_> = duplicates
+                .iter()
+                .map(|&(index, other)| {
+                    format!("  {}", duplicate_line(index, other, &subject, &credentials))
+                })
+                .collect();
+            if !self.allow_duplicate {
+                return Err(format!(
+                    "Subject '{} {}' already exists, pass --allow-duplicate to add it anyway:\n{}",
+                    subject.name,
+                    subject.surname,
+                    existing.join("\n")
+                ));
+            }
+            eprintln!(
+                "WARNING: adding a subject named like existing ones:\n{}",
+                existing.join("\n")
+            );
+        }
+        if let Some(key) = key {
+            let store = key_store();
+            if self.encrypt {
+                let owner = format!("subject '{} {}'", subject.name, subject.surname);
+                store.save_encrypted(subject.uuid, &key, &new_passphrase(&owner)?)
+            } else {
+                store.save(subject.uuid, &key)
+            }
+            .map_err(|e| e.to_string())?;
+        }
         subjects.push(subject);
         save_subjects(&subjects)?;
         println!("Created new subject");
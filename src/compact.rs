@@ -0,0 +1,117 @@
+//! Compact CBOR encodings of credentials and verification results, for QR codes and other
+//! channels where JSON is too bulky.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::blockchain::VerificationStatus;
+use crate::credential::{Credential, SignedCredential};
+use crate::hash::Hash;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompactError {
+    Encode(String),
+    Decode(String),
+}
+
+impl Display for CompactError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Encode(error) => write!(f, "failed to encode as CBOR: {error}"),
+            Self::Decode(error) => write!(f, "failed to decode CBOR: {error}"),
+        }
+    }
+}
+
+impl Error for CompactError {}
+
+/// Credential with the issuer's signatures over its issuance and revocation hashes. The issuer
+/// verifying key the signatures check against is the credential's own `issuer.verifying`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompactCredential {
+    pub credential: Credential,
+    pub issuance: SignedCredential,
+    pub revocation: SignedCredential,
+}
+
+/// Outcome of verifying a credential, along with the chain it was verified against as it stood
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationReceipt {
+    pub credential: Uuid,
+    pub status: VerificationStatus,
+    /// UUID of the chain's genesis block, none for chains without one
+    pub chain: Option<Uuid>,
+    /// Hash of the chain's last block, none for an empty chain
+    pub head: Option<Hash>,
+    pub verified_at: DateTime<Utc>,
+}
+
+/// Encodes the value as CBOR
+///
+/// # Errors
+/// Returns [`CompactError::Encode`] if the value can't be serialized.
+pub fn to_cbor<T: Serialize>(value: &T) -> Result<Vec<u8>, CompactError> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).map_err(|e| CompactError::Encode(e.to_string()))?;
+    Ok(bytes)
+}
+
+/// Decodes a value encoded by [`to_cbor`]
+///
+/// # Errors
+/// Returns [`CompactError::Decode`] if the bytes aren't CBOR of the expected value.
+pub fn from_cbor<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CompactError> {
+    ciborium::from_reader(bytes).map_err(|e| CompactError::Decode(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::credential::{Attribute, Issuer, Subject, ValidDuration};
+    use crate::hash::HashVersion;
+
+    fn compact() -> CompactCredential {
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let attributes = vec![Attribute::new("degree".into(), "MSc".into())];
+        let subject = Subject::new("Alice".into(), "Smith".into());
+        let valid = ValidDuration::starting(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        let credential = Credential::new(attributes, issuer, subject, valid);
+        let issuance = credential.sign(&signing, false, HashVersion::V2).unwrap();
+        let revocation = credential.sign(&signing, true, HashVersion::V2).unwrap();
+        CompactCredential { credential, issuance, revocation }
+    }
+
+    #[test]
+    fn test_compact_credential_roundtrip() {
+        let compact = compact();
+        let bytes = to_cbor(&compact).unwrap();
+        let decoded: CompactCredential = from_cbor(&bytes).unwrap();
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap(),
+            serde_json::to_value(&compact).unwrap()
+        );
+        assert!(decoded.issuance.verify(&decoded.credential.issuer.verifying));
+        assert!(bytes.len() < serde_json::to_vec(&compact).unwrap().len());
+    }
+
+    #[test]
+    fn test_receipt_roundtrip() {
+        let receipt = VerificationReceipt {
+            credential: Uuid::new_v4(),
+            status: VerificationStatus::Revoked { issued_in: Some(1), revoked_in: 3 },
+            chain: Some(Uuid::new_v4()),
+            head: Some(compact().issuance.credential),
+            verified_at: Utc::now(),
+        };
+        let decoded: VerificationReceipt = from_cbor(&to_cbor(&receipt).unwrap()).unwrap();
+        assert_eq!(decoded, receipt);
+        assert!(matches!(from_cbor::<VerificationReceipt>(&[0xff]), Err(CompactError::Decode(_))));
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::import_policy::ImportPolicy;
+use crate::server::ServerConfig;
+use crate::signer::ExternalSignerConfig;
+use crate::webhooks::WebhookConfig;
+
+/// Settings read from `attestation.toml` in the working directory
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// How many seconds a new block's timestamp may be earlier than the previous block's
+    pub allow_clock_skew: u64,
+    /// Rules the blocks brought in by `blockchain import` and `blockchain sync` have to follow
+    pub import: ImportPolicy,
+    /// Programs signing on behalf of issuers, by issuer UUID, instead of their key files
+    pub signers: HashMap<Uuid, ExternalSignerConfig>,
+    /// Settings of `serve`
+    pub server: ServerConfig,
+    /// Systems notified of finalized blocks
+    pub webhooks: Vec<WebhookConfig>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_defaults() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.allow_clock_skew, 0);
+        assert!(config.signers.is_empty());
+        assert_eq!(config.server.token, None);
+        assert!(config.webhooks.is_empty());
+        assert_eq!(config.import, ImportPolicy::default());
+    }
+
+    #[test]
+    fn test_config_parsing() {
+        let config: Config = toml::from_str("allow_clock_skew = 30").unwrap();
+        assert_eq!(config.allow_clock_skew, 30);
+        assert!(toml::from_str::<Config>("unknown = 1").is_err());
+        let config: Config = toml::from_str("[server]\ntoken = \"s3cret\"").unwrap();
+        assert_eq!(config.server.token.as_deref(), Some("s3cret"));
+        let config: Config = toml::from_str(
+            "[server.tls]\ncertificate = \"server.pem\"\nkey = \"server.key\"\n\
+             client_ca = \"clients.pem\"",
+        )
+        .unwrap();
+        let tls = config.server.tls.unwrap();
+        assert_eq!(tls.client_ca, Some("clients.pem".into()));
+        assert!(toml::from_str::<Config>("[server.tls]\ncertificate = \"server.pem\"").is_err());
+        let config: Config =
+            toml::from_str("[import]\ntrusted_signers = true\nmax_block_bytes = 65536").unwrap();
+        assert!(config.import.trusted_signers);
+        assert_eq!(config.import.max_block_bytes, Some(65536));
+        assert!(toml::from_str::<Config>("[import]\nmax_blocks = 1").is_err());
+    }
+
+    #[test]
+    fn test_config_signers() {
+        let config: Config = toml::from_str(
+            r#"
+            [signers.00000000-0000-0000-0000-000000000002]
+            command = "/usr/bin/hsm-sign"
+            args = ["--slot", "1"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.signers[&Uuid::from_u128(2)], ExternalSignerConfig {
+            command: "/usr/bin/hsm-sign".into(),
+            args: vec!["--slot".to_string(), "1".to_string()],
+        });
+        assert!(toml::from_str::<Config>("[signers.not-a-uuid]\ncommand = \"sign\"").is_err());
+    }
+}
@@ -1,16 +1,23 @@
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
-use chrono::NaiveDate;
-use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
-use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha512};
+use chrono::{DateTime, Months, NaiveDate, Utc};
+use ed25519_dalek::{SigningKey, Verifier, VerifyingKey};
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
 use uuid::Uuid;
 
-use crate::hash::Hash;
+use crate::evidence::EvidenceRef;
+use crate::hash::{Algorithm, Hash, HashFormat, HashVersion, Hasher, domain};
+use crate::keys::fingerprint;
+use crate::signature::Sig;
+use crate::signer::{BlockSigner, SignError};
 
 /// Custom serialization for `VerifyingKey`
-mod verifying_key_serde {
+pub(crate) mod verifying_key_serde {
     use ed25519_dalek::VerifyingKey;
     use serde::{Deserialize, Deserializer, Serializer, de};
 
@@ -30,34 +37,168 @@ mod verifying_key_serde {
     }
 }
 
+/// Custom serialization for an optional `VerifyingKey`, as a hex string
+mod optional_verifying_key_serde {
+    use ed25519_dalek::VerifyingKey;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::verifying_key_serde;
+
+    // serde's `with` hands over a reference to the field
+    #[expect(clippy::ref_option)]
+    pub fn serialize<S>(key: &Option<VerifyingKey>, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        match key {
+            Some(key) => verifying_key_serde::serialize(key, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<VerifyingKey>, D::Error>
+    where D: Deserializer<'de> {
+        #[derive(Deserialize)]
+        struct Key(#[serde(with = "verifying_key_serde")] VerifyingKey);
+        Ok(Option::<Key>::deserialize(deserializer)?.map(|Key(key)| key))
+    }
+}
+
+/// Custom serialization for lists of `VerifyingKey`s, as hex strings
+mod verifying_keys_serde {
+    use ed25519_dalek::VerifyingKey;
+    use serde::{Deserialize, Deserializer, Serializer, de};
+
+    pub fn serialize<S>(keys: &[VerifyingKey], serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        serializer.collect_seq(keys.iter().map(|key| hex::encode(key.as_bytes())))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<VerifyingKey>, D::Error>
+    where D: Deserializer<'de> {
+        let hex_strings: Vec<String> = Deserialize::deserialize(deserializer)?;
+        hex_strings
+            .into_iter()
+            .map(|hex_str| {
+                let bytes = hex::decode(hex_str).map_err(de::Error::custom)?;
+                let bytes = bytes
+                    .try_into()
+                    .map_err(|_| de::Error::custom("Verifying key must be 32 bytes"))?;
+                VerifyingKey::from_bytes(&bytes).map_err(de::Error::custom)
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Issuer {
     pub uuid: Uuid,
     pub name: String,
     #[serde(with = "verifying_key_serde")]
     pub verifying: VerifyingKey,
+    /// Keys the issuer signed with before rotating to `verifying`, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "verifying_keys_serde")]
+    pub previous_keys: Vec<VerifyingKey>,
+    /// Website verifiers can find out about the issuer on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Address verifiers can contact the issuer at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// ISO 3166-1 alpha-2 code of the country whose law the issuer operates under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// Names the issuer went by before being renamed, oldest first. Only kept for display and
+    /// not hashed, credentials and blocks keeping the name they were hashed with.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub former_names: Vec<String>,
+    /// Removed from the issuers, its record only kept so that the other issuers keep their index.
+    /// Not hashed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
 }
 
 impl Issuer {
     #[must_use]
     pub fn new(name: String) -> (Self, SigningKey) {
-        let signing = SigningKey::generate(&mut rand::thread_rng());
-        let verifying = signing.verifying_key();
-        let uuid = Uuid::new_v4();
-        let issuer = Self { uuid, name, verifying };
-        (issuer, signing)
+        Self::new_with_rng(name, &mut rand::thread_rng())
+    }
+
+    /// Creates an issuer with a key drawn from `rng`, so that seeded generators give reproducible
+    /// keys
+    #[must_use]
+    pub fn new_with_rng(name: String, rng: &mut (impl CryptoRng + RngCore)) -> (Self, SigningKey) {
+        let signing = SigningKey::generate(rng);
+        (Self::from_signing_key(name, &signing), signing)
+    }
+
+    /// Creates an issuer verifying with the given key's public half
+    #[must_use]
+    pub fn from_signing_key(name: String, signing: &SigningKey) -> Self {
+        Self {
+            uuid: Uuid::new_v4(),
+            name,
+            verifying: signing.verifying_key(),
+            previous_keys: Vec::new(),
+            url: None,
+            email: None,
+            country: None,
+            former_names: Vec::new(),
+            deleted: false,
+        }
+    }
+
+    /// Switches the issuer over to `new`, keeping the current key in `previous_keys`
+    pub fn rotate_key(&mut self, new: VerifyingKey) {
+        self.previous_keys.push(self.verifying);
+        self.verifying = new;
     }
 
-    pub fn update_hash(&self, hasher: &mut impl Digest) {
+    /// Gives the issuer a new name, keeping the current one in `former_names`
+    pub fn rename(&mut self, name: String) {
+        let former = std::mem::replace(&mut self.name, name);
+        self.former_names.push(former);
+    }
+
+    /// Fingerprint of the issuer's current verifying key
+    #[must_use]
+    pub fn fingerprint(&self) -> String { fingerprint(&self.verifying) }
+
+    /// Whether `key` is the issuer's current key or one it rotated away from
+    #[must_use]
+    pub fn has_used_key(&self, key: &VerifyingKey) -> bool {
+        &self.verifying == key || self.previous_keys.contains(key)
+    }
+
+    pub fn update_hash(&self, hasher: &mut Hasher) {
+        hasher.domain(domain::ISSUER);
         hasher.update(self.uuid);
-        hasher.update(&self.name);
+        hasher.field(&self.name);
         hasher.update(self.verifying);
+        if !self.previous_keys.is_empty() {
+            hasher.field("previous_keys");
+            hasher.length(self.previous_keys.len());
+            for key in &self.previous_keys {
+                hasher.update(key);
+            }
+        }
+        if self.url.is_some() || self.email.is_some() || self.country.is_some() {
+            hasher.field("metadata");
+            for field in [&self.url, &self.email, &self.country] {
+                hasher.optional(field.as_ref());
+            }
+        }
     }
 }
 
 impl Display for Issuer {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.write_str(&serde_json::to_string_pretty(self).unwrap())
+        #[derive(Serialize)]
+        struct WithFingerprint<'a> {
+            #[serde(flatten)]
+            issuer: &'a Issuer,
+            fingerprint: String,
+        }
+        let issuer = WithFingerprint { issuer: self, fingerprint: self.fingerprint() };
+        f.write_str(&serde_json::to_string_pretty(&issuer).unwrap())
     }
 }
 
@@ -66,19 +207,98 @@ pub struct Subject {
     pub uuid: Uuid,
     pub name: String,
     pub surname: String,
+    /// Further fields telling the subject apart from namesakes, e.g. date of birth or email,
+    /// kept sorted so that they hash the same whatever order they were given in
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra: BTreeMap<String, String>,
+    /// Key the subject consents to credentials with, if it has one
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "optional_verifying_key_serde"
+    )]
+    pub verifying: Option<VerifyingKey>,
+    /// Removed from the subjects, its record only kept so that the other subjects keep their
+    /// index. Not hashed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
 }
 
 impl Subject {
     #[must_use]
     pub fn new(name: String, surname: String) -> Self {
         let uuid = Uuid::new_v4();
-        Self { uuid, name, surname }
+        Self { uuid, name, surname, extra: BTreeMap::new(), verifying: None, deleted: false }
+    }
+
+    /// Creates a subject with a key pair to consent to its credentials with
+    #[must_use]
+    pub fn with_key(name: String, surname: String) -> (Self, SigningKey) {
+        let signing = SigningKey::generate(&mut rand::thread_rng());
+        let subject = Self { verifying: Some(signing.verifying_key()), ..Self::new(name, surname) };
+        (subject, signing)
+    }
+
+    /// Name and surname, lowercased and with whitespace collapsed, the same for every record of a
+    /// person however the name was typed in
+    #[must_use]
+    pub fn normalized_name(&self) -> String {
+        let name = format!("{} {}", self.name, self.surname).to_lowercase();
+        name.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Extra fields both subjects have, split into those holding the same value and those that
+    /// don't
+    #[must_use]
+    pub fn compare_extra(&self, other: &Self) -> (Vec<&str>, Vec<&str>) {
+        self.extra
+            .keys()
+            .map(String::as_str)
+            .filter(|key| other.extra.contains_key(*key))
+            .partition(|key| self.extra.get(*key) == other.extra.get(*key))
     }
 
-    fn hash(&self, hasher: &mut impl Digest) {
+    /// Whether the subjects likely stand for the same person: they have the same normalized name
+    /// and none of the extra fields they both have differ
+    #[must_use]
+    pub fn is_likely_duplicate(&self, other: &Self) -> bool {
+        self.normalized_name() == other.normalized_name() && self.compare_extra(other).1.is_empty()
+    }
+
+    /// Whether the name or surname contains `query`, or the UUID starts with it, ignoring case.
+    /// With `exact`, the full name or the UUID has to equal it instead.
+    #[must_use]
+    pub fn matches(&self, query: &str, exact: bool) -> bool {
+        let query = query.trim().to_lowercase();
+        let full_name = format!("{} {}", self.name, self.surname).to_lowercase();
+        let uuid = self.uuid.to_string();
+        if exact {
+            return full_name == query || uuid == query;
+        }
+        !query.is_empty()
+            && (self.name.to_lowercase().contains(&query)
+                || self.surname.to_lowercase().contains(&query)
+                || full_name.contains(&query)
+                || uuid.starts_with(&query))
+    }
+
+    fn hash(&self, hasher: &mut Hasher) {
+        hasher.domain(domain::SUBJECT);
         hasher.update(self.uuid);
-        hasher.update(&self.name);
-        hasher.update(&self.surname);
+        hasher.field(&self.name);
+        hasher.field(&self.surname);
+        if !self.extra.is_empty() {
+            hasher.field("extra");
+            hasher.count(self.extra.len());
+            for (key, value) in &self.extra {
+                hasher.prefixed(key);
+                hasher.prefixed(value);
+            }
+        }
+        if let Some(verifying) = &self.verifying {
+            hasher.field("verifying");
+            hasher.update(verifying.as_bytes());
+        }
     }
 }
 
@@ -88,119 +308,614 @@ impl Display for Subject {
     }
 }
 
+/// Salted hash standing in for a subject in a credential, so that the credential doesn't carry
+/// the subject's name. The salt is kept off the chain by the issuer and the subject, to open the
+/// commitment with.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubjectCommitment {
+    /// SHA-512 of the salt and the subject, hashed as in credentials
+    pub commitment: Hash,
+    /// Subject's key, left in the clear so that the subject can still consent to the credential
+    /// and answer challenges with it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "optional_verifying_key_serde"
+    )]
+    pub verifying: Option<VerifyingKey>,
+}
+
+impl SubjectCommitment {
+    #[must_use]
+    pub fn new(subject: &Subject, salt: &Salt) -> Self {
+        Self { commitment: Self::digest(subject, salt), verifying: subject.verifying }
+    }
+
+    fn digest(subject: &Subject, salt: &Salt) -> Hash {
+        let mut hasher =
+            Hasher::new(HashFormat { version: HashVersion::V4, algorithm: Algorithm::Sha512 });
+        hasher.domain(domain::SUBJECT_COMMITMENT);
+        hasher.update(salt.0);
+        subject.hash(&mut hasher);
+        hasher.finalize()
+    }
+
+    /// Whether the commitment opens to the subject with the salt
+    #[must_use]
+    pub fn opens_to(&self, subject: &Subject, salt: &Salt) -> bool {
+        Self::digest(subject, salt) == self.commitment && self.verifying == subject.verifying
+    }
+
+    fn hash(&self, hasher: &mut Hasher) {
+        hasher.domain(domain::SUBJECT_COMMITMENT);
+        hasher.update(self.commitment.bytes);
+        hasher.presence(self.verifying.is_some());
+        if let Some(verifying) = &self.verifying {
+            hasher.update(verifying.as_bytes());
+        }
+    }
+}
+
+/// Subject of a credential, in the clear or only committed to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CredentialSubject {
+    Disclosed(Subject),
+    Committed(SubjectCommitment),
+}
+
+impl CredentialSubject {
+    /// The subject, unless the credential only commits to it
+    #[must_use]
+    pub fn disclosed(&self) -> Option<&Subject> {
+        match self {
+            Self::Disclosed(subject) => Some(subject),
+            Self::Committed(_) => None,
+        }
+    }
+
+    /// Key the subject consents to credentials with, if it has one
+    #[must_use]
+    pub fn verifying(&self) -> Option<&VerifyingKey> {
+        match self {
+            Self::Disclosed(subject) => subject.verifying.as_ref(),
+            Self::Committed(commitment) => commitment.verifying.as_ref(),
+        }
+    }
+
+    /// Whether this is the given subject, never for a commitment, which can't be told apart
+    /// without its salt
+    #[must_use]
+    pub fn is(&self, subject: &Subject) -> bool {
+        self.disclosed().is_some_and(|disclosed| disclosed.uuid == subject.uuid)
+    }
+
+    /// Name and surname, or a placeholder for a commitment
+    #[must_use]
+    pub fn full_name(&self) -> String {
+        match self {
+            Self::Disclosed(subject) => format!("{} {}", subject.name, subject.surname),
+            Self::Committed(_) => "(private subject)".to_string(),
+        }
+    }
+
+    fn hash(&self, hasher: &mut Hasher) {
+        match self {
+            Self::Disclosed(subject) => subject.hash(hasher),
+            Self::Committed(commitment) => commitment.hash(hasher),
+        }
+    }
+}
+
+impl From<Subject> for CredentialSubject {
+    fn from(subject: Subject) -> Self { Self::Disclosed(subject) }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DurationError {
+    /// The period would end before it starts
+    EndsBeforeStart { from: NaiveDate, to: NaiveDate },
+    /// The end of the period can't be represented
+    OutOfRange,
+}
+
+impl Display for DurationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EndsBeforeStart { from, to } =>
+                write!(f, "Validity period would end on {to}, before it starts on {from}"),
+            Self::OutOfRange => f.write_str("Validity period ends too far in the future"),
+        }
+    }
+}
+
+impl Error for DurationError {}
+
+/// Period a credential is valid in, both ends included. Only constructors check that it doesn't
+/// end before it starts, periods stored by older versions are read as they are.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ValidDuration {
     pub from: NaiveDate,
+    /// Last day of the period, which never ends if not set
     pub to: Option<NaiveDate>,
 }
 
 impl ValidDuration {
+    /// Period from `from` to `to`, or indefinitely
+    ///
+    /// # Errors
+    /// Fails if `to` is before `from`.
+    pub fn new(from: NaiveDate, to: Option<NaiveDate>) -> Result<Self, DurationError> {
+        match to {
+            Some(to) if to < from => Err(DurationError::EndsBeforeStart { from, to }),
+            _ => Ok(Self { from, to }),
+        }
+    }
+
+    /// Period from `from` that never ends
+    #[must_use]
+    pub fn starting(from: NaiveDate) -> Self { Self { from, to: None } }
+
+    /// Period lasting `years` from `from`, its last day being the day before the same date
+    /// `years` later. A period starting on 29 February ends on 28 February, one of zero years
+    /// lasts the single day `from`.
+    ///
+    /// # Errors
+    /// Fails if the period would end beyond the dates that can be represented.
+    pub fn for_years(from: NaiveDate, years: u32) -> Result<Self, DurationError> {
+        let months = years.checked_mul(12).ok_or(DurationError::OutOfRange)?;
+        let to = from
+            .pred_opt()
+            .and_then(|day_before| day_before.checked_add_months(Months::new(months)))
+            .ok_or(DurationError::OutOfRange)?;
+        Ok(Self { from, to: Some(to.max(from)) })
+    }
+
+    /// Whether `date` is within the period, `from` and `to` themselves included
     #[must_use]
-    pub fn new(from: NaiveDate, to: Option<NaiveDate>) -> Self { Self { from, to } }
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from <= date && self.to.is_none_or(|to| date <= to)
+    }
 
-    fn hash(&self, hasher: &mut impl Digest) {
-        hasher.update(self.from.format("%Y-%m-%d").to_string());
+    /// Whether the period ended before `today`. A credential is still valid on its `to` date
+    /// and expired only the day after.
+    #[must_use]
+    pub fn is_expired(&self, today: NaiveDate) -> bool { self.to.is_some_and(|to| today > to) }
+
+    pub(crate) fn hash(&self, hasher: &mut Hasher) {
+        hasher.domain(domain::VALID_DURATION);
+        hasher.field(self.from.format("%Y-%m-%d").to_string());
+        hasher.presence(self.to.is_some());
         if let Some(to) = &self.to {
-            hasher.update(to.format("%Y-%m-%d").to_string());
+            hasher.field(to.format("%Y-%m-%d").to_string());
         }
     }
 }
 
+/// Random bytes hashed into an attribute's commitment so that a withheld attribute can't be
+/// found by hashing likely values. Written as a hex string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Salt(pub [u8; 32]);
+
+impl Salt {
+    #[must_use]
+    pub fn random() -> Self {
+        let mut salt = [0; 32];
+        rand::thread_rng().fill_bytes(&mut salt);
+        Self(salt)
+    }
+}
+
+impl Display for Salt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str(&hex::encode(self.0)) }
+}
+
+impl FromStr for Salt {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s).map_err(|e| e.to_string())?;
+        let bytes = bytes
+            .try_into()
+            .map_err(|b: Vec<u8>| format!("Salt must be 32 bytes, got {}", b.len()))?;
+        Ok(Self(bytes))
+    }
+}
+
+impl Serialize for Salt {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Salt {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        let bytes = hex::decode(s).map_err(de::Error::custom)?;
+        let bytes = bytes.try_into().map_err(|b: Vec<u8>| {
+            de::Error::custom(format!("Salt must be 32 bytes, got {}", b.len()))
+        })?;
+        Ok(Self(bytes))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Attribute {
     pub name: String,
     pub value: String,
+    /// URI of the schema the value was validated against when the credential was issued
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// Salt of the attribute's commitment, given to attributes when their credential is made.
+    /// Credentials stored by versions without salts commit to their attributes unsalted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<Salt>,
 }
 
 impl Attribute {
     #[must_use]
-    pub fn new(name: String, value: String) -> Self { Self { name, value } }
+    pub fn new(name: String, value: String) -> Self {
+        Self { name, value, schema: None, salt: None }
+    }
+
+    /// Salted hash of the attribute that credentials commit to from V4 on, which can be shown in
+    /// place of the attribute to withhold it
+    #[must_use]
+    pub fn commitment(&self, format: impl Into<HashFormat>) -> Hash {
+        let mut hasher = Hasher::new(format);
+        hasher.domain(domain::ATTRIBUTE_COMMITMENT);
+        hasher.presence(self.salt.is_some());
+        if let Some(salt) = &self.salt {
+            hasher.update(salt.0);
+        }
+        self.hash(&mut hasher);
+        hasher.finalize()
+    }
 
-    fn hash(&self, hasher: &mut impl Digest) {
-        hasher.update(&self.name);
-        hasher.update(&self.value);
+    fn hash(&self, hasher: &mut Hasher) {
+        hasher.domain(domain::ATTRIBUTE);
+        hasher.field(&self.name);
+        hasher.field(&self.value);
+        if let Some(schema) = &self.schema {
+            hasher.field("schema");
+            hasher.field(schema);
+        }
     }
 }
 
+impl Display for Attribute {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.value)
+    }
+}
+
+/// Reads a credential's attributes as a list, or as the single object older versions stored
+/// under `attribute`
+fn deserialize_attributes<'de, D>(deserializer: D) -> Result<Vec<Attribute>, D::Error>
+where D: Deserializer<'de> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(Attribute),
+        Many(Vec<Attribute>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(attribute) => vec![attribute],
+        OneOrMany::Many(attributes) => attributes,
+    })
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Credential {
     pub uuid: Uuid,
-    pub attribute: Attribute,
+    /// Claims of the credential. Up to V3 they're hashed in this order, so the same claims listed
+    /// in another order make another credential; from V4 on their commitments are sorted.
+    #[serde(alias = "attribute", deserialize_with = "deserialize_attributes")]
+    pub attributes: Vec<Attribute>,
+    /// Commitments to attributes withheld from a presentation of the credential, hashed along
+    /// with those of `attributes`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub withheld: Vec<Hash>,
     pub issuer: Issuer,
-    pub subject: Subject,
+    pub subject: CredentialSubject,
     pub valid_duration: ValidDuration,
+    /// When the credential was produced, [`Credential::UNKNOWN_ISSUANCE`] for credentials stored
+    /// by versions that didn't record it
+    #[serde(default = "unknown_issuance", skip_serializing_if = "is_unknown_issuance")]
+    pub issued_at: DateTime<Utc>,
+    /// Credential this one renews
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supersedes: Option<Uuid>,
+    /// Supporting documents bound to the credential by their digests
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence: Vec<EvidenceRef>,
+}
+
+fn unknown_issuance() -> DateTime<Utc> { Credential::UNKNOWN_ISSUANCE }
+
+fn is_unknown_issuance(issued_at: &DateTime<Utc>) -> bool {
+    *issued_at == Credential::UNKNOWN_ISSUANCE
 }
 
 impl Credential {
+    /// Issuance time of credentials stored before it was recorded, never hashed so that their
+    /// hashes stay the same
+    pub const UNKNOWN_ISSUANCE: DateTime<Utc> = DateTime::UNIX_EPOCH;
+
+    /// Makes a credential, salting the attributes that aren't salted yet
     #[must_use]
     pub fn new(
-        attribute: Attribute, issuer: Issuer, subject: Subject, valid_duration: ValidDuration,
+        mut attributes: Vec<Attribute>, issuer: Issuer, subject: Subject,
+        valid_duration: ValidDuration,
     ) -> Self {
         let uuid = Uuid::new_v4();
-        Self { uuid, attribute, issuer, subject, valid_duration }
+        let issued_at = Utc::now();
+        for attribute in &mut attributes {
+            attribute.salt.get_or_insert_with(Salt::random);
+        }
+        Self {
+            uuid,
+            attributes,
+            withheld: Vec::new(),
+            issuer,
+            subject: subject.into(),
+            valid_duration,
+            issued_at,
+            supersedes: None,
+            evidence: Vec::new(),
+        }
+    }
+
+    /// Replaces the subject with a commitment to it under a random salt, returned for the issuer
+    /// to hand to the subject. None if the credential already commits to its subject.
+    pub fn commit_subject(&mut self) -> Option<Salt> {
+        let subject = self.subject.disclosed()?;
+        let salt = Salt::random();
+        self.subject = CredentialSubject::Committed(SubjectCommitment::new(subject, &salt));
+        Some(salt)
     }
 
+    /// Whether the credential records when it was issued
+    #[must_use]
+    pub fn has_issuance_time(&self) -> bool { !is_unknown_issuance(&self.issued_at) }
+
+    /// Hash the issuer signs to issue the credential, or to revoke it if `revoking`, following
+    /// the rules of the chain's hash version
     #[must_use]
-    pub fn hash(&self, revoking: bool) -> Hash {
-        let mut hasher = Sha512::new();
+    pub fn hash(&self, revoking: bool, format: impl Into<HashFormat>) -> Hash {
+        let format = format.into();
+        let mut hasher = Hasher::new(format);
+        hasher.domain(if revoking { domain::REVOCATION } else { domain::CREDENTIAL });
         hasher.update(self.uuid);
-        self.attribute.hash(&mut hasher);
+        if format.version >= HashVersion::V4 {
+            let attributes = self.attributes.iter().map(|a| a.commitment(format));
+            let mut commitments: Vec<_> = attributes.chain(self.withheld.clone()).collect();
+            commitments.sort_by_key(|commitment| commitment.bytes);
+            hasher.domain(domain::ATTRIBUTE_COMMITMENTS);
+            hasher.length(commitments.len());
+            for commitment in &commitments {
+                hasher.update(commitment);
+            }
+        } else if let [attribute] = self.attributes.as_slice() {
+            // A single attribute hashes the way it did before credentials could hold several
+            attribute.hash(&mut hasher);
+        } else {
+            hasher.domain(domain::ATTRIBUTES);
+            hasher.count(self.attributes.len());
+            for attribute in &self.attributes {
+                hasher.prefixed(&attribute.name);
+                hasher.prefixed(&attribute.value);
+                hasher.optional(attribute.schema.as_ref());
+            }
+        }
         self.issuer.update_hash(&mut hasher);
         self.subject.hash(&mut hasher);
         self.valid_duration.hash(&mut hasher);
-        if revoking {
-            hasher.update("revoking");
+        // V1 chains hash credentials the way they always did
+        if hasher.version() >= HashVersion::V2 && self.has_issuance_time() {
+            hasher.field("issued_at");
+            hasher.field(self.issued_at.to_string());
+        }
+        if let Some(supersedes) = self.supersedes {
+            hasher.field("supersedes");
+            hasher.update(supersedes);
+        }
+        if !self.evidence.is_empty() {
+            hasher.domain(domain::EVIDENCE);
+            hasher.length(self.evidence.len());
+            for evidence in &self.evidence {
+                evidence.update_hash(&mut hasher);
+            }
         }
-        hasher.finalize().into()
+        // V1 tells revocations apart by this trailing marker instead of the domain
+        if revoking && hasher.version() == HashVersion::V1 {
+            hasher.field("revoking");
+        }
+        hasher.finalize()
     }
 
-    #[must_use]
-    pub fn sign(&self, signer: &SigningKey, revoking: bool) -> SignedCredential {
-        let hash = self.hash(revoking);
-        let signature = signer.sign(&hash.0).into();
-        SignedCredential::new(hash, signature)
+    /// Signs the credential's issuance hash, or its revocation hash if `revoking`
+    ///
+    /// # Errors
+    /// Fails if the signer can't sign.
+    pub fn sign(
+        &self, signer: &dyn BlockSigner, revoking: bool, format: impl Into<HashFormat>,
+    ) -> Result<SignedCredential, SignError> {
+        let hash = self.hash(revoking, format);
+        let signature = signer.sign(&hash.bytes)?.into();
+        Ok(SignedCredential::new(hash, signature))
     }
 }
 
+#[cfg(test)]
+impl Credential {
+    /// Credential of a subject with a detail and two attributes, valid from 2024 through 2028,
+    /// with the key of its issuer
+    pub(crate) fn sample() -> (Self, SigningKey) {
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let mut subject = Subject::new("Alice".to_string(), "Smith".to_string());
+        subject.extra.insert("born".to_string(), "1990-04-01".to_string());
+        let attributes = vec![
+            Attribute::new("degree".to_string(), "MSc".to_string()),
+            Attribute::new("field".to_string(), "Physics".to_string()),
+        ];
+        let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let valid = ValidDuration::new(from, NaiveDate::from_ymd_opt(2028, 12, 31)).unwrap();
+        (Self::new(attributes, issuer, subject, valid), signing)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineageError {
+    /// Following `supersedes` links led back to this credential
+    Cycle(Uuid),
+}
+
+impl Display for LineageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cycle(uuid) => write!(f, "Credential {uuid} is among its own renewals"),
+        }
+    }
+}
+
+impl Error for LineageError {}
+
+/// Credentials `credential` renews and the ones renewing it, oldest first, found by following
+/// `supersedes` links among `credentials`. A credential renewed more than once is followed by
+/// its most recently issued renewal.
+///
+/// # Errors
+/// Fails if the links form a cycle.
+pub fn lineage<'a>(
+    credential: &'a Credential, credentials: &[&'a Credential],
+) -> Result<Vec<&'a Credential>, LineageError> {
+    let mut seen = HashSet::from([credential.uuid]);
+    let mut visit =
+        |c: &Credential| seen.insert(c.uuid).then_some(()).ok_or(LineageError::Cycle(c.uuid));
+
+    let mut generations = vec![credential];
+    let mut oldest = credential;
+    while let Some(previous) =
+        oldest.supersedes.and_then(|uuid| credentials.iter().find(|c| c.uuid == uuid))
+    {
+        visit(previous)?;
+        generations.push(previous);
+        oldest = previous;
+    }
+    generations.reverse();
+
+    let mut newest = credential;
+    while let Some(&next) =
+        credentials.iter().filter(|c| c.supersedes == Some(newest.uuid)).max_by_key(|c| c.issued_at)
+    {
+        visit(next)?;
+        generations.push(next);
+        newest = next;
+    }
+    Ok(generations)
+}
+
 impl Display for Credential {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.write_str(&serde_json::to_string_pretty(self).unwrap())
     }
 }
 
+/// Whether the subject agreed to a credential being issued to it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConsentStatus {
+    /// The subject countersigned the credential
+    Signed,
+    #[default]
+    Absent,
+    /// The countersignature wasn't made with the subject's key
+    Invalid,
+}
+
+impl Display for ConsentStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Signed => "signed by subject",
+            Self::Absent => "absent",
+            Self::Invalid => "invalid, not signed with the subject's key",
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignedCredential {
     pub credential: Hash,
-    pub signature: Hash,
+    pub signature: Sig,
+    /// Subject's countersignature over the same hash, as evidence it agreed to the credential
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consent: Option<Sig>,
 }
 
 impl SignedCredential {
     #[must_use]
-    pub fn new(credential: Hash, signature: Hash) -> Self { Self { credential, signature } }
+    pub fn new(credential: Hash, signature: Sig) -> Self {
+        Self { credential, signature, consent: None }
+    }
 
+    /// Checks the signature with ed25519's verification, without comparing any bytes directly
     #[must_use]
     pub fn verify(&self, verifying: &VerifyingKey) -> bool {
-        verifying.verify(&self.credential.0, &Signature::from_bytes(&self.signature.0)).is_ok()
+        verifying.verify(&self.credential.bytes, &(&self.signature).into()).is_ok()
+    }
+
+    /// Countersigns the credential on behalf of its subject
+    ///
+    /// # Errors
+    /// Fails if the subject's signer can't produce a signature.
+    pub fn add_consent(&mut self, subject: &dyn BlockSigner) -> Result<(), SignError> {
+        self.consent = Some(subject.sign(&self.credential.bytes)?.into());
+        Ok(())
+    }
+
+    /// Checks the consent signature against the subject's key
+    #[must_use]
+    pub fn consent_status(&self, subject: &CredentialSubject) -> ConsentStatus {
+        match (&self.consent, subject.verifying()) {
+            (None, _) => ConsentStatus::Absent,
+            (Some(consent), Some(verifying))
+                if verifying.verify(&self.credential.bytes, &consent.into()).is_ok() =>
+                ConsentStatus::Signed,
+            (Some(_), _) => ConsentStatus::Invalid,
+        }
     }
 
-    pub fn update_hash(&self, hasher: &mut impl Digest) {
-        hasher.update(self.credential.0);
-        hasher.update(self.signature.0);
+    pub fn update_hash(&self, hasher: &mut Hasher) {
+        hasher.domain(domain::SIGNED_CREDENTIAL);
+        hasher.update(self.credential.bytes);
+        hasher.update(&self.signature);
+        if let Some(consent) = &self.consent {
+            hasher.field("consent");
+            hasher.update(consent);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::NaiveDate;
+    use chrono::{NaiveDate, TimeDelta};
+    use ed25519_dalek::Signer;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
     use serde_json;
 
     use super::*;
+    use crate::hash::Algorithm;
 
     #[test]
     fn test_issuer_creation_and_display() {
         let (issuer, _) = Issuer::new("Governmnent Authority".to_string());
         let display = format!("{issuer}");
         assert!(display.contains("Governmnent Authority"));
+        assert!(display.contains(&format!("\"fingerprint\": \"{}\"", issuer.fingerprint())));
         assert!(display.contains(&issuer.uuid.to_string()));
     }
 
@@ -217,34 +932,125 @@ mod tests {
         let valid = ValidDuration::new(
             NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
             Some(NaiveDate::from_ymd_opt(2030, 12, 31).unwrap()),
-        );
-        let mut hasher = Sha512::new();
+        )
+        .unwrap();
+        let mut hasher = Hasher::new(HashVersion::LATEST);
         valid.hash(&mut hasher);
         let hash = hasher.finalize();
-        assert_eq!(hash.len(), 64);
+        assert_ne!(hash, Hash::default());
+    }
+
+    #[test]
+    fn test_valid_duration_rejects_end_before_start() {
+        let from = NaiveDate::from_ymd_opt(2030, 1, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        let error = ValidDuration::new(from, Some(to)).unwrap_err();
+        assert_eq!(error, DurationError::EndsBeforeStart { from, to });
+        assert_eq!(
+            error.to_string(),
+            "Validity period would end on 2020-01-01, before it starts on 2030-01-01"
+        );
+        // A period of a single day is fine
+        assert!(ValidDuration::new(from, Some(from)).is_ok());
+        assert_eq!(ValidDuration::new(from, None), Ok(ValidDuration::starting(from)));
+    }
+
+    #[test]
+    fn test_valid_duration_for_years() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let duration = ValidDuration::for_years(date(2025, 1, 1), 5).unwrap();
+        assert_eq!(
+            duration,
+            ValidDuration::new(date(2025, 1, 1), Some(date(2029, 12, 31))).unwrap()
+        );
+        let duration = ValidDuration::for_years(date(2024, 2, 29), 1).unwrap();
+        assert_eq!(duration.to, Some(date(2025, 2, 28)));
+        let duration = ValidDuration::for_years(date(2025, 1, 1), 0).unwrap();
+        assert_eq!(duration.to, Some(date(2025, 1, 1)));
+        assert_eq!(
+            ValidDuration::for_years(date(2025, 1, 1), u32::MAX),
+            Err(DurationError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_valid_duration_boundaries_are_inclusive() {
+        let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2030, 1, 1).unwrap();
+        let day = TimeDelta::days(1);
+        let duration = ValidDuration::new(from, Some(to)).unwrap();
+        assert!(!duration.contains(from - day));
+        assert!(duration.contains(from));
+        assert!(duration.contains(to));
+        assert!(!duration.contains(to + day));
+        assert!(!duration.is_expired(from - day));
+        assert!(!duration.is_expired(to));
+        assert!(duration.is_expired(to + day));
+
+        let indefinite = ValidDuration::starting(from);
+        assert!(indefinite.contains(NaiveDate::MAX));
+        assert!(!indefinite.is_expired(NaiveDate::MAX));
+        assert!(!indefinite.contains(from - day));
+    }
+
+    #[test]
+    fn test_valid_duration_without_end_is_unambiguous() {
+        let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let to = NaiveDate::from_ymd_opt(2030, 1, 1).unwrap();
+        let digest = |duration: ValidDuration, next: Option<&str>, version| {
+            let mut hasher = Hasher::new(version);
+            duration.hash(&mut hasher);
+            if let Some(next) = next {
+                hasher.field(next);
+            }
+            hasher.finalize()
+        };
+        let indefinite =
+            |version| digest(ValidDuration::starting(from), Some("2030-01-01"), version);
+        let bounded = |version| digest(ValidDuration::new(from, Some(to)).unwrap(), None, version);
+        assert_ne!(indefinite(HashVersion::V2), bounded(HashVersion::V2));
+        // The ambiguity stays in V1 so that existing chains keep verifying
+        assert_eq!(indefinite(HashVersion::V1), bounded(HashVersion::V1));
     }
 
     #[test]
     fn test_attribute_creation_and_hashing() {
         let attr =
             Attribute::new("Company Owner".to_string(), "Owner of Super Company".to_string());
-        let mut hasher = Sha512::new();
+        let mut hasher = Hasher::new(HashVersion::LATEST);
         attr.hash(&mut hasher);
         let hash = hasher.finalize();
-        assert_eq!(hash.len(), 64);
+        assert_ne!(hash, Hash::default());
+    }
+
+    #[test]
+    fn test_seeded_issuers_are_reproducible() {
+        let seeded = || Issuer::new_with_rng("Seeded".to_string(), &mut StdRng::seed_from_u64(7));
+        let ((first, first_key), (second, second_key)) = (seeded(), seeded());
+        assert_eq!(first.verifying, second.verifying);
+        assert_eq!(first_key.to_bytes(), second_key.to_bytes());
+        // Only the UUIDs differ, the signatures don't depend on them
+        assert_ne!(first.uuid, second.uuid);
+        assert_eq!(Signer::sign(&first_key, b"message"), Signer::sign(&second_key, b"message"));
+
+        let key = SigningKey::from_bytes(&[9; 32]);
+        let issuer = Issuer::from_signing_key("Keyed".to_string(), &key);
+        assert_eq!(issuer.verifying, key.verifying_key());
     }
 
     #[test]
     fn test_credential_sign_and_verify() {
-        let (issuer, signing_key) = Issuer::new("Issuer A".to_string());
+        let signing_key = SigningKey::from_bytes(&[5; 32]);
+        let issuer = Issuer::from_signing_key("Issuer A".to_string(), &signing_key);
         let subject = Subject::new("Bob".to_string(), "Builder".to_string());
         let attribute = Attribute::new("Digital Identity".to_string(), "Bob Builder".to_string());
         let valid = ValidDuration::new(
             NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
             Some(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()),
-        );
-        let credential = Credential::new(attribute, issuer.clone(), subject, valid);
-        let signed = credential.sign(&signing_key, false);
+        )
+        .unwrap();
+        let credential = Credential::new(vec![attribute], issuer.clone(), subject, valid);
+        let signed = credential.sign(&signing_key, false, HashVersion::LATEST).unwrap();
         assert!(signed.verify(&issuer.verifying));
     }
 
@@ -257,22 +1063,22 @@ mod tests {
         let valid = ValidDuration::new(
             NaiveDate::from_ymd_opt(2022, 1, 1).unwrap(),
             Some(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
-        );
-        let credential = Credential::new(attribute, issuer, subject, valid);
-        let hash_issue = credential.hash(false);
-        let hash_revoke = credential.hash(true);
-        assert_ne!(hash_issue.0, hash_revoke.0);
+        )
+        .unwrap();
+        let credential = Credential::new(vec![attribute], issuer, subject, valid);
+        for version in [HashVersion::V1, HashVersion::V2] {
+            assert_ne!(credential.hash(false, version), credential.hash(true, version));
+        }
     }
 
     #[test]
     fn test_signed_credential_update_hash() {
         let data = [1u8; 64];
-        let hash = Hash(data);
-        let signed = SignedCredential::new(Hash(data), hash);
-        let mut hasher = Sha512::new();
+        let signed = SignedCredential::new(Hash::from(data), Sig(data));
+        let mut hasher = Hasher::new(HashVersion::LATEST);
         signed.update_hash(&mut hasher);
         let hash = hasher.finalize();
-        assert_eq!(hash.len(), 64);
+        assert_ne!(hash, Hash::default());
     }
 
     #[test]
@@ -283,5 +1089,528 @@ mod tests {
         assert_eq!(issuer.name, deserialized.name);
         assert_eq!(issuer.uuid, deserialized.uuid);
         assert_eq!(issuer.verifying.as_bytes(), deserialized.verifying.as_bytes());
+        assert!(!json.contains("previous_keys"));
+    }
+
+    #[test]
+    fn test_issuer_rotate_key() {
+        let (mut issuer, first) = Issuer::new("Rotating".into());
+        let unrotated = issuer.clone();
+        let second = SigningKey::from_bytes(&[8; 32]);
+        issuer.rotate_key(second.verifying_key());
+        assert_eq!(issuer.verifying, second.verifying_key());
+        assert_eq!(issuer.previous_keys, [first.verifying_key()]);
+        assert!(issuer.has_used_key(&first.verifying_key()));
+        assert!(!unrotated.has_used_key(&second.verifying_key()));
+
+        let json = serde_json::to_string(&issuer).unwrap();
+        let deserialized: Issuer = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.previous_keys, issuer.previous_keys);
+
+        // Rotating back to a former key still commits to the rotation history
+        let mut rotated_back = issuer.clone();
+        rotated_back.rotate_key(first.verifying_key());
+        let hash = |issuer: &Issuer| {
+            let mut hasher = Hasher::new(HashVersion::V2);
+            issuer.update_hash(&mut hasher);
+            hasher.finalize()
+        };
+        assert_ne!(hash(&rotated_back), hash(&unrotated));
+    }
+
+    #[test]
+    fn test_issuer_metadata() {
+        let issuer =
+            Issuer::from_signing_key("Registry".to_string(), &SigningKey::from_bytes(&[7; 32]));
+        let hash = |issuer: &Issuer, version: HashVersion| {
+            let mut hasher = Hasher::new(version);
+            issuer.update_hash(&mut hasher);
+            hasher.finalize()
+        };
+        // Issuers stored before the fields existed read back without them
+        let legacy = format!(
+            r#"{{"uuid":"{}","name":"Registry","verifying":"{}"}}"#,
+            issuer.uuid,
+            hex::encode(issuer.verifying.as_bytes())
+        );
+        let legacy: Issuer = serde_json::from_str(&legacy).unwrap();
+        assert_eq!((&legacy.url, &legacy.email, &legacy.country), (&None, &None, &None));
+        assert!(!serde_json::to_string(&legacy).unwrap().contains("url"));
+
+        let with = |url: Option<&str>, email: Option<&str>, country: Option<&str>| Issuer {
+            url: url.map(Into::into),
+            email: email.map(Into::into),
+            country: country.map(Into::into),
+            ..issuer.clone()
+        };
+        let described = with(Some("https://registry.example.org"), None, Some("PL"));
+        let json = serde_json::to_string(&described).unwrap();
+        let roundtrip: Issuer = serde_json::from_str(&json).unwrap();
+        assert_eq!(roundtrip.url.as_deref(), Some("https://registry.example.org"));
+        assert_eq!(roundtrip.country.as_deref(), Some("PL"));
+        assert!(!json.contains("email"));
+
+        for version in [HashVersion::V1, HashVersion::V2] {
+            let mut hasher = Hasher::new(version);
+            hasher.domain(domain::ISSUER);
+            hasher.update(issuer.uuid);
+            hasher.field(&issuer.name);
+            hasher.update(issuer.verifying);
+            assert_eq!(hash(&legacy, version), hasher.finalize());
+            assert_ne!(hash(&described, version), hash(&legacy, version));
+            assert_eq!(hash(&roundtrip, version), hash(&described, version));
+            // The same value in another field hashes differently
+            let value = Some("x");
+            assert_ne!(
+                hash(&with(value, None, None), version),
+                hash(&with(None, value, None), version)
+            );
+            assert_ne!(
+                hash(&with(None, value, None), version),
+                hash(&with(None, None, value), version)
+            );
+        }
+    }
+
+    #[test]
+    fn test_issuer_rename() {
+        let (mut issuer, _) = Issuer::new("Registry of Lower Town".into());
+        let subject = Subject::new("Alice".into(), "Doe".into());
+        let attribute = Attribute::new("degree".into(), "PhD".into());
+        let duration = ValidDuration::starting(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        let before = Credential::new(vec![attribute], issuer.clone(), subject, duration);
+        let hash = before.hash(false, HashVersion::V2);
+
+        issuer.rename("Town Registry".into());
+        issuer.rename("Regional Registry".into());
+        assert_eq!(issuer.name, "Regional Registry");
+        assert_eq!(issuer.former_names, ["Registry of Lower Town", "Town Registry"]);
+        // Credentials embed the issuer as it was, and keep their hash
+        let stored: Credential =
+            serde_json::from_str(&serde_json::to_string(&before).unwrap()).unwrap();
+        assert_eq!(stored.issuer.name, "Registry of Lower Town");
+        assert_eq!(stored.hash(false, HashVersion::V2), hash);
+
+        // Former names aren't committed to
+        let hash = |issuer: &Issuer| {
+            let mut hasher = Hasher::new(HashVersion::V2);
+            issuer.update_hash(&mut hasher);
+            hasher.finalize()
+        };
+        let forgotten = Issuer { former_names: Vec::new(), ..issuer.clone() };
+        assert_eq!(hash(&forgotten), hash(&issuer));
+    }
+
+    fn fixed_credential() -> Credential {
+        let mut issuer =
+            Issuer::from_signing_key("Registry".to_string(), &SigningKey::from_bytes(&[7; 32]));
+        issuer.uuid = Uuid::from_u128(2);
+        let subject = Subject {
+            uuid: Uuid::from_u128(3),
+            name: "Alice".to_string(),
+            surname: "Smith".to_string(),
+            extra: BTreeMap::new(),
+            verifying: None,
+            deleted: false,
+        };
+        let valid = ValidDuration::new(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            Some(NaiveDate::from_ymd_opt(2030, 1, 1).unwrap()),
+        )
+        .unwrap();
+        let attribute = Attribute::new("degree".to_string(), "PhD".to_string());
+        Credential {
+            uuid: Uuid::from_u128(1),
+            attributes: vec![attribute],
+            withheld: Vec::new(),
+            issuer,
+            subject: subject.into(),
+            valid_duration: valid,
+            issued_at: Credential::UNKNOWN_ISSUANCE,
+            supersedes: None,
+            evidence: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_credential_hash_vectors() {
+        let credential = fixed_credential();
+        let hex = |revoking, version| hex::encode(credential.hash(revoking, version).bytes);
+        assert_eq!(
+            hex(false, HashVersion::V1),
+            "c817daa1fe3c3452c0bb52576db1023f6bab63d10520d8badfd678d2a18616372b6774916c101f88e2b3c107d999f20c5c299c420d96c23eb9b3a27c6ca84a99"
+        );
+        assert_eq!(
+            hex(true, HashVersion::V1),
+            "ba5e9388e21c643b77768b83866204edd9c35a13bc4fc05cc66401a640563c5b961a61da86d2b94c0d25ac344d9e92a8d1f08ac8e30ad00c703fbb2d3f59c8ff"
+        );
+        assert_eq!(
+            hex(false, HashVersion::V2),
+            "ba2f3f693089de985fde5d0be883e95239d64447efdfaefbcfe8513d895fb206131ec03b3478e9804f3a6d5fa752f3093c0ff699d6b2180358df9a7534a34712"
+        );
+        assert_eq!(
+            hex(true, HashVersion::V2),
+            "ac834055129935101faa2dcb605870bb780d841fb929cb69ac9c4777b32a61d6efb8353b3ba56553aab9e205bdb82bdffaf912772b078e81f0e513f1056745dc"
+        );
+    }
+
+    #[test]
+    fn test_credential_hash_vectors_across_algorithms() {
+        let credential = fixed_credential();
+        let signing = SigningKey::from_bytes(&[7; 32]);
+        let hash =
+            |algorithm| credential.hash(false, HashFormat { version: HashVersion::V2, algorithm });
+        assert_eq!(
+            hex::encode(hash(Algorithm::Sha3_512).bytes),
+            "61e6b6437bd3870905f70a1da05c63c0e29140010e81cc3f754e27caf1c7a66926ef5389ab6514be9d1adcd5dc078de748a43568f4e25ced4d60ff3075467f6f"
+        );
+        assert_eq!(
+            hex::encode(hash(Algorithm::Blake3).bytes),
+            "7cbdeccc50df06c366b303ea714904affea56f5017957d4c653741b9cad64443a6ce086cdde381f9034abda1336b96c29a1ce86b36d73b8ef88c55fbd1c957be"
+        );
+        for algorithm in Algorithm::ALL {
+            let format = HashFormat { version: HashVersion::V2, algorithm };
+            let signed = credential.sign(&signing, false, format).unwrap();
+            assert_eq!(signed.credential.algorithm, algorithm);
+            // The signature covers the digest bytes whatever algorithm produced them
+            assert_eq!(signed.signature, Signer::sign(&signing, &hash(algorithm).bytes).into());
+            assert!(signed.verify(&credential.issuer.verifying));
+        }
+    }
+
+    #[test]
+    fn test_shifted_attribute_fields_hash_differently() {
+        let hash = |name: &str, value: &str, version| {
+            let mut credential = fixed_credential();
+            credential.attributes = vec![Attribute::new(name.to_string(), value.to_string())];
+            credential.hash(false, version)
+        };
+        assert_ne!(hash("ab", "c", HashVersion::V2), hash("a", "bc", HashVersion::V2));
+        // The ambiguity stays in V1 so that existing chains keep verifying
+        assert_eq!(hash("ab", "c", HashVersion::V1), hash("a", "bc", HashVersion::V1));
+    }
+
+    #[test]
+    fn test_attribute_order_is_significant() {
+        let degree = Attribute::new("degree".to_string(), "PhD".to_string());
+        let field = Attribute::new("field".to_string(), "Physics".to_string());
+        let hash = |attributes: &[&Attribute], version| {
+            let mut credential = fixed_credential();
+            credential.attributes = attributes.iter().map(|&a| a.clone()).collect();
+            credential.hash(false, version)
+        };
+        // Concatenated, these make the same bytes as the two attributes
+        let run_together = Attribute::new("degreePhD".to_string(), "fieldPhysics".to_string());
+        let mut with_schema = degree.clone();
+        with_schema.schema = Some("field".to_string());
+        let schema_field = Attribute::new("schema".to_string(), "field".to_string());
+        for version in [HashVersion::V1, HashVersion::V2] {
+            assert_eq!(hash(&[&degree], version), fixed_credential().hash(false, version));
+            assert_ne!(hash(&[&degree, &field], version), hash(&[&field, &degree], version));
+            assert_ne!(hash(&[&degree, &field], version), hash(&[&degree], version));
+            assert_ne!(hash(&[&degree, &field], version), hash(&[&run_together], version));
+            assert_ne!(
+                hash(&[&with_schema, &field], version),
+                hash(&[&degree, &schema_field], version)
+            );
+        }
+    }
+
+    #[test]
+    fn test_v4_commits_to_salted_attributes() {
+        let mut degree = Attribute::new("degree".to_string(), "PhD".to_string());
+        let field = Attribute::new("field".to_string(), "Physics".to_string());
+        let hash = |attributes: &[&Attribute]| {
+            let mut credential = fixed_credential();
+            credential.attributes = attributes.iter().map(|&a| a.clone()).collect();
+            credential.hash(false, HashVersion::V4)
+        };
+        // The commitments are sorted, so the order of the attributes no longer matters
+        assert_eq!(hash(&[&degree, &field]), hash(&[&field, &degree]));
+        let unsalted = hash(&[&degree, &field]);
+        degree.salt = Some(Salt([1; 32]));
+        assert_ne!(hash(&[&degree, &field]), unsalted);
+
+        // A commitment stands in for the attribute it commits to
+        let mut withheld = fixed_credential();
+        withheld.attributes = vec![field.clone()];
+        withheld.withheld = vec![degree.commitment(HashVersion::V4)];
+        assert_eq!(withheld.hash(false, HashVersion::V4), hash(&[&degree, &field]));
+    }
+
+    #[test]
+    fn test_subject_commitment() {
+        let plain = fixed_credential();
+        let subject = plain.subject.disclosed().unwrap().clone();
+        let mut private = fixed_credential();
+        let salt = private.commit_subject().unwrap();
+        assert!(private.commit_subject().is_none());
+        let CredentialSubject::Committed(commitment) = &private.subject else {
+            panic!("subject still disclosed");
+        };
+        assert!(commitment.opens_to(&subject, &salt));
+        assert!(!commitment.opens_to(&subject, &Salt([0; 32])));
+        let namesake = Subject { uuid: Uuid::from_u128(4), ..subject.clone() };
+        assert!(!commitment.opens_to(&namesake, &salt));
+        assert_ne!(private.hash(false, HashVersion::V4), plain.hash(false, HashVersion::V4));
+
+        let json = serde_json::to_string(&private).unwrap();
+        assert!(!json.contains(&subject.name) && !json.contains(&subject.surname));
+        let read: Credential = serde_json::from_str(&json).unwrap();
+        assert!(read.subject.disclosed().is_none());
+        assert_eq!(read.hash(false, HashVersion::V4), private.hash(false, HashVersion::V4));
+    }
+
+    #[test]
+    fn test_credential_with_single_attribute_deserializes() {
+        let credential = fixed_credential();
+        let mut json = serde_json::to_value(&credential).unwrap();
+        assert_eq!(json["attributes"], serde_json::json!([{ "name": "degree", "value": "PhD" }]));
+        let object = json.as_object_mut().unwrap();
+        let attributes = object.remove("attributes").unwrap();
+        object.insert("attribute".to_string(), attributes[0].clone());
+
+        let legacy: Credential = serde_json::from_value(json).unwrap();
+        assert_eq!(legacy.attributes.len(), 1);
+        assert_eq!(legacy.attributes[0].to_string(), "degree: PhD");
+        for version in [HashVersion::V1, HashVersion::V2] {
+            assert_eq!(legacy.hash(false, version), credential.hash(false, version));
+        }
+    }
+
+    #[test]
+    fn test_issued_at_is_hashed_from_v2() {
+        let legacy = fixed_credential();
+        assert!(!legacy.has_issuance_time());
+        let json = serde_json::to_value(&legacy).unwrap();
+        assert!(json.get("issued_at").is_none());
+
+        let mut dated = fixed_credential();
+        dated.issued_at = "2024-01-01T12:00:00Z".parse().unwrap();
+        let mut later = fixed_credential();
+        later.issued_at = "2024-01-01T12:00:01Z".parse().unwrap();
+        assert_ne!(dated.hash(false, HashVersion::V2), legacy.hash(false, HashVersion::V2));
+        assert_ne!(dated.hash(false, HashVersion::V2), later.hash(false, HashVersion::V2));
+        assert_eq!(dated.hash(false, HashVersion::V1), legacy.hash(false, HashVersion::V1));
+
+        let json = serde_json::to_string(&dated).unwrap();
+        assert!(json.contains("\"issued_at\":\"2024-01-01T12:00:00Z\""));
+        let restored: Credential = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.issued_at, dated.issued_at);
+        assert_eq!(restored.hash(false, HashVersion::V2), dated.hash(false, HashVersion::V2));
+    }
+
+    #[test]
+    fn test_supersedes_is_hashed() {
+        let original = fixed_credential();
+        let mut renewal = fixed_credential();
+        renewal.supersedes = Some(original.uuid);
+        for version in [HashVersion::V1, HashVersion::V2] {
+            assert_ne!(renewal.hash(false, version), original.hash(false, version));
+        }
+        assert!(!serde_json::to_string(&original).unwrap().contains("supersedes"));
+    }
+
+    #[test]
+    fn test_lineage_of_three_generations() {
+        let first = fixed_credential();
+        let mut second = fixed_credential();
+        second.uuid = Uuid::from_u128(10);
+        second.supersedes = Some(first.uuid);
+        let mut third = fixed_credential();
+        third.uuid = Uuid::from_u128(11);
+        third.supersedes = Some(second.uuid);
+        let mut unrelated = fixed_credential();
+        unrelated.uuid = Uuid::from_u128(12);
+        let credentials = [&third, &unrelated, &first, &second];
+
+        let uuids = |credential| {
+            lineage(credential, &credentials).unwrap().iter().map(|c| c.uuid).collect::<Vec<_>>()
+        };
+        for credential in [&first, &second, &third] {
+            assert_eq!(uuids(credential), [first.uuid, second.uuid, third.uuid]);
+        }
+        assert_eq!(uuids(&unrelated), [unrelated.uuid]);
+    }
+
+    #[test]
+    fn test_lineage_rejects_cycles() {
+        let mut first = fixed_credential();
+        let mut second = fixed_credential();
+        second.uuid = Uuid::from_u128(10);
+        second.supersedes = Some(first.uuid);
+        first.supersedes = Some(second.uuid);
+        let credentials = [&first, &second];
+        assert_eq!(lineage(&first, &credentials).err(), Some(LineageError::Cycle(first.uuid)));
+
+        let mut own = fixed_credential();
+        own.supersedes = Some(own.uuid);
+        assert_eq!(lineage(&own, &[&own]).err(), Some(LineageError::Cycle(own.uuid)));
+    }
+
+    #[test]
+    fn test_evidence_is_hashed_when_attached() {
+        let plain = fixed_credential();
+        let attach = |documents: &[&[u8]]| {
+            let mut credential = fixed_credential();
+            credential.evidence = documents
+                .iter()
+                .map(|document| {
+                    EvidenceRef::from_reader(
+                        "a.pdf".into(),
+                        "application/pdf".into(),
+                        &mut &document[..],
+                    )
+                    .unwrap()
+                })
+                .collect();
+            credential
+        };
+        // Without evidence the hash is the one from before evidence existed
+        for version in [HashVersion::V1, HashVersion::V2] {
+            assert_eq!(attach(&[]).hash(false, version), plain.hash(false, version));
+        }
+        let one = attach(&[b"transcript"]);
+        let two = attach(&[b"transcript", b"certificate"]);
+        let tampered = attach(&[b"transcripT"]);
+        for version in [HashVersion::V1, HashVersion::V2] {
+            assert_ne!(one.hash(false, version), plain.hash(false, version));
+            assert_ne!(one.hash(false, version), two.hash(false, version));
+            assert_ne!(one.hash(false, version), tampered.hash(false, version));
+        }
+        assert!(!serde_json::to_string(&plain).unwrap().contains("evidence"));
+    }
+
+    #[test]
+    fn test_attribute_schema_is_hashed() {
+        let plain = fixed_credential();
+        let mut validated = fixed_credential();
+        validated.attributes[0].schema = Some("https://schemas.example.org/degree".into());
+        let mut other = fixed_credential();
+        other.attributes[0].schema = Some("https://schemas.example.org/title".into());
+        for version in [HashVersion::V1, HashVersion::V2] {
+            assert_ne!(validated.hash(false, version), plain.hash(false, version));
+            assert_ne!(validated.hash(false, version), other.hash(false, version));
+        }
+        assert!(!serde_json::to_string(&plain).unwrap().contains("schema"));
+    }
+
+    #[test]
+    fn test_subject_extra_fields_are_hashed_in_order() {
+        let plain = fixed_credential();
+        let with_fields = |fields: &[(&str, &str)]| {
+            let mut credential = fixed_credential();
+            let CredentialSubject::Disclosed(subject) = &mut credential.subject else {
+                unreachable!("fixed credentials disclose their subject")
+            };
+            for (key, value) in fields {
+                subject.extra.insert(key.to_string(), value.to_string());
+            }
+            credential
+        };
+        let dob_first = with_fields(&[("dob", "1990-02-03"), ("email", "a@example.org")]);
+        let email_first = with_fields(&[("email", "a@example.org"), ("dob", "1990-02-03")]);
+        let shifted = with_fields(&[("dob", "1990-02-03e"), ("mail", "a@example.org")]);
+        for version in [HashVersion::V1, HashVersion::V2] {
+            assert_eq!(dob_first.hash(false, version), email_first.hash(false, version));
+            assert_ne!(dob_first.hash(false, version), plain.hash(false, version));
+            assert_ne!(dob_first.hash(false, version), shifted.hash(false, version));
+        }
+        let subject = dob_first.subject.disclosed().unwrap();
+        assert!(format!("{subject}").contains("\"dob\": \"1990-02-03\""));
+    }
+
+    #[test]
+    fn test_subject_matches() {
+        let mut subject = Subject::new("Łukasz".into(), "Żółkiewski".into());
+        subject.uuid = Uuid::parse_str("6f1c2d3e-0000-4000-8000-000000000000").unwrap();
+        for query in ["łukasz", "ŁUKASZ", "ukas", "żółk", "Łukasz Żół", "6F1C", "6f1c2d3e-0000"]
+        {
+            assert!(subject.matches(query, false), "{query}");
+        }
+        for query in ["", "Lukasz", "Nowak", "2d3e"] {
+            assert!(!subject.matches(query, false), "{query}");
+        }
+        assert!(subject.matches("łukasz ŻÓŁKIEWSKI", true));
+        assert!(subject.matches("6f1c2d3e-0000-4000-8000-000000000000", true));
+        assert!(!subject.matches("Łukasz", true));
+        assert!(!subject.matches("6f1c", true));
+    }
+
+    #[test]
+    fn test_subject_duplicates() {
+        let subject = |name: &str, surname: &str, dob: Option<&str>| {
+            let mut subject = Subject::new(name.into(), surname.into());
+            if let Some(dob) = dob {
+                subject.extra.insert("dob".into(), dob.into());
+            }
+            subject
+        };
+        let john = subject("John", "Doe", Some("1990-02-03"));
+        assert_eq!(subject("  john ", "DOE", None).normalized_name(), "john doe");
+        assert!(john.is_likely_duplicate(&subject("JOHN", "Doe", None)));
+        let twin = subject("John", " Doe", Some("1990-02-03"));
+        assert!(john.is_likely_duplicate(&twin));
+        assert_eq!(john.compare_extra(&twin), (vec!["dob"], vec![]));
+        // A namesake born on another day is someone else
+        let namesake = subject("John", "Doe", Some("1975-11-30"));
+        assert!(!john.is_likely_duplicate(&namesake));
+        assert_eq!(john.compare_extra(&namesake), (vec![], vec!["dob"]));
+        assert!(!john.is_likely_duplicate(&subject("John", "Does", None)));
+    }
+
+    #[test]
+    fn test_subject_consent() {
+        let mut credential = fixed_credential();
+        let subject_key = SigningKey::from_bytes(&[9; 32]);
+        let other_key = SigningKey::from_bytes(&[10; 32]);
+        if let CredentialSubject::Disclosed(subject) = &mut credential.subject {
+            subject.verifying = Some(subject_key.verifying_key());
+        }
+        let issuer_key = SigningKey::from_bytes(&[7; 32]);
+        let signed = credential.sign(&issuer_key, false, HashVersion::V2).unwrap();
+        assert_eq!(signed.consent_status(&credential.subject), ConsentStatus::Absent);
+
+        let mut consented = signed.clone();
+        consented.add_consent(&subject_key).unwrap();
+        assert_eq!(consented.consent_status(&credential.subject), ConsentStatus::Signed);
+        assert!(consented.verify(&issuer_key.verifying_key()));
+
+        let mut forged = signed.clone();
+        forged.add_consent(&other_key).unwrap();
+        assert_eq!(forged.consent_status(&credential.subject), ConsentStatus::Invalid);
+        // A subject without a key can't have consented
+        assert_eq!(consented.consent_status(&fixed_credential().subject), ConsentStatus::Invalid);
+
+        // The consent is anchored with the block, signed credentials without it keep their hashes
+        let hash = |signed: &SignedCredential| {
+            let mut hasher = Hasher::new(HashVersion::V2);
+            signed.update_hash(&mut hasher);
+            hasher.finalize()
+        };
+        let mut legacy = Hasher::new(HashVersion::V2);
+        legacy.domain(domain::SIGNED_CREDENTIAL);
+        legacy.update(signed.credential.bytes);
+        legacy.update(&signed.signature);
+        assert_eq!(hash(&signed), legacy.finalize());
+        assert_ne!(hash(&consented), hash(&signed));
+        assert_ne!(hash(&consented), hash(&forged));
+        assert!(!serde_json::to_string(&signed).unwrap().contains("consent"));
+    }
+
+    #[test]
+    fn test_subject_without_extra_fields_deserializes() {
+        // Subjects stored before further fields existed keep their hashes
+        let legacy: Subject = serde_json::from_str(
+            r#"{"uuid":"00000000-0000-0000-0000-000000000003","name":"Alice","surname":"Smith"}"#,
+        )
+        .unwrap();
+        assert!(legacy.extra.is_empty());
+        let plain = fixed_credential();
+        let credential = Credential { subject: legacy.into(), ..fixed_credential() };
+        for version in [HashVersion::V1, HashVersion::V2] {
+            assert_eq!(credential.hash(false, version), plain.hash(false, version));
+        }
+        assert!(!serde_json::to_string(&credential.subject).unwrap().contains("extra"));
     }
 }
@@ -0,0 +1,326 @@
+//! Records read in bulk from CSV files with a header row, one row at a time so that large files
+//! are never held in memory whole.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::Read;
+
+use chrono::NaiveDate;
+use csv::{ReaderBuilder, StringRecord, StringRecordsIntoIter, Trim};
+
+use crate::credential::{Attribute, Subject, ValidDuration};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// A column the mapping names isn't in the header row
+    MissingColumn(String),
+    /// The file isn't valid CSV, at the given line if known
+    Csv { line: Option<u64>, error: String },
+}
+
+impl Display for ImportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingColumn(column) => write!(f, "no column '{column}' in the header row"),
+            Self::Csv { line: Some(line), error } => write!(f, "line {line}: {error}"),
+            Self::Csv { line: None, error } => f.write_str(error),
+        }
+    }
+}
+
+impl Error for ImportError {}
+
+impl From<csv::Error> for ImportError {
+    fn from(error: csv::Error) -> Self {
+        let line = error.position().map(csv::Position::line);
+        let error = match error.kind() {
+            csv::ErrorKind::UnequalLengths { expected_len, len, .. } =>
+                format!("expected {expected_len} fields like the header row, found {len}"),
+            _ => error.to_string(),
+        };
+        Self::Csv { line, error }
+    }
+}
+
+/// Finds the column with the given header
+fn column(headers: &StringRecord, name: &str) -> Result<usize, ImportError> {
+    headers.iter().position(|header| header == name).ok_or(ImportError::MissingColumn(name.into()))
+}
+
+/// Finds the column with the given header, if the file has it
+fn optional_column(headers: &StringRecord, name: &str) -> Option<usize> {
+    headers.iter().position(|header| header == name)
+}
+
+/// Columns subjects are read from, by header
+#[derive(Debug, Clone)]
+pub struct SubjectColumns {
+    pub name: String,
+    pub surname: String,
+    /// Columns read as further fields telling subjects apart, keyed by their header
+    pub fields: Vec<String>,
+}
+
+/// Row read as a subject, or with the reason it can't be one
+#[derive(Debug)]
+pub struct SubjectRow {
+    /// Line of the file the row starts on
+    pub line: u64,
+    pub subject: Result<Subject, String>,
+}
+
+/// Reads the rows of a CSV file as subjects, in order
+pub struct SubjectReader<R> {
+    records: StringRecordsIntoIter<R>,
+    name: usize,
+    surname: usize,
+    fields: Vec<(String, usize)>,
+}
+
+impl<R: Read> SubjectReader<R> {
+    /// Reads the header row and finds the mapped columns in it
+    ///
+    /// # Errors
+    /// Returns [`ImportError::MissingColumn`] if a mapped column isn't in the header row, and
+    /// [`ImportError::Csv`] if it can't be read.
+    pub fn new(reader: R, columns: &SubjectColumns) -> Result<Self, ImportError> {
+        let mut reader = ReaderBuilder::new().trim(Trim::All).from_reader(reader);
+        let headers = reader.headers()?;
+        let fields = columns
+            .fields
+            .iter()
+            .map(|field| Ok((field.clone(), column(headers, field)?)))
+            .collect::<Result<_, ImportError>>()?;
+        Ok(Self {
+            name: column(headers, &columns.name)?,
+            surname: column(headers, &columns.surname)?,
+            fields,
+            records: reader.into_records(),
+        })
+    }
+
+    fn subject(&self, record: &StringRecord) -> Result<Subject, String> {
+        let name = record.get(self.name).unwrap_or_default();
+        let surname = record.get(self.surname).unwrap_or_default();
+        if name.is_empty() {
+            return Err("empty name".into());
+        }
+        if surname.is_empty() {
+            return Err("empty surname".into());
+        }
+        let mut subject = Subject::new(name.into(), surname.into());
+        for (field, index) in &self.fields {
+            // Rows may leave out fields they don't know
+            match record.get(*index) {
+                Some(value) if !value.is_empty() => {
+                    subject.extra.insert(field.clone(), value.into());
+                },
+                _ => {},
+            }
+        }
+        Ok(subject)
+    }
+}
+
+impl<R: Read> Iterator for SubjectReader<R> {
+    /// Rows that aren't valid CSV end the import, as the rest of the file can't be trusted to be
+    /// read right either
+    type Item = Result<SubjectRow, ImportError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match self.records.next()? {
+            Ok(record) => record,
+            Err(error) => return Some(Err(error.into())),
+        };
+        let line = record.position().map_or(0, csv::Position::line);
+        Some(Ok(SubjectRow { line, subject: self.subject(&record) }))
+    }
+}
+
+/// How an issuance row names the credential's subject
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubjectRef {
+    /// Index, UUID or UUID prefix of the subject
+    Id(String),
+    /// Name and surname of the subject, which have to match a single subject
+    Name { name: String, surname: String },
+}
+
+/// Credential a row of an issuance file asks for
+#[derive(Debug, Clone)]
+pub struct Issuance {
+    pub subject: SubjectRef,
+    pub attribute: Attribute,
+    pub valid_duration: ValidDuration,
+}
+
+/// Row read as an issuance, or with the reason it can't be one
+#[derive(Debug)]
+pub struct IssuanceRow {
+    /// Line of the file the row starts on
+    pub line: u64,
+    pub issuance: Result<Issuance, String>,
+}
+
+/// Reads the rows of a CSV file as credentials to issue, in order. The subject is given in a
+/// `subject` column or in `name` and `surname` columns, followed by `attribute`, `value`, `from`
+/// and an optional `to` column, dates being written as `YYYY-MM-DD`.
+pub struct IssuanceReader<R> {
+    records: StringRecordsIntoIter<R>,
+    subject: Option<usize>,
+    name: Option<(usize, usize)>,
+    attribute: usize,
+    value: usize,
+    from: usize,
+    to: Option<usize>,
+}
+
+impl<R: Read> IssuanceReader<R> {
+    /// Reads the header row and finds the columns in it
+    ///
+    /// # Errors
+    /// Returns [`ImportError::MissingColumn`] if a column is missing from the header row, and
+    /// [`ImportError::Csv`] if it can't be read.
+    pub fn new(reader: R) -> Result<Self, ImportError> {
+        let mut reader = ReaderBuilder::new().trim(Trim::All).from_reader(reader);
+        let headers = reader.headers()?;
+        let subject = optional_column(headers, "subject");
+        let name = match subject {
+            Some(_) => optional_column(headers, "name").zip(optional_column(headers, "surname")),
+            None => Some((column(headers, "name")?, column(headers, "surname")?)),
+        };
+        Ok(Self {
+            subject,
+            name,
+            attribute: column(headers, "attribute")?,
+            value: column(headers, "value")?,
+            from: column(headers, "from")?,
+            to: optional_column(headers, "to"),
+            records: reader.into_records(),
+        })
+    }
+
+    fn issuance(&self, record: &StringRecord) -> Result<Issuance, String> {
+        let cell = |index: usize| record.get(index).unwrap_or_default();
+        let subject = match (self.subject.map(cell), self.name) {
+            (Some(subject), _) if !subject.is_empty() => SubjectRef::Id(subject.into()),
+            (_, Some((name, surname))) if !cell(name).is_empty() && !cell(surname).is_empty() =>
+                SubjectRef::Name { name: cell(name).into(), surname: cell(surname).into() },
+            _ => return Err("no subject given".into()),
+        };
+        let name = cell(self.attribute);
+        if name.is_empty() {
+            return Err("empty attribute name".into());
+        }
+        let date = |date: &str| {
+            date.parse::<NaiveDate>()
+                .map_err(|_| format!("invalid date '{date}', expected YYYY-MM-DD"))
+        };
+        let from = date(cell(self.from))?;
+        let to = match self.to.map(cell) {
+            Some(to) if !to.is_empty() => Some(date(to)?),
+            _ => None,
+        };
+        Ok(Issuance {
+            subject,
+            attribute: Attribute::new(name.into(), cell(self.value).into()),
+            valid_duration: ValidDuration::new(from, to).map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+impl<R: Read> Iterator for IssuanceReader<R> {
+    /// Rows that aren't valid CSV end the import, like [`SubjectReader`]'s
+    type Item = Result<IssuanceRow, ImportError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match self.records.next()? {
+            Ok(record) => record,
+            Err(error) => return Some(Err(error.into())),
+        };
+        let line = record.position().map_or(0, csv::Position::line);
+        Some(Ok(IssuanceRow { line, issuance: self.issuance(&record) }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(fields: &[&str]) -> SubjectColumns {
+        SubjectColumns {
+            name: "first_name".into(),
+            surname: "last_name".into(),
+            fields: fields.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn test_subject_columns_mapped_by_header() {
+        let csv =
+            "id,last_name,first_name,dob\n1,Smith,Alice,1990-04-01\n2, Doe ,Bob,\n3,,Carol,\n";
+        let rows: Vec<_> = SubjectReader::new(csv.as_bytes(), &columns(&["dob"]))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(rows.iter().map(|row| row.line).collect::<Vec<_>>(), [2, 3, 4]);
+        let alice = rows[0].subject.as_ref().unwrap();
+        assert_eq!((alice.name.as_str(), alice.surname.as_str()), ("Alice", "Smith"));
+        assert_eq!(alice.extra["dob"], "1990-04-01");
+        let bob = rows[1].subject.as_ref().unwrap();
+        assert_eq!(bob.surname, "Doe");
+        assert!(bob.extra.is_empty());
+        assert_eq!(rows[2].subject.as_ref().unwrap_err(), "empty surname");
+
+        let error = SubjectReader::new(csv.as_bytes(), &columns(&["email"])).err();
+        assert_eq!(error, Some(ImportError::MissingColumn("email".into())));
+    }
+
+    #[test]
+    fn test_malformed_row_reports_its_line() {
+        let csv = "first_name,last_name\nAlice,Smith\nBob,Doe,extra\nCarol,Jones\n";
+        let mut rows = SubjectReader::new(csv.as_bytes(), &columns(&[])).unwrap();
+        assert!(rows.next().unwrap().is_ok());
+        let error = rows.next().unwrap().unwrap_err();
+        assert_eq!(error, ImportError::Csv {
+            line: Some(3),
+            error: "expected 2 fields like the header row, found 3".into()
+        });
+        assert_eq!(error.to_string(), "line 3: expected 2 fields like the header row, found 3");
+    }
+
+    #[test]
+    fn test_issuance_rows() {
+        let csv = "subject,name,surname,attribute,value,from,to\n0,,,degree,MSc,2024-01-01,\n,\
+                   Alice,Smith,degree,PhD,2024-01-01,2028-12-31\n,Bob,,degree,BSc,2024-01-01,\n1,,\
+                   ,,BSc,2024-01-01,\n1,,,degree,BSc,2024-13-01,\n1,,,degree,BSc,2024-06-01,\
+                   2024-01-01\n";
+        let rows: Vec<_> =
+            IssuanceReader::new(csv.as_bytes()).unwrap().map(Result::unwrap).collect();
+        assert_eq!(rows.iter().map(|row| row.line).collect::<Vec<_>>(), [2, 3, 4, 5, 6, 7]);
+        let first = rows[0].issuance.as_ref().unwrap();
+        assert_eq!(first.subject, SubjectRef::Id("0".into()));
+        assert_eq!(
+            (first.attribute.name.as_str(), first.attribute.value.as_str()),
+            ("degree", "MSc")
+        );
+        assert_eq!(first.valid_duration.to, None);
+        let second = rows[1].issuance.as_ref().unwrap();
+        assert_eq!(second.subject, SubjectRef::Name {
+            name: "Alice".into(),
+            surname: "Smith".into()
+        });
+        assert!(second.valid_duration.to.is_some());
+        assert_eq!(rows[2].issuance.as_ref().unwrap_err(), "no subject given");
+        assert_eq!(rows[3].issuance.as_ref().unwrap_err(), "empty attribute name");
+        assert_eq!(
+            rows[4].issuance.as_ref().unwrap_err(),
+            "invalid date '2024-13-01', expected YYYY-MM-DD"
+        );
+        assert!(rows[5].issuance.is_err());
+
+        // Without a subject column, subjects are named
+        let error = IssuanceReader::new("name,attribute,value,from\n".as_bytes()).err();
+        assert_eq!(error, Some(ImportError::MissingColumn("surname".into())));
+    }
+}
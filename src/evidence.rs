@@ -0,0 +1,126 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+
+use crate::hash::{Hash, Hasher, domain};
+
+/// Supporting document bound to a credential by its digest, e.g. a scanned certificate or an exam
+/// transcript, without the document itself being stored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvidenceRef {
+    /// Name of the document's file when it was attached, only a hint for people
+    pub filename: String,
+    pub media_type: String,
+    pub length: u64,
+    /// SHA-512 digest of the document, whatever algorithm the chain hashes with
+    pub sha512: Hash,
+}
+
+impl EvidenceRef {
+    /// Reference to the document read from `reader`
+    ///
+    /// # Errors
+    /// Fails if the document can't be read.
+    pub fn from_reader(
+        filename: String, media_type: String, reader: &mut impl io::Read,
+    ) -> io::Result<Self> {
+        let mut digest = Sha512::new();
+        let length = io::copy(reader, &mut digest)?;
+        Ok(Self { filename, media_type, length, sha512: digest.finalize().into() })
+    }
+
+    /// Reference to the document in the file at `path`, its media type guessed from the extension
+    ///
+    /// # Errors
+    /// Fails if the file can't be read.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let filename =
+            path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let media_type = media_type(path).to_string();
+        Self::from_reader(filename, media_type, &mut File::open(path)?)
+    }
+
+    /// Whether the document read from `reader` is the one referenced
+    ///
+    /// # Errors
+    /// Fails if the document can't be read.
+    pub fn matches(&self, reader: &mut impl io::Read) -> io::Result<bool> {
+        let presented = Self::from_reader(String::new(), String::new(), reader)?;
+        Ok(presented.length == self.length && presented.sha512 == self.sha512)
+    }
+
+    pub fn update_hash(&self, hasher: &mut Hasher) {
+        hasher.domain(domain::EVIDENCE);
+        hasher.prefixed(&self.filename);
+        hasher.prefixed(&self.media_type);
+        hasher.update(self.length.to_le_bytes());
+        hasher.update(self.sha512.bytes);
+    }
+}
+
+/// Media type of the common kinds of supporting documents, by file extension
+fn media_type(path: &Path) -> &'static str {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("pdf") => "application/pdf",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("tif" | "tiff") => "image/tiff",
+        Some("txt") => "text/plain",
+        Some("json") => "application/json",
+        Some("xml") => "application/xml",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::HashVersion;
+
+    #[test]
+    fn test_evidence_from_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("Transcript.PDF");
+        std::fs::write(&path, b"abc").unwrap();
+        let evidence = EvidenceRef::from_file(&path).unwrap();
+        assert_eq!(evidence.filename, "Transcript.PDF");
+        assert_eq!(evidence.media_type, "application/pdf");
+        assert_eq!(evidence.length, 3);
+        // SHA-512 test vector of "abc"
+        assert_eq!(
+            evidence.sha512.to_string(),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+        assert!(evidence.matches(&mut &b"abc"[..]).unwrap());
+        assert!(!evidence.matches(&mut &b"abd"[..]).unwrap());
+        assert!(EvidenceRef::from_file(&dir.path().join("missing.pdf")).is_err());
+    }
+
+    #[test]
+    fn test_evidence_fields_are_hashed() {
+        let evidence =
+            EvidenceRef::from_reader("a.pdf".into(), "application/pdf".into(), &mut &b"abc"[..])
+                .unwrap();
+        let hash = |evidence: &EvidenceRef, version| {
+            let mut hasher = Hasher::new(version);
+            evidence.update_hash(&mut hasher);
+            hasher.finalize()
+        };
+        let renamed = EvidenceRef { filename: "b.pdf".into(), ..evidence.clone() };
+        let retyped = EvidenceRef { media_type: "text/plain".into(), ..evidence.clone() };
+        let shifted = EvidenceRef {
+            filename: "a.pdfapplication/".into(),
+            media_type: "pdf".into(),
+            ..evidence.clone()
+        };
+        for version in [HashVersion::V1, HashVersion::V2] {
+            assert_ne!(hash(&evidence, version), hash(&renamed, version));
+            assert_ne!(hash(&evidence, version), hash(&retyped, version));
+            assert_ne!(hash(&evidence, version), hash(&shifted, version));
+        }
+    }
+}
@@ -0,0 +1,488 @@
+//! Standalone credential files, holding everything a third party needs to verify a credential
+//! besides the chain itself, and chain files to move or publish a whole chain.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::VerifyingKey;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::anchor::{AnchorError, ChainAnchor};
+use crate::blockchain::{Block, Blockchain};
+use crate::credential::{Credential, Issuer, SignedCredential, verifying_key_serde};
+use crate::hash::Hash;
+use crate::signer::BlockSigner;
+use crate::status_list::status_index;
+
+/// Version of the format exports are written in
+pub const EXCHANGE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExchangeError {
+    /// The file isn't JSON of an exported credential
+    Parse(String),
+    /// The file was written in a format version this version can't read
+    UnsupportedVersion(u64),
+    /// The file's description of its chain doesn't match the chain it holds
+    Inconsistent(String),
+}
+
+impl Display for ExchangeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(error) => write!(f, "not an exported credential: {error}"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "exported credential format version {version} isn't supported, only version \
+                 {EXCHANGE_VERSION} is"
+            ),
+            Self::Inconsistent(error) => write!(f, "export doesn't match its chain: {error}"),
+        }
+    }
+}
+
+impl Error for ExchangeError {}
+
+/// Why an export doesn't hold together, or doesn't belong to the chain it's checked against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportMismatch {
+    /// Exported from another chain than the one it's checked against
+    OtherChain { exported: Option<Uuid>, local: Option<Uuid> },
+    /// The embedded issuer key isn't the key of the credential's issuer
+    IssuerKey,
+    /// An embedded hash isn't the credential's issuance or revocation hash
+    Hash { revoking: bool },
+    /// An embedded signature doesn't check against the embedded issuer key
+    Signature { revoking: bool },
+    /// The chain records other keys for the credential's issuer
+    UnrecordedKey { issuer: Uuid },
+}
+
+impl Display for ExportMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let entry = |revoking: &bool| if *revoking { "revocation" } else { "issuance" };
+        let chain = |uuid: &Option<Uuid>| uuid.map_or("no chain ID".into(), |u| u.to_string());
+        match self {
+            Self::OtherChain { exported, local } => write!(
+                f,
+                "exported from chain {}, not this chain ({})",
+                chain(exported),
+                chain(local)
+            ),
+            Self::IssuerKey => f.write_str(
+                "the embedded issuer key isn't the credential's issuer key, potential forgery",
+            ),
+            Self::Hash { revoking } => write!(
+                f,
+                "the embedded {} hash isn't the credential's, potential forgery",
+                entry(revoking)
+            ),
+            Self::Signature { revoking } => write!(
+                f,
+                "the embedded {} signature doesn't check against the embedded issuer key, \
+                 potential forgery",
+                entry(revoking)
+            ),
+            Self::UnrecordedKey { issuer } => write!(
+                f,
+                "the embedded key of issuer {issuer} isn't one the chain records for it, \
+                 potential forgery"
+            ),
+        }
+    }
+}
+
+impl Error for ExportMismatch {}
+
+/// Credential exported with the issuer's signatures over its issuance and revocation hashes, the
+/// issuer key they check against, and the chain it was exported from as it stood. Displayed as
+/// indented JSON, the same way each time for the same content, and conventionally written to a
+/// `.attest.json` file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CredentialExport {
+    /// Format version, [`EXCHANGE_VERSION`] for files written by this version
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    /// UUID of the chain's genesis block, none for chains without one
+    pub chain: Option<Uuid>,
+    /// Hash of the chain's last block when exported, none for an empty chain
+    pub head: Option<Hash>,
+    /// Key the issuer signed the credential with
+    #[serde(with = "verifying_key_serde")]
+    pub issuer_key: VerifyingKey,
+    pub credential: Credential,
+    pub issuance: SignedCredential,
+    pub revocation: SignedCredential,
+    /// Index of the credential's bit in the chain's status lists, none if the chain doesn't
+    /// issue it yet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_index: Option<u64>,
+}
+
+impl CredentialExport {
+    #[must_use]
+    pub fn new(
+        credential: Credential, issuance: SignedCredential, revocation: SignedCredential,
+        blockchain: &Blockchain,
+    ) -> Self {
+        Self {
+            version: EXCHANGE_VERSION,
+            exported_at: Utc::now(),
+            chain: blockchain.id(),
+            head: blockchain.tip().map(|block| block.hash().clone()),
+            issuer_key: credential.issuer.verifying,
+            status_index: status_index(blockchain, &issuance.credential),
+            credential,
+            issuance,
+            revocation,
+        }
+    }
+
+    /// Reads an export as written by its [`Display`] implementation
+    ///
+    /// # Errors
+    /// Returns [`ExchangeError::UnsupportedVersion`] for exports in another format version, and
+    /// [`ExchangeError::Parse`] if the JSON isn't an export at all.
+    pub fn from_json(json: &str) -> Result<Self, ExchangeError> { from_versioned_json(json) }
+}
+
+/// Reads a file written in [`EXCHANGE_VERSION`], whose version is checked first as the other
+/// fields may not parse in another version
+pub(crate) fn from_versioned_json<T: DeserializeOwned>(json: &str) -> Result<T, ExchangeError> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| ExchangeError::Parse(e.to_string()))?;
+    let version = value.get("version").and_then(serde_json::Value::as_u64);
+    match version {
+        Some(version) if version == u64::from(EXCHANGE_VERSION) => {},
+        Some(version) => return Err(ExchangeError::UnsupportedVersion(version)),
+        None => return Err(ExchangeError::Parse("missing format version".into())),
+    }
+    serde_json::from_value(value).map_err(|e| ExchangeError::Parse(e.to_string()))
+}
+
+impl CredentialExport {
+    /// Checks that the export holds together and belongs to the chain: exported from it, with
+    /// the credential's own hashes signed by the embedded issuer key, which has to be one the
+    /// chain records for the issuer if it knows the issuer at all. Doesn't check whether the
+    /// chain holds the credential.
+    ///
+    /// # Errors
+    /// Returns the first mismatch found.
+    pub fn check(&self, blockchain: &Blockchain) -> Result<(), ExportMismatch> {
+        let local = blockchain.id();
+        if self.chain != local {
+            return Err(ExportMismatch::OtherChain { exported: self.chain, local });
+        }
+        if self.issuer_key != self.credential.issuer.verifying {
+            return Err(ExportMismatch::IssuerKey);
+        }
+        for (revoking, signed) in [(false, &self.issuance), (true, &self.revocation)] {
+            if signed.credential != self.credential.hash(revoking, blockchain.format()) {
+                return Err(ExportMismatch::Hash { revoking });
+            }
+            if !signed.verify(&self.issuer_key) {
+                return Err(ExportMismatch::Signature { revoking });
+            }
+        }
+        let issuer = self.credential.issuer.uuid;
+        let recorded = blockchain.issuer_keys(issuer);
+        if !recorded.is_empty() && !recorded.contains(&self.issuer_key) {
+            return Err(ExportMismatch::UnrecordedKey { issuer });
+        }
+        Ok(())
+    }
+}
+
+impl Display for CredentialExport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(&self).unwrap())
+    }
+}
+
+/// Whole chain, genesis block included, along with a description of it to check it against.
+/// Displayed as indented JSON and conventionally written to a `.export.json` file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChainExport {
+    /// Format version, [`EXCHANGE_VERSION`] for files written by this version
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    /// UUID of the chain's genesis block, none for chains without one
+    pub chain: Option<Uuid>,
+    /// Number of blocks after the genesis block
+    pub blocks: u64,
+    /// Hash of the chain's last block, none for an empty chain
+    pub head: Option<Hash>,
+    pub blockchain: Blockchain,
+}
+
+impl ChainExport {
+    #[must_use]
+    pub fn new(blockchain: Blockchain) -> Self {
+        Self {
+            version: EXCHANGE_VERSION,
+            exported_at: Utc::now(),
+            chain: blockchain.id(),
+            blocks: blockchain.len() as u64,
+            head: blockchain.tip().map(|block| block.hash().clone()),
+            blockchain,
+        }
+    }
+
+    /// Reads an export as written by its [`Display`] implementation. The chain itself isn't
+    /// validated.
+    ///
+    /// # Errors
+    /// Returns [`ExchangeError::UnsupportedVersion`] for exports in another format version,
+    /// [`ExchangeError::Parse`] if the JSON isn't an export at all and
+    /// [`ExchangeError::Inconsistent`] if the chain isn't the one the export describes.
+    pub fn from_json(json: &str) -> Result<Self, ExchangeError> {
+        let export: Self = from_versioned_json(json)?;
+        let head = ChainHead {
+            version: export.version,
+            chain: export.chain,
+            blocks: export.blocks,
+            head: export.head.clone(),
+            anchor: None,
+        };
+        head.check(&export.blockchain)?;
+        Ok(export)
+    }
+}
+
+impl Display for ChainExport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(&self).unwrap())
+    }
+}
+
+/// Description of a chain's head, for nodes reading the chain block by block to tell which blocks
+/// they're missing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainHead {
+    /// Format version, [`EXCHANGE_VERSION`] for heads written by this version
+    pub version: u32,
+    /// UUID of the chain's genesis block, none for chains without one
+    pub chain: Option<Uuid>,
+    /// Number of blocks after the genesis block
+    pub blocks: u64,
+    /// Hash of the chain's last block, none for an empty chain
+    pub head: Option<Hash>,
+    /// The head anchored by a chain authority, for copies of the chain on hosts that aren't
+    /// trusted themselves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<ChainAnchor>,
+}
+
+impl ChainHead {
+    #[must_use]
+    pub fn new(blockchain: &Blockchain) -> Self {
+        Self {
+            version: EXCHANGE_VERSION,
+            chain: blockchain.id(),
+            blocks: blockchain.len() as u64,
+            head: blockchain.tip().map(|block| block.hash().clone()),
+            anchor: None,
+        }
+    }
+
+    /// Head anchored as the issuer
+    ///
+    /// # Errors
+    /// See [`ChainAnchor::new`].
+    pub fn signed(
+        blockchain: &Blockchain, issuer: &Issuer, signer: &dyn BlockSigner,
+    ) -> Result<Self, AnchorError> {
+        let anchor = ChainAnchor::new(blockchain, issuer, signer)?;
+        Ok(Self { anchor: Some(anchor), ..Self::new(blockchain) })
+    }
+
+    /// Reads a head as served, checking its anchor's signature if it has one
+    ///
+    /// # Errors
+    /// Returns [`ExchangeError::UnsupportedVersion`] for heads in another format version,
+    /// [`ExchangeError::Parse`] if the JSON isn't a head at all and
+    /// [`ExchangeError::Inconsistent`] if its anchor doesn't check or anchors another head.
+    pub fn from_json(json: &str) -> Result<Self, ExchangeError> {
+        let head: Self = from_versioned_json(json)?;
+        let Some(anchor) = &head.anchor else {
+            return Ok(head);
+        };
+        anchor.check_signature().map_err(|e| ExchangeError::Inconsistent(e.to_string()))?;
+        if anchor.chain != head.chain
+            || Some(&anchor.head) != head.head.as_ref()
+            || anchor.height + 1 != head.blocks
+        {
+            return Err(ExchangeError::Inconsistent("the head's anchor is of another head".into()));
+        }
+        Ok(head)
+    }
+
+    /// Checks that the chain is the one described
+    ///
+    /// # Errors
+    /// Returns [`ExchangeError::Inconsistent`] with the first difference found.
+    pub fn check(&self, blockchain: &Blockchain) -> Result<(), ExchangeError> {
+        if self.chain != blockchain.id() {
+            return Err(ExchangeError::Inconsistent("chain ID differs".into()));
+        }
+        if self.blocks != blockchain.len() as u64 {
+            return Err(ExchangeError::Inconsistent(format!(
+                "{} blocks described, {} held",
+                self.blocks,
+                blockchain.len()
+            )));
+        }
+        if self.head.as_ref() != blockchain.tip().map(Block::hash) {
+            return Err(ExchangeError::Inconsistent("head hash differs".into()));
+        }
+        let authorities =
+            blockchain.authorities().map_err(|e| ExchangeError::Inconsistent(e.to_string()))?;
+        if let Some(anchor) = &self.anchor
+            && let Some(authorities) = authorities
+            && !authorities.iter().any(|authority| authority.verifying == anchor.signer_key)
+        {
+            return Err(ExchangeError::Inconsistent(
+                "the head is anchored by an issuer that isn't a chain authority".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Hash of each block of a chain by height, for copies of the chain to tell which blocks changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainIndex {
+    /// Format version, [`EXCHANGE_VERSION`] for indexes written by this version
+    pub version: u32,
+    /// UUID of the chain's genesis block, none for chains without one
+    pub chain: Option<Uuid>,
+    pub blocks: BTreeMap<u64, Hash>,
+}
+
+impl ChainIndex {
+    #[must_use]
+    pub fn new(blockchain: &Blockchain) -> Self {
+        let blocks = blockchain.blocks().map(|block| (block.height(), block.hash().clone()));
+        Self { version: EXCHANGE_VERSION, chain: blockchain.id(), blocks: blocks.collect() }
+    }
+
+    /// Reads an index as served
+    ///
+    /// # Errors
+    /// Returns [`ExchangeError::UnsupportedVersion`] for indexes in another format version and
+    /// [`ExchangeError::Parse`] if the JSON isn't an index at all.
+    pub fn from_json(json: &str) -> Result<Self, ExchangeError> { from_versioned_json(json) }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::blockchain::GenesisBlock;
+    use crate::credential::{Attribute, Issuer, Subject, ValidDuration};
+
+    fn export() -> CredentialExport { export_with_chain().0 }
+
+    fn export_with_chain() -> (CredentialExport, Blockchain) {
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let mut subject = Subject::new("Alice".into(), "Smith".into());
+        subject.extra.insert("born".into(), "1990-04-01".into());
+        let attributes = vec![Attribute::new("degree".into(), "MSc".into())];
+        let valid = ValidDuration::starting(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        let credential = Credential::new(attributes, issuer.clone(), subject, valid);
+        let mut blockchain =
+            Blockchain::with_genesis(GenesisBlock::new("Test".to_string(), None, None).unwrap());
+        let format = blockchain.format();
+        let issuance = credential.sign(&signing, false, format).unwrap();
+        let revocation = credential.sign(&signing, true, format).unwrap();
+        let mut block = Block::new(issuer);
+        block.add_credential(issuance.clone(), false).unwrap();
+        blockchain.add_block(block, &signing).unwrap();
+        (CredentialExport::new(credential, issuance, revocation, &blockchain), blockchain)
+    }
+
+    #[test]
+    fn test_export_roundtrip_is_byte_stable() {
+        let export = export();
+        assert_eq!(export.version, EXCHANGE_VERSION);
+        assert!(export.chain.is_some() && export.head.is_some());
+        assert_eq!(export.issuer_key, export.credential.issuer.verifying);
+
+        let json = export.to_string();
+        let read = CredentialExport::from_json(&json).unwrap();
+        assert_eq!(read.to_string(), json);
+        assert!(read.issuance.verify(&read.issuer_key));
+        assert!(read.revocation.verify(&read.issuer_key));
+    }
+
+    #[test]
+    fn test_export_versions() {
+        let mut value = serde_json::to_value(export()).unwrap();
+        value["version"] = 2.into();
+        assert_eq!(
+            CredentialExport::from_json(&value.to_string()).unwrap_err(),
+            ExchangeError::UnsupportedVersion(2)
+        );
+        value.as_object_mut().unwrap().remove("version");
+        assert!(matches!(
+            CredentialExport::from_json(&value.to_string()),
+            Err(ExchangeError::Parse(_))
+        ));
+        assert!(matches!(CredentialExport::from_json("[]"), Err(ExchangeError::Parse(_))));
+    }
+
+    #[test]
+    fn test_export_check() {
+        let (mut export, blockchain) = export_with_chain();
+        assert_eq!(export.check(&blockchain), Ok(()));
+
+        let other =
+            Blockchain::with_genesis(GenesisBlock::new("Other".to_string(), None, None).unwrap());
+        assert!(matches!(export.check(&other), Err(ExportMismatch::OtherChain { .. })));
+
+        // A key the chain never recorded for the issuer, with signatures that check against it
+        let (impostor, signing) = Issuer::new("Registrar".to_string());
+        export.credential.issuer.verifying = impostor.verifying;
+        assert_eq!(export.check(&blockchain), Err(ExportMismatch::IssuerKey));
+        export.issuer_key = impostor.verifying;
+        assert_eq!(export.check(&blockchain), Err(ExportMismatch::Hash { revoking: false }));
+        let format = blockchain.format();
+        export.issuance = export.credential.sign(&signing, false, format).unwrap();
+        export.revocation = export.credential.sign(&signing, true, format).unwrap();
+        let issuer = export.credential.issuer.uuid;
+        assert_eq!(export.check(&blockchain), Err(ExportMismatch::UnrecordedKey { issuer }));
+
+        let (_, stranger) = Issuer::new("Stranger".to_string());
+        export.revocation = export.credential.sign(&stranger, true, format).unwrap();
+        assert_eq!(export.check(&blockchain), Err(ExportMismatch::Signature { revoking: true }));
+    }
+
+    #[test]
+    fn test_chain_export_roundtrip() {
+        let (_, blockchain) = export_with_chain();
+        let export = ChainExport::new(blockchain);
+        assert_eq!((export.blocks, export.chain), (1, export.blockchain.id()));
+        let json = export.to_string();
+        let read = ChainExport::from_json(&json).unwrap();
+        assert_eq!(read.to_string(), json);
+        assert_eq!(read.blockchain.validate(), Ok(()));
+
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["blocks"] = 2.into();
+        assert!(matches!(
+            ChainExport::from_json(&value.to_string()),
+            Err(ExchangeError::Inconsistent(_))
+        ));
+        value["blocks"] = 1.into();
+        value["head"] = value["blockchain"]["chain"][0]["previous_hash"].clone();
+        assert!(matches!(
+            ChainExport::from_json(&value.to_string()),
+            Err(ExchangeError::Inconsistent(_))
+        ));
+    }
+}
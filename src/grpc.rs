@@ -0,0 +1,949 @@
+//! gRPC service verifying credentials and serving the chain, for platforms speaking gRPC rather
+//! than HTTP and JSON. The schema is `proto/attestation.proto`.
+//!
+//! Messages convert from the crate's structures with `From` and back with `TryFrom`, which fails
+//! on the keys, hashes, UUIDs and dates that don't parse. Hashes, keys and signatures are carried
+//! as raw bytes where the JSON files hold them hex-encoded.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use ed25519_dalek::VerifyingKey;
+use prost_types::Timestamp;
+use subtle::ConstantTimeEq;
+use tokio::net::TcpListener;
+use tokio::runtime::Runtime;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use crate::blockchain::{Block, Blockchain, VerificationStatus};
+use crate::credential::{
+    Attribute, Credential, CredentialSubject, Issuer, Salt, Subject, SubjectCommitment,
+    ValidDuration,
+};
+use crate::evidence::EvidenceRef;
+use crate::exchange::{ChainHead, EXCHANGE_VERSION};
+use crate::hash::{Algorithm, Hash};
+use crate::server::{ApiError, Backend, ServerConfig, ServerError, TlsConfig};
+
+/// Types generated from the schema
+#[allow(clippy::pedantic)]
+pub mod proto {
+    tonic::include_proto!("attestation.v1");
+}
+
+use proto::attestation_server::{Attestation, AttestationServer};
+use proto::verification_status::Status as StatusCode;
+
+/// Message that doesn't make up the structure it's converted to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConversionError {
+    /// A required field is absent
+    Missing(&'static str),
+    /// A field holds a value that doesn't parse, with the reason
+    Invalid { field: &'static str, error: String },
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(field) => write!(f, "missing {field}"),
+            Self::Invalid { field, error } => write!(f, "invalid {field}: {error}"),
+        }
+    }
+}
+
+impl Error for ConversionError {}
+
+fn invalid(field: &'static str, error: impl Display) -> ConversionError {
+    ConversionError::Invalid { field, error: error.to_string() }
+}
+
+fn required<T>(value: Option<T>, field: &'static str) -> Result<T, ConversionError> {
+    value.ok_or(ConversionError::Missing(field))
+}
+
+fn uuid(value: &str, field: &'static str) -> Result<Uuid, ConversionError> {
+    Uuid::parse_str(value).map_err(|e| invalid(field, e))
+}
+
+fn date(value: &str, field: &'static str) -> Result<NaiveDate, ConversionError> {
+    value.parse().map_err(|e| invalid(field, e))
+}
+
+fn verifying_key(bytes: &[u8], field: &'static str) -> Result<VerifyingKey, ConversionError> {
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| invalid(field, format!("expected 32 bytes, got {}", bytes.len())))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| invalid(field, e))
+}
+
+fn timestamp(time: DateTime<Utc>) -> Timestamp {
+    // Nanoseconds are below 2 * 10^9, leap seconds included
+    let nanos = i32::try_from(time.timestamp_subsec_nanos()).unwrap_or(i32::MAX);
+    Timestamp { seconds: time.timestamp(), nanos }
+}
+
+fn time(timestamp: &Timestamp, field: &'static str) -> Result<DateTime<Utc>, ConversionError> {
+    u32::try_from(timestamp.nanos)
+        .ok()
+        .and_then(|nanos| DateTime::from_timestamp(timestamp.seconds, nanos))
+        .ok_or_else(|| invalid(field, "out of range"))
+}
+
+impl From<Algorithm> for proto::Algorithm {
+    fn from(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Sha512 => Self::Sha512,
+            Algorithm::Sha3_512 => Self::Sha3512,
+            Algorithm::Blake3 => Self::Blake3,
+        }
+    }
+}
+
+impl From<proto::Algorithm> for Algorithm {
+    fn from(algorithm: proto::Algorithm) -> Self {
+        match algorithm {
+            proto::Algorithm::Sha512 => Self::Sha512,
+            proto::Algorithm::Sha3512 => Self::Sha3_512,
+            proto::Algorithm::Blake3 => Self::Blake3,
+        }
+    }
+}
+
+impl From<&Hash> for proto::Hash {
+    fn from(hash: &Hash) -> Self {
+        let algorithm = proto::Algorithm::from(hash.algorithm).into();
+        Self { algorithm, digest: hash.bytes.to_vec() }
+    }
+}
+
+impl TryFrom<proto::Hash> for Hash {
+    type Error = ConversionError;
+
+    fn try_from(hash: proto::Hash) -> Result<Self, Self::Error> {
+        let algorithm =
+            proto::Algorithm::try_from(hash.algorithm).map_err(|e| invalid("hash algorithm", e))?;
+        let bytes = hash.digest.try_into().map_err(|digest: Vec<u8>| {
+            invalid("hash", format!("expected 64 bytes, got {}", digest.len()))
+        })?;
+        Ok(Self::new(algorithm.into(), bytes))
+    }
+}
+
+fn hash(hash: Option<proto::Hash>, field: &'static str) -> Result<Hash, ConversionError> {
+    required(hash, field)?.try_into()
+}
+
+impl From<&Issuer> for proto::Issuer {
+    fn from(issuer: &Issuer) -> Self {
+        Self {
+            uuid: issuer.uuid.to_string(),
+            name: issuer.name.clone(),
+            verifying_key: issuer.verifying.to_bytes().to_vec(),
+            previous_keys: issuer.previous_keys.iter().map(|key| key.to_bytes().to_vec()).collect(),
+            url: issuer.url.clone(),
+            email: issuer.email.clone(),
+            country: issuer.country.clone(),
+        }
+    }
+}
+
+impl TryFrom<proto::Issuer> for Issuer {
+    type Error = ConversionError;
+
+    fn try_from(issuer: proto::Issuer) -> Result<Self, Self::Error> {
+        Ok(Self {
+            uuid: uuid(&issuer.uuid, "issuer UUID")?,
+            name: issuer.name,
+            verifying: verifying_key(&issuer.verifying_key, "issuer key")?,
+            previous_keys: issuer
+                .previous_keys
+                .iter()
+                .map(|key| verifying_key(key, "issuer's previous key"))
+                .collect::<Result<_, _>>()?,
+            url: issuer.url,
+            email: issuer.email,
+            country: issuer.country,
+            former_names: Vec::new(),
+            deleted: false,
+        })
+    }
+}
+
+impl From<&Subject> for proto::Subject {
+    fn from(subject: &Subject) -> Self {
+        Self {
+            uuid: subject.uuid.to_string(),
+            name: subject.name.clone(),
+            surname: subject.surname.clone(),
+            extra: subject.extra.clone(),
+            verifying_key: subject.verifying.map(|key| key.to_bytes().to_vec()),
+        }
+    }
+}
+
+impl TryFrom<proto::Subject> for Subject {
+    type Error = ConversionError;
+
+    fn try_from(subject: proto::Subject) -> Result<Self, Self::Error> {
+        Ok(Self {
+            uuid: uuid(&subject.uuid, "subject UUID")?,
+            name: subject.name,
+            surname: subject.surname,
+            extra: subject.extra,
+            verifying: subject
+                .verifying_key
+                .map(|key| verifying_key(&key, "subject key"))
+                .transpose()?,
+            deleted: false,
+        })
+    }
+}
+
+impl From<&SubjectCommitment> for proto::SubjectCommitment {
+    fn from(commitment: &SubjectCommitment) -> Self {
+        Self {
+            commitment: Some((&commitment.commitment).into()),
+            verifying_key: commitment.verifying.map(|key| key.to_bytes().to_vec()),
+        }
+    }
+}
+
+impl TryFrom<proto::SubjectCommitment> for SubjectCommitment {
+    type Error = ConversionError;
+
+    fn try_from(commitment: proto::SubjectCommitment) -> Result<Self, Self::Error> {
+        Ok(Self {
+            commitment: hash(commitment.commitment, "subject commitment")?,
+            verifying: commitment
+                .verifying_key
+                .map(|key| verifying_key(&key, "subject key"))
+                .transpose()?,
+        })
+    }
+}
+
+impl From<&CredentialSubject> for proto::credential::Subject {
+    fn from(subject: &CredentialSubject) -> Self {
+        match subject {
+            CredentialSubject::Disclosed(subject) => Self::Disclosed(subject.into()),
+            CredentialSubject::Committed(commitment) => Self::Committed(commitment.into()),
+        }
+    }
+}
+
+impl TryFrom<proto::credential::Subject> for CredentialSubject {
+    type Error = ConversionError;
+
+    fn try_from(subject: proto::credential::Subject) -> Result<Self, Self::Error> {
+        Ok(match subject {
+            proto::credential::Subject::Disclosed(subject) => Self::Disclosed(subject.try_into()?),
+            proto::credential::Subject::Committed(commitment) =>
+                Self::Committed(commitment.try_into()?),
+        })
+    }
+}
+
+impl From<&Attribute> for proto::Attribute {
+    fn from(attribute: &Attribute) -> Self {
+        Self {
+            name: attribute.name.clone(),
+            value: attribute.value.clone(),
+            schema: attribute.schema.clone(),
+            salt: attribute.salt.map(|salt| salt.0.to_vec()),
+        }
+    }
+}
+
+impl TryFrom<proto::Attribute> for Attribute {
+    type Error = ConversionError;
+
+    fn try_from(attribute: proto::Attribute) -> Result<Self, Self::Error> {
+        let salt = attribute
+            .salt
+            .map(|salt| {
+                let length = salt.len();
+                salt.try_into()
+                    .map(Salt)
+                    .map_err(|_| invalid("salt", format!("expected 32 bytes, got {length}")))
+            })
+            .transpose()?;
+        Ok(Self { name: attribute.name, value: attribute.value, schema: attribute.schema, salt })
+    }
+}
+
+impl From<&ValidDuration> for proto::ValidDuration {
+    fn from(duration: &ValidDuration) -> Self {
+        Self { from: duration.from.to_string(), to: duration.to.map(|to| to.to_string()) }
+    }
+}
+
+impl TryFrom<proto::ValidDuration> for ValidDuration {
+    type Error = ConversionError;
+
+    /// The period as given, even ending before it starts, like periods read from files
+    fn try_from(duration: proto::ValidDuration) -> Result<Self, Self::Error> {
+        Ok(Self {
+            from: date(&duration.from, "start of the valid duration")?,
+            to: duration.to.map(|to| date(&to, "end of the valid duration")).transpose()?,
+        })
+    }
+}
+
+impl From<&EvidenceRef> for proto::EvidenceRef {
+    fn from(evidence: &EvidenceRef) -> Self {
+        Self {
+            filename: evidence.filename.clone(),
+            media_type: evidence.media_type.clone(),
+            length: evidence.length,
+            sha512: Some((&evidence.sha512).into()),
+        }
+    }
+}
+
+impl TryFrom<proto::EvidenceRef> for EvidenceRef {
+    type Error = ConversionError;
+
+    fn try_from(evidence: proto::EvidenceRef) -> Result<Self, Self::Error> {
+        Ok(Self {
+            filename: evidence.filename,
+            media_type: evidence.media_type,
+            length: evidence.length,
+            sha512: hash(evidence.sha512, "evidence digest")?,
+        })
+    }
+}
+
+impl From<&Credential> for proto::Credential {
+    fn from(credential: &Credential) -> Self {
+        Self {
+            uuid: credential.uuid.to_string(),
+            attributes: credential.attributes.iter().map(Into::into).collect(),
+            withheld: credential.withheld.iter().map(Into::into).collect(),
+            issuer: Some((&credential.issuer).into()),
+            subject: Some((&credential.subject).into()),
+            valid_duration: Some((&credential.valid_duration).into()),
+            issued_at: credential.has_issuance_time().then(|| timestamp(credential.issued_at)),
+            supersedes: credential.supersedes.map(|uuid| uuid.to_string()),
+            evidence: credential.evidence.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<proto::Credential> for Credential {
+    type Error = ConversionError;
+
+    fn try_from(credential: proto::Credential) -> Result<Self, Self::Error> {
+        let issued_at = match &credential.issued_at {
+            Some(issued_at) => time(issued_at, "issuance time")?,
+            None => Self::UNKNOWN_ISSUANCE,
+        };
+        Ok(Self {
+            uuid: uuid(&credential.uuid, "credential UUID")?,
+            attributes: credential
+                .attributes
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            withheld: credential
+                .withheld
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            issuer: required(credential.issuer, "issuer")?.try_into()?,
+            subject: required(credential.subject, "subject")?.try_into()?,
+            valid_duration: required(credential.valid_duration, "valid duration")?.try_into()?,
+            issued_at,
+            supersedes: credential
+                .supersedes
+                .map(|supersedes| uuid(&supersedes, "superseded credential"))
+                .transpose()?,
+            evidence: credential
+                .evidence
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl From<&VerificationStatus> for proto::VerificationStatus {
+    fn from(status: &VerificationStatus) -> Self {
+        let (code, date, block) = match *status {
+            VerificationStatus::Valid { .. } => (StatusCode::Valid, None, None),
+            VerificationStatus::Revoked { .. } => (StatusCode::Revoked, None, None),
+            VerificationStatus::NotYetValid { from, .. } =>
+                (StatusCode::NotYetValid, Some(from), None),
+            VerificationStatus::Expired { to, .. } => (StatusCode::Expired, Some(to), None),
+            VerificationStatus::NotFound => (StatusCode::NotFound, None, None),
+            VerificationStatus::NotYetIssued { .. } => (StatusCode::NotYetIssued, None, None),
+            VerificationStatus::SignatureInvalid { block } =>
+                (StatusCode::SignatureInvalid, None, Some(block)),
+            VerificationStatus::IssuerKeyConflict => (StatusCode::IssuerKeyConflict, None, None),
+            VerificationStatus::IssuerDeactivated { effective, .. } =>
+                (StatusCode::IssuerDeactivated, Some(effective), None),
+            VerificationStatus::NotAccredited { .. } => (StatusCode::NotAccredited, None, None),
+            VerificationStatus::UntrustedIssuer => (StatusCode::UntrustedIssuer, None, None),
+        };
+        Self {
+            status: code.into(),
+            issued_in: status.issued_in(),
+            revoked_in: status.revoked_in(),
+            date: date.map(|date| date.to_string()),
+            block,
+            description: status.to_string(),
+        }
+    }
+}
+
+impl TryFrom<proto::VerificationStatus> for VerificationStatus {
+    type Error = ConversionError;
+
+    fn try_from(status: proto::VerificationStatus) -> Result<Self, Self::Error> {
+        let code = StatusCode::try_from(status.status).map_err(|e| invalid("status", e))?;
+        let issued_in = || required(status.issued_in, "block issuing the credential");
+        let date = || date(&required(status.date.clone(), "date")?, "date");
+        Ok(match code {
+            StatusCode::Unspecified => return Err(ConversionError::Missing("status")),
+            StatusCode::Valid => Self::Valid { issued_in: issued_in()? },
+            StatusCode::Revoked => Self::Revoked {
+                issued_in: status.issued_in,
+                revoked_in: required(status.revoked_in, "block revoking the credential")?,
+            },
+            StatusCode::NotYetValid => Self::NotYetValid { issued_in: issued_in()?, from: date()? },
+            StatusCode::Expired => Self::Expired { issued_in: issued_in()?, to: date()? },
+            StatusCode::NotFound => Self::NotFound,
+            StatusCode::NotYetIssued => Self::NotYetIssued { issued_in: issued_in()? },
+            StatusCode::SignatureInvalid =>
+                Self::SignatureInvalid { block: required(status.block, "block")? },
+            StatusCode::IssuerKeyConflict => Self::IssuerKeyConflict,
+            StatusCode::IssuerDeactivated =>
+                Self::IssuerDeactivated { issued_in: issued_in()?, effective: date()? },
+            StatusCode::NotAccredited => Self::NotAccredited { issued_in: issued_in()? },
+            StatusCode::UntrustedIssuer => Self::UntrustedIssuer,
+        })
+    }
+}
+
+impl From<&ChainHead> for proto::ChainHead {
+    fn from(head: &ChainHead) -> Self {
+        Self {
+            chain: head.chain.map(|uuid| uuid.to_string()),
+            blocks: head.blocks,
+            head: head.head.as_ref().map(Into::into),
+        }
+    }
+}
+
+impl TryFrom<proto::ChainHead> for ChainHead {
+    type Error = ConversionError;
+
+    /// The head, without an anchor, which the service doesn't serve
+    fn try_from(head: proto::ChainHead) -> Result<Self, Self::Error> {
+        Ok(Self {
+            version: EXCHANGE_VERSION,
+            chain: head.chain.map(|chain| uuid(&chain, "chain UUID")).transpose()?,
+            blocks: head.blocks,
+            head: head.head.map(TryInto::try_into).transpose()?,
+            anchor: None,
+        })
+    }
+}
+
+impl From<&Block> for proto::BlockHeader {
+    fn from(block: &Block) -> Self {
+        let revoked = block.revoked_credentials().iter().map(|r| (&r.signed.credential).into());
+        Self {
+            height: block.height(),
+            timestamp: Some(timestamp(block.timestamp())),
+            previous_hash: Some(block.previous_hash().into()),
+            hash: Some(block.hash().into()),
+            merkle_root: block.merkle_root().map(Into::into),
+            signer: Some(block.signer().into()),
+            signature: block.signature().0.to_vec(),
+            issued: block.issued_credentials().map(|signed| (&signed.credential).into()).collect(),
+            revoked: revoked.collect(),
+        }
+    }
+}
+
+impl From<&Block> for proto::Block {
+    fn from(block: &Block) -> Self {
+        Self {
+            header: Some(block.into()),
+            document: serde_json::to_vec(block).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<proto::Block> for Block {
+    type Error = ConversionError;
+
+    /// The block its document holds, which its header must describe
+    fn try_from(block: proto::Block) -> Result<Self, Self::Error> {
+        let header = required(block.header, "block header")?;
+        let document: Self =
+            serde_json::from_slice(&block.document).map_err(|e| invalid("block document", e))?;
+        if header.height != document.height()
+            || hash(header.hash, "block hash")? != *document.hash()
+        {
+            return Err(invalid("block header", "describes another block than the document"));
+        }
+        Ok(document)
+    }
+}
+
+impl From<ApiError> for Status {
+    fn from(error: ApiError) -> Self {
+        let message = error.to_string();
+        match error {
+            ApiError::BadRequest(_) => Self::invalid_argument(message),
+            ApiError::Unauthorized(_) => Self::unauthenticated(message),
+            ApiError::Forbidden(_) => Self::permission_denied(message),
+            ApiError::NotFound(_) => Self::not_found(message),
+            ApiError::MethodNotAllowed => Self::unimplemented(message),
+            ApiError::PayloadTooLarge => Self::resource_exhausted(message),
+            ApiError::Internal(_) => Self::internal(message),
+        }
+    }
+}
+
+/// Service answering from a backend, which is read on a blocking thread like the CLI reads its
+/// stores
+pub struct Service<B> {
+    backend: Arc<B>,
+}
+
+impl<B: Backend + Send + Sync + 'static> Service<B> {
+    #[must_use]
+    pub fn new(backend: B) -> Self { Self { backend: Arc::new(backend) } }
+
+    /// Answers from the backend's chain
+    async fn with_blockchain<T: Send + 'static>(
+        &self, answer: impl FnOnce(Blockchain) -> Result<T, Status> + Send + 'static,
+    ) -> Result<T, Status> {
+        let backend = Arc::clone(&self.backend);
+        tokio::task::spawn_blocking(move || answer(backend.blockchain()?))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+    }
+}
+
+type BlockStream = tokio_stream::Iter<std::vec::IntoIter<Result<proto::Block, Status>>>;
+
+#[tonic::async_trait]
+impl<B: Backend + Send + Sync + 'static> Attestation for Service<B> {
+    async fn verify(
+        &self, request: Request<proto::Credential>,
+    ) -> Result<Response<proto::VerificationStatus>, Status> {
+        let credential = Credential::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let status = self
+            .with_blockchain(move |blockchain| {
+                let today = Utc::now().date_naive();
+                let status = blockchain.check_credential(&credential);
+                Ok(status.within(&credential.valid_duration, today))
+            })
+            .await?;
+        Ok(Response::new((&status).into()))
+    }
+
+    async fn get_head(
+        &self, _: Request<proto::GetHeadRequest>,
+    ) -> Result<Response<proto::ChainHead>, Status> {
+        let head = self.with_blockchain(|blockchain| Ok(ChainHead::new(&blockchain))).await?;
+        Ok(Response::new((&head).into()))
+    }
+
+    async fn get_block(
+        &self, request: Request<proto::GetBlockRequest>,
+    ) -> Result<Response<proto::Block>, Status> {
+        let height = request.into_inner().height;
+        let block = self
+            .with_blockchain(move |blockchain| {
+                let missing = || Status::not_found(format!("no block #{height}"));
+                blockchain.block(height).map(Into::into).ok_or_else(missing)
+            })
+            .await?;
+        Ok(Response::new(block))
+    }
+
+    type StreamBlocksStream = BlockStream;
+
+    async fn stream_blocks(
+        &self, request: Request<proto::StreamBlocksRequest>,
+    ) -> Result<Response<Self::StreamBlocksStream>, Status> {
+        let from = request.into_inner().from_height;
+        let blocks: Vec<_> = self
+            .with_blockchain(move |blockchain| {
+                let blocks = blockchain.blocks().filter(|block| block.height() >= from);
+                Ok(blocks.map(Into::into).collect())
+            })
+            .await?;
+        Ok(Response::new(tokio_stream::iter(blocks.into_iter().map(Ok).collect::<Vec<_>>())))
+    }
+}
+
+/// gRPC server, running its own runtime so that callers stay synchronous
+pub struct GrpcServer {
+    runtime: Runtime,
+    listener: TcpListener,
+    builder: Server,
+    token: Option<String>,
+}
+
+impl GrpcServer {
+    /// Listens on the address, e.g. `127.0.0.1:50051`, or on a free port with port 0, requiring
+    /// the config's token and serving its TLS like the HTTP server
+    ///
+    /// # Errors
+    /// Returns [`ServerError::Bind`] if the runtime can't start or the address can't be listened
+    /// on, and [`ServerError::Tls`] if TLS is configured but can't be set up.
+    pub fn bind(address: &str, config: ServerConfig) -> Result<Self, ServerError> {
+        let builder = match &config.tls {
+            Some(tls) => tls_server(tls)?,
+            None => Server::builder(),
+        };
+        let bind = |e: std::io::Error| ServerError::Bind(e.to_string());
+        let runtime = Runtime::new().map_err(bind)?;
+        let listener = runtime.block_on(TcpListener::bind(address)).map_err(bind)?;
+        Ok(Self { runtime, listener, builder, token: config.token })
+    }
+
+    /// Address listened on
+    #[must_use]
+    pub fn local_addr(&self) -> Option<SocketAddr> { self.listener.local_addr().ok() }
+
+    /// Answers requests until the process ends
+    ///
+    /// # Errors
+    /// Returns [`ServerError::Transport`] if the connections can't be served.
+    pub fn run<B: Backend + Send + Sync + 'static>(self, backend: B) -> Result<(), ServerError> {
+        let token = self.token;
+        let service = AttestationServer::with_interceptor(Service::new(backend), move |request| {
+            authenticate(token.as_deref(), request)
+        });
+        let incoming = TcpListenerStream::new(self.listener);
+        let mut builder = self.builder;
+        self.runtime
+            .block_on(builder.add_service(service).serve_with_incoming(incoming))
+            .map_err(|e| ServerError::Transport(e.to_string()))
+    }
+}
+
+/// Checks the request's bearer token against the configured one, if any
+fn authenticate(token: Option<&str>, request: Request<()>) -> Result<Request<()>, Status> {
+    let Some(token) = token else {
+        return Ok(request);
+    };
+    let sent = request
+        .metadata()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| Status::unauthenticated("missing bearer token"))?;
+    if !bool::from(sent.as_bytes().ct_eq(token.as_bytes())) {
+        return Err(Status::unauthenticated("wrong bearer token"));
+    }
+    Ok(request)
+}
+
+/// Server serving the TLS the config sets up, requiring client certificates when it has a CA to
+/// check them against
+#[cfg(feature = "tls")]
+fn tls_server(config: &TlsConfig) -> Result<Server, ServerError> {
+    use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+
+    let read = |path: &std::path::Path| {
+        std::fs::read(path).map_err(|e| ServerError::Tls(format!("{}: {e}", path.display())))
+    };
+    let identity = Identity::from_pem(read(&config.certificate)?, read(&config.key)?);
+    let mut tls = ServerTlsConfig::new().identity(identity);
+    if let Some(path) = &config.client_ca {
+        tls = tls.client_ca_root(Certificate::from_pem(read(path)?));
+    }
+    Server::builder().tls_config(tls).map_err(|e| ServerError::Tls(e.to_string()))
+}
+
+#[cfg(not(feature = "tls"))]
+fn tls_server(_: &TlsConfig) -> Result<Server, ServerError> {
+    Err(ServerError::Tls("this build can't serve TLS, build with `--features tls`".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use chrono::NaiveDate;
+    use ed25519_dalek::SigningKey;
+    use tokio_stream::StreamExt;
+
+    use super::proto::attestation_client::AttestationClient;
+    use super::*;
+    use crate::blockchain::GenesisBlock;
+    use crate::hash::HashVersion;
+    use crate::requests::{IssuanceRequest, RequestSubmission};
+
+    fn credential() -> (Credential, SigningKey) {
+        let (mut credential, signing) = Credential::sample();
+        // Every optional field set, and a duration that never ends
+        let CredentialSubject::Disclosed(subject) = &mut credential.subject else {
+            unreachable!("the sample credential discloses its subject");
+        };
+        subject.verifying = Some(SigningKey::from_bytes(&[7; 32]).verifying_key());
+        credential.attributes[0].schema = Some("https://schemas.example.org/degree".to_string());
+        credential.valid_duration.to = None;
+        credential.supersedes = Some(Uuid::new_v4());
+        credential.withheld.push(Hash::new(Algorithm::Blake3, [9; 64]));
+        (credential, signing)
+    }
+
+    fn roundtrip(credential: &Credential) -> Credential {
+        let message = proto::Credential::from(credential);
+        Credential::try_from(message).unwrap()
+    }
+
+    #[test]
+    fn test_hash_and_key_encodings() {
+        for algorithm in Algorithm::ALL {
+            let hash = Hash::new(algorithm, [0xab; 64]);
+            let message = proto::Hash::from(&hash);
+            // The raw digest, not its hex digits
+            assert_eq!(message.digest.len(), 64);
+            assert!(hash.to_string().ends_with(&hex::encode(&message.digest)));
+            assert_eq!(Hash::try_from(message).unwrap(), hash);
+        }
+        let short = proto::Hash { algorithm: 0, digest: vec![0xab; 32] };
+        assert!(matches!(Hash::try_from(short), Err(ConversionError::Invalid { .. })));
+        let unknown = proto::Hash { algorithm: 9, digest: vec![0xab; 64] };
+        assert!(matches!(Hash::try_from(unknown), Err(ConversionError::Invalid { .. })));
+
+        let (issuer, _) = Issuer::new("Registrar".to_string());
+        let message = proto::Issuer::from(&issuer);
+        let json = serde_json::to_value(&issuer).unwrap();
+        assert_eq!(hex::encode(&message.verifying_key), json["verifying"].as_str().unwrap());
+        let hex_encoded = proto::Issuer {
+            verifying_key: hex::encode(&message.verifying_key).into_bytes(),
+            ..message.clone()
+        };
+        let error = Issuer::try_from(hex_encoded).unwrap_err();
+        assert_eq!(error.to_string(), "invalid issuer key: expected 32 bytes, got 64");
+        assert_eq!(Issuer::try_from(message).unwrap().verifying, issuer.verifying);
+    }
+
+    #[test]
+    fn test_credential_roundtrip_keeps_its_hash() {
+        let (mut credential, _) = credential();
+        let format = HashVersion::LATEST;
+        let converted = roundtrip(&credential);
+        assert_eq!(converted.hash(false, format), credential.hash(false, format));
+        assert_eq!(converted.hash(true, format), credential.hash(true, format));
+        assert_eq!(converted.subject.disclosed().unwrap().extra["born"], "1990-04-01");
+
+        // Credentials stored before issuance times were recorded carry none
+        credential.issued_at = Credential::UNKNOWN_ISSUANCE;
+        let Some(subject) = credential.subject.disclosed() else {
+            panic!("expected a disclosed subject");
+        };
+        let commitment = SubjectCommitment::new(subject, &Salt::random());
+        credential.subject = CredentialSubject::Committed(commitment);
+        let message = proto::Credential::from(&credential);
+        assert!(message.issued_at.is_none());
+        let converted = Credential::try_from(message.clone()).unwrap();
+        assert!(!converted.has_issuance_time());
+        assert_eq!(converted.hash(false, format), credential.hash(false, format));
+
+        let unnamed = proto::Credential { subject: None, ..message };
+        assert_eq!(
+            Credential::try_from(unnamed).unwrap_err(),
+            ConversionError::Missing("subject")
+        );
+    }
+
+    #[test]
+    fn test_status_roundtrip() {
+        let day = NaiveDate::from_ymd_opt(2025, 6, 30).unwrap();
+        for status in [
+            VerificationStatus::Valid { issued_in: 1 },
+            VerificationStatus::Revoked { issued_in: None, revoked_in: 4 },
+            VerificationStatus::Revoked { issued_in: Some(1), revoked_in: 4 },
+            VerificationStatus::NotYetValid { issued_in: 1, from: day },
+            VerificationStatus::Expired { issued_in: 1, to: day },
+            VerificationStatus::NotFound,
+            VerificationStatus::NotYetIssued { issued_in: 2 },
+            VerificationStatus::SignatureInvalid { block: 3 },
+            VerificationStatus::IssuerKeyConflict,
+            VerificationStatus::IssuerDeactivated { issued_in: 2, effective: day },
+            VerificationStatus::NotAccredited { issued_in: 2 },
+            VerificationStatus::UntrustedIssuer,
+        ] {
+            let message = proto::VerificationStatus::from(&status);
+            assert_eq!(message.description, status.to_string());
+            assert_eq!(VerificationStatus::try_from(message).unwrap(), status);
+        }
+        let unspecified = proto::VerificationStatus::default();
+        assert!(VerificationStatus::try_from(unspecified).is_err());
+    }
+
+    struct Served(Blockchain);
+
+    impl Backend for Served {
+        fn blockchain(&self) -> Result<Blockchain, ApiError> { Ok(self.0.clone()) }
+
+        fn submit_request(&self, _: RequestSubmission) -> Result<IssuanceRequest, ApiError> {
+            Err(ApiError::Forbidden("read-only".into()))
+        }
+    }
+
+    #[test]
+    fn test_client_against_served_chain() {
+        let (credential, signing) = credential();
+        let genesis = GenesisBlock::new("Test".to_string(), None, None).unwrap();
+        let mut blockchain = Blockchain::with_genesis(genesis);
+        let mut block = Block::new(credential.issuer.clone());
+        let signed = credential.sign(&signing, false, blockchain.format()).unwrap();
+        block.add_credential(signed, false).unwrap();
+        blockchain.add_block(block, &signing).unwrap();
+        for _ in 0..2 {
+            blockchain.add_block(Block::new(credential.issuer.clone()), &signing).unwrap();
+        }
+
+        let server = GrpcServer::bind("127.0.0.1:0", ServerConfig::default()).unwrap();
+        let address = format!("http://{}", server.local_addr().unwrap());
+        let backend = Served(blockchain.clone());
+        thread::spawn(move || server.run(backend));
+
+        let runtime = Runtime::new().unwrap();
+        runtime.block_on(async {
+            let mut client = AttestationClient::connect(address).await.unwrap();
+            let status = client.verify(proto::Credential::from(&credential)).await.unwrap();
+            let status = VerificationStatus::try_from(status.into_inner()).unwrap();
+            assert_eq!(status, VerificationStatus::Valid { issued_in: 0 });
+            let (unknown, _) = self::credential();
+            let status = client.verify(proto::Credential::from(&unknown)).await.unwrap();
+            assert_eq!(status.into_inner().status(), StatusCode::NotFound);
+            let malformed = proto::Credential { uuid: "x".into(), ..(&credential).into() };
+            let error = client.verify(malformed).await.unwrap_err();
+            assert_eq!(error.code(), tonic::Code::InvalidArgument);
+
+            let head = client.get_head(proto::GetHeadRequest {}).await.unwrap().into_inner();
+            assert_eq!(ChainHead::try_from(head).unwrap(), ChainHead::new(&blockchain));
+            let block = client.get_block(proto::GetBlockRequest { height: 0 }).await.unwrap();
+            let block = Block::try_from(block.into_inner()).unwrap();
+            assert_eq!(block.hash(), blockchain.block(0).unwrap().hash());
+            let missing = client.get_block(proto::GetBlockRequest { height: 3 }).await;
+            assert_eq!(missing.unwrap_err().code(), tonic::Code::NotFound);
+
+            let request = proto::StreamBlocksRequest { from_height: 1 };
+            let mut stream = client.stream_blocks(request).await.unwrap().into_inner();
+            let mut heights = Vec::new();
+            while let Some(block) = stream.next().await {
+                heights.push(block.unwrap().header.unwrap().height);
+            }
+            assert_eq!(heights, [1, 2]);
+        });
+    }
+
+    fn serve(config: ServerConfig) -> SocketAddr {
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let mut blockchain = Blockchain::new();
+        blockchain.add_block(Block::new(issuer), &signing).unwrap();
+        let server = GrpcServer::bind("127.0.0.1:0", config).unwrap();
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run(Served(blockchain)));
+        address
+    }
+
+    fn sending(authorization: Option<&'static str>) -> impl tonic::service::Interceptor {
+        move |mut request: Request<()>| {
+            if let Some(authorization) = authorization {
+                request.metadata_mut().insert("authorization", authorization.parse().unwrap());
+            }
+            Ok(request)
+        }
+    }
+
+    #[test]
+    fn test_token_required() {
+        let config = ServerConfig { token: Some("s3cret".into()), ..ServerConfig::default() };
+        let address = format!("http://{}", serve(config));
+
+        let runtime = Runtime::new().unwrap();
+        runtime.block_on(async {
+            let endpoint = tonic::transport::Endpoint::new(address).unwrap();
+            let channel = endpoint.connect().await.unwrap();
+            // No token, a wrong one and the right one not sent as a bearer token
+            for sent in [None, Some("Bearer wrong"), Some("s3cret")] {
+                let mut client =
+                    AttestationClient::with_interceptor(channel.clone(), sending(sent));
+                let error = client.get_head(proto::GetHeadRequest {}).await.unwrap_err();
+                assert_eq!(error.code(), tonic::Code::Unauthenticated);
+            }
+            let bearer = sending(Some("Bearer s3cret"));
+            let mut client = AttestationClient::with_interceptor(channel, bearer);
+            let head = client.get_head(proto::GetHeadRequest {}).await.unwrap().into_inner();
+            assert_eq!(head.blocks, 1);
+        });
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_mutual_tls() {
+        use std::path::Path;
+
+        use tonic::transport::{Certificate, ClientTlsConfig, Endpoint, Identity};
+
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls");
+        let fixture = |name: &str| std::fs::read(fixtures.join(name)).unwrap();
+        let tls = TlsConfig {
+            certificate: fixtures.join("server.pem"),
+            key: fixtures.join("server.key"),
+            client_ca: Some(fixtures.join("ca.pem")),
+        };
+        let missing = TlsConfig { client_ca: Some(fixtures.join("none.pem")), ..tls.clone() };
+        let config = ServerConfig { token: None, tls: Some(missing) };
+        let error = GrpcServer::bind("127.0.0.1:0", config).err().unwrap();
+        assert!(error.to_string().contains("none.pem"));
+        let address = serve(ServerConfig { token: None, tls: Some(tls) });
+
+        let runtime = Runtime::new().unwrap();
+        runtime.block_on(async {
+            let endpoint = Endpoint::new(format!("https://localhost:{}", address.port())).unwrap();
+            let ca = Certificate::from_pem(fixture("ca.pem"));
+            let server_only = ClientTlsConfig::new().ca_certificate(ca);
+            // Without a client certificate the handshake fails, before or at the first call
+            let anonymous = endpoint.clone().tls_config(server_only.clone()).unwrap();
+            if let Ok(channel) = anonymous.connect().await {
+                let mut client = AttestationClient::new(channel);
+                assert!(client.get_head(proto::GetHeadRequest {}).await.is_err());
+            }
+            let identity = Identity::from_pem(fixture("client.pem"), fixture("client.key"));
+            let mutual = endpoint.tls_config(server_only.identity(identity)).unwrap();
+            let mut client = AttestationClient::new(mutual.connect().await.unwrap());
+            let head = client.get_head(proto::GetHeadRequest {}).await.unwrap().into_inner();
+            assert_eq!(head.blocks, 1);
+        });
+    }
+
+    #[test]
+    fn test_block_header_must_describe_the_document() {
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let mut blockchain = Blockchain::new();
+        for _ in 0..2 {
+            blockchain.add_block(Block::new(issuer.clone()), &signing).unwrap();
+        }
+        let first = proto::Block::from(blockchain.block(0).unwrap());
+        let second = proto::Block::from(blockchain.block(1).unwrap());
+        let header = second.header.as_ref().unwrap();
+        assert_eq!(header.previous_hash, first.header.as_ref().unwrap().hash);
+        assert_eq!(header.signature, blockchain.block(1).unwrap().signature().0);
+        let swapped = proto::Block { header: second.header, ..first };
+        assert!(Block::try_from(swapped).is_err());
+    }
+}
@@ -1,27 +1,420 @@
-use ed25519_dalek::Signature;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
 use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
-use sha2::Sha512;
 use sha2::digest::Output;
+use sha2::{Digest, Sha512};
+use sha3::Sha3_512;
+use subtle::{Choice, ConstantTimeEq};
+
+/// Digest algorithm a chain hashes with, recorded in its genesis block and in every hash
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Algorithm {
+    #[default]
+    #[serde(rename = "sha512")]
+    Sha512,
+    #[serde(rename = "sha3-512")]
+    Sha3_512,
+    /// BLAKE3 in extended output mode, producing 64 bytes like the others
+    #[serde(rename = "blake3")]
+    Blake3,
+}
+
+impl Algorithm {
+    pub const ALL: [Self; 3] = [Self::Sha512, Self::Sha3_512, Self::Blake3];
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha512 => "sha512",
+            Self::Sha3_512 => "sha3-512",
+            Self::Blake3 => "blake3",
+        }
+    }
+}
+
+impl Display for Algorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str(self.name()) }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name() == s)
+            .ok_or_else(|| format!("Unknown hash algorithm {s}"))
+    }
+}
+
+/// Rules used to compute block hashes, recorded in the chain's genesis block
+///
+/// Fields added to a structure after chains started hashing it are only committed to when set,
+/// or when they differ from their default, so that what was hashed before they existed keeps its
+/// hash on every version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "u32", into = "u32")]
+pub enum HashVersion {
+    /// Original format, used by chains without a genesis block
+    V1 = 1,
+    /// Commits to the block height, length-prefixes variable-length fields, marks whether
+    /// optional fields are present and tags every structure with its [`domain`]
+    V2 = 2,
+    /// Like V2, but blocks commit to a Merkle root of their credential entries and to digests of
+    /// their other contents instead of the contents themselves
+    V3 = 3,
+    /// Like V3, but credentials commit to a sorted list of salted commitments to their
+    /// attributes, so that a holder can disclose some attributes and withhold the others
+    V4 = 4,
+}
+
+impl HashVersion {
+    pub const LATEST: Self = Self::V4;
+}
+
+impl From<HashVersion> for u32 {
+    fn from(value: HashVersion) -> Self { value as u32 }
+}
+
+impl TryFrom<u32> for HashVersion {
+    type Error = String;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::V1),
+            2 => Ok(Self::V2),
+            3 => Ok(Self::V3),
+            4 => Ok(Self::V4),
+            v => Err(format!("Unsupported hash version {v}")),
+        }
+    }
+}
+
+/// Domain separation tags hashed first by every structure from V2 on, so that preimages of
+/// different structures can't be confused
+pub mod domain {
+    pub const ACCREDITATION: &[u8] = b"attest/v2/accreditation";
+    pub const ATTRIBUTE: &[u8] = b"attest/v2/attribute";
+    pub const ATTRIBUTES: &[u8] = b"attest/v2/attributes";
+    /// Salted commitment to a single attribute, from V4 on
+    pub const ATTRIBUTE_COMMITMENT: &[u8] = b"attest/v4/attribute-commitment";
+    /// Replaces [`ATTRIBUTES`] in credential hashes from V4 on
+    pub const ATTRIBUTE_COMMITMENTS: &[u8] = b"attest/v4/attribute-commitments";
+    pub const AUTHORITY_CHANGE: &[u8] = b"attest/v2/authority-change";
+    pub const BLOCK: &[u8] = b"attest/v2/block";
+    /// Digest of a block's contents besides its credential entries
+    pub const BLOCK_CONTENTS: &[u8] = b"attest/v3/block-contents";
+    /// Block hashes from V3 on, committing to a [`BlockHeader`](crate::blockchain::BlockHeader)
+    pub const BLOCK_HEADER: &[u8] = b"attest/v3/block-header";
+    pub const CHAIN_METADATA: &[u8] = b"attest/v2/chain-metadata";
+    pub const CREDENTIAL: &[u8] = b"attest/v2/credential";
+    pub const EVIDENCE: &[u8] = b"attest/v2/evidence";
+    pub const ISSUER: &[u8] = b"attest/v2/issuer";
+    pub const ISSUER_DEACTIVATION: &[u8] = b"attest/v2/issuer-deactivation";
+    pub const KEY_ROTATION: &[u8] = b"attest/v2/key-rotation";
+    /// Root of a Merkle tree without leaves
+    pub const MERKLE_EMPTY: &[u8] = b"attest/v3/merkle-empty";
+    pub const MERKLE_LEAF: &[u8] = b"attest/v3/merkle-leaf";
+    pub const MERKLE_NODE: &[u8] = b"attest/v3/merkle-node";
+    /// Replaces [`CREDENTIAL`] in the hash signed to revoke a credential
+    pub const REVOCATION: &[u8] = b"attest/v2/revocation";
+    pub const REVOCATION_DETAILS: &[u8] = b"attest/v2/revocation-details";
+    pub const SIGNED_CREDENTIAL: &[u8] = b"attest/v2/signed-credential";
+    /// Subtree of a sparse Merkle tree without keys
+    pub const SPARSE_MERKLE_EMPTY: &[u8] = b"attest/v4/sparse-merkle-empty";
+    pub const SPARSE_MERKLE_LEAF: &[u8] = b"attest/v4/sparse-merkle-leaf";
+    pub const SPARSE_MERKLE_NODE: &[u8] = b"attest/v4/sparse-merkle-node";
+    pub const SUBJECT: &[u8] = b"attest/v2/subject";
+    /// Salted commitment standing in for a subject, from V4 on
+    pub const SUBJECT_COMMITMENT: &[u8] = b"attest/v4/subject-commitment";
+    pub const VALID_DURATION: &[u8] = b"attest/v2/valid-duration";
+}
+
+/// Hash version and digest algorithm a chain hashes with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashFormat {
+    pub version: HashVersion,
+    pub algorithm: Algorithm,
+}
+
+impl From<HashVersion> for HashFormat {
+    fn from(version: HashVersion) -> Self { Self { version, algorithm: Algorithm::default() } }
+}
+
+enum Digester {
+    Sha512(Sha512),
+    Sha3_512(Sha3_512),
+    Blake3(Box<blake3::Hasher>),
+}
+
+/// Digest fed with fields encoded according to a hash version
+pub struct Hasher {
+    digest: Digester,
+    version: HashVersion,
+}
+
+impl Hasher {
+    #[must_use]
+    pub fn new(format: impl Into<HashFormat>) -> Self {
+        let HashFormat { version, algorithm } = format.into();
+        let digest = match algorithm {
+            Algorithm::Sha512 => Digester::Sha512(Sha512::new()),
+            Algorithm::Sha3_512 => Digester::Sha3_512(Sha3_512::new()),
+            Algorithm::Blake3 => Digester::Blake3(Box::new(blake3::Hasher::new())),
+        };
+        Self { digest, version }
+    }
+
+    #[must_use]
+    pub fn version(&self) -> HashVersion { self.version }
+
+    fn digest(&mut self, data: impl AsRef<[u8]>) {
+        match &mut self.digest {
+            Digester::Sha512(digest) => digest.update(data),
+            Digester::Sha3_512(digest) => digest.update(data),
+            Digester::Blake3(digest) => {
+                digest.update(data.as_ref());
+            },
+        }
+    }
+
+    /// Starts a structure with its domain separation tag from V2 on
+    pub fn domain(&mut self, tag: &[u8]) {
+        if self.version >= HashVersion::V2 {
+            self.field(tag);
+        }
+    }
+
+    /// Adds fixed-size data as is
+    pub fn update(&mut self, data: impl AsRef<[u8]>) { self.digest(data); }
+
+    /// Adds variable-length data, prefixed with its length from V2 on so that adjacent fields
+    /// can't run into each other
+    pub fn field(&mut self, data: impl AsRef<[u8]>) {
+        let data = data.as_ref();
+        self.length(data.len());
+        self.digest(data);
+    }
+
+    /// Adds the length of a field or list that follows, as a little-endian `u64` from V2 on
+    pub fn length(&mut self, length: usize) {
+        if self.version >= HashVersion::V2 {
+            self.digest((length as u64).to_le_bytes());
+        }
+    }
+
+    /// Adds a marker byte telling whether an optional field follows, from V2 on, so that a
+    /// missing field can't be confused with the contents of the next one
+    pub fn presence(&mut self, present: bool) {
+        if self.version >= HashVersion::V2 {
+            self.digest([u8::from(present)]);
+        }
+    }
+
+    /// Adds variable-length data prefixed with its length on every version, V1 included, for
+    /// structures V1 chains can hold that didn't exist when V1 hashed fields as they are
+    pub fn prefixed(&mut self, data: impl AsRef<[u8]>) {
+        let data = data.as_ref();
+        self.count(data.len());
+        self.digest(data);
+    }
+
+    /// Adds the number of entries that follow on every version, like [`Hasher::prefixed`]
+    pub fn count(&mut self, count: usize) { self.digest((count as u64).to_le_bytes()); }
+
+    /// Adds a marker byte telling whether the field is set, then the field if it is, prefixed
+    /// with its length, on every version like [`Hasher::prefixed`]
+    pub fn optional(&mut self, data: Option<impl AsRef<[u8]>>) {
+        self.digest([u8::from(data.is_some())]);
+        if let Some(data) = data {
+            self.prefixed(data);
+        }
+    }
+
+    #[must_use]
+    pub fn finalize(self) -> Hash {
+        match self.digest {
+            Digester::Sha512(digest) => digest.finalize().into(),
+            Digester::Sha3_512(digest) => Hash::new(Algorithm::Sha3_512, digest.finalize().into()),
+            Digester::Blake3(digest) => {
+                let mut bytes = [0; 64];
+                digest.finalize_xof().fill(&mut bytes);
+                Hash::new(Algorithm::Blake3, bytes)
+            },
+        }
+    }
+}
+
+/// 64-byte digest tagged with the algorithm that produced it, compared in constant time
+#[derive(Debug, Clone)]
+pub struct Hash {
+    pub algorithm: Algorithm,
+    pub bytes: [u8; 64],
+}
+
+impl Hash {
+    #[must_use]
+    pub fn new(algorithm: Algorithm, bytes: [u8; 64]) -> Self { Self { algorithm, bytes } }
+
+    /// First 8 hex characters, for compact output
+    #[must_use]
+    pub fn short(&self) -> String { hex::encode(&self.bytes[..4]) }
+
+    /// Whether the hash's hex digits start with `prefix`, ignoring case
+    #[must_use]
+    pub fn matches_prefix(&self, prefix: &str) -> bool {
+        hex::encode(self.bytes).starts_with(&prefix.to_ascii_lowercase())
+    }
+}
+
+/// Finds the position of the only hash starting with `prefix`
+///
+/// # Errors
+///
+/// Returns an error if no hash or more than one hash matches.
+pub fn resolve_prefix<'a>(
+    prefix: &str, hashes: impl IntoIterator<Item = &'a Hash>,
+) -> Result<usize, PrefixError> {
+    let mut matches = hashes.into_iter().enumerate().filter(|(_, h)| h.matches_prefix(prefix));
+    let Some((position, _)) = matches.next() else {
+        return Err(PrefixError::NotFound);
+    };
+    match matches.count() {
+        0 => Ok(position),
+        others => Err(PrefixError::Ambiguous(others + 1)),
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Hash(pub [u8; 64]);
+pub enum ParseHashError {
+    /// The string has an odd number of hex digits
+    OddLength,
+    /// The string contains a character that isn't a hex digit
+    InvalidHex,
+    /// The decoded hash isn't 64 bytes long
+    WrongLength(usize),
+    /// The hash is tagged with an algorithm that isn't supported
+    UnknownAlgorithm(String),
+}
+
+impl Display for ParseHashError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OddLength => write!(f, "Hash has an odd number of hex digits"),
+            Self::InvalidHex => write!(f, "Hash contains a non-hex character"),
+            Self::WrongLength(len) => write!(f, "Hash must be 64 bytes, got {len}"),
+            Self::UnknownAlgorithm(name) => write!(f, "Unknown hash algorithm {name}"),
+        }
+    }
+}
+
+impl Error for ParseHashError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrefixError {
+    /// No hash starts with the prefix
+    NotFound,
+    /// The given number of hashes start with the prefix
+    Ambiguous(usize),
+}
+
+impl Display for PrefixError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "No hash matches the prefix"),
+            Self::Ambiguous(count) => write!(f, "Prefix is ambiguous, {count} hashes match it"),
+        }
+    }
+}
+
+impl Error for PrefixError {}
+
+impl std::hash::Hash for Hash {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.algorithm.hash(state);
+        self.bytes.hash(state);
+    }
+}
+
+impl ConstantTimeEq for Hash {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        // The algorithm is public, only the digest bytes need constant-time comparison
+        Choice::from(u8::from(self.algorithm == other.algorithm)) & self.bytes.ct_eq(&other.bytes)
+    }
+}
+
+impl PartialEq for Hash {
+    fn eq(&self, other: &Self) -> bool { self.ct_eq(other).into() }
+}
+
+impl Eq for Hash {}
 
 impl Default for Hash {
-    fn default() -> Self { Self([0; 64]) }
+    fn default() -> Self { Self::from([0; 64]) }
 }
 
-impl From<Signature> for Hash {
-    fn from(value: Signature) -> Self { Self(value.to_bytes()) }
+/// SHA-512 digest bytes
+impl From<[u8; 64]> for Hash {
+    fn from(bytes: [u8; 64]) -> Self { Self::new(Algorithm::Sha512, bytes) }
 }
 
 impl From<Output<Sha512>> for Hash {
-    fn from(value: Output<Sha512>) -> Self { Self(value.into()) }
+    fn from(value: Output<Sha512>) -> Self { Self::from(<[u8; 64]>::from(value)) }
+}
+
+/// SHA-512 digest bytes
+impl TryFrom<&[u8]> for Hash {
+    type Error = ParseHashError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let bytes: [u8; 64] =
+            value.try_into().map_err(|_| ParseHashError::WrongLength(value.len()))?;
+        Ok(Self::from(bytes))
+    }
+}
+
+impl AsRef<[u8]> for Hash {
+    fn as_ref(&self) -> &[u8] { &self.bytes }
+}
+
+/// Lowercase hex, prefixed with the algorithm name unless it's the original SHA-512 so that
+/// hashes written before algorithms were recorded keep their format
+impl Display for Hash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.algorithm != Algorithm::Sha512 {
+            write!(f, "{}:", self.algorithm)?;
+        }
+        f.write_str(&hex::encode(self.bytes))
+    }
+}
+
+impl FromStr for Hash {
+    type Err = ParseHashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (algorithm, digits) = match s.split_once(':') {
+            Some((name, digits)) => (
+                name.parse().map_err(|_| ParseHashError::UnknownAlgorithm(name.to_string()))?,
+                digits,
+            ),
+            None => (Algorithm::Sha512, s),
+        };
+        let bytes = hex::decode(digits).map_err(|e| match e {
+            hex::FromHexError::OddLength => ParseHashError::OddLength,
+            _ => ParseHashError::InvalidHex,
+        })?;
+        let bytes = bytes.try_into().map_err(|b: Vec<u8>| ParseHashError::WrongLength(b.len()))?;
+        Ok(Self::new(algorithm, bytes))
+    }
 }
 
 impl Serialize for Hash {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where S: Serializer {
-        serializer.serialize_str(&hex::encode(self.0))
+        serializer.serialize_str(&self.to_string())
     }
 }
 
@@ -29,25 +422,75 @@ impl<'de> Deserialize<'de> for Hash {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where D: Deserializer<'de> {
         let s: String = Deserialize::deserialize(deserializer)?;
-        let bytes = hex::decode(s).map_err(de::Error::custom)?;
-        let bytes =
-            bytes.try_into().map_err(|_| de::Error::custom("Verifying key must be 32 bytes"))?;
-        Ok(Self(bytes))
+        s.parse().map_err(de::Error::custom)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use ed25519_dalek::{Signer, SigningKey};
     use hex;
     use sha2::{Digest, Sha512};
+    use subtle::ConstantTimeEq;
 
-    use crate::hash::Hash;
+    use crate::hash::{
+        Algorithm, Hash, HashFormat, HashVersion, Hasher, ParseHashError, PrefixError, domain,
+        resolve_prefix,
+    };
+
+    #[test]
+    fn test_hasher_length_prefixes_fields_from_v2() {
+        let digest = |version, fields: [&str; 2]| {
+            let mut hasher = Hasher::new(version);
+            for field in fields {
+                hasher.field(field);
+            }
+            hasher.finalize()
+        };
+        assert_eq!(digest(HashVersion::V1, ["ab", "c"]), digest(HashVersion::V1, ["a", "bc"]));
+        assert_ne!(digest(HashVersion::V2, ["ab", "c"]), digest(HashVersion::V2, ["a", "bc"]));
+        assert_eq!(digest(HashVersion::V1, ["ab", "c"]), Hash::from(Sha512::digest("abc")));
+    }
+
+    #[test]
+    fn test_hasher_byte_layout() {
+        let mut hasher = Hasher::new(HashVersion::V2);
+        hasher.domain(domain::ATTRIBUTE);
+        hasher.field("degree");
+        hasher.presence(true);
+        hasher.update([9; 4]);
+
+        let mut preimage = Vec::new();
+        preimage.extend(19u64.to_le_bytes());
+        preimage.extend(b"attest/v2/attribute");
+        preimage.extend(6u64.to_le_bytes());
+        preimage.extend(b"degree");
+        preimage.push(1);
+        preimage.extend([9; 4]);
+        assert_eq!(hasher.finalize(), Hash::from(Sha512::digest(&preimage)));
+
+        let mut hasher = Hasher::new(HashVersion::V1);
+        hasher.domain(domain::ATTRIBUTE);
+        hasher.field("degree");
+        hasher.presence(true);
+        assert_eq!(hasher.finalize(), Hash::from(Sha512::digest("degree")));
+    }
+
+    #[test]
+    fn test_hash_equality() {
+        let mut other = [7; 64];
+        assert_eq!(Hash::from([7; 64]), Hash::from(other));
+        assert!(bool::from(Hash::from([7; 64]).ct_eq(&Hash::from(other))));
+        other[63] = 8;
+        assert_ne!(Hash::from([7; 64]), Hash::from(other));
+        assert!(!bool::from(Hash::from([7; 64]).ct_eq(&Hash::from(other))));
+        other[0] = 8;
+        assert_ne!(Hash::from([7; 64]), Hash::from(other));
+    }
 
     #[test]
     fn test_hash_default_is_zero() {
         let hash = Hash::default();
-        assert_eq!(hash.0, [0u8; 64]);
+        assert_eq!(hash.bytes, [0u8; 64]);
     }
 
     #[test]
@@ -56,21 +499,12 @@ mod tests {
         hasher.update(b"test data");
         let output = hasher.finalize();
         let hash = Hash::from(output);
-        assert_eq!(hash.0, output[..]);
-    }
-
-    #[test]
-    fn test_hash_from_signature() {
-        let key = SigningKey::generate(&mut rand::thread_rng());
-        let message = b"hello world";
-        let signature = key.sign(message);
-        let hash = Hash::from(signature);
-        assert_eq!(hash.0, signature.to_bytes());
+        assert_eq!(hash.bytes, output[..]);
     }
 
     #[test]
     fn test_hash_serialization() {
-        let original = Hash([1u8; 64]);
+        let original = Hash::from([1u8; 64]);
         let json = serde_json::to_string(&original).unwrap();
         let expected = format!("\"{}\"", hex::encode([1u8; 64]));
         assert_eq!(json, expected);
@@ -80,9 +514,9 @@ mod tests {
     fn test_hash_deserialization() {
         let bytes = [2u8; 64];
         let hex_string = hex::encode(bytes);
-        let json = format!("\"{}\"", hex_string);
+        let json = format!("\"{hex_string}\"");
         let deserialized: Hash = serde_json::from_str(&json).unwrap();
-        assert_eq!(deserialized.0, bytes);
+        assert_eq!(deserialized.bytes, bytes);
     }
 
     #[test]
@@ -91,4 +525,87 @@ mod tests {
         let result: Result<Hash, _> = serde_json::from_str(short_hex);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_hash_parse_round_trips() {
+        let hash = Hash::from(Sha512::digest("abc"));
+        assert_eq!(hash.to_string().parse::<Hash>().unwrap(), hash);
+        assert_eq!(hash.to_string().to_uppercase().parse::<Hash>().unwrap(), hash);
+        assert_eq!(Hash::try_from(hash.as_ref()).unwrap(), hash);
+        assert_eq!(hash.short(), hash.to_string()[..8]);
+    }
+
+    #[test]
+    fn test_hash_parse_failures() {
+        assert_eq!("abc".parse::<Hash>(), Err(ParseHashError::OddLength));
+        assert_eq!("zz".repeat(64).parse::<Hash>(), Err(ParseHashError::InvalidHex));
+        assert_eq!("deadbeef".parse::<Hash>(), Err(ParseHashError::WrongLength(4)));
+        assert_eq!(Hash::try_from(&[0u8; 65][..]), Err(ParseHashError::WrongLength(65)));
+        let error = serde_json::from_str::<Hash>("\"deadbeef\"").unwrap_err();
+        assert!(error.to_string().contains("Hash must be 64 bytes, got 4"));
+    }
+
+    #[test]
+    fn test_resolve_prefix() {
+        let mut first = [0u8; 64];
+        first[..2].copy_from_slice(&[0xab, 0xcd]);
+        let mut second = [0u8; 64];
+        second[..2].copy_from_slice(&[0xab, 0xef]);
+        let hashes = [Hash::from(first), Hash::from(second)];
+        assert_eq!(resolve_prefix("abcd", &hashes), Ok(0));
+        assert_eq!(resolve_prefix("ABE", &hashes), Ok(1));
+        assert_eq!(resolve_prefix(&hashes[1].to_string(), &hashes), Ok(1));
+        assert_eq!(resolve_prefix("ab", &hashes), Err(PrefixError::Ambiguous(2)));
+        assert_eq!(resolve_prefix("", &hashes), Err(PrefixError::Ambiguous(2)));
+        assert_eq!(resolve_prefix("ff", &hashes), Err(PrefixError::NotFound));
+    }
+
+    #[test]
+    fn test_algorithm_vectors() {
+        let digest = |algorithm| {
+            let mut hasher = Hasher::new(HashFormat { version: HashVersion::V1, algorithm });
+            hasher.update("abc");
+            hex::encode(hasher.finalize().bytes)
+        };
+        assert_eq!(
+            digest(Algorithm::Sha512),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+        assert_eq!(
+            digest(Algorithm::Sha3_512),
+            "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0"
+        );
+        // The first 32 bytes of BLAKE3's extended output are its regular digest
+        assert!(
+            digest(Algorithm::Blake3)
+                .starts_with("6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85")
+        );
+    }
+
+    #[test]
+    fn test_hash_algorithm_tag() {
+        let sha512 = Hash::from([3u8; 64]);
+        let blake3 = Hash::new(Algorithm::Blake3, [3u8; 64]);
+        assert_ne!(sha512, blake3);
+        assert_eq!(serde_json::to_string(&sha512).unwrap(), format!("\"{}\"", "03".repeat(64)));
+        let json = serde_json::to_string(&blake3).unwrap();
+        assert_eq!(json, format!("\"blake3:{}\"", "03".repeat(64)));
+        assert_eq!(serde_json::from_str::<Hash>(&json).unwrap(), blake3);
+        let sha3 = Hash::new(Algorithm::Sha3_512, [3u8; 64]);
+        assert_eq!(sha3.to_string().parse::<Hash>().unwrap(), sha3);
+        assert_eq!(
+            format!("md5:{}", "03".repeat(64)).parse::<Hash>(),
+            Err(ParseHashError::UnknownAlgorithm("md5".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_hash_version_serialization() {
+        assert_eq!(serde_json::to_string(&HashVersion::V2).unwrap(), "2");
+        let version: HashVersion = serde_json::from_str("1").unwrap();
+        assert_eq!(version, HashVersion::V1);
+        let version: HashVersion = serde_json::from_str("4").unwrap();
+        assert_eq!(version, HashVersion::V4);
+        assert!(serde_json::from_str::<HashVersion>("5").is_err());
+    }
 }
@@ -0,0 +1,128 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use uuid::Uuid;
+
+/// Fewest hex digits of a UUID accepted as a prefix, so that a stray short argument doesn't
+/// happen to match something
+pub const MIN_PREFIX_LEN: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdError {
+    /// The prefix has fewer than [`MIN_PREFIX_LEN`] hex digits
+    TooShort { prefix: String },
+    /// The prefix has characters other than hex digits and hyphens
+    NotHex { prefix: String },
+    /// No candidate's UUID starts with the prefix
+    NotFound { prefix: String, kind: &'static str },
+    /// Several candidates' UUIDs start with the prefix, listed with their display names
+    Ambiguous { prefix: String, kind: &'static str, candidates: Vec<(Uuid, String)> },
+}
+
+impl Display for IdError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooShort { prefix } =>
+                write!(f, "id '{prefix}' is too short, give at least {MIN_PREFIX_LEN} hex digits"),
+            Self::NotHex { prefix } => write!(f, "id '{prefix}' isn't a UUID prefix"),
+            Self::NotFound { prefix, kind } => write!(f, "no {kind} with id '{prefix}'"),
+            Self::Ambiguous { prefix, kind, candidates } => {
+                let candidates: Vec<_> =
+                    candidates.iter().map(|(uuid, name)| format!("{uuid} ({name})")).collect();
+                write!(
+                    f,
+                    "ambiguous id '{prefix}' matches {} {kind}s: {}",
+                    candidates.len(),
+                    candidates.join(", ")
+                )
+            },
+        }
+    }
+}
+
+impl Error for IdError {}
+
+/// Finds the only candidate whose UUID starts with `prefix`, like git does with commit hashes.
+/// The prefix is matched case-insensitively against the UUID's hex digits, ignoring hyphens, so a
+/// full UUID matches too. `kind` names the candidates in errors, and each candidate comes with the
+/// name listed if the prefix is ambiguous.
+///
+/// # Errors
+///
+/// Returns an error if the prefix is too short or not hex, or if no candidate or more than one
+/// matches.
+pub fn resolve_prefix<D: Display>(
+    prefix: &str, kind: &'static str, candidates: impl IntoIterator<Item = (Uuid, D)>,
+) -> Result<Uuid, IdError> {
+    let digits = prefix.replace('-', "").to_ascii_lowercase();
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(IdError::NotHex { prefix: prefix.to_string() });
+    }
+    if digits.len() < MIN_PREFIX_LEN {
+        return Err(IdError::TooShort { prefix: prefix.to_string() });
+    }
+    let mut matches: Vec<_> = candidates
+        .into_iter()
+        .filter(|(uuid, _)| uuid.simple().to_string().starts_with(&digits))
+        .map(|(uuid, name)| (uuid, name.to_string()))
+        .collect();
+    match matches.len() {
+        0 => Err(IdError::NotFound { prefix: prefix.to_string(), kind }),
+        1 => Ok(matches.remove(0).0),
+        _ => Err(IdError::Ambiguous { prefix: prefix.to_string(), kind, candidates: matches }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> Vec<(Uuid, &'static str)> {
+        ["3f2a1111-0000-4000-8000-000000000000", "3f2a2222-0000-4000-8000-000000000000"]
+            .into_iter()
+            .zip(["Alice Smith", "Bob Jones"])
+            .map(|(uuid, name)| (Uuid::parse_str(uuid).unwrap(), name))
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_unique_prefix() {
+        let bob = candidates()[1].0;
+        assert_eq!(resolve_prefix("3f2a2", "subject", candidates()), Ok(bob));
+        assert_eq!(resolve_prefix("3F2A-22", "subject", candidates()), Ok(bob));
+        assert_eq!(resolve_prefix(&bob.to_string(), "subject", candidates()), Ok(bob));
+    }
+
+    #[test]
+    fn test_resolve_ambiguous_prefix() {
+        let error = resolve_prefix("3f2a", "credential", candidates()).unwrap_err();
+        assert!(matches!(&error, IdError::Ambiguous { candidates, .. } if candidates.len() == 2));
+        assert_eq!(
+            error.to_string(),
+            "ambiguous id '3f2a' matches 2 credentials: 3f2a1111-0000-4000-8000-000000000000 \
+             (Alice Smith), 3f2a2222-0000-4000-8000-000000000000 (Bob Jones)"
+        );
+    }
+
+    #[test]
+    fn test_resolve_prefix_not_found() {
+        assert_eq!(
+            resolve_prefix("3f2b", "issuer", candidates()),
+            Err(IdError::NotFound { prefix: "3f2b".into(), kind: "issuer" })
+        );
+        assert_eq!(
+            resolve_prefix("3f2x", "issuer", candidates()),
+            Err(IdError::NotHex { prefix: "3f2x".into() })
+        );
+    }
+
+    #[test]
+    fn test_resolve_prefix_too_short() {
+        for prefix in ["", "3f2", "3f-2"] {
+            assert_eq!(
+                resolve_prefix(prefix, "issuer", candidates()),
+                Err(IdError::TooShort { prefix: prefix.into() })
+            );
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Local rules the blocks brought in from other nodes, by `blockchain import` or `blockchain sync`,
+//! have to follow on top of validating, read from the `[import]` section of `attestation.toml`.
+//!
+//! A chain validating only tells that its blocks are consistent: whoever holds a key can append
+//! to a chain without authorities. The policy tells which of those blocks the node accepts,
+//! reporting every block breaking it so that the whole import can be refused or accepted at once.
+
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::blockchain::{Block, Blockchain};
+use crate::keys::fingerprint;
+use crate::trust::TrustStore;
+
+/// `[import]` section of `attestation.toml`, every rule off by default
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImportPolicy {
+    /// Whether blocks have to be signed with a key of the trust store. Chains with authorities
+    /// only hold blocks signed by them, which validating already checks, and pass.
+    pub trusted_signers: bool,
+    /// Seconds a block may be timestamped before its predecessor, or after the local clock
+    pub max_clock_skew: Option<u64>,
+    /// Most credentials a block may issue, revoke and anchor together
+    pub max_block_credentials: Option<usize>,
+    /// Largest block, in bytes of its JSON document
+    pub max_block_bytes: Option<usize>,
+}
+
+/// Rule of the policy a block breaks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum Rule {
+    /// Signed with a key the trust store doesn't hold, given by its fingerprint
+    UntrustedSigner { fingerprint: String },
+    /// Timestamped the given number of seconds before its predecessor
+    BeforePredecessor { seconds: i64 },
+    /// Timestamped the given number of seconds after the local clock
+    InTheFuture { seconds: i64 },
+    TooManyCredentials { credentials: usize, limit: usize },
+    TooLarge { bytes: usize, limit: usize },
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UntrustedSigner { fingerprint } =>
+                write!(f, "signed with untrusted key {fingerprint}"),
+            Self::BeforePredecessor { seconds } =>
+                write!(f, "timestamped {seconds} seconds before the previous block"),
+            Self::InTheFuture { seconds } =>
+                write!(f, "timestamped {seconds} seconds after the local clock"),
+            Self::TooManyCredentials { credentials, limit } =>
+                write!(f, "holds {credentials} credentials, more than {limit}"),
+            Self::TooLarge { bytes, limit } => write!(f, "is {bytes} bytes, more than {limit}"),
+        }
+    }
+}
+
+/// Block breaking a rule of the policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Violation {
+    pub height: u64,
+    #[serde(flatten)]
+    pub rule: Rule,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "block #{} {}", self.height, self.rule)
+    }
+}
+
+impl ImportPolicy {
+    /// Checks the chain's blocks from the height on, the ones brought in, against the policy as
+    /// of `now`. The chain has to be validated first, which the signer rule relies on for chains
+    /// with authorities.
+    #[must_use]
+    pub fn check(
+        &self, blockchain: &Blockchain, from: u64, trust: &TrustStore, now: DateTime<Utc>,
+    ) -> Vec<Violation> {
+        let has_authorities =
+            blockchain.genesis().is_some_and(|genesis| genesis.metadata().authorities.is_some());
+        let mut violations = Vec::new();
+        for block in blockchain.blocks().filter(|block| block.height() >= from) {
+            let previous = block.height().checked_sub(1).and_then(|h| blockchain.block(h));
+            let mut violated = |rule| violations.push(Violation { height: block.height(), rule });
+            let signer = &block.signer().verifying;
+            if self.trusted_signers && !has_authorities && !trust.trusts(signer) {
+                violated(Rule::UntrustedSigner { fingerprint: fingerprint(signer) });
+            }
+            if let Some(skew) = self.max_clock_skew {
+                let skew = i64::try_from(skew).ok().and_then(TimeDelta::try_seconds);
+                let skew = skew.unwrap_or(TimeDelta::MAX);
+                if let Some(previous) = previous
+                    && let early = previous.timestamp().signed_duration_since(block.timestamp())
+                    && early > skew
+                {
+                    violated(Rule::BeforePredecessor { seconds: early.num_seconds() });
+                }
+                let ahead = block.timestamp().signed_duration_since(now);
+                if ahead > skew {
+                    violated(Rule::InTheFuture { seconds: ahead.num_seconds() });
+                }
+            }
+            if let Some(limit) = self.max_block_credentials
+                && let credentials = credentials(block)
+                && credentials > limit
+            {
+                violated(Rule::TooManyCredentials { credentials, limit });
+            }
+            if let Some(limit) = self.max_block_bytes
+                && let bytes = serde_json::to_vec(block).map_or(0, |document| document.len())
+                && bytes > limit
+            {
+                violated(Rule::TooLarge { bytes, limit });
+            }
+        }
+        violations
+    }
+}
+
+/// Credentials the block issues, revokes and anchors
+fn credentials(block: &Block) -> usize {
+    block.new_credentials().len()
+        + block.revoked_credentials().len()
+        + block.anchored_credentials().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::blockchain::GenesisBlock;
+    use crate::credential::{Attribute, Credential, Issuer, Subject, ValidDuration};
+    use crate::trust::TrustedIssuer;
+
+    fn trusting(issuer: &Issuer) -> TrustStore {
+        let mut trust = TrustStore::default();
+        trust.trust(TrustedIssuer { verifying: issuer.verifying, uuid: None, name: None });
+        trust
+    }
+
+    #[test]
+    fn test_untrusted_signers() {
+        let genesis = GenesisBlock::new("Test".to_string(), None, None).unwrap();
+        let (ours, our_signing) = Issuer::new("Registrar".to_string());
+        let (peer, peer_signing) = Issuer::new("Unknown office".to_string());
+        let mut blockchain = Blockchain::with_genesis(genesis);
+        blockchain.add_block(Block::new(ours.clone()), &our_signing).unwrap();
+        for _ in 0..2 {
+            blockchain.add_block(Block::new(peer.clone()), &peer_signing).unwrap();
+        }
+        let policy = ImportPolicy { trusted_signers: true, ..ImportPolicy::default() };
+        let now = Utc::now();
+
+        let violations = policy.check(&blockchain, 1, &trusting(&ours), now);
+        let heights: Vec<u64> = violations.iter().map(|violation| violation.height).collect();
+        assert_eq!(heights, [1, 2]);
+        let fingerprint = fingerprint(&peer.verifying);
+        assert_eq!(violations[0].rule, Rule::UntrustedSigner { fingerprint: fingerprint.clone() });
+        assert_eq!(
+            violations[0].to_string(),
+            format!("block #1 signed with untrusted key {fingerprint}")
+        );
+        // Blocks already held aren't checked again
+        assert_eq!(policy.check(&blockchain, 0, &trusting(&ours), now).len(), 2);
+        assert!(policy.check(&blockchain, 1, &trusting(&peer), now).is_empty());
+        let untrusting = TrustStore::default();
+        assert!(ImportPolicy::default().check(&blockchain, 0, &untrusting, now).is_empty());
+
+        // The authorities of a chain having them are the signers it accepts
+        let authorities = Some(vec![peer.clone()]);
+        let genesis = GenesisBlock::new("Test".to_string(), None, authorities).unwrap();
+        let mut blockchain = Blockchain::with_genesis(genesis);
+        blockchain.add_block(Block::new(peer), &peer_signing).unwrap();
+        assert!(policy.check(&blockchain, 0, &trusting(&ours), now).is_empty());
+    }
+
+    #[test]
+    fn test_timestamps_and_limits() {
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let credential = Credential::new(
+            vec![Attribute::new("degree".to_string(), "MSc".to_string())],
+            issuer.clone(),
+            Subject::new("Alice".to_string(), "Smith".to_string()),
+            ValidDuration::starting(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
+        );
+        let mut blockchain = Blockchain::new();
+        let mut block = Block::new(issuer.clone());
+        let signed = credential.sign(&signing, false, blockchain.format()).unwrap();
+        block.add_credential(signed, false).unwrap();
+        blockchain.add_block(block, &signing).unwrap();
+        blockchain.add_block(Block::new(issuer), &signing).unwrap();
+        let trust = TrustStore::default();
+
+        let limits = ImportPolicy {
+            max_block_credentials: Some(0),
+            max_block_bytes: Some(10),
+            ..ImportPolicy::default()
+        };
+        let violations = limits.check(&blockchain, 0, &trust, Utc::now());
+        let too_many = Rule::TooManyCredentials { credentials: 1, limit: 0 };
+        assert_eq!(violations[0].rule, too_many);
+        assert!(violations[1..].iter().all(|v| matches!(v.rule, Rule::TooLarge { limit: 10, .. })));
+        assert_eq!(violations.len(), 3);
+
+        // Seen from an hour ago, the blocks are dated in the future
+        let skew = ImportPolicy { max_clock_skew: Some(60), ..ImportPolicy::default() };
+        let an_hour_ago = Utc::now() - TimeDelta::hours(1);
+        let violations = skew.check(&blockchain, 1, &trust, an_hour_ago);
+        assert_eq!(violations.len(), 1);
+        assert!(matches!(violations[0].rule, Rule::InTheFuture { seconds } if seconds > 3500));
+        assert!(skew.check(&blockchain, 0, &trust, Utc::now()).is_empty());
+        let unbounded = ImportPolicy { max_clock_skew: Some(u64::MAX), ..ImportPolicy::default() };
+        assert!(unbounded.check(&blockchain, 0, &trust, an_hour_ago).is_empty());
+    }
+}
@@ -0,0 +1,6 @@
+//! Credentials in the formats other systems understand, for holders whose wallets or relying
+//! parties don't read this crate's own.
+
+pub mod cose;
+pub mod jwt;
+pub mod w3c;
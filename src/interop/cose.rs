@@ -0,0 +1,188 @@
+//! Credentials as `COSE_Sign1` structures (RFC 9052) signed with the issuer's ed25519 key, for NFC
+//! tags, QR codes and other constrained channels.
+//!
+//! The payload is the credential in CBOR, as [`compact::to_cbor`] encodes it: fields in a fixed
+//! order and maps sorted, so the same credential always gives the same bytes. The signature is over
+//! the `Sig_structure` of the protected header, an empty external AAD and that payload, not over
+//! the credential's chain hash.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use ciborium::Value;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+use crate::compact;
+use crate::credential::Credential;
+use crate::keys::fingerprint;
+use crate::signer::{BlockSigner, SignError};
+
+/// CBOR tag of `COSE_Sign1` structures
+pub const COSE_SIGN1_TAG: u64 = 18;
+
+/// Header label of the algorithm
+const ALG: i64 = 1;
+/// Header label of the key identifier
+const KID: i64 = 4;
+/// Algorithm identifier of `EdDSA`
+const EDDSA: i64 = -8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoseError {
+    /// The bytes aren't a `COSE_Sign1` of a credential
+    Malformed(String),
+    /// The structure is signed with another algorithm than `EdDSA`
+    Algorithm,
+    /// The signature doesn't check against the issuer key
+    Signature,
+    Encode(String),
+    Sign(SignError),
+}
+
+impl Display for CoseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(error) => write!(f, "not a COSE_Sign1 credential: {error}"),
+            Self::Algorithm => f.write_str("unsupported algorithm, expected EdDSA"),
+            Self::Signature => f.write_str("the signature doesn't match the issuer key"),
+            Self::Encode(error) => write!(f, "failed to encode: {error}"),
+            Self::Sign(error) => write!(f, "failed to sign: {error}"),
+        }
+    }
+}
+
+impl Error for CoseError {}
+
+fn encode(value: &Value) -> Result<Vec<u8>, CoseError> {
+    compact::to_cbor(value).map_err(|e| CoseError::Encode(e.to_string()))
+}
+
+/// What the signature is over: the context, the protected header, an empty external AAD and the
+/// payload
+fn sig_structure(protected: &[u8], payload: &[u8]) -> Result<Vec<u8>, CoseError> {
+    encode(&Value::Array(vec![
+        Value::Text("Signature1".into()),
+        Value::Bytes(protected.to_vec()),
+        Value::Bytes(Vec::new()),
+        Value::Bytes(payload.to_vec()),
+    ]))
+}
+
+/// Signs the credential into a tagged `COSE_Sign1`, with the key's fingerprint as the `kid`
+///
+/// # Errors
+/// Returns [`CoseError::Sign`] if the signer can't sign.
+pub fn sign(credential: &Credential, signer: &dyn BlockSigner) -> Result<Vec<u8>, CoseError> {
+    let payload = compact::to_cbor(credential).map_err(|e| CoseError::Encode(e.to_string()))?;
+    let protected = encode(&Value::Map(vec![(ALG.into(), EDDSA.into())]))?;
+    let signature = signer.sign(&sig_structure(&protected, &payload)?).map_err(CoseError::Sign)?;
+    let kid = fingerprint(&signer.verifying_key()).into_bytes();
+    encode(&Value::Tag(
+        COSE_SIGN1_TAG,
+        Box::new(Value::Array(vec![
+            Value::Bytes(protected),
+            Value::Map(vec![(KID.into(), Value::Bytes(kid))]),
+            Value::Bytes(payload),
+            Value::Bytes(signature.to_bytes().to_vec()),
+        ])),
+    ))
+}
+
+/// Checks the `COSE_Sign1`'s signature against the issuer key and reads the credential it signs
+///
+/// # Errors
+/// Returns [`CoseError::Malformed`] if the bytes aren't a `COSE_Sign1` of a credential,
+/// [`CoseError::Algorithm`] if it isn't signed with `EdDSA` and [`CoseError::Signature`] if it
+/// isn't signed with the key.
+pub fn verify(bytes: &[u8], key: &VerifyingKey) -> Result<Credential, CoseError> {
+    let malformed = |error: &str| CoseError::Malformed(error.into());
+    let value: Value =
+        compact::from_cbor(bytes).map_err(|e| CoseError::Malformed(e.to_string()))?;
+    // The tag is optional when the context tells it's a COSE_Sign1
+    let value = match value {
+        Value::Tag(COSE_SIGN1_TAG, value) => *value,
+        Value::Tag(tag, _) => return Err(CoseError::Malformed(format!("unexpected tag {tag}"))),
+        value => value,
+    };
+    let Value::Array(parts) = value else {
+        return Err(malformed("expected an array"));
+    };
+    let [Value::Bytes(protected), Value::Map(_), Value::Bytes(payload), Value::Bytes(signature)] =
+        parts.as_slice()
+    else {
+        return Err(malformed("expected protected header, header, payload and signature"));
+    };
+    let header: Value =
+        compact::from_cbor(protected).map_err(|e| CoseError::Malformed(e.to_string()))?;
+    let alg = header
+        .as_map()
+        .and_then(|map| map.iter().find(|(label, _)| *label == Value::from(ALG)))
+        .map(|(_, alg)| alg);
+    if alg != Some(&Value::from(EDDSA)) {
+        return Err(CoseError::Algorithm);
+    }
+    let signature =
+        Signature::from_slice(signature).map_err(|e| CoseError::Malformed(e.to_string()))?;
+    key.verify(&sig_structure(protected, payload)?, &signature)
+        .map_err(|_| CoseError::Signature)?;
+    compact::from_cbor(payload).map_err(|e| CoseError::Malformed(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::Blockchain;
+    use crate::compact::CompactCredential;
+    use crate::credential::Issuer;
+    use crate::exchange::CredentialExport;
+    use crate::hash::HashVersion;
+
+    #[test]
+    fn test_cose_roundtrip() {
+        let (credential, signing) = Credential::sample();
+        let cose = sign(&credential, &signing).unwrap();
+        let read = verify(&cose, &credential.issuer.verifying).unwrap();
+        assert_eq!(
+            serde_json::to_value(&read).unwrap(),
+            serde_json::to_value(&credential).unwrap()
+        );
+        // The same credential signs to the same bytes, ed25519 being deterministic
+        assert_eq!(sign(&credential, &signing).unwrap(), cose);
+
+        let other = Issuer::new("Impostor".to_string()).0.verifying;
+        assert_eq!(verify(&cose, &other).err(), Some(CoseError::Signature));
+        assert!(matches!(verify(b"not cbor", &other), Err(CoseError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_cose_rejects_flipped_payload_bits() {
+        let (credential, signing) = Credential::sample();
+        let cose = sign(&credential, &signing).unwrap();
+        let payload = compact::to_cbor(&credential).unwrap();
+        let start = cose.windows(payload.len()).position(|w| w == payload).unwrap();
+        for bit in [0, 7, 8 * payload.len() / 2, 8 * payload.len() - 1] {
+            let mut flipped = cose.clone();
+            flipped[start + bit / 8] ^= 1 << (bit % 8);
+            let error = verify(&flipped, &credential.issuer.verifying).err();
+            assert_eq!(error, Some(CoseError::Signature));
+        }
+    }
+
+    #[test]
+    fn test_cbor_is_smaller_than_json() {
+        let (credential, signing) = Credential::sample();
+        let issuance = credential.sign(&signing, false, HashVersion::V2).unwrap();
+        let revocation = credential.sign(&signing, true, HashVersion::V2).unwrap();
+        let compact = CompactCredential { credential, issuance, revocation };
+        let cbor = compact::to_cbor(&compact).unwrap();
+        let CompactCredential { credential, issuance, revocation } = compact;
+        let json = CredentialExport::new(credential, issuance, revocation, &Blockchain::new());
+        let json = json.to_string();
+        assert!(
+            cbor.len() * 4 < json.len() * 3,
+            "{} bytes of CBOR, {} of JSON",
+            cbor.len(),
+            json.len()
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Credentials as compact JWS tokens signed with the issuer's ed25519 key (`alg` `EdDSA`), for
+//! relying parties that only consume JWTs.
+//!
+//! Unlike the W3C export, the token signs its whole payload, so its claims can be trusted once the
+//! signature checks against the issuer's key. The payload's `vc.credentialHash` is the credential's
+//! issuance hash, to find the credential on the chain by.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, Utc};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::w3c::{CREDENTIAL_TYPE, VcAttribute, did_key};
+use crate::credential::{Credential, CredentialSubject, SignedCredential};
+use crate::hash::Hash;
+use crate::keys::fingerprint;
+use crate::signer::{BlockSigner, SignError};
+
+pub const ALGORITHM: &str = "EdDSA";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JwtError {
+    /// The token isn't three base64url parts of JSON
+    Malformed(String),
+    /// The token is signed with another algorithm than `EdDSA`
+    Algorithm(String),
+    /// The signature doesn't check against the issuer key
+    Signature,
+    NotYetValid {
+        nbf: i64,
+    },
+    Expired {
+        exp: i64,
+    },
+    Sign(SignError),
+}
+
+impl Display for JwtError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let date = |timestamp: &i64| {
+            DateTime::from_timestamp(*timestamp, 0).map_or(timestamp.to_string(), |d| d.to_string())
+        };
+        match self {
+            Self::Malformed(error) => write!(f, "malformed token: {error}"),
+            Self::Algorithm(alg) =>
+                write!(f, "unsupported algorithm '{alg}', expected {ALGORITHM}"),
+            Self::Signature => f.write_str("the token's signature doesn't match the issuer key"),
+            Self::NotYetValid { nbf } => write!(f, "the token isn't valid before {}", date(nbf)),
+            Self::Expired { exp } => write!(f, "the token expired at {}", date(exp)),
+            Self::Sign(error) => write!(f, "failed to sign the token: {error}"),
+        }
+    }
+}
+
+impl Error for JwtError {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Header {
+    pub alg: String,
+    pub typ: String,
+    /// Fingerprint of the key the token is signed with
+    pub kid: String,
+}
+
+/// Credential claims, named as the W3C JWT encoding of verifiable credentials names them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VcClaims {
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+    /// UUID of the issuer, whose key `iss` identifies
+    pub issuer: Uuid,
+    /// Claims of the credential, in the order they were hashed
+    pub attributes: Vec<VcAttribute>,
+    /// Issuance hash of the credential as recorded on the chain
+    pub credential_hash: Hash,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    /// `did:key` of the key the token is signed with
+    pub iss: String,
+    /// Subject UUID, or the subject's commitment for credentials that only commit to it
+    pub sub: String,
+    /// Credential UUID
+    pub jti: Uuid,
+    pub iat: i64,
+    /// Start of the first day of the credential's valid duration
+    pub nbf: i64,
+    /// Start of the day after the last day of the credential's valid duration, none if it never
+    /// ends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+    pub vc: VcClaims,
+}
+
+impl Claims {
+    /// Claims of the credential, to be signed by the issuer with the given key
+    #[must_use]
+    pub fn new(credential: &Credential, issuance: &SignedCredential, key: &VerifyingKey) -> Self {
+        let start = |date: NaiveDate| date.and_time(NaiveTime::MIN).and_utc().timestamp();
+        let valid = &credential.valid_duration;
+        Self {
+            iss: did_key(key),
+            sub: match &credential.subject {
+                CredentialSubject::Disclosed(subject) => subject.uuid.to_string(),
+                CredentialSubject::Committed(commitment) => commitment.commitment.to_string(),
+            },
+            jti: credential.uuid,
+            iat: Utc::now().timestamp(),
+            nbf: start(valid.from),
+            exp: valid.to.and_then(|to| to.checked_add_days(Days::new(1))).map(start),
+            vc: VcClaims {
+                types: vec!["VerifiableCredential".into(), CREDENTIAL_TYPE.into()],
+                issuer: credential.issuer.uuid,
+                attributes: credential
+                    .attributes
+                    .iter()
+                    .map(|a| VcAttribute { name: a.name.clone(), value: a.value.clone() })
+                    .collect(),
+                credential_hash: issuance.credential.clone(),
+            },
+        }
+    }
+}
+
+fn encode_part(value: &impl Serialize) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(value).unwrap_or_default())
+}
+
+fn decode_part<T: DeserializeOwned>(part: &str) -> Result<T, JwtError> {
+    let bytes = URL_SAFE_NO_PAD.decode(part).map_err(|e| JwtError::Malformed(e.to_string()))?;
+    serde_json::from_slice(&bytes).map_err(|e| JwtError::Malformed(e.to_string()))
+}
+
+/// Signs the claims into a compact JWS
+///
+/// # Errors
+/// Returns [`JwtError::Sign`] if the signer can't sign.
+pub fn encode(claims: &Claims, signer: &dyn BlockSigner) -> Result<String, JwtError> {
+    let header = Header {
+        alg: ALGORITHM.into(),
+        typ: "JWT".into(),
+        kid: fingerprint(&signer.verifying_key()),
+    };
+    let input = format!("{}.{}", encode_part(&header), encode_part(claims));
+    let signature = signer.sign(input.as_bytes()).map_err(JwtError::Sign)?;
+    Ok(format!("{input}.{}", URL_SAFE_NO_PAD.encode(signature.to_bytes())))
+}
+
+/// Checks the token's signature against the issuer key and that it's valid at `now`, offline
+///
+/// # Errors
+/// Returns [`JwtError::Malformed`] or [`JwtError::Algorithm`] if the token isn't an `EdDSA`
+/// JWS, [`JwtError::Signature`] if it isn't signed with the key, and
+/// [`JwtError::NotYetValid`] or [`JwtError::Expired`] if it isn't valid at `now`.
+pub fn verify(token: &str, key: &VerifyingKey, now: DateTime<Utc>) -> Result<Claims, JwtError> {
+    let token = token.trim();
+    let (input, signature) =
+        token.rsplit_once('.').ok_or_else(|| JwtError::Malformed("expected 3 parts".into()))?;
+    let (header, payload) =
+        input.split_once('.').ok_or_else(|| JwtError::Malformed("expected 3 parts".into()))?;
+    let header: Header = decode_part(header)?;
+    if header.alg != ALGORITHM {
+        return Err(JwtError::Algorithm(header.alg));
+    }
+    let signature =
+        URL_SAFE_NO_PAD.decode(signature).map_err(|e| JwtError::Malformed(e.to_string()))?;
+    let signature =
+        Signature::from_slice(&signature).map_err(|e| JwtError::Malformed(e.to_string()))?;
+    key.verify(input.as_bytes(), &signature).map_err(|_| JwtError::Signature)?;
+    let claims: Claims = decode_part(payload)?;
+    let now = now.timestamp();
+    if now < claims.nbf {
+        return Err(JwtError::NotYetValid { nbf: claims.nbf });
+    }
+    if let Some(exp) = claims.exp
+        && now >= exp
+    {
+        return Err(JwtError::Expired { exp });
+    }
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credential::Issuer;
+    use crate::hash::HashVersion;
+
+    fn token() -> (String, Credential) {
+        let (credential, signing) = Credential::sample();
+        let issuance = credential.sign(&signing, false, HashVersion::V2).unwrap();
+        let claims = Claims::new(&credential, &issuance, &credential.issuer.verifying);
+        (encode(&claims, &signing).unwrap(), credential)
+    }
+
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap().and_time(NaiveTime::MIN).and_utc()
+    }
+
+    #[test]
+    fn test_jwt_roundtrip() {
+        let (token, credential) = token();
+        let key = credential.issuer.verifying;
+        let header: Header = decode_part(token.split('.').next().unwrap()).unwrap();
+        assert_eq!(header.alg, ALGORITHM);
+        assert_eq!(header.kid, fingerprint(&key));
+
+        let claims = verify(&token, &key, at(2025, 6, 1)).unwrap();
+        assert_eq!(claims.iss, did_key(&key));
+        let subject = credential.subject.disclosed().unwrap();
+        assert_eq!((claims.sub, claims.jti), (subject.uuid.to_string(), credential.uuid));
+        assert_eq!(claims.vc.attributes[0].value, "MSc");
+        assert_eq!(claims.vc.credential_hash, credential.hash(false, HashVersion::V2));
+        // Valid through the last day, expired on the next
+        assert!(verify(&token, &key, at(2028, 12, 31)).is_ok());
+        assert_eq!(
+            verify(&token, &key, at(2029, 1, 1)),
+            Err(JwtError::Expired { exp: at(2029, 1, 1).timestamp() })
+        );
+        assert!(matches!(
+            verify(&token, &key, at(2023, 12, 31)),
+            Err(JwtError::NotYetValid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_jwt_rejects_tampering() {
+        let (token, credential) = token();
+        let key = credential.issuer.verifying;
+        let parts: Vec<_> = token.split('.').collect();
+        let mut claims: Claims = decode_part(parts[1]).unwrap();
+        claims.vc.attributes[0].value = "PhD".into();
+        let tampered = format!("{}.{}.{}", parts[0], encode_part(&claims), parts[2]);
+        assert_eq!(verify(&tampered, &key, at(2025, 6, 1)), Err(JwtError::Signature));
+
+        let other = Issuer::new("Impostor".to_string()).0.verifying;
+        assert_eq!(verify(&token, &other, at(2025, 6, 1)), Err(JwtError::Signature));
+        let header = Header { alg: "none".into(), typ: "JWT".into(), kid: String::new() };
+        let unsigned = format!("{}.{}.", encode_part(&header), parts[1]);
+        assert_eq!(
+            verify(&unsigned, &key, at(2025, 6, 1)),
+            Err(JwtError::Algorithm("none".into()))
+        );
+        assert!(matches!(verify("not a token", &key, at(2025, 6, 1)), Err(JwtError::Malformed(_))));
+    }
+}
@@ -0,0 +1,316 @@
+//! W3C Verifiable Credentials, as in the 1.1 data model, with the issuer identified by a
+//! `did:key`.
+//!
+//! The proof is shaped like an `Ed25519Signature2020` proof, but its `proofValue` isn't a
+//! signature over the document canonicalized with URDNA2015: it's the issuer's existing signature
+//! over the credential's issuance hash, which the proof carries as `credentialHash`. Verifying it
+//! takes checking `proofValue` against `credentialHash` with the issuer's key, and recomputing the
+//! hash from the credential kept on the chain to tie the two together. The rest of the document
+//! isn't signed, so a verifier has to take its claims from the chain's credential, not from the
+//! document.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::credential::{self, Credential, SignedCredential};
+use crate::hash::Hash;
+
+/// Base context of every verifiable credential
+pub const CONTEXT_V1: &str = "https://www.w3.org/2018/credentials/v1";
+
+/// Context defining the `Ed25519Signature2020` proof terms
+pub const ED25519_2020_CONTEXT: &str = "https://w3id.org/security/suites/ed25519-2020/v1";
+
+/// Type of the credentials exported, along with `VerifiableCredential`
+pub const CREDENTIAL_TYPE: &str = "AttributesAttestationCredential";
+
+pub const PROOF_TYPE: &str = "Ed25519Signature2020";
+
+/// Multicodec prefix of ed25519 public keys, as a varint
+const ED25519_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum W3cError {
+    /// The issuer isn't a `did:key` of an ed25519 key
+    Did(String),
+    /// The proof isn't one this module writes
+    Proof(String),
+    /// The proof's signature doesn't check against the issuer's key
+    Signature,
+}
+
+impl Display for W3cError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Did(error) => write!(f, "invalid issuer DID: {error}"),
+            Self::Proof(error) => write!(f, "invalid proof: {error}"),
+            Self::Signature => f.write_str("the proof's signature doesn't match the issuer key"),
+        }
+    }
+}
+
+impl Error for W3cError {}
+
+/// `did:key` identifying the ed25519 key: its multicodec bytes in multibase base58btc
+#[must_use]
+pub fn did_key(key: &VerifyingKey) -> String {
+    let bytes = [&ED25519_MULTICODEC[..], key.as_bytes()].concat();
+    format!("did:key:z{}", bs58::encode(bytes).into_string())
+}
+
+/// Reads the ed25519 key back from a `did:key`
+///
+/// # Errors
+/// Returns [`W3cError::Did`] if the DID isn't a `did:key` of an ed25519 key.
+pub fn key_from_did(did: &str) -> Result<VerifyingKey, W3cError> {
+    let encoded = did
+        .strip_prefix("did:key:z")
+        .ok_or_else(|| W3cError::Did(format!("'{did}' isn't a base58btc did:key")))?;
+    let bytes = bs58::decode(encoded).into_vec().map_err(|e| W3cError::Did(e.to_string()))?;
+    let key = bytes
+        .strip_prefix(&ED25519_MULTICODEC[..])
+        .ok_or_else(|| W3cError::Did("not an ed25519 key".into()))?;
+    let key: &[u8; 32] = key
+        .try_into()
+        .map_err(|_| W3cError::Did(format!("key must be 32 bytes, got {}", key.len())))?;
+    VerifyingKey::from_bytes(key).map_err(|e| W3cError::Did(e.to_string()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VcIssuer {
+    /// `did:key` of the key the issuer signed the credential with
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VcAttribute {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialSubject {
+    /// Subject UUID as a URN, or the subject's commitment for credentials that only commit to it
+    pub id: String,
+    /// Left out along with the family name for credentials that only commit to their subject
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub given_name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub family_name: String,
+    /// Further fields telling the subject apart from namesakes
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, String>,
+    /// Claims of the credential, in the order they were hashed
+    pub attributes: Vec<VcAttribute>,
+}
+
+/// Issuer's signature over the credential's issuance hash, see the module documentation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Proof {
+    #[serde(rename = "type")]
+    pub proof_type: String,
+    pub created: DateTime<Utc>,
+    /// Issuer DID followed by the key's fragment, as `did:key` verification methods are named
+    pub verification_method: String,
+    pub proof_purpose: String,
+    /// Issuance hash of the credential as recorded on the chain, which the signature is over
+    pub credential_hash: Hash,
+    /// Signature in multibase base58btc
+    pub proof_value: String,
+}
+
+/// Credential as a W3C verifiable credential, displayed as indented JSON
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifiableCredential {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    /// Credential UUID as a URN
+    pub id: String,
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+    pub issuer: VcIssuer,
+    pub issuance_date: DateTime<Utc>,
+    /// Start of the first day of the credential's valid duration
+    pub valid_from: DateTime<Utc>,
+    /// End of the last day of the credential's valid duration, none if it never ends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<DateTime<Utc>>,
+    pub credential_subject: CredentialSubject,
+    pub proof: Proof,
+}
+
+fn start_of(date: NaiveDate) -> DateTime<Utc> { date.and_time(NaiveTime::MIN).and_utc() }
+
+fn end_of(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(23, 59, 59).unwrap_or_default().and_utc()
+}
+
+impl VerifiableCredential {
+    /// Describes the credential with the issuer's signature of its issuance hash as the proof
+    #[must_use]
+    pub fn new(credential: &Credential, issuance: &SignedCredential) -> Self {
+        let did = did_key(&credential.issuer.verifying);
+        let valid_from = start_of(credential.valid_duration.from);
+        // Credentials stored by versions that didn't record it count as issued once valid
+        let issuance_date = if credential.issued_at == Credential::UNKNOWN_ISSUANCE {
+            valid_from
+        } else {
+            credential.issued_at
+        };
+        let subject = match &credential.subject {
+            credential::CredentialSubject::Disclosed(subject) => CredentialSubject {
+                id: format!("urn:uuid:{}", subject.uuid),
+                given_name: subject.name.clone(),
+                family_name: subject.surname.clone(),
+                details: subject.extra.clone(),
+                attributes: Vec::new(),
+            },
+            credential::CredentialSubject::Committed(commitment) => CredentialSubject {
+                id: format!("urn:attest:subject-commitment:{}", commitment.commitment),
+                given_name: String::new(),
+                family_name: String::new(),
+                details: BTreeMap::new(),
+                attributes: Vec::new(),
+            },
+        };
+        Self {
+            context: vec![CONTEXT_V1.into(), ED25519_2020_CONTEXT.into()],
+            id: format!("urn:uuid:{}", credential.uuid),
+            types: vec!["VerifiableCredential".into(), CREDENTIAL_TYPE.into()],
+            issuer: VcIssuer { id: did.clone(), name: credential.issuer.name.clone() },
+            issuance_date,
+            valid_from,
+            valid_until: credential.valid_duration.to.map(end_of),
+            credential_subject: CredentialSubject {
+                attributes: credential
+                    .attributes
+                    .iter()
+                    .map(|a| VcAttribute { name: a.name.clone(), value: a.value.clone() })
+                    .collect(),
+                ..subject
+            },
+            proof: Proof {
+                proof_type: PROOF_TYPE.into(),
+                created: issuance_date,
+                verification_method: format!("{did}#{}", &did["did:key:".len()..]),
+                proof_purpose: "assertionMethod".into(),
+                credential_hash: issuance.credential.clone(),
+                proof_value: format!("z{}", bs58::encode(issuance.signature.0).into_string()),
+            },
+        }
+    }
+
+    /// Checks the proof's signature over its credential hash against the issuer's `did:key`.
+    /// Whether the hash is that of a credential on the chain is for the caller to check.
+    ///
+    /// # Errors
+    /// Returns [`W3cError::Did`] if the issuer isn't an ed25519 `did:key`, [`W3cError::Proof`]
+    /// if the proof isn't one [`VerifiableCredential::new`] writes and [`W3cError::Signature`]
+    /// if the signature doesn't check.
+    pub fn verify_proof(&self) -> Result<VerifyingKey, W3cError> {
+        let key = key_from_did(&self.issuer.id)?;
+        let proof = &self.proof;
+        if proof.proof_type != PROOF_TYPE {
+            return Err(W3cError::Proof(format!("unsupported proof type '{}'", proof.proof_type)));
+        }
+        if proof.verification_method.split('#').next() != Some(self.issuer.id.as_str()) {
+            return Err(W3cError::Proof("verification method isn't the issuer's key".into()));
+        }
+        let signature = proof
+            .proof_value
+            .strip_prefix('z')
+            .ok_or_else(|| W3cError::Proof("proof value isn't base58btc".into()))?;
+        let signature =
+            bs58::decode(signature).into_vec().map_err(|e| W3cError::Proof(e.to_string()))?;
+        let signature =
+            Signature::from_slice(&signature).map_err(|e| W3cError::Proof(e.to_string()))?;
+        key.verify(&proof.credential_hash.bytes, &signature).map_err(|_| W3cError::Signature)?;
+        Ok(key)
+    }
+}
+
+impl Display for VerifiableCredential {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(&self).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credential::Issuer;
+    use crate::hash::HashVersion;
+
+    fn credential() -> (Credential, SignedCredential) {
+        let (credential, signing) = Credential::sample();
+        let issuance = credential.sign(&signing, false, HashVersion::V2).unwrap();
+        (credential, issuance)
+    }
+
+    #[test]
+    fn test_did_key_roundtrip() {
+        let (credential, _) = credential();
+        let did = did_key(&credential.issuer.verifying);
+        // Every ed25519 did:key starts the same, from the multicodec prefix
+        assert!(did.starts_with("did:key:z6Mk"));
+        assert_eq!(key_from_did(&did), Ok(credential.issuer.verifying));
+        assert!(matches!(key_from_did("did:web:example.org"), Err(W3cError::Did(_))));
+        assert!(matches!(key_from_did("did:key:z6Mk"), Err(W3cError::Did(_))));
+    }
+
+    #[test]
+    fn test_verifiable_credential_fields() {
+        let (credential, issuance) = credential();
+        let vc = VerifiableCredential::new(&credential, &issuance);
+        let json: serde_json::Value = serde_json::from_str(&vc.to_string()).unwrap();
+
+        assert_eq!(json["@context"][0], CONTEXT_V1);
+        assert_eq!(json["type"], serde_json::json!(["VerifiableCredential", CREDENTIAL_TYPE]));
+        assert_eq!(json["id"], format!("urn:uuid:{}", credential.uuid));
+        assert_eq!(json["issuer"]["id"], did_key(&credential.issuer.verifying));
+        assert!(json["issuanceDate"].is_string());
+        assert_eq!(json["validFrom"], "2024-01-01T00:00:00Z");
+        assert_eq!(json["validUntil"], "2028-12-31T23:59:59Z");
+        let subject = &json["credentialSubject"];
+        let uuid = credential.subject.disclosed().unwrap().uuid;
+        assert_eq!(subject["id"], format!("urn:uuid:{uuid}"));
+        assert_eq!(subject["givenName"], "Alice");
+        assert_eq!(subject["details"]["born"], "1990-04-01");
+        assert_eq!(
+            subject["attributes"][1],
+            serde_json::json!({
+                "name": "field",
+                "value": "Physics"
+            })
+        );
+        assert_eq!(json["proof"]["type"], PROOF_TYPE);
+        assert_eq!(json["proof"]["proofPurpose"], "assertionMethod");
+        assert_eq!(json["proof"]["credentialHash"], issuance.credential.to_string());
+    }
+
+    #[test]
+    fn test_proof_verifies_against_issuer_key() {
+        let (credential, issuance) = credential();
+        let vc = VerifiableCredential::new(&credential, &issuance);
+        let read: VerifiableCredential = serde_json::from_str(&vc.to_string()).unwrap();
+        assert_eq!(read, vc);
+        assert_eq!(read.verify_proof(), Ok(credential.issuer.verifying));
+        assert_eq!(read.proof.credential_hash, credential.hash(false, HashVersion::V2));
+
+        let mut tampered = read.clone();
+        tampered.proof.credential_hash = credential.hash(true, HashVersion::V2);
+        assert_eq!(tampered.verify_proof(), Err(W3cError::Signature));
+        let mut impostor = read;
+        impostor.issuer.id = did_key(&Issuer::new("Impostor".to_string()).0.verifying);
+        assert!(matches!(impostor.verify_proof(), Err(W3cError::Proof(_))));
+    }
+}
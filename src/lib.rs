@@ -1,7 +1,45 @@
 #![feature(file_buffered)]
 #![warn(clippy::pedantic)]
 
-pub mod cli;
+pub mod anchor;
+pub mod audit;
 pub mod blockchain;
+pub mod cache;
+pub mod cli;
+pub mod compact;
+pub mod config;
 pub mod credential;
+pub mod csv_import;
+pub mod evidence;
+pub mod exchange;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod hash;
+pub mod ids;
+pub mod import_policy;
+pub mod interop;
+pub mod keys;
+pub mod merkle;
+pub mod nonrevocation;
+pub mod presentation;
+pub mod progress;
+pub mod proof;
+pub mod qr;
+pub mod query;
+#[cfg(feature = "client")]
+pub mod remote;
+pub mod requests;
+pub mod revocation_list;
+pub mod schema;
+pub mod server;
+pub mod signature;
+pub mod signer;
+pub mod site;
+pub mod sparse_merkle;
+pub mod status_list;
+pub mod sync;
+pub mod table;
+pub mod template;
+pub mod trust;
+pub mod ui;
+pub mod webhooks;
@@ -1,8 +1,12 @@
+use std::process::ExitCode;
+
 use attributes_attestation::cli::Cli;
 use clap::Parser;
 
-fn main() {
+fn main() -> ExitCode {
     if let Err(s) = Cli::parse().run() {
         eprintln!("{s}");
+        return ExitCode::FAILURE;
     }
+    ExitCode::SUCCESS
 }
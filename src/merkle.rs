@@ -0,0 +1,177 @@
+//! Merkle trees over the credential entries of a block, so that a single entry can be proven to
+//! be in the block with a path of hashes instead of the whole block.
+//!
+//! Leaves are sorted and deduplicated before the tree is built. A level with an odd number of
+//! nodes carries its last node up unpaired rather than pairing it with itself, so that no two sets
+//! of leaves share a root.
+
+use serde::{Deserialize, Serialize};
+
+use crate::hash::{Algorithm, Hash, HashFormat, HashVersion, Hasher, domain};
+
+/// Which side of the node on the path a sibling is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofStep {
+    pub sibling: Hash,
+    pub side: Side,
+}
+
+/// Siblings of the nodes from a leaf up to the root, leaf first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub path: Vec<ProofStep>,
+}
+
+impl MerkleProof {
+    /// Whether the path leads from `leaf` to `root`
+    #[must_use]
+    pub fn verify(&self, root: &Hash, leaf: &Hash) -> bool {
+        let node = self.path.iter().fold(leaf.clone(), |node, step| match step.side {
+            Side::Left => parent(&step.sibling, &node),
+            Side::Right => parent(&node, &step.sibling),
+        });
+        &node == root
+    }
+}
+
+fn hasher(algorithm: Algorithm) -> Hasher {
+    Hasher::new(HashFormat { version: HashVersion::V3, algorithm })
+}
+
+fn parent(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = hasher(left.algorithm);
+    hasher.domain(domain::MERKLE_NODE);
+    hasher.update(left.bytes);
+    hasher.update(right.bytes);
+    hasher.finalize()
+}
+
+/// Hash of a leaf, fed by `update` after the domain tag and the leaf's kind
+pub fn leaf(algorithm: Algorithm, kind: u8, update: impl FnOnce(&mut Hasher)) -> Hash {
+    let mut hasher = hasher(algorithm);
+    hasher.domain(domain::MERKLE_LEAF);
+    hasher.update([kind]);
+    update(&mut hasher);
+    hasher.finalize()
+}
+
+fn sorted(mut leaves: Vec<Hash>) -> Vec<Hash> {
+    leaves.sort_by_key(|leaf| leaf.bytes);
+    leaves.dedup();
+    leaves
+}
+
+/// Levels of the tree from the sorted leaves up to the root
+fn levels(leaves: Vec<Hash>) -> Vec<Vec<Hash>> {
+    let mut levels = vec![leaves];
+    while levels[levels.len() - 1].len() > 1 {
+        let level = levels[levels.len() - 1]
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => parent(left, right),
+                [last] => last.clone(),
+                _ => unreachable!("chunks of two"),
+            })
+            .collect();
+        levels.push(level);
+    }
+    levels
+}
+
+/// Root of the tree over the leaves, a tagged hash of nothing if there are none
+#[must_use]
+pub fn root(leaves: Vec<Hash>, algorithm: Algorithm) -> Hash {
+    if let Some(root) = levels(sorted(leaves)).pop().and_then(|mut root| root.pop()) {
+        return root;
+    }
+    let mut hasher = hasher(algorithm);
+    hasher.domain(domain::MERKLE_EMPTY);
+    hasher.finalize()
+}
+
+/// Path from `leaf` to the root of the tree over the leaves, none if it isn't one of them
+#[must_use]
+pub fn proof(leaves: Vec<Hash>, leaf: &Hash) -> Option<MerkleProof> {
+    let levels = levels(sorted(leaves));
+    let mut index = levels[0].iter().position(|l| l == leaf)?;
+    let mut path = Vec::new();
+    for level in &levels[..levels.len() - 1] {
+        let sibling = index ^ 1;
+        // The last node of an odd level goes up unpaired
+        if let Some(hash) = level.get(sibling) {
+            let side = if sibling < index { Side::Left } else { Side::Right };
+            path.push(ProofStep { sibling: hash.clone(), side });
+        }
+        index /= 2;
+    }
+    Some(MerkleProof { path })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: u8) -> Vec<Hash> {
+        (0..count).map(|i| leaf(Algorithm::Sha512, 0, |hasher| hasher.update([i]))).collect()
+    }
+
+    #[test]
+    fn test_every_leaf_proves_against_the_root() {
+        for count in 1..=9 {
+            let leaves = leaves(count);
+            let root = root(leaves.clone(), Algorithm::Sha512);
+            for leaf in &leaves {
+                let proof = proof(leaves.clone(), leaf).unwrap();
+                assert!(proof.verify(&root, leaf), "leaf of {count}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_leaf_is_the_root() {
+        let leaves = leaves(1);
+        assert_eq!(root(leaves.clone(), Algorithm::Sha512), leaves[0]);
+        let proof = proof(leaves.clone(), &leaves[0]).unwrap();
+        assert!(proof.path.is_empty());
+        assert!(proof.verify(&leaves[0], &leaves[0]));
+    }
+
+    #[test]
+    fn test_odd_leaf_count() {
+        let leaves = leaves(5);
+        let root = root(leaves.clone(), Algorithm::Sha512);
+        // Sorted, the last leaf is carried up to the root's level unpaired
+        let last = sorted(leaves.clone()).pop().unwrap();
+        let proof = proof(leaves.clone(), &last).unwrap();
+        assert_eq!(proof.path.len(), 1);
+        assert!(proof.verify(&root, &last));
+    }
+
+    #[test]
+    fn test_order_and_duplicates_dont_change_the_root() {
+        let leaves = leaves(4);
+        let mut shuffled: Vec<_> = leaves.iter().rev().cloned().collect();
+        shuffled.push(leaves[0].clone());
+        assert_eq!(root(shuffled, Algorithm::Sha512), root(leaves, Algorithm::Sha512));
+        assert_ne!(root(Vec::new(), Algorithm::Sha512), root(self::leaves(1), Algorithm::Sha512));
+    }
+
+    #[test]
+    fn test_tampered_leaf_or_path_fails() {
+        let leaves = leaves(6);
+        let root = root(leaves.clone(), Algorithm::Sha512);
+        let mut proof = proof(leaves.clone(), &leaves[2]).unwrap();
+        assert!(!proof.verify(&root, &leaves[3]));
+        assert!(!proof.verify(&root, &Hash::from([7; 64])));
+        proof.path[0].sibling.bytes[0] ^= 1;
+        assert!(!proof.verify(&root, &leaves[2]));
+        assert!(super::proof(leaves, &Hash::from([7; 64])).is_none());
+    }
+}
@@ -0,0 +1,321 @@
+//! Proofs that a chain doesn't revoke a credential as of its head, for verifiers that don't hold
+//! the chain.
+//!
+//! The revocation hashes the chain honors are the keys of a [`sparse_merkle`] tree, whose root a
+//! chain authority signs along with the chain head it was computed at. Two verifiers computing the
+//! root from the same chain always agree on it. A proof holds a signed root and the path to the
+//! credential's empty slot, and fails against any later root the revocation is part of.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::blockchain::{Blockchain, BlockchainError};
+use crate::credential::{Issuer, verifying_key_serde};
+use crate::exchange::{EXCHANGE_VERSION, ExchangeError, from_versioned_json};
+use crate::hash::Hash;
+use crate::signature::Sig;
+use crate::signer::{BlockSigner, SignError};
+use crate::sparse_merkle::{self, SparseMerkleProof};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NonRevocationError {
+    /// The file isn't a root or proof this version can read
+    Exchange(ExchangeError),
+    /// The root's signature doesn't check against the key it names
+    Signature,
+    /// The chain restricts block signers, and the issuer signing the root isn't one of them
+    NotAuthority,
+    /// The chain's authority set can't be followed through its blocks
+    Chain(BlockchainError),
+    Sign(SignError),
+    /// The chain revokes the credential, so there's no proof it doesn't
+    Revoked,
+    /// The root checked against belongs to another chain than the proof's
+    OtherChain,
+    /// The path doesn't lead from the credential's empty slot to the root
+    PathMismatch,
+}
+
+impl Display for NonRevocationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exchange(error) => error.fmt(f),
+            Self::Signature => f.write_str(
+                "the root's signature doesn't match its signer's key, potential forgery",
+            ),
+            Self::NotAuthority =>
+                f.write_str("only a chain authority can sign the chain's revocation root"),
+            Self::Chain(error) => write!(f, "the chain's authorities can't be followed: {error}"),
+            Self::Sign(error) => write!(f, "failed to sign the root: {error}"),
+            Self::Revoked => f.write_str("the chain revokes the credential"),
+            Self::OtherChain => f.write_str("the root belongs to another chain"),
+            Self::PathMismatch => f.write_str(
+                "the path doesn't lead to the root, the credential may have been revoked since",
+            ),
+        }
+    }
+}
+
+impl Error for NonRevocationError {}
+
+/// Revocation hashes the chain honors, the keys of its revocation tree
+fn revocation_keys(blockchain: &Blockchain) -> Vec<Hash> {
+    let revocations = blockchain.revocations();
+    revocations.into_iter().map(|(_, revocation)| revocation.signed.credential.clone()).collect()
+}
+
+/// Root of the sparse Merkle tree of the revocations the chain honors
+#[must_use]
+pub fn revocation_root(blockchain: &Blockchain) -> Hash {
+    sparse_merkle::root(&revocation_keys(blockchain), blockchain.algorithm())
+}
+
+/// Root of a chain's revocation tree as of its head, signed by a chain authority. Displayed as
+/// indented JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevocationRoot {
+    /// Format version, [`EXCHANGE_VERSION`] for files written by this version
+    pub version: u32,
+    pub generated_at: DateTime<Utc>,
+    /// UUID of the chain's genesis block, none for chains without one
+    pub chain: Option<Uuid>,
+    /// Hash of the chain's last block, none for an empty chain
+    pub head: Option<Hash>,
+    /// Number of revocations in the tree
+    pub revocations: usize,
+    pub root: Hash,
+    /// UUID and name of the issuer signing the root
+    pub signer: Uuid,
+    pub signer_name: String,
+    #[serde(with = "verifying_key_serde")]
+    pub signer_key: VerifyingKey,
+    pub signature: Sig,
+}
+
+/// The fields of a [`RevocationRoot`] its signature covers
+#[derive(Serialize)]
+struct Unsigned<'a> {
+    version: u32,
+    generated_at: &'a DateTime<Utc>,
+    chain: &'a Option<Uuid>,
+    head: &'a Option<Hash>,
+    revocations: usize,
+    root: &'a Hash,
+    signer: Uuid,
+    signer_name: &'a str,
+    #[serde(with = "verifying_key_serde")]
+    signer_key: &'a VerifyingKey,
+}
+
+impl RevocationRoot {
+    /// Computes the chain's revocation root, signed as the issuer
+    ///
+    /// # Errors
+    /// Returns [`NonRevocationError::NotAuthority`] if the chain has authorities and the issuer
+    /// isn't one, and [`NonRevocationError::Sign`] if the signer fails or doesn't hold the
+    /// issuer's key.
+    pub fn new(
+        blockchain: &Blockchain, issuer: &Issuer, signer: &dyn BlockSigner,
+    ) -> Result<Self, NonRevocationError> {
+        if let Some(authorities) = blockchain.authorities().map_err(NonRevocationError::Chain)?
+            && !authorities.iter().any(|authority| authority.verifying == issuer.verifying)
+        {
+            return Err(NonRevocationError::NotAuthority);
+        }
+        let keys = revocation_keys(blockchain);
+        let mut root = Self {
+            version: EXCHANGE_VERSION,
+            generated_at: Utc::now(),
+            chain: blockchain.id(),
+            head: blockchain.tip().map(|block| block.hash().clone()),
+            revocations: keys.len(),
+            root: sparse_merkle::root(&keys, blockchain.algorithm()),
+            signer: issuer.uuid,
+            signer_name: issuer.name.clone(),
+            signer_key: signer.verifying_key(),
+            signature: Sig::default(),
+        };
+        let signature = signer.sign(&root.message()).map_err(NonRevocationError::Sign)?;
+        if root.signer_key.verify(&root.message(), &signature).is_err() {
+            return Err(NonRevocationError::Sign(SignError::InvalidSignature));
+        }
+        root.signature = signature.into();
+        Ok(root)
+    }
+
+    fn message(&self) -> Vec<u8> {
+        let unsigned = Unsigned {
+            version: self.version,
+            generated_at: &self.generated_at,
+            chain: &self.chain,
+            head: &self.head,
+            revocations: self.revocations,
+            root: &self.root,
+            signer: self.signer,
+            signer_name: &self.signer_name,
+            signer_key: &self.signer_key,
+        };
+        serde_json::to_vec(&unsigned).unwrap_or_default()
+    }
+
+    /// Checks the signature against the key the root names, which the caller still has to trust
+    ///
+    /// # Errors
+    /// Returns [`NonRevocationError::Signature`] if it doesn't check.
+    pub fn check_signature(&self) -> Result<(), NonRevocationError> {
+        let signature = Signature::from(&self.signature);
+        self.signer_key
+            .verify(&self.message(), &signature)
+            .map_err(|_| NonRevocationError::Signature)
+    }
+
+    /// Reads a root as written by its [`Display`] implementation and checks its signature
+    ///
+    /// # Errors
+    /// Returns [`NonRevocationError::Exchange`] if the JSON isn't a root in this format version,
+    /// and [`NonRevocationError::Signature`] if the signature doesn't check.
+    pub fn from_json(json: &str) -> Result<Self, NonRevocationError> {
+        let root: Self = from_versioned_json(json).map_err(NonRevocationError::Exchange)?;
+        root.check_signature()?;
+        Ok(root)
+    }
+}
+
+impl Display for RevocationRoot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(&self).unwrap())
+    }
+}
+
+/// Proof that a chain doesn't revoke a credential as of a signed revocation root. Displayed as
+/// indented JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NonRevocationProof {
+    /// Format version, [`EXCHANGE_VERSION`] for files written by this version
+    pub version: u32,
+    pub credential: Uuid,
+    /// Path to the credential's revocation hash, the key the proof shows missing
+    pub path: SparseMerkleProof,
+    pub root: RevocationRoot,
+}
+
+impl NonRevocationProof {
+    /// Proves the chain doesn't revoke the credential with the given revocation hash, against a
+    /// root signed as the issuer
+    ///
+    /// # Errors
+    /// Returns [`NonRevocationError::Revoked`] if the chain revokes it, and the errors of
+    /// [`RevocationRoot::new`].
+    pub fn new(
+        blockchain: &Blockchain, credential: Uuid, revocation_hash: &Hash, issuer: &Issuer,
+        signer: &dyn BlockSigner,
+    ) -> Result<Self, NonRevocationError> {
+        let keys = revocation_keys(blockchain);
+        let path = sparse_merkle::prove_absent(&keys, revocation_hash, blockchain.algorithm())
+            .ok_or(NonRevocationError::Revoked)?;
+        let root = RevocationRoot::new(blockchain, issuer, signer)?;
+        Ok(Self { version: EXCHANGE_VERSION, credential, path, root })
+    }
+
+    /// Reads a proof as written by its [`Display`] implementation and checks the signature of
+    /// its root, without checking the path
+    ///
+    /// # Errors
+    /// Returns [`NonRevocationError::Exchange`] if the JSON isn't a proof in this format version,
+    /// and [`NonRevocationError::Signature`] if the root's signature doesn't check.
+    pub fn from_json(json: &str) -> Result<Self, NonRevocationError> {
+        let proof: Self = from_versioned_json(json).map_err(NonRevocationError::Exchange)?;
+        proof.root.check_signature()?;
+        Ok(proof)
+    }
+
+    /// Revocation hash the proof shows missing
+    #[must_use]
+    pub fn revocation_hash(&self) -> &Hash { &self.path.key }
+
+    /// Checks the path against the proof's own root, or against a later root of the same chain,
+    /// which fails if the credential was revoked in between
+    ///
+    /// # Errors
+    /// Returns [`NonRevocationError::OtherChain`] if the later root is another chain's, and
+    /// [`NonRevocationError::PathMismatch`] if the path doesn't lead to the root.
+    pub fn verify(&self, against: Option<&RevocationRoot>) -> Result<(), NonRevocationError> {
+        let root = against.unwrap_or(&self.root);
+        if root.chain != self.root.chain {
+            return Err(NonRevocationError::OtherChain);
+        }
+        if !self.path.verify_absent(&root.root) {
+            return Err(NonRevocationError::PathMismatch);
+        }
+        Ok(())
+    }
+}
+
+impl Display for NonRevocationProof {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(&self).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::blockchain::{Block, GenesisBlock};
+    use crate::credential::{Attribute, Credential, Subject, ValidDuration};
+
+    #[test]
+    fn test_proof_fails_against_the_root_after_revocation() {
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let valid = ValidDuration::starting(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        let credential = |value: &str| {
+            let attributes = vec![Attribute::new("degree".into(), value.into())];
+            let subject = Subject::new("Alice".into(), "Smith".into());
+            Credential::new(attributes, issuer.clone(), subject, valid.clone())
+        };
+        let (earlier, revoked) = (credential("MSc"), credential("BSc"));
+        let mut blockchain =
+            Blockchain::with_genesis(GenesisBlock::new("Test".to_string(), None, None).unwrap());
+        let format = blockchain.format();
+        let mut block = Block::new(issuer.clone());
+        for credential in [&earlier, &revoked] {
+            block.add_credential(credential.sign(&signing, false, format).unwrap(), false).unwrap();
+        }
+        block.add_credential(earlier.sign(&signing, true, format).unwrap(), true).unwrap();
+        blockchain.add_block(block, &signing).unwrap();
+
+        let hash = revoked.hash(true, format);
+        let json = NonRevocationProof::new(&blockchain, revoked.uuid, &hash, &issuer, &signing)
+            .unwrap()
+            .to_string();
+        let proof = NonRevocationProof::from_json(&json).unwrap();
+        assert_eq!(proof.root.revocations, 1);
+        assert_eq!(proof.root.root, revocation_root(&blockchain));
+        proof.verify(None).unwrap();
+
+        let mut block = Block::new(issuer.clone());
+        block.add_credential(revoked.sign(&signing, true, format).unwrap(), true).unwrap();
+        blockchain.add_block(block, &signing).unwrap();
+        let later = RevocationRoot::new(&blockchain, &issuer, &signing).unwrap();
+        assert_eq!(proof.verify(Some(&later)).unwrap_err(), NonRevocationError::PathMismatch);
+        assert_eq!(
+            NonRevocationProof::new(&blockchain, revoked.uuid, &hash, &issuer, &signing)
+                .unwrap_err(),
+            NonRevocationError::Revoked
+        );
+
+        // Changing the root breaks its signature
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["root"]["root"] = serde_json::to_value(&later.root).unwrap();
+        assert_eq!(
+            NonRevocationProof::from_json(&value.to_string()).unwrap_err(),
+            NonRevocationError::Signature
+        );
+    }
+}
@@ -0,0 +1,417 @@
+//! Presentations of a credential revealing only some of its attributes. The credential's other
+//! attributes are replaced by their salted commitments, which still let the verifier recompute
+//! the issuance hash the issuer signed and the chain records, but not the values they commit to.
+//!
+//! A presentation can also answer a verifier's challenge: the holder signs the challenge, the
+//! issuance hash and the time with the subject key the credential names, so that a presentation
+//! copied from someone else or replayed later doesn't pass.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use ed25519_dalek::Verifier;
+use serde::{Deserialize, Serialize};
+
+use crate::credential::{Credential, SignedCredential};
+use crate::exchange::{EXCHANGE_VERSION, ExchangeError, from_versioned_json};
+use crate::hash::{Algorithm, Hash, HashFormat, HashVersion};
+use crate::signature::Sig;
+use crate::signer::{BlockSigner, SignError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PresentationError {
+    /// The file isn't a presentation this version can read
+    Exchange(ExchangeError),
+    /// Credentials only commit to their attributes one by one from V4 on
+    UnsupportedVersion(HashVersion),
+    /// The credential has no attribute with the name to reveal
+    UnknownAttribute(String),
+    /// The attribute to withhold has no salt, so its commitment could be found by guessing
+    UnsaltedAttribute(String),
+    /// The credential doesn't hash to the issuance hash, as when an attribute is revealed with
+    /// another value or salt than it was issued with
+    HashMismatch,
+    /// The issuer's signature doesn't check against the credential's issuer key
+    Signature,
+    /// The credential's subject has no key to answer a challenge with
+    NoSubjectKey,
+    Sign(SignError),
+}
+
+impl Display for PresentationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exchange(error) => error.fmt(f),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "the chain hashes with version {}, attributes can only be withheld from version 4",
+                u32::from(*version)
+            ),
+            Self::UnknownAttribute(name) => write!(f, "the credential has no attribute '{name}'"),
+            Self::UnsaltedAttribute(name) => write!(
+                f,
+                "attribute '{name}' has no salt, withholding it would only hide it from those who \
+                 don't guess it"
+            ),
+            Self::HashMismatch => f.write_str(
+                "the presented credential doesn't match the issuance hash, an attribute was \
+                 revealed with another value or salt",
+            ),
+            Self::Signature =>
+                f.write_str("the issuance signature doesn't match the credential's issuer key"),
+            Self::NoSubjectKey =>
+                f.write_str("the credential's subject has no key to answer a challenge with"),
+            Self::Sign(error) => write!(f, "failed to sign the challenge: {error}"),
+        }
+    }
+}
+
+impl Error for PresentationError {}
+
+/// Why a presentation's answer to a challenge isn't accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HolderStatus {
+    /// The presentation doesn't answer a challenge
+    Missing,
+    /// The credential names no subject key to check the answer against
+    NoSubjectKey,
+    /// The answer isn't signed with the subject key
+    Signature,
+    /// The answer is to another challenge than the verifier's, as when replayed
+    ChallengeMismatch,
+    /// The answer is older than the verifier accepts
+    Expired { age: TimeDelta },
+    /// The answer is timestamped later than the verifier's clock allows
+    FromTheFuture,
+}
+
+impl Display for HolderStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => f.write_str("the presentation doesn't answer a challenge"),
+            Self::NoSubjectKey => f.write_str("the credential names no subject key"),
+            Self::Signature =>
+                f.write_str("the answer isn't signed with the subject key, potential forgery"),
+            Self::ChallengeMismatch =>
+                f.write_str("the answer is to another challenge, potential replay"),
+            Self::Expired { age } => write!(
+                f,
+                "the answer was made {}s ago, too long for it to be fresh, potential replay",
+                age.num_seconds()
+            ),
+            Self::FromTheFuture => f.write_str("the answer is timestamped in the future"),
+        }
+    }
+}
+
+impl Error for HolderStatus {}
+
+/// Subject's signature over a verifier's challenge, the issuance hash and the time it answered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HolderProof {
+    /// Challenge as hex
+    pub challenge: String,
+    pub timestamp: DateTime<Utc>,
+    pub signature: Sig,
+}
+
+impl HolderProof {
+    /// Clock difference tolerated between the holder and the verifier
+    pub const CLOCK_SKEW: TimeDelta = TimeDelta::minutes(1);
+
+    /// What the subject signs: `challenge || issuance hash || timestamp`, the timestamp in
+    /// RFC 3339
+    fn message(challenge: &[u8], issuance: &Hash, timestamp: &DateTime<Utc>) -> Vec<u8> {
+        [challenge, &issuance.bytes, timestamp.to_rfc3339().as_bytes()].concat()
+    }
+}
+
+/// Credential with only the attributes its holder reveals, the others withheld as commitments,
+/// along with the issuer's signature of its issuance hash. Displayed as indented JSON.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Presentation {
+    /// Format version, [`EXCHANGE_VERSION`] for files written by this version
+    pub version: u32,
+    pub presented_at: DateTime<Utc>,
+    /// Hash version and algorithm of the chain the credential is issued on
+    pub hash_version: HashVersion,
+    pub algorithm: Algorithm,
+    /// The credential with its revealed attributes and salts, and the commitments to the others
+    pub credential: Credential,
+    pub issuance: SignedCredential,
+    /// Answer to the verifier's challenge, for subjects with keys
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub holder: Option<HolderProof>,
+}
+
+impl Presentation {
+    /// Presents the credential revealing the attributes with the given names
+    ///
+    /// # Errors
+    /// Returns [`PresentationError::UnsupportedVersion`] for chains hashing before V4,
+    /// [`PresentationError::UnknownAttribute`] if a name isn't one of the credential's attributes
+    /// and [`PresentationError::UnsaltedAttribute`] if an attribute to withhold has no salt.
+    pub fn new(
+        credential: &Credential, issuance: &SignedCredential, reveal: &[String],
+        format: HashFormat,
+    ) -> Result<Self, PresentationError> {
+        if format.version < HashVersion::V4 {
+            return Err(PresentationError::UnsupportedVersion(format.version));
+        }
+        let named = |name: &String| credential.attributes.iter().any(|a| &a.name == name);
+        if let Some(name) = reveal.iter().find(|name| !named(name)) {
+            return Err(PresentationError::UnknownAttribute(name.clone()));
+        }
+        let (revealed, withheld): (Vec<_>, Vec<_>) =
+            credential.attributes.iter().cloned().partition(|a| reveal.contains(&a.name));
+        if let Some(unsalted) = withheld.iter().find(|a| a.salt.is_none()) {
+            return Err(PresentationError::UnsaltedAttribute(unsalted.name.clone()));
+        }
+        let mut commitments = credential.withheld.clone();
+        commitments.extend(withheld.iter().map(|a| a.commitment(format)));
+        let presented = Credential {
+            uuid: credential.uuid,
+            attributes: revealed,
+            withheld: commitments,
+            issuer: credential.issuer.clone(),
+            subject: credential.subject.clone(),
+            valid_duration: credential.valid_duration.clone(),
+            issued_at: credential.issued_at,
+            supersedes: credential.supersedes,
+            evidence: credential.evidence.clone(),
+        };
+        Ok(Self {
+            version: EXCHANGE_VERSION,
+            presented_at: Utc::now(),
+            hash_version: format.version,
+            algorithm: format.algorithm,
+            credential: presented,
+            issuance: issuance.clone(),
+            holder: None,
+        })
+    }
+
+    /// Answers the verifier's challenge as of `timestamp`, signing with the subject's key
+    ///
+    /// # Errors
+    /// Returns [`PresentationError::NoSubjectKey`] if the credential names no subject key, and
+    /// [`PresentationError::Sign`] if the signer fails or doesn't hold that key.
+    pub fn answer(
+        &mut self, challenge: &[u8], timestamp: DateTime<Utc>, subject: &dyn BlockSigner,
+    ) -> Result<(), PresentationError> {
+        let key =
+            self.credential.subject.verifying().copied().ok_or(PresentationError::NoSubjectKey)?;
+        let message = HolderProof::message(challenge, &self.issuance.credential, &timestamp);
+        let signature = subject.sign(&message).map_err(PresentationError::Sign)?;
+        if key.verify(&message, &signature).is_err() {
+            return Err(PresentationError::Sign(SignError::InvalidSignature));
+        }
+        let signature = signature.into();
+        self.holder = Some(HolderProof { challenge: hex::encode(challenge), timestamp, signature });
+        Ok(())
+    }
+
+    /// Checks the answer to the challenge against the subject key the credential names, then that
+    /// it's to `challenge` and no older than `max_age` at `now`. The credential itself is checked
+    /// by [`Presentation::verify`].
+    ///
+    /// # Errors
+    /// Returns the [`HolderStatus`] telling why the answer isn't accepted.
+    pub fn check_holder(
+        &self, challenge: &[u8], max_age: TimeDelta, now: DateTime<Utc>,
+    ) -> Result<(), HolderStatus> {
+        let holder = self.holder.as_ref().ok_or(HolderStatus::Missing)?;
+        let key = self.credential.subject.verifying().copied().ok_or(HolderStatus::NoSubjectKey)?;
+        let answered = hex::decode(&holder.challenge).map_err(|_| HolderStatus::Signature)?;
+        let message = HolderProof::message(&answered, &self.issuance.credential, &holder.timestamp);
+        if key.verify(&message, &(&holder.signature).into()).is_err() {
+            return Err(HolderStatus::Signature);
+        }
+        if answered != challenge {
+            return Err(HolderStatus::ChallengeMismatch);
+        }
+        let age = now - holder.timestamp;
+        if age > max_age {
+            return Err(HolderStatus::Expired { age });
+        }
+        if -age > HolderProof::CLOCK_SKEW {
+            return Err(HolderStatus::FromTheFuture);
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn format(&self) -> HashFormat {
+        HashFormat { version: self.hash_version, algorithm: self.algorithm }
+    }
+
+    /// Reads a presentation as written by its [`Display`] implementation and checks that the
+    /// presented credential hashes to the issuance hash the issuer signed. Whether the chain
+    /// issued it is for the caller to check.
+    ///
+    /// # Errors
+    /// Returns [`PresentationError::Exchange`] if the JSON isn't a presentation in this format
+    /// version, and [`PresentationError::HashMismatch`] or [`PresentationError::Signature`] if it
+    /// doesn't check.
+    pub fn from_json(json: &str) -> Result<Self, PresentationError> {
+        let presentation: Self = from_versioned_json(json).map_err(PresentationError::Exchange)?;
+        presentation.verify()?;
+        Ok(presentation)
+    }
+
+    /// Checks that the presented credential hashes to the issuance hash, and the issuer's
+    /// signature over it
+    ///
+    /// # Errors
+    /// Returns [`PresentationError::HashMismatch`] or [`PresentationError::Signature`] for the
+    /// check that failed.
+    pub fn verify(&self) -> Result<(), PresentationError> {
+        if self.credential.hash(false, self.format()) != self.issuance.credential {
+            return Err(PresentationError::HashMismatch);
+        }
+        if !self.issuance.verify(&self.credential.issuer.verifying) {
+            return Err(PresentationError::Signature);
+        }
+        Ok(())
+    }
+}
+
+impl Display for Presentation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(&self).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::blockchain::{Block, Blockchain, GenesisBlock, VerificationStatus};
+    use crate::credential::{Attribute, Issuer, Salt, Subject, ValidDuration};
+
+    fn issued() -> (Blockchain, Credential, SignedCredential) {
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let valid = ValidDuration::starting(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        let attributes = vec![
+            Attribute::new("degree".into(), "PhD".into()),
+            Attribute::new("date_of_birth".into(), "1990-05-17".into()),
+        ];
+        let subject = Subject::new("Alice".into(), "Smith".into());
+        let credential = Credential::new(attributes, issuer.clone(), subject, valid);
+        let mut blockchain =
+            Blockchain::with_genesis(GenesisBlock::new("Test".to_string(), None, None).unwrap());
+        let issuance = credential.sign(&signing, false, blockchain.format()).unwrap();
+        let mut block = Block::new(issuer);
+        block.add_credential(issuance.clone(), false).unwrap();
+        blockchain.add_block(block, &signing).unwrap();
+        (blockchain, credential, issuance)
+    }
+
+    fn present(credential: &Credential, issuance: &SignedCredential) -> Presentation {
+        let format = HashFormat { version: HashVersion::V4, algorithm: Algorithm::Sha512 };
+        Presentation::new(credential, issuance, &["degree".to_string()], format).unwrap()
+    }
+
+    #[test]
+    fn test_presentation_reveals_only_chosen_attributes() {
+        let (blockchain, credential, issuance) = issued();
+        let presentation = present(&credential, &issuance);
+        let json = presentation.to_string();
+        assert!(json.contains("PhD"));
+        assert!(!json.contains("date_of_birth") && !json.contains("1990-05-17"));
+
+        let read = Presentation::from_json(&json).unwrap();
+        assert_eq!(read.credential.withheld.len(), 1);
+        assert!(matches!(
+            blockchain.check_credential(&read.credential),
+            VerificationStatus::Valid { issued_in: 0 }
+        ));
+    }
+
+    #[test]
+    fn test_withheld_attribute_cant_be_guessed() {
+        let (_, credential, issuance) = issued();
+        let presentation = present(&credential, &issuance);
+        let hidden = &presentation.credential.withheld[0];
+        // The salt is 256 random bits, so knowing the name and value isn't enough to find the
+        // commitment
+        let salt = credential.attributes[1].salt.unwrap();
+        assert_eq!(salt.0.len(), 32);
+        let format = presentation.format();
+        let mut guess = Attribute::new("date_of_birth".into(), "1990-05-17".into());
+        assert_ne!(&guess.commitment(format), hidden);
+        guess.salt = Some(Salt([0; 32]));
+        assert_ne!(&guess.commitment(format), hidden);
+        guess.salt = Some(salt);
+        assert_eq!(&guess.commitment(format), hidden);
+    }
+
+    #[test]
+    fn test_wrong_salt_or_value_fails() {
+        let (_, credential, issuance) = issued();
+        let presentation = present(&credential, &issuance);
+        let mut json: serde_json::Value = serde_json::from_str(&presentation.to_string()).unwrap();
+        json["credential"]["attributes"][0]["salt"] = serde_json::json!(Salt::random());
+        assert_eq!(
+            Presentation::from_json(&json.to_string()).unwrap_err(),
+            PresentationError::HashMismatch
+        );
+        let mut json: serde_json::Value = serde_json::from_str(&presentation.to_string()).unwrap();
+        json["credential"]["attributes"][0]["value"] = serde_json::json!("MSc");
+        assert_eq!(
+            Presentation::from_json(&json.to_string()).unwrap_err(),
+            PresentationError::HashMismatch
+        );
+    }
+
+    #[test]
+    fn test_challenge_answered_by_subject() {
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let (subject, subject_key) = Subject::with_key("Alice".into(), "Smith".into());
+        let valid = ValidDuration::starting(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        let attributes = vec![Attribute::new("degree".into(), "PhD".into())];
+        let credential = Credential::new(attributes, issuer, subject, valid);
+        let issuance = credential.sign(&signing, false, HashVersion::V4).unwrap();
+        let mut presentation = present(&credential, &issuance);
+        let max_age = TimeDelta::minutes(5);
+        let now = Utc::now();
+        assert_eq!(presentation.check_holder(b"nonce", max_age, now), Err(HolderStatus::Missing));
+
+        // Only the subject's key answers
+        let error = presentation.answer(b"nonce", now, &signing).unwrap_err();
+        assert_eq!(error, PresentationError::Sign(SignError::InvalidSignature));
+        presentation.answer(b"nonce", now, &subject_key).unwrap();
+        let read = Presentation::from_json(&presentation.to_string()).unwrap();
+        assert_eq!(read.check_holder(b"nonce", max_age, now), Ok(()));
+
+        // Replayed to another verifier, or later
+        let status = read.check_holder(b"other", max_age, now);
+        assert_eq!(status, Err(HolderStatus::ChallengeMismatch));
+        let later = now + TimeDelta::minutes(10);
+        let status = read.check_holder(b"nonce", max_age, later);
+        assert_eq!(status, Err(HolderStatus::Expired { age: TimeDelta::minutes(10) }));
+        let earlier = now - TimeDelta::minutes(10);
+        let status = read.check_holder(b"nonce", max_age, earlier);
+        assert_eq!(status, Err(HolderStatus::FromTheFuture));
+
+        // Moving the answer to the verifier's challenge breaks its signature
+        let mut forged = presentation;
+        forged.holder.as_mut().unwrap().challenge = hex::encode(b"other");
+        let status = forged.check_holder(b"other", max_age, now);
+        assert_eq!(status, Err(HolderStatus::Signature));
+    }
+
+    #[test]
+    fn test_presentation_needs_v4() {
+        let (_, credential, issuance) = issued();
+        let format = HashVersion::V3.into();
+        let error = Presentation::new(&credential, &issuance, &[], format).unwrap_err();
+        assert_eq!(error, PresentationError::UnsupportedVersion(HashVersion::V3));
+        let reveal = ["grade".to_string()];
+        let format = HashVersion::V4.into();
+        let error = Presentation::new(&credential, &issuance, &reveal, format).unwrap_err();
+        assert_eq!(error, PresentationError::UnknownAttribute("grade".to_string()));
+    }
+}
@@ -0,0 +1,16 @@
+/// Receives the progress of operations going over a whole chain, one tick per block, so that
+/// callers can report it however they like. `()` ignores it.
+pub trait ProgressSink {
+    /// Called once before the first tick with the number of ticks to expect
+    fn start(&mut self, total: u64) { let _ = total; }
+
+    /// Called once per block processed
+    fn tick(&mut self);
+
+    /// Called once the operation is done, whether it succeeded or not
+    fn finish(&mut self) {}
+}
+
+impl ProgressSink for () {
+    fn tick(&mut self) {}
+}
@@ -0,0 +1,393 @@
+//! Inclusion proofs: a credential's entries on the chain with the Merkle paths to their blocks'
+//! roots and the headers of those blocks, enough to check that the chain recorded them with
+//! nothing but the issuer's and the block signers' keys.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::blockchain::{Block, BlockHeader, Blockchain, ListKind, RevocationDetails, merkle_leaf};
+use crate::credential::{SignedCredential, verifying_key_serde};
+use crate::exchange::{EXCHANGE_VERSION, ExchangeError, from_versioned_json};
+use crate::hash::Hash;
+use crate::merkle::{MerkleProof, ProofStep};
+use crate::signature::Sig;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofError {
+    /// The chain doesn't issue the credential
+    NotAnchored,
+    /// The block recording the credential was finalized before blocks had Merkle roots
+    NoMerkleRoot { height: u64 },
+}
+
+impl Display for ProofError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAnchored => f.write_str("the credential isn't issued on the chain"),
+            Self::NoMerkleRoot { height } => write!(
+                f,
+                "block #{height} has no Merkle root, proofs need blocks hashed with version 3 or \
+                 later"
+            ),
+        }
+    }
+}
+
+impl Error for ProofError {}
+
+/// Entry of a block with the path from its leaf to the block's Merkle root and the block's header
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryProof {
+    /// List of the block holding the entry
+    pub list: ListKind,
+    /// Issuance or revocation hash with the issuer's signature over it
+    pub entry: SignedCredential,
+    /// Details recorded with a revocation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<RevocationDetails>,
+    /// Siblings from the entry's leaf up to the block's Merkle root
+    pub path: Vec<ProofStep>,
+    pub block: BlockHeader,
+    pub block_hash: Hash,
+    /// Key of the block signer
+    #[serde(with = "verifying_key_serde")]
+    pub signer_key: VerifyingKey,
+    pub signature: Sig,
+}
+
+/// Why an entry's proof doesn't hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryMismatch {
+    /// The entry's signature doesn't check against the issuer key
+    EntrySignature,
+    /// The path doesn't lead from the entry's leaf to the block's Merkle root
+    MerklePath,
+    /// The header doesn't hash to the block hash
+    BlockHash,
+    /// The block signature doesn't check against the signer key
+    BlockSignature,
+    /// The signer key isn't the one the verifier trusts
+    UntrustedSigner,
+    /// The entry is in a list of its block it can't be proven from, as an issuance in the revoked
+    /// list or a revocation in another
+    WrongList,
+}
+
+impl Display for EntryMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::EntrySignature => "the entry's signature doesn't match the issuer key",
+            Self::MerklePath => "the Merkle path doesn't lead to the block's Merkle root",
+            Self::BlockHash => "the block header doesn't match the block hash",
+            Self::BlockSignature => "the block signature doesn't match the signer key",
+            Self::UntrustedSigner => "the block signer's key isn't the trusted one",
+            Self::WrongList => "the entry is in the wrong list of its block",
+        })
+    }
+}
+
+/// Failed check of an [`InclusionProof`], on its issuance or its revocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidProof {
+    /// Whether the revocation's proof failed rather than the issuance's
+    pub revocation: bool,
+    pub mismatch: EntryMismatch,
+}
+
+impl Display for InvalidProof {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let entry = if self.revocation { "revocation" } else { "issuance" };
+        write!(f, "{entry}: {}", self.mismatch)
+    }
+}
+
+impl Error for InvalidProof {}
+
+impl EntryProof {
+    fn new(block: &Block, credential: &Hash) -> Result<Option<Self>, ProofError> {
+        let Some(list) = block.list_of(credential) else {
+            return Ok(None);
+        };
+        let root = block.merkle_root().ok_or(ProofError::NoMerkleRoot { height: block.height() })?;
+        let (entry, details) = if let Some(revocation) = block.revocation(credential) {
+            (revocation.signed.clone(), revocation.details.clone())
+        } else {
+            let issued = block.issued_credentials().find(|s| &s.credential == credential);
+            (issued.expect("issued in the block").clone(), None)
+        };
+        let path = block.merkle_proof(credential).expect("entry of the block").path;
+        Ok(Some(Self {
+            list,
+            entry,
+            details,
+            path,
+            block: block.header(root.algorithm),
+            block_hash: block.hash().clone(),
+            signer_key: block.signer().verifying,
+            signature: block.signature().clone(),
+        }))
+    }
+
+    /// Leaf of the entry in its block's Merkle tree
+    #[must_use]
+    pub fn leaf(&self) -> Hash {
+        let algorithm = self.block.merkle_root.algorithm;
+        merkle_leaf(algorithm, self.list, &self.entry, self.details.as_ref())
+    }
+
+    /// Checks the entry against the issuer key, then each link from it to the block signature,
+    /// then the signer key against the trusted one if given
+    ///
+    /// # Errors
+    /// Returns the first link that doesn't hold.
+    pub fn check(
+        &self, issuer_key: &VerifyingKey, trust: Option<&VerifyingKey>,
+    ) -> Result<(), EntryMismatch> {
+        if !self.entry.verify(issuer_key) {
+            return Err(EntryMismatch::EntrySignature);
+        }
+        let path = MerkleProof { path: self.path.clone() };
+        if !path.verify(&self.block.merkle_root, &self.leaf()) {
+            return Err(EntryMismatch::MerklePath);
+        }
+        if self.block.hash() != self.block_hash {
+            return Err(EntryMismatch::BlockHash);
+        }
+        self.signer_key
+            .verify(&self.block_hash.bytes, &(&self.signature).into())
+            .map_err(|_| EntryMismatch::BlockSignature)?;
+        match trust {
+            Some(trusted) if *trusted != self.signer_key => Err(EntryMismatch::UntrustedSigner),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Proof that a chain issued a credential, and revoked it if it did, checkable without the
+/// chain. Displayed as indented JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InclusionProof {
+    /// Format version, [`EXCHANGE_VERSION`] for files written by this version
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    /// UUID of the chain's genesis block, none for chains without one
+    pub chain: Option<Uuid>,
+    /// Hash of the chain's last block when exported
+    pub head: Hash,
+    /// UUID of the credential
+    pub credential: Uuid,
+    /// Key the issuer signed the credential's entries with
+    #[serde(with = "verifying_key_serde")]
+    pub issuer_key: VerifyingKey,
+    pub issuance: EntryProof,
+    /// Whether the chain revokes the credential, in which case `revocation` proves it
+    pub revoked: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revocation: Option<EntryProof>,
+}
+
+impl InclusionProof {
+    /// Proves the first issuance of the credential with the given hashes on the chain, along
+    /// with the revocation the chain honors if there is one
+    ///
+    /// # Errors
+    /// Returns [`ProofError::NotAnchored`] if the chain doesn't issue the credential and
+    /// [`ProofError::NoMerkleRoot`] if a block recording it has no Merkle root.
+    pub fn new(
+        blockchain: &Blockchain, credential: Uuid, issuer_key: VerifyingKey, issuance: &Hash,
+        revocation: &Hash,
+    ) -> Result<Self, ProofError> {
+        let mut issued = None;
+        for block in blockchain.blocks() {
+            issued = EntryProof::new(block, issuance)?;
+            if issued.is_some() {
+                break;
+            }
+        }
+        let issuance = issued.ok_or(ProofError::NotAnchored)?;
+        let revocation = blockchain
+            .revocations()
+            .into_iter()
+            .find(|(_, r)| &r.signed.credential == revocation)
+            .map(|(block, _)| EntryProof::new(block, revocation))
+            .transpose()?
+            .flatten();
+        Ok(Self {
+            version: EXCHANGE_VERSION,
+            exported_at: Utc::now(),
+            chain: blockchain.id(),
+            head: blockchain.tip_hash(),
+            credential,
+            issuer_key,
+            issuance,
+            revoked: revocation.is_some(),
+            revocation,
+        })
+    }
+
+    /// Reads a proof as written by its [`Display`] implementation, without checking it
+    ///
+    /// # Errors
+    /// Returns [`ExchangeError::UnsupportedVersion`] for proofs in another format version, and
+    /// [`ExchangeError::Parse`] if the JSON isn't a proof at all.
+    pub fn from_json(json: &str) -> Result<Self, ExchangeError> { from_versioned_json(json) }
+}
+
+/// Checks the proof with nothing but what it holds: the issuance's, then the revocation's entry
+/// signature, Merkle path, block hash and block signature, and if `trust` is given that it's the
+/// key the blocks are signed with
+///
+/// # Errors
+/// Returns the first check that failed and the entry it failed on.
+pub fn verify(proof: &InclusionProof, trust: Option<&VerifyingKey>) -> Result<(), InvalidProof> {
+    let issuance = match proof.issuance.list {
+        ListKind::Revoked => Err(EntryMismatch::WrongList),
+        ListKind::New | ListKind::Anchored => proof.issuance.check(&proof.issuer_key, trust),
+    };
+    issuance.map_err(|mismatch| InvalidProof { revocation: false, mismatch })?;
+    let Some(revocation) = &proof.revocation else {
+        return Ok(());
+    };
+    let revoked = match revocation.list {
+        ListKind::Revoked => revocation.check(&proof.issuer_key, trust),
+        ListKind::New | ListKind::Anchored => Err(EntryMismatch::WrongList),
+    };
+    revoked.map_err(|mismatch| InvalidProof { revocation: true, mismatch })
+}
+
+impl Display for InclusionProof {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(&self).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::blockchain::{GenesisBlock, Revocation, RevocationReason};
+    use crate::credential::{Attribute, Credential, Issuer, Subject, ValidDuration};
+
+    fn chain(revoke: bool) -> (Blockchain, Credential) {
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let valid = ValidDuration::starting(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        let mut blockchain =
+            Blockchain::with_genesis(GenesisBlock::new("Test".to_string(), None, None).unwrap());
+        let format = blockchain.format();
+        let mut block = Block::new(issuer.clone());
+        let mut credentials = Vec::new();
+        for value in ["BSc", "MSc", "PhD"] {
+            let attributes = vec![Attribute::new("degree".into(), value.into())];
+            let subject = Subject::new("Alice".into(), "Smith".into());
+            let credential = Credential::new(attributes, issuer.clone(), subject, valid.clone());
+            block.add_credential(credential.sign(&signing, false, format).unwrap(), false).unwrap();
+            credentials.push(credential);
+        }
+        blockchain.add_block(block, &signing).unwrap();
+        let credential = credentials.swap_remove(1);
+        if revoke {
+            let mut block = Block::new(issuer);
+            let details = RevocationDetails::new(RevocationReason::Superseded, None);
+            let signed = credential.sign(&signing, true, format).unwrap();
+            block.add_revocation(Revocation::new(signed, Some(details))).unwrap();
+            blockchain.add_block(block, &signing).unwrap();
+        }
+        (blockchain, credential)
+    }
+
+    fn prove(chain: &Blockchain, credential: &Credential) -> Result<InclusionProof, ProofError> {
+        let format = chain.format();
+        let issuance = credential.hash(false, format);
+        let revocation = credential.hash(true, format);
+        let key = credential.issuer.verifying;
+        InclusionProof::new(chain, credential.uuid, key, &issuance, &revocation)
+    }
+
+    #[test]
+    fn test_proof_of_issuance() {
+        let (blockchain, credential) = chain(false);
+        let proof = prove(&blockchain, &credential).unwrap();
+        assert!(!proof.revoked);
+        assert_eq!(proof.head, blockchain.tip_hash());
+        assert_eq!(proof.issuance.list, ListKind::New);
+        assert_eq!(verify(&proof, None), Ok(()));
+
+        let read = InclusionProof::from_json(&proof.to_string()).unwrap();
+        assert_eq!(read.to_string(), proof.to_string());
+        let signer = read.issuance.signer_key;
+        assert_eq!(verify(&read, Some(&signer)), Ok(()));
+    }
+
+    #[test]
+    fn test_proof_of_revocation() {
+        let (blockchain, credential) = chain(true);
+        let proof = prove(&blockchain, &credential).unwrap();
+        assert!(proof.revoked);
+        let revocation = proof.revocation.as_ref().unwrap();
+        assert_eq!((revocation.list, revocation.block.height), (ListKind::Revoked, 1));
+        assert!(revocation.details.is_some());
+        assert_eq!(verify(&proof, None), Ok(()));
+
+        // A revocation passed off as the issuance isn't proven by it
+        let mut swapped = proof.clone();
+        swapped.issuance = revocation.clone();
+        let mismatch = EntryMismatch::WrongList;
+        assert_eq!(verify(&swapped, None), Err(InvalidProof { revocation: false, mismatch }));
+    }
+
+    #[test]
+    fn test_tampered_proof_fails() {
+        let (blockchain, credential) = chain(false);
+        let proof = prove(&blockchain, &credential).unwrap();
+        let mut entry = proof.issuance.clone();
+        entry.path[0].sibling.bytes[0] ^= 1;
+        assert_eq!(entry.check(&proof.issuer_key, None), Err(EntryMismatch::MerklePath));
+
+        let mut entry = proof.issuance.clone();
+        entry.block.height = 1;
+        assert_eq!(entry.check(&proof.issuer_key, None), Err(EntryMismatch::BlockHash));
+        let (other, _) = Issuer::new("Other".to_string());
+        let mismatch = proof.issuance.check(&other.verifying, None);
+        assert_eq!(mismatch, Err(EntryMismatch::EntrySignature));
+    }
+
+    #[test]
+    fn test_truncated_path_fails() {
+        let (blockchain, credential) = chain(true);
+        let mut proof = prove(&blockchain, &credential).unwrap();
+        proof.issuance.path.pop();
+        let mismatch = EntryMismatch::MerklePath;
+        assert_eq!(verify(&proof, None), Err(InvalidProof { revocation: false, mismatch }));
+    }
+
+    #[test]
+    fn test_wrong_signer_key_fails() {
+        let (blockchain, credential) = chain(true);
+        let proof = prove(&blockchain, &credential).unwrap();
+        let (other, _) = Issuer::new("Other".to_string());
+        let mismatch = EntryMismatch::UntrustedSigner;
+        let error = InvalidProof { revocation: false, mismatch };
+        assert_eq!(verify(&proof, Some(&other.verifying)), Err(error));
+
+        // Swapping in another key breaks the block signature
+        let mut forged = proof.clone();
+        forged.revocation.as_mut().unwrap().signer_key = other.verifying;
+        let mismatch = EntryMismatch::BlockSignature;
+        let error = InvalidProof { revocation: true, mismatch };
+        assert_eq!(verify(&forged, Some(&proof.issuance.signer_key)), Err(error));
+        assert_eq!(verify(&forged, None), Err(error));
+    }
+
+    #[test]
+    fn test_unanchored_credential_has_no_proof() {
+        let (blockchain, _) = chain(false);
+        let (_, other) = chain(false);
+        assert_eq!(prove(&blockchain, &other).unwrap_err(), ProofError::NotAnchored);
+    }
+}
@@ -0,0 +1,134 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::Write;
+
+use qrcode::render::unicode::Dense1x2;
+use qrcode::types::QrError as EncodeError;
+use qrcode::{Color, EcLevel, QrCode};
+
+/// Most bytes a QR code holds, at the largest version and lowest error correction
+pub const MAX_BYTES: usize = 2953;
+
+/// Pixels per module side in PNG images
+pub const MODULE_PIXELS: usize = 8;
+
+/// Light modules around the code in PNG images, as scanners expect
+const QUIET_ZONE: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QrError {
+    /// The payload is more than [`MAX_BYTES`] long
+    TooLarge {
+        bytes: usize,
+    },
+    Encode(String),
+    Png(String),
+}
+
+impl Display for QrError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLarge { bytes } =>
+                write!(f, "{bytes} bytes don't fit in a QR code, which holds at most {MAX_BYTES}"),
+            Self::Encode(error) => write!(f, "failed to encode the QR code: {error}"),
+            Self::Png(error) => write!(f, "failed to write the PNG image: {error}"),
+        }
+    }
+}
+
+impl Error for QrError {}
+
+/// Encodes the bytes as a QR code, with medium error correction if they fit and low otherwise
+///
+/// # Errors
+/// Returns [`QrError::TooLarge`] if the bytes don't fit even with low error correction.
+pub fn encode(payload: &[u8]) -> Result<QrCode, QrError> {
+    match QrCode::with_error_correction_level(payload, EcLevel::M) {
+        Err(EncodeError::DataTooLong) => QrCode::with_error_correction_level(payload, EcLevel::L),
+        result => result,
+    }
+    .map_err(|e| match e {
+        EncodeError::DataTooLong => QrError::TooLarge { bytes: payload.len() },
+        e => QrError::Encode(e.to_string()),
+    })
+}
+
+/// Renders the code with half-block characters, two modules per character, light on dark as
+/// terminals usually are
+#[must_use]
+pub fn render_terminal(code: &QrCode) -> String {
+    code.render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build()
+}
+
+/// Writes the code as a greyscale PNG image, [`MODULE_PIXELS`] to a module with a quiet zone
+/// around it
+///
+/// # Errors
+/// Returns [`QrError::Png`] if the image can't be written.
+pub fn write_png(code: &QrCode, writer: impl Write) -> Result<(), QrError> {
+    let width = code.width();
+    let side = (width + 2 * QUIET_ZONE) * MODULE_PIXELS;
+    let mut pixels = vec![u8::MAX; side * side];
+    for (index, color) in code.to_colors().into_iter().enumerate() {
+        if color == Color::Light {
+            continue;
+        }
+        let (x, y) = (index % width + QUIET_ZONE, index / width + QUIET_ZONE);
+        for row in y * MODULE_PIXELS..(y + 1) * MODULE_PIXELS {
+            pixels[row * side + x * MODULE_PIXELS..][..MODULE_PIXELS].fill(0);
+        }
+    }
+    let png_error = |e: png::EncodingError| QrError::Png(e.to_string());
+    let side = u32::try_from(side).map_err(|e| QrError::Png(e.to_string()))?;
+    let mut encoder = png::Encoder::new(writer, side, side);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header().map_err(png_error)?.write_image_data(&pixels).map_err(png_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_keeps_payload() {
+        let code = encode(b"attributes attestation").unwrap();
+        assert_eq!(code.error_correction_level(), EcLevel::M);
+        assert_eq!(code.to_colors().len(), code.width() * code.width());
+        let rendered = render_terminal(&code);
+        // Two rows of modules per line, plus the quiet zone
+        assert_eq!(rendered.lines().count(), (code.width() + 8).div_ceil(2));
+    }
+
+    #[test]
+    fn test_encode_falls_back_to_low_correction() {
+        let code = encode(&[7; 2800]).unwrap();
+        assert_eq!(code.error_correction_level(), EcLevel::L);
+        let error = encode(&[7; MAX_BYTES + 1]).err();
+        assert_eq!(error, Some(QrError::TooLarge { bytes: MAX_BYTES + 1 }));
+    }
+
+    #[test]
+    fn test_png_matches_modules() {
+        let code = encode(b"receipt").unwrap();
+        let mut png = Vec::new();
+        write_png(&code, &mut png).unwrap();
+
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        let side = (code.width() + 2 * QUIET_ZONE) * MODULE_PIXELS;
+        assert_eq!((info.width as usize, info.height as usize), (side, side));
+        let pixel = |x: usize, y: usize| pixels[y * side + x];
+        assert_eq!(pixel(0, 0), u8::MAX);
+        for (index, color) in code.to_colors().into_iter().enumerate() {
+            let (x, y) = (index % code.width() + QUIET_ZONE, index / code.width() + QUIET_ZONE);
+            let expected = if color == Color::Dark { 0 } else { u8::MAX };
+            assert_eq!(pixel(x * MODULE_PIXELS + 3, y * MODULE_PIXELS + 3), expected);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use regex::{Regex, RegexBuilder};
+use uuid::Uuid;
+
+use crate::credential::{Attribute, Credential};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    /// The pattern isn't a valid regular expression
+    InvalidPattern(String),
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPattern(error) => write!(f, "invalid pattern: {error}"),
+        }
+    }
+}
+
+impl Error for QueryError {}
+
+/// Search for credentials by their attributes: a credential matches if any of its attributes has
+/// a name or value matching the pattern, and it was issued by the query's issuer if it has one
+#[derive(Debug, Clone)]
+pub struct CredentialQuery {
+    pattern: Regex,
+    issuer: Option<Uuid>,
+}
+
+impl CredentialQuery {
+    /// Query for a glob that has to match the whole name or value, where `*` stands for any run
+    /// of characters and `?` for a single one
+    ///
+    /// # Errors
+    /// Returns [`QueryError::InvalidPattern`] if the glob is too long to compile.
+    pub fn glob(glob: &str, case_sensitive: bool) -> Result<Self, QueryError> {
+        let mut pattern = String::from("^(?s:");
+        for c in glob.chars() {
+            match c {
+                '*' => pattern.push_str(".*"),
+                '?' => pattern.push('.'),
+                c => pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+            }
+        }
+        pattern.push_str(")$");
+        Self::regex(&pattern, case_sensitive)
+    }
+
+    /// Query for a regular expression found anywhere in the name or value, unless anchored
+    ///
+    /// # Errors
+    /// Returns [`QueryError::InvalidPattern`] if the pattern isn't a valid regular expression.
+    pub fn regex(pattern: &str, case_sensitive: bool) -> Result<Self, QueryError> {
+        let pattern = RegexBuilder::new(pattern)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map_err(|e| QueryError::InvalidPattern(e.to_string()))?;
+        Ok(Self { pattern, issuer: None })
+    }
+
+    /// Restricts the query to credentials of the issuer with the given UUID
+    #[must_use]
+    pub fn issuer(mut self, uuid: Uuid) -> Self {
+        self.issuer = Some(uuid);
+        self
+    }
+
+    /// Attributes of the credential matching the query, none if it's from another issuer
+    pub fn matching_attributes<'a>(
+        &self, credential: &'a Credential,
+    ) -> impl Iterator<Item = &'a Attribute> {
+        let issuer_matches = self.issuer.is_none_or(|uuid| credential.issuer.uuid == uuid);
+        credential.attributes.iter().filter(move |attribute| {
+            issuer_matches
+                && (self.pattern.is_match(&attribute.name)
+                    || self.pattern.is_match(&attribute.value))
+        })
+    }
+
+    #[must_use]
+    pub fn matches(&self, credential: &Credential) -> bool {
+        self.matching_attributes(credential).next().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::credential::{Issuer, Subject, ValidDuration};
+
+    fn credential(issuer: &Issuer, attributes: &[(&str, &str)]) -> Credential {
+        let attributes = attributes
+            .iter()
+            .map(|(name, value)| Attribute::new((*name).to_string(), (*value).to_string()))
+            .collect();
+        let subject = Subject::new("Alice".into(), "Smith".into());
+        let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        Credential::new(
+            attributes,
+            issuer.clone(),
+            subject,
+            ValidDuration::new(from, None).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_glob_query() {
+        let issuer = Issuer::new("Registrar".into()).0;
+        let licence = credential(&issuer, &[("Driving Licence", "B"), ("degree", "BSc")]);
+        let degree = credential(&issuer, &[("degree", "MSc")]);
+
+        let query = CredentialQuery::glob("*Licence*", false).unwrap();
+        assert!(query.matches(&licence));
+        assert!(!query.matches(&degree));
+        let names: Vec<_> = query.matching_attributes(&licence).map(|a| &a.name).collect();
+        assert_eq!(names, ["Driving Licence"]);
+
+        // Globs match the whole name or value, `?` a single character
+        assert!(!CredentialQuery::glob("Licence", false).unwrap().matches(&licence));
+        assert!(CredentialQuery::glob("?sc", false).unwrap().matches(&degree));
+        assert!(!CredentialQuery::glob("?c", false).unwrap().matches(&degree));
+        // Other regex syntax is taken literally
+        assert!(!CredentialQuery::glob("M.c", false).unwrap().matches(&degree));
+    }
+
+    #[test]
+    fn test_regex_query() {
+        let issuer = Issuer::new("Registrar".into()).0;
+        let degree = credential(&issuer, &[("degree", "MSc")]);
+        assert!(CredentialQuery::regex("^(B|M)Sc$", false).unwrap().matches(&degree));
+        assert!(CredentialQuery::regex("gre", false).unwrap().matches(&degree));
+        assert!(!CredentialQuery::regex("^PhD", false).unwrap().matches(&degree));
+        assert!(matches!(
+            CredentialQuery::regex("(unclosed", false),
+            Err(QueryError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_query_case_sensitivity() {
+        let issuer = Issuer::new("Registrar".into()).0;
+        let licence = credential(&issuer, &[("Driving Licence", "B")]);
+        assert!(CredentialQuery::glob("*licence*", false).unwrap().matches(&licence));
+        assert!(!CredentialQuery::glob("*licence*", true).unwrap().matches(&licence));
+        assert!(CredentialQuery::glob("*Licence*", true).unwrap().matches(&licence));
+        assert!(!CredentialQuery::regex("DRIVING", true).unwrap().matches(&licence));
+        assert!(CredentialQuery::regex("DRIVING", false).unwrap().matches(&licence));
+    }
+
+    #[test]
+    fn test_query_issuer() {
+        let registrar = Issuer::new("Registrar".into()).0;
+        let other = Issuer::new("Other".into()).0;
+        let query = CredentialQuery::glob("*Licence*", false).unwrap().issuer(registrar.uuid);
+        assert!(query.matches(&credential(&registrar, &[("Licence", "B")])));
+        assert!(!query.matches(&credential(&other, &[("Licence", "B")])));
+        assert_eq!(query.matching_attributes(&credential(&other, &[("Licence", "B")])).count(), 0);
+    }
+}
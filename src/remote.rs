@@ -0,0 +1,369 @@
+//! Client reading a chain served by another node, by `serve` or as a static copy of the same
+//! files, for verifiers that don't keep the chain themselves.
+//!
+//! The chain is read block by block and only trusted as far as it validates: a node can serve an
+//! old or forged chain, which pins on the chain's UUID or its head signer's key catch.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use reqwest::blocking::Client;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
+use reqwest::{Certificate, Identity, StatusCode};
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+
+use crate::blockchain::{Block, Blockchain, GenesisBlock};
+use crate::exchange::{ChainHead, ExchangeError};
+use crate::keys::fingerprint;
+use crate::sync::{ChainSource, SyncError};
+
+#[derive(Debug)]
+pub enum RemoteError {
+    /// The node can't be reached, the connection failed or the TLS certificate doesn't check
+    Network { url: String, error: String },
+    /// The node answered with an error status
+    Status { url: String, status: u16 },
+    /// The node answered with something else than what was asked for
+    Malformed { url: String, error: String },
+    /// The blocks served don't make up the chain the served head describes
+    Inconsistent(ExchangeError),
+    /// The chain isn't the pinned one, or its last block isn't signed with the pinned key
+    PinMismatch { pin: Pin, found: String },
+}
+
+impl Display for RemoteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network { url, error } => write!(f, "failed to reach {url}: {error}"),
+            Self::Status { url, status } => write!(f, "{url} answered with status {status}"),
+            Self::Malformed { url, error } => write!(f, "{url} served an unreadable file: {error}"),
+            Self::Inconsistent(error) => error.fmt(f),
+            Self::PinMismatch { pin, found } => write!(f, "expected {pin}, found {found}"),
+        }
+    }
+}
+
+impl Error for RemoteError {}
+
+/// What a remote chain must match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pin {
+    /// UUID of the chain's genesis block
+    Chain(Uuid),
+    /// Fingerprint of the key the chain's last block is signed with, without spaces
+    HeadSigner(String),
+}
+
+impl FromStr for Pin {
+    type Err = String;
+
+    fn from_str(pin: &str) -> Result<Self, Self::Err> {
+        // Fingerprints are 32 hex digits too, which only hyphenated UUIDs are told apart from
+        if pin.contains('-')
+            && let Ok(uuid) = Uuid::parse_str(pin)
+        {
+            return Ok(Self::Chain(uuid));
+        }
+        let digits = pin.replace(' ', "").to_uppercase();
+        if digits.len() != 32 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("'{pin}' is neither a chain UUID nor a key fingerprint"));
+        }
+        Ok(Self::HeadSigner(digits))
+    }
+}
+
+impl Display for Pin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Chain(uuid) => write!(f, "chain {uuid}"),
+            Self::HeadSigner(digits) => write!(f, "head signed with key {digits}"),
+        }
+    }
+}
+
+/// How a client authenticates to nodes requiring it, and which nodes it trusts besides the web's
+/// roots
+#[derive(Debug, Clone, Default)]
+pub struct ClientAuth {
+    /// Bearer token sent with every request
+    pub token: Option<String>,
+    /// PEM client certificate chain and its private key, for nodes requiring client certificates
+    pub identity: Option<Vec<u8>>,
+    /// PEM bundle of CAs the node's certificate may be issued by, e.g. an internal one
+    pub ca: Option<Vec<u8>>,
+}
+
+/// Chain served at a base URL, e.g. `https://attest.example.org`
+pub struct RemoteChain {
+    base: String,
+    client: Client,
+}
+
+impl RemoteChain {
+    /// Client for the chain served at the URL, checking TLS certificates against the web's roots
+    ///
+    /// # Errors
+    /// Returns [`RemoteError::Network`] if the client can't be set up.
+    pub fn new(base: &str) -> Result<Self, RemoteError> {
+        Self::with_auth(base, &ClientAuth::default())
+    }
+
+    /// Client for the chain served at the URL, authenticating with the given credentials
+    ///
+    /// # Errors
+    /// Returns [`RemoteError::Network`] if the credentials can't be read or the client can't be
+    /// set up.
+    pub fn with_auth(base: &str, auth: &ClientAuth) -> Result<Self, RemoteError> {
+        let setup = |error: String| RemoteError::Network { url: base.to_string(), error };
+        let mut builder = Client::builder();
+        if let Some(token) = &auth.token {
+            let mut value = HeaderValue::from_str(&format!("Bearer {token}"))
+                .map_err(|_| setup("the token isn't a valid header value".into()))?;
+            value.set_sensitive(true);
+            builder = builder.default_headers(HeaderMap::from_iter([(AUTHORIZATION, value)]));
+        }
+        if let Some(identity) = &auth.identity {
+            let identity = Identity::from_pem(identity).map_err(|e| setup(e.to_string()))?;
+            builder = builder.identity(identity);
+        }
+        if let Some(ca) = &auth.ca {
+            for certificate in Certificate::from_pem_bundle(ca).map_err(|e| setup(e.to_string()))? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        let client = builder.build().map_err(|e| setup(e.to_string()))?;
+        Ok(Self { base: base.trim_end_matches('/').to_string(), client })
+    }
+
+    fn url(&self, path: &str) -> String { format!("{}/{path}", self.base) }
+
+    /// Reads the file at the path, none if the node has no such file
+    fn get_text(&self, path: &str) -> Result<Option<String>, RemoteError> {
+        let url = self.url(path);
+        let network = |e: reqwest::Error| {
+            // Without the URL, which the error is already told with
+            RemoteError::Network { url: url.clone(), error: e.without_url().to_string() }
+        };
+        let response = self.client.get(&url).send().map_err(network)?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => response.text().map(Some).map_err(network),
+            status => Err(RemoteError::Status { url, status: status.as_u16() }),
+        }
+    }
+
+    /// Reads the JSON file at the path, none if the node has no such file
+    fn get<T: DeserializeOwned>(&self, path: &str) -> Result<Option<T>, RemoteError> {
+        let Some(text) = self.get_text(path)? else {
+            return Ok(None);
+        };
+        let malformed = |e: serde_json::Error| RemoteError::Malformed {
+            url: self.url(path),
+            error: e.to_string(),
+        };
+        serde_json::from_str(&text).map(Some).map_err(malformed)
+    }
+
+    fn not_found(&self, path: &str) -> RemoteError {
+        RemoteError::Status { url: self.url(path), status: StatusCode::NOT_FOUND.as_u16() }
+    }
+
+    /// The head the node describes
+    ///
+    /// # Errors
+    /// Fails if the node can't be reached or doesn't serve a head.
+    pub fn head(&self) -> Result<ChainHead, RemoteError> {
+        let path = "head.json";
+        let text = self.get_text(path)?.ok_or_else(|| self.not_found(path))?;
+        ChainHead::from_json(&text)
+            .map_err(|e| RemoteError::Malformed { url: self.url(path), error: e.to_string() })
+    }
+
+    /// The chain's genesis block, none for chains without one
+    ///
+    /// # Errors
+    /// Fails if the node can't be reached or serves something else.
+    pub fn genesis(&self) -> Result<Option<GenesisBlock>, RemoteError> { self.get("genesis.json") }
+
+    /// The block at the height
+    ///
+    /// # Errors
+    /// Fails if the node can't be reached or has no such block.
+    pub fn block(&self, height: u64) -> Result<Block, RemoteError> {
+        let path = format!("blocks/{height}.json");
+        self.get(&path)?.ok_or_else(|| self.not_found(&path))
+    }
+
+    /// Reads the whole chain and checks that it's the one the head describes and matches the pin.
+    /// The chain isn't validated: its last block's signer only holds as the pinned one once it
+    /// is.
+    ///
+    /// # Errors
+    /// Fails if the node can't be reached, doesn't serve every block, or they don't make up the
+    /// described chain or the pinned one.
+    pub fn fetch(&self, pin: Option<&Pin>) -> Result<Blockchain, RemoteError> {
+        let head = self.head()?;
+        if let Some(pin @ Pin::Chain(uuid)) = pin
+            && head.chain != Some(*uuid)
+        {
+            let found =
+                head.chain.map_or("a chain without UUID".into(), |id| format!("chain {id}"));
+            return Err(RemoteError::PinMismatch { pin: pin.clone(), found });
+        }
+        let genesis = if head.chain.is_some() { self.genesis()? } else { None };
+        let blocks = (0..head.blocks).map(|height| self.block(height)).collect::<Result<_, _>>()?;
+        let blockchain = Blockchain::from_blocks(genesis, blocks);
+        head.check(&blockchain).map_err(RemoteError::Inconsistent)?;
+        if let Some(pin @ Pin::HeadSigner(digits)) = pin {
+            let signer = blockchain.tip().map(|block| fingerprint(&block.signer().verifying));
+            if signer.as_ref().map(|signer| signer.replace(' ', "")).as_ref() != Some(digits) {
+                let found = signer.map_or("an empty chain".into(), |key| format!("key {key}"));
+                return Err(RemoteError::PinMismatch { pin: pin.clone(), found });
+            }
+        }
+        Ok(blockchain)
+    }
+}
+
+impl From<RemoteError> for SyncError {
+    fn from(error: RemoteError) -> Self { Self::Source(error.to_string()) }
+}
+
+impl ChainSource for RemoteChain {
+    fn head(&self) -> Result<ChainHead, SyncError> { Ok(Self::head(self)?) }
+
+    fn genesis(&self) -> Result<Option<GenesisBlock>, SyncError> { Ok(Self::genesis(self)?) }
+
+    fn block(&self, height: u64) -> Result<Block, SyncError> { Ok(Self::block(self, height)?) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+    use crate::credential::Issuer;
+    use crate::requests::{IssuanceRequest, RequestSubmission};
+    use crate::server::{ApiError, Backend, Server, ServerConfig};
+
+    struct Served(Blockchain);
+
+    impl Backend for Served {
+        fn blockchain(&self) -> Result<Blockchain, ApiError> { Ok(self.0.clone()) }
+
+        fn submit_request(&self, _: RequestSubmission) -> Result<IssuanceRequest, ApiError> {
+            Err(ApiError::Forbidden("read-only".into()))
+        }
+    }
+
+    fn serve(blockchain: Blockchain) -> String { serve_with(blockchain, ServerConfig::default()) }
+
+    fn serve_with(blockchain: Blockchain, config: ServerConfig) -> String {
+        let scheme = if config.tls.is_some() { "https" } else { "http" };
+        let server = Server::bind("127.0.0.1:0", config).unwrap();
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run(&Served(blockchain)));
+        format!("{scheme}://{address}")
+    }
+
+    fn chain(blocks: usize) -> (Blockchain, Issuer) {
+        let genesis = GenesisBlock::new("Test".to_string(), None, None).unwrap();
+        let mut blockchain = Blockchain::with_genesis(genesis);
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        for _ in 0..blocks {
+            blockchain.add_block(Block::new(issuer.clone()), &signing).unwrap();
+        }
+        (blockchain, issuer)
+    }
+
+    #[test]
+    fn test_fetch_served_chain() {
+        let (blockchain, issuer) = chain(3);
+        let remote = RemoteChain::new(&serve(blockchain.clone())).unwrap();
+        assert_eq!(remote.head().unwrap(), ChainHead::new(&blockchain));
+
+        let fetched = remote.fetch(None).unwrap();
+        fetched.validate().unwrap();
+        assert_eq!(fetched.to_string(), blockchain.to_string());
+
+        let chain = Pin::Chain(blockchain.id().unwrap());
+        let signer = fingerprint(&issuer.verifying).parse::<Pin>().unwrap();
+        assert!(matches!(signer, Pin::HeadSigner(_)));
+        for pin in [chain, signer] {
+            remote.fetch(Some(&pin)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_pins_and_failures() {
+        let (blockchain, _) = chain(1);
+        let remote = RemoteChain::new(&serve(blockchain)).unwrap();
+        let (other, other_issuer) = chain(0);
+        let signer = fingerprint(&other_issuer.verifying).parse().unwrap();
+        for pin in [Pin::Chain(other.id().unwrap()), signer] {
+            let error = remote.fetch(Some(&pin)).unwrap_err();
+            assert!(matches!(error, RemoteError::PinMismatch { .. }));
+        }
+        assert!("not a pin".parse::<Pin>().is_err());
+        let digits = "00112233445566778899AABBCCDDEEFF";
+        assert_eq!(digits.to_lowercase().parse(), Ok(Pin::HeadSigner(digits.into())));
+
+        // Nothing listens on a port just freed
+        let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let unreachable = RemoteChain::new(&format!("http://{address}")).unwrap();
+        assert!(matches!(unreachable.head().unwrap_err(), RemoteError::Network { .. }));
+    }
+
+    #[test]
+    fn test_token() {
+        let (blockchain, _) = chain(1);
+        let config = ServerConfig { token: Some("s3cret".into()), ..ServerConfig::default() };
+        let url = serve_with(blockchain, config);
+        let anonymous = RemoteChain::new(&url).unwrap();
+        assert!(matches!(anonymous.head().unwrap_err(), RemoteError::Status { status: 401, .. }));
+        let token = Some("s3cret".to_string());
+        let remote = RemoteChain::with_auth(&url, &ClientAuth { token, ..ClientAuth::default() });
+        assert_eq!(remote.unwrap().fetch(None).unwrap().len(), 1);
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_client_certificates() {
+        use std::fs;
+        use std::path::Path;
+
+        use crate::server::TlsConfig;
+
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls");
+        let fixture = |name: &str| fixtures.join(name);
+        let pem = |names: &[&str]| -> Vec<u8> {
+            names.iter().flat_map(|name| fs::read(fixture(name)).unwrap()).collect()
+        };
+        let tls = TlsConfig {
+            certificate: fixture("server.pem"),
+            key: fixture("server.key"),
+            client_ca: Some(fixture("ca.pem")),
+        };
+        let (blockchain, _) = chain(2);
+        let url = serve_with(blockchain, ServerConfig { token: None, tls: Some(tls) });
+
+        let ca = Some(pem(&["ca.pem"]));
+        let identity = Some(pem(&["client.pem", "client.key"]));
+        let auth = ClientAuth { token: None, identity, ca: ca.clone() };
+        assert_eq!(RemoteChain::with_auth(&url, &auth).unwrap().fetch(None).unwrap().len(), 2);
+
+        // The handshake fails without a certificate, or with one the CA didn't issue
+        let anonymous = ClientAuth { ca: ca.clone(), ..ClientAuth::default() };
+        let rogue = ClientAuth { identity: Some(pem(&["rogue.pem", "rogue.key"])), ..auth };
+        for auth in [anonymous, rogue] {
+            let error = RemoteChain::with_auth(&url, &auth).unwrap().head().unwrap_err();
+            assert!(matches!(error, RemoteError::Network { .. }));
+        }
+        // Nor is the server trusted without its CA
+        let error = RemoteChain::new(&url).unwrap().head().unwrap_err();
+        assert!(matches!(error, RemoteError::Network { .. }));
+    }
+}
@@ -0,0 +1,219 @@
+//! Issuance requests submitted from elsewhere, e.g. a field office over the HTTP server, queued
+//! until someone holding the issuer's key approves or rejects them.
+//!
+//! A request only names the issuer, the subject and the claims. Nothing is signed until it's
+//! approved on the machine holding the keys.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::credential::{Attribute, DurationError, ValidDuration};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestError {
+    /// The submission isn't JSON of the expected shape
+    Malformed(String),
+    /// The submission asks for no attribute
+    NoAttributes,
+    /// An attribute has an empty name
+    UnnamedAttribute,
+    Duration(DurationError),
+}
+
+impl Display for RequestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(error) => write!(f, "malformed request: {error}"),
+            Self::NoAttributes => f.write_str("the request asks for no attribute"),
+            Self::UnnamedAttribute => f.write_str("the request has an attribute without a name"),
+            Self::Duration(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for RequestError {}
+
+/// Subject a request is about, a stored one by UUID or one to find or add by name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RequestedSubject {
+    Uuid(Uuid),
+    Named { name: String, surname: String },
+}
+
+impl Display for RequestedSubject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Uuid(uuid) => uuid.fmt(f),
+            Self::Named { name, surname } => write!(f, "{name} {surname}"),
+        }
+    }
+}
+
+/// Attribute as submitted, before any schema or salt is attached to it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestedAttribute {
+    pub name: String,
+    pub value: String,
+}
+
+/// Body of `POST /requests`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RequestSubmission {
+    pub issuer: Uuid,
+    pub subject: RequestedSubject,
+    pub attributes: Vec<RequestedAttribute>,
+    pub valid_from: NaiveDate,
+    #[serde(default)]
+    pub valid_to: Option<NaiveDate>,
+}
+
+impl RequestSubmission {
+    /// Reads a submission from its JSON body
+    ///
+    /// # Errors
+    /// Returns [`RequestError::Malformed`] if it isn't JSON of the expected shape.
+    pub fn from_json(json: &[u8]) -> Result<Self, RequestError> {
+        serde_json::from_slice(json).map_err(|e| RequestError::Malformed(e.to_string()))
+    }
+}
+
+/// Where a request stands, decided ones recording when and how
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestStatus {
+    Pending,
+    /// Issued as the credential with the given UUID
+    Approved { at: DateTime<Utc>, credential: Uuid },
+    Rejected { at: DateTime<Utc>, reason: String },
+}
+
+impl Display for RequestStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pending => "pending",
+            Self::Approved { .. } => "approved",
+            Self::Rejected { .. } => "rejected",
+        })
+    }
+}
+
+/// Queued issuance request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssuanceRequest {
+    pub id: Uuid,
+    pub submitted_at: DateTime<Utc>,
+    /// UUID of the issuer asked to sign the credential
+    pub issuer: Uuid,
+    pub subject: RequestedSubject,
+    pub attributes: Vec<RequestedAttribute>,
+    pub valid_duration: ValidDuration,
+    pub status: RequestStatus,
+}
+
+impl IssuanceRequest {
+    /// Pending request for a submission
+    ///
+    /// # Errors
+    /// Fails if the submission asks for no attribute, has an unnamed one or its validity period
+    /// ends before it starts.
+    pub fn new(submission: RequestSubmission) -> Result<Self, RequestError> {
+        if submission.attributes.is_empty() {
+            return Err(RequestError::NoAttributes);
+        }
+        if submission.attributes.iter().any(|attribute| attribute.name.trim().is_empty()) {
+            return Err(RequestError::UnnamedAttribute);
+        }
+        let valid_duration = ValidDuration::new(submission.valid_from, submission.valid_to)
+            .map_err(RequestError::Duration)?;
+        Ok(Self {
+            id: Uuid::new_v4(),
+            submitted_at: Utc::now(),
+            issuer: submission.issuer,
+            subject: submission.subject,
+            attributes: submission.attributes,
+            valid_duration,
+            status: RequestStatus::Pending,
+        })
+    }
+
+    /// Attributes to issue the credential with
+    #[must_use]
+    pub fn credential_attributes(&self) -> Vec<Attribute> {
+        let attributes = self.attributes.iter();
+        attributes.map(|a| Attribute::new(a.name.clone(), a.value.clone())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submission(json: &str) -> Result<IssuanceRequest, RequestError> {
+        IssuanceRequest::new(RequestSubmission::from_json(json.as_bytes())?)
+    }
+
+    #[test]
+    fn test_submission() {
+        let request = submission(
+            r#"{
+                "issuer": "00000000-0000-0000-0000-000000000001",
+                "subject": {"name": "Alice", "surname": "Smith"},
+                "attributes": [{"name": "degree", "value": "MSc"}],
+                "valid_from": "2024-01-01"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(request.issuer, Uuid::from_u128(1));
+        assert_eq!(request.subject.to_string(), "Alice Smith");
+        assert_eq!(request.credential_attributes()[0].value, "MSc");
+        assert_eq!(request.valid_duration.to, None);
+        assert_eq!(request.status, RequestStatus::Pending);
+
+        let stored = serde_json::to_string(&request).unwrap();
+        assert_eq!(serde_json::from_str::<IssuanceRequest>(&stored).unwrap(), request);
+
+        let request = submission(
+            r#"{
+                "issuer": "00000000-0000-0000-0000-000000000001",
+                "subject": "00000000-0000-0000-0000-000000000002",
+                "attributes": [{"name": "degree", "value": "MSc"}],
+                "valid_from": "2024-01-01",
+                "valid_to": "2025-01-01"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(request.subject, RequestedSubject::Uuid(Uuid::from_u128(2)));
+    }
+
+    #[test]
+    fn test_invalid_submissions() {
+        let with = |attributes: &str, dates: &str| {
+            submission(&format!(
+                r#"{{"issuer": "00000000-0000-0000-0000-000000000001", "subject":
+                "00000000-0000-0000-0000-000000000002", "attributes": {attributes}, {dates}}}"#
+            ))
+            .unwrap_err()
+        };
+        let degree = r#"[{"name": "degree", "value": "MSc"}]"#;
+        assert_eq!(with("[]", r#""valid_from": "2024-01-01""#), RequestError::NoAttributes);
+        assert_eq!(
+            with(r#"[{"name": " ", "value": "MSc"}]"#, r#""valid_from": "2024-01-01""#),
+            RequestError::UnnamedAttribute
+        );
+        assert!(matches!(
+            with(degree, r#""valid_from": "2024-01-01", "valid_to": "2023-01-01""#),
+            RequestError::Duration(_)
+        ));
+        assert!(matches!(with(degree, r#""valid_from": "soon""#), RequestError::Malformed(_)));
+        assert!(matches!(
+            with(degree, r#""valid_from": "2024-01-01", "signature": "00""#),
+            RequestError::Malformed(_)
+        ));
+    }
+}
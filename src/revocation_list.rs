@@ -0,0 +1,213 @@
+//! Signed lists of the credentials revoked on a chain, for relying parties that cache credentials
+//! to fetch now and then instead of the whole chain, as they would a certificate revocation list.
+//!
+//! The list signs the compact JSON of all its other fields, in the order they're declared, so
+//! that it reads back to the same bytes and its signature can be checked without the chain.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::blockchain::Blockchain;
+use crate::credential::{Issuer, verifying_key_serde};
+use crate::exchange::{EXCHANGE_VERSION, ExchangeError, from_versioned_json};
+use crate::hash::Hash;
+use crate::signature::Sig;
+use crate::signer::{BlockSigner, SignError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevocationListError {
+    /// The file isn't a revocation list this version can read
+    Exchange(ExchangeError),
+    /// The signature doesn't check against the key the list names
+    Signature,
+    Sign(SignError),
+}
+
+impl Display for RevocationListError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exchange(error) => error.fmt(f),
+            Self::Signature => f.write_str(
+                "the list's signature doesn't match its signer's key, potential forgery",
+            ),
+            Self::Sign(error) => write!(f, "failed to sign the list: {error}"),
+        }
+    }
+}
+
+impl Error for RevocationListError {}
+
+/// Revocation the chain honors, by the hash it's recorded under
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevokedEntry {
+    pub hash: Hash,
+    /// Height of the block recording the revocation
+    pub height: u64,
+    /// Time the block recording the revocation was finalized
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Every revocation a chain honors as of its head, signed by an issuer. Displayed as indented
+/// JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevocationList {
+    /// Format version, [`EXCHANGE_VERSION`] for files written by this version
+    pub version: u32,
+    pub generated_at: DateTime<Utc>,
+    /// UUID of the chain's genesis block, none for chains without one
+    pub chain: Option<Uuid>,
+    /// Hash of the chain's last block, none for an empty chain
+    pub head: Option<Hash>,
+    pub revocations: Vec<RevokedEntry>,
+    /// UUID and name of the issuer signing the list
+    pub signer: Uuid,
+    pub signer_name: String,
+    #[serde(with = "verifying_key_serde")]
+    pub signer_key: VerifyingKey,
+    pub signature: Sig,
+}
+
+/// The fields of a [`RevocationList`] its signature covers
+#[derive(Serialize)]
+struct Unsigned<'a> {
+    version: u32,
+    generated_at: &'a DateTime<Utc>,
+    chain: &'a Option<Uuid>,
+    head: &'a Option<Hash>,
+    revocations: &'a [RevokedEntry],
+    signer: Uuid,
+    signer_name: &'a str,
+    #[serde(with = "verifying_key_serde")]
+    signer_key: &'a VerifyingKey,
+}
+
+impl RevocationList {
+    /// Lists the revocations the chain honors, signed as the issuer
+    ///
+    /// # Errors
+    /// Returns [`RevocationListError::Sign`] if the signer fails or doesn't hold the issuer's key.
+    pub fn new(
+        blockchain: &Blockchain, issuer: &Issuer, signer: &dyn BlockSigner,
+    ) -> Result<Self, RevocationListError> {
+        let revocations = blockchain
+            .revocations()
+            .into_iter()
+            .map(|(block, revocation)| RevokedEntry {
+                hash: revocation.signed.credential.clone(),
+                height: block.height(),
+                timestamp: block.timestamp(),
+            })
+            .collect();
+        let mut list = Self {
+            version: EXCHANGE_VERSION,
+            generated_at: Utc::now(),
+            chain: blockchain.id(),
+            head: blockchain.tip().map(|block| block.hash().clone()),
+            revocations,
+            signer: issuer.uuid,
+            signer_name: issuer.name.clone(),
+            signer_key: signer.verifying_key(),
+            signature: Sig::default(),
+        };
+        let signature = signer.sign(&list.message()).map_err(RevocationListError::Sign)?;
+        if list.signer_key.verify(&list.message(), &signature).is_err() {
+            return Err(RevocationListError::Sign(SignError::InvalidSignature));
+        }
+        list.signature = signature.into();
+        Ok(list)
+    }
+
+    fn message(&self) -> Vec<u8> {
+        let unsigned = Unsigned {
+            version: self.version,
+            generated_at: &self.generated_at,
+            chain: &self.chain,
+            head: &self.head,
+            revocations: &self.revocations,
+            signer: self.signer,
+            signer_name: &self.signer_name,
+            signer_key: &self.signer_key,
+        };
+        serde_json::to_vec(&unsigned).unwrap_or_default()
+    }
+
+    /// Reads a list as written by its [`Display`] implementation and checks its signature against
+    /// the key it names, which the caller still has to trust
+    ///
+    /// # Errors
+    /// Returns [`RevocationListError::Exchange`] if the JSON isn't a list in this format version,
+    /// and [`RevocationListError::Signature`] if the signature doesn't check.
+    pub fn from_json(json: &str) -> Result<Self, RevocationListError> {
+        let list: Self = from_versioned_json(json).map_err(RevocationListError::Exchange)?;
+        let signature = Signature::from(&list.signature);
+        if list.signer_key.verify(&list.message(), &signature).is_err() {
+            return Err(RevocationListError::Signature);
+        }
+        Ok(list)
+    }
+
+    /// Entry of the revocation recorded under the given hash, if the list has it
+    #[must_use]
+    pub fn find(&self, hash: &Hash) -> Option<&RevokedEntry> {
+        self.revocations.iter().find(|entry| &entry.hash == hash)
+    }
+}
+
+impl Display for RevocationList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(&self).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::blockchain::{Block, GenesisBlock};
+    use crate::credential::{Attribute, Credential, Subject, ValidDuration};
+
+    #[test]
+    fn test_list_holds_revocations_and_checks_signature() {
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let valid = ValidDuration::starting(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        let credential = |value: &str| {
+            let attributes = vec![Attribute::new("degree".into(), value.into())];
+            let subject = Subject::new("Alice".into(), "Smith".into());
+            Credential::new(attributes, issuer.clone(), subject, valid.clone())
+        };
+        let (kept, revoked) = (credential("MSc"), credential("BSc"));
+        let mut blockchain =
+            Blockchain::with_genesis(GenesisBlock::new("Test".to_string(), None, None).unwrap());
+        let format = blockchain.format();
+        let mut block = Block::new(issuer.clone());
+        for credential in [&kept, &revoked] {
+            block.add_credential(credential.sign(&signing, false, format).unwrap(), false).unwrap();
+        }
+        block.add_credential(revoked.sign(&signing, true, format).unwrap(), true).unwrap();
+        blockchain.add_block(block, &signing).unwrap();
+
+        let list = RevocationList::new(&blockchain, &issuer, &signing).unwrap();
+        assert_eq!(list.revocations.len(), 1);
+        assert_eq!(list.revocations[0].height, 0);
+        assert!(list.find(&revoked.hash(true, format)).is_some());
+        assert!(list.find(&kept.hash(true, format)).is_none());
+
+        let json = list.to_string();
+        let read = RevocationList::from_json(&json).unwrap();
+        assert_eq!(read.to_string(), json);
+
+        // Dropping an entry breaks the signature
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["revocations"] = serde_json::json!([]);
+        assert_eq!(
+            RevocationList::from_json(&value.to_string()).unwrap_err(),
+            RevocationListError::Signature
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::credential::Attribute;
+
+/// Kind of value an attribute holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    #[default]
+    String,
+    Integer,
+    Decimal,
+    Boolean,
+    /// Date written as `YYYY-MM-DD`
+    Date,
+}
+
+impl ValueType {
+    fn accepts(self, value: &str) -> bool {
+        match self {
+            Self::String => true,
+            Self::Integer => value.parse::<i64>().is_ok(),
+            Self::Decimal => value.parse::<f64>().is_ok_and(f64::is_finite),
+            Self::Boolean => matches!(value, "true" | "false"),
+            Self::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+        }
+    }
+}
+
+impl Display for ValueType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::String => "string",
+            Self::Integer => "integer",
+            Self::Decimal => "decimal",
+            Self::Boolean => "boolean",
+            Self::Date => "date",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    /// The attribute is named differently than the schema requires
+    NameMismatch { expected: String, got: String },
+    /// The value isn't of the schema's type
+    WrongType { value: String, expected: ValueType },
+    /// The value doesn't match the whole of the schema's pattern
+    PatternMismatch { value: String, pattern: String },
+    /// The value isn't one of those the schema allows
+    NotAllowed { value: String, allowed: Vec<String> },
+    /// The schema's pattern isn't a valid regular expression
+    InvalidPattern(String),
+}
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NameMismatch { expected, got } =>
+                write!(f, "attribute name '{got}' doesn't match the schema's name '{expected}'"),
+            Self::WrongType { value, expected } =>
+                write!(f, "value '{value}' isn't a valid {expected}"),
+            Self::PatternMismatch { value, pattern } =>
+                write!(f, "value '{value}' doesn't match pattern {pattern}"),
+            Self::NotAllowed { value, allowed } =>
+                write!(f, "value '{value}' not in allowed set [{}]", allowed.join(", ")),
+            Self::InvalidPattern(error) => write!(f, "invalid pattern: {error}"),
+        }
+    }
+}
+
+impl Error for SchemaError {}
+
+/// Rules an attribute must follow, so that issuers name and fill in the same attribute the same
+/// way
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttributeSchema {
+    pub name: String,
+    /// Identifier of the schema recorded in credentials checked against it
+    pub uri: String,
+    #[serde(default)]
+    pub value_type: ValueType,
+    /// Regular expression the whole value must match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Values allowed for the attribute, any value of the right type if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed: Vec<String>,
+}
+
+impl AttributeSchema {
+    #[must_use]
+    pub fn new(name: String, uri: String, value_type: ValueType) -> Self {
+        Self { name, uri, value_type, pattern: None, allowed: Vec::new() }
+    }
+
+    /// Compiles the pattern, anchored so that it has to match the whole value
+    fn pattern(&self) -> Result<Option<Regex>, SchemaError> {
+        self.pattern
+            .as_ref()
+            .map(|pattern| Regex::new(&format!("^(?:{pattern})$")))
+            .transpose()
+            .map_err(|e| SchemaError::InvalidPattern(e.to_string()))
+    }
+
+    /// Checks that the schema's pattern is a valid regular expression
+    ///
+    /// # Errors
+    /// Returns [`SchemaError::InvalidPattern`] if it isn't.
+    pub fn check(&self) -> Result<(), SchemaError> { self.pattern().map(|_| ()) }
+
+    /// Checks the attribute's name and value against the schema
+    ///
+    /// # Errors
+    /// Returns the first rule the attribute breaks.
+    pub fn validate(&self, attribute: &Attribute) -> Result<(), SchemaError> {
+        if attribute.name != self.name {
+            return Err(SchemaError::NameMismatch {
+                expected: self.name.clone(),
+                got: attribute.name.clone(),
+            });
+        }
+        let value = &attribute.value;
+        if !self.value_type.accepts(value) {
+            return Err(SchemaError::WrongType { value: value.clone(), expected: self.value_type });
+        }
+        if let Some(pattern) = self.pattern()?
+            && !pattern.is_match(value)
+        {
+            return Err(SchemaError::PatternMismatch {
+                value: value.clone(),
+                pattern: self.pattern.clone().unwrap_or_default(),
+            });
+        }
+        if !self.allowed.is_empty() && !self.allowed.contains(value) {
+            return Err(SchemaError::NotAllowed {
+                value: value.clone(),
+                allowed: self.allowed.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Display for AttributeSchema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(self).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(name: &str, value: &str) -> Attribute {
+        Attribute::new(name.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_schema_pattern() {
+        let mut schema = AttributeSchema::new(
+            "national_id".into(),
+            "https://schemas.example.org/national_id".into(),
+            ValueType::String,
+        );
+        schema.pattern = Some("[A-Z]{3}[0-9]{6}".into());
+        assert_eq!(schema.validate(&attribute("national_id", "ABC123456")), Ok(()));
+        // The pattern has to match the whole value
+        let error = schema.validate(&attribute("national_id", "xABC123456")).unwrap_err();
+        assert_eq!(error.to_string(), "value 'xABC123456' doesn't match pattern [A-Z]{3}[0-9]{6}");
+        assert_eq!(
+            schema.validate(&attribute("National ID", "ABC123456")),
+            Err(SchemaError::NameMismatch {
+                expected: "national_id".into(),
+                got: "National ID".into()
+            })
+        );
+
+        schema.pattern = Some("[A-Z".into());
+        assert!(matches!(schema.check(), Err(SchemaError::InvalidPattern(_))));
+    }
+
+    #[test]
+    fn test_schema_allowed_values() {
+        let mut schema = AttributeSchema::new(
+            "driving_licence".into(),
+            "https://schemas.example.org/driving_licence".into(),
+            ValueType::String,
+        );
+        schema.allowed = vec!["A".into(), "B".into(), "C".into()];
+        assert_eq!(schema.validate(&attribute("driving_licence", "B")), Ok(()));
+        let error = schema.validate(&attribute("driving_licence", "X")).unwrap_err();
+        assert_eq!(error.to_string(), "value 'X' not in allowed set [A, B, C]");
+    }
+
+    #[test]
+    fn test_schema_value_types() {
+        let schema = |value_type| {
+            AttributeSchema::new("value".into(), "urn:example:value".into(), value_type)
+        };
+        for (value_type, good, bad) in [
+            (ValueType::Integer, "42", "4.2"),
+            (ValueType::Decimal, "4.2", "four"),
+            (ValueType::Boolean, "true", "yes"),
+            (ValueType::Date, "1990-02-03", "03/02/1990"),
+        ] {
+            assert_eq!(schema(value_type).validate(&attribute("value", good)), Ok(()));
+            assert_eq!(
+                schema(value_type).validate(&attribute("value", bad)),
+                Err(SchemaError::WrongType { value: bad.into(), expected: value_type })
+            );
+        }
+        assert_eq!(
+            schema(ValueType::Integer).validate(&attribute("value", "x")).unwrap_err().to_string(),
+            "value 'x' isn't a valid integer"
+        );
+    }
+}
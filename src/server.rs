@@ -0,0 +1,613 @@
+//! HTTP server exposing the local stores to other machines, answering one request at a time so
+//! that handlers can read and write the stores like the CLI does.
+//!
+//! What the server serves comes from a [`Backend`], which the CLI implements over the files in
+//! the working directory. Errors are answered as `{"error": "..."}` with their status code.
+//!
+//! The chain is served as files, `head.json`, `genesis.json`, `index.json` and
+//! `blocks/<height>.json`, so that clients can read it the same way from a static copy.
+//!
+//! With a token set, every request but health checks has to carry it. With the `tls` feature the
+//! server can serve over HTTPS instead, optionally requiring certificates issued by a given CA
+//! from clients, which are checked before any request is read.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::Read;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use subtle::ConstantTimeEq;
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::blockchain::Blockchain;
+use crate::exchange::{ChainHead, ChainIndex};
+use crate::requests::{IssuanceRequest, RequestSubmission};
+
+/// Largest request body read, larger ones are refused
+pub const MAX_BODY: u64 = 64 * 1024;
+
+/// Paths answered without the bearer token, for monitoring that doesn't hold it
+const PUBLIC_PATHS: [&str; 2] = ["/health", "/metrics"];
+
+/// `[server]` section of `attestation.toml`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Bearer token clients have to send with every request but health checks. Without one, the
+    /// chain can be read by anyone and issuance requests are refused.
+    pub token: Option<String>,
+    /// Serve over HTTPS
+    pub tls: Option<TlsConfig>,
+}
+
+/// `[server.tls]` section of `attestation.toml`, paths relative to the working directory
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM file of the server's certificate chain, its own certificate first
+    pub certificate: PathBuf,
+    /// PEM file of the certificate's private key
+    pub key: PathBuf,
+    /// PEM bundle of the CAs clients' certificates have to be issued by. When set, clients
+    /// without such a certificate can't connect.
+    pub client_ca: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum ServerError {
+    /// The address can't be listened on
+    Bind(String),
+    /// The connections can't be served
+    Transport(String),
+    /// The certificates and keys configured can't be used
+    Tls(String),
+}
+
+impl Display for ServerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bind(error) => write!(f, "failed to listen: {error}"),
+            Self::Transport(error) => write!(f, "failed to serve: {error}"),
+            Self::Tls(error) => write!(f, "failed to set up TLS: {error}"),
+        }
+    }
+}
+
+impl Error for ServerError {}
+
+/// Request the server can't answer, with the status code it's answered with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiError {
+    BadRequest(String),
+    /// The request has no valid bearer token
+    Unauthorized(String),
+    /// The request is authenticated but not allowed
+    Forbidden(String),
+    NotFound(String),
+    MethodNotAllowed,
+    PayloadTooLarge,
+    Internal(String),
+}
+
+impl ApiError {
+    #[must_use]
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::BadRequest(_) => 400,
+            Self::Unauthorized(_) => 401,
+            Self::Forbidden(_) => 403,
+            Self::NotFound(_) => 404,
+            Self::MethodNotAllowed => 405,
+            Self::PayloadTooLarge => 413,
+            Self::Internal(_) => 500,
+        }
+    }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadRequest(error)
+            | Self::Forbidden(error)
+            | Self::NotFound(error)
+            | Self::Internal(error) => f.write_str(error),
+            Self::Unauthorized(error) => write!(f, "unauthorized: {error}"),
+            Self::MethodNotAllowed => f.write_str("method not allowed on this endpoint"),
+            Self::PayloadTooLarge => write!(f, "request body larger than {MAX_BODY} bytes"),
+        }
+    }
+}
+
+impl Error for ApiError {}
+
+/// Stores the server answers from
+pub trait Backend {
+    /// Chain to serve
+    ///
+    /// # Errors
+    /// Fails if the chain can't be read.
+    fn blockchain(&self) -> Result<Blockchain, ApiError>;
+
+    /// Queues an issuance request
+    ///
+    /// # Errors
+    /// Fails if the request names an unknown issuer or subject, or the queue can't be written.
+    fn submit_request(&self, submission: RequestSubmission) -> Result<IssuanceRequest, ApiError>;
+}
+
+pub struct Server {
+    http: tiny_http::Server,
+    config: ServerConfig,
+    /// Terminates TLS on the address, `http` then only listening on a private socket
+    #[cfg(feature = "tls")]
+    tls: Option<tls::Terminator>,
+}
+
+impl Server {
+    /// Listens on the address, e.g. `127.0.0.1:8080`, or on a free port with port 0
+    ///
+    /// # Errors
+    /// Returns [`ServerError::Bind`] if the address can't be listened on, and
+    /// [`ServerError::Tls`] if TLS is configured but can't be set up.
+    pub fn bind(address: &str, config: ServerConfig) -> Result<Self, ServerError> {
+        let bind = |e: Box<dyn Error + Send + Sync>| ServerError::Bind(e.to_string());
+        let Some(tls) = &config.tls else {
+            let http = tiny_http::Server::http(address).map_err(bind)?;
+            #[cfg(feature = "tls")]
+            return Ok(Self { http, config, tls: None });
+            #[cfg(not(feature = "tls"))]
+            return Ok(Self { http, config });
+        };
+        #[cfg(feature = "tls")]
+        {
+            let terminator = tls::Terminator::bind(address, tls)?;
+            let http = terminator.listen().map_err(bind)?;
+            Ok(Self { http, config, tls: Some(terminator) })
+        }
+        #[cfg(not(feature = "tls"))]
+        {
+            let _ = tls;
+            Err(ServerError::Tls("this build can't serve TLS, build with `--features tls`".into()))
+        }
+    }
+
+    /// Address listened on
+    #[must_use]
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        #[cfg(feature = "tls")]
+        if let Some(terminator) = &self.tls {
+            return terminator.local_addr();
+        }
+        self.http.server_addr().to_ip()
+    }
+
+    /// Answers requests until the process ends
+    ///
+    /// # Errors
+    /// Returns [`ServerError::Transport`] if TLS connections can't be accepted.
+    pub fn run(&self, backend: &dyn Backend) -> Result<(), ServerError> {
+        #[cfg(feature = "tls")]
+        if let Some(terminator) = &self.tls {
+            terminator.spawn()?;
+        }
+        for mut request in self.http.incoming_requests() {
+            let (status, body) = match self.handle(&mut request, backend) {
+                Ok(answer) => answer,
+                Err(error) => (error.status(), json!({ "error": error.to_string() })),
+            };
+            let mut response = Response::from_string(body.to_string()).with_status_code(status);
+            if let Ok(content_type) = Header::from_bytes("Content-Type", "application/json") {
+                response.add_header(content_type);
+            }
+            if status == 401
+                && let Ok(challenge) = Header::from_bytes("WWW-Authenticate", "Bearer")
+            {
+                response.add_header(challenge);
+            }
+            // The client hanging up before the answer is its problem
+            let _ = request.respond(response);
+        }
+        Ok(())
+    }
+
+    fn handle(
+        &self, request: &mut Request, backend: &dyn Backend,
+    ) -> Result<(u16, serde_json::Value), ApiError> {
+        let path = request.url().split('?').next().unwrap_or_default().to_string();
+        if !PUBLIC_PATHS.contains(&path.as_str()) {
+            self.authenticate(request)?;
+        }
+        match (request.method(), path.as_str()) {
+            (Method::Get, "/health") => Ok((200, json!({ "status": "ok" }))),
+            (Method::Get, "/head.json") => {
+                let head = ChainHead::new(&backend.blockchain()?);
+                Ok((200, to_value(&head)?))
+            },
+            (Method::Get, "/genesis.json") => {
+                let blockchain = backend.blockchain()?;
+                let genesis = blockchain.genesis().ok_or(ApiError::NotFound(
+                    "the chain has no genesis block".into(),
+                ))?;
+                Ok((200, to_value(genesis)?))
+            },
+            (Method::Get, "/index.json") => {
+                let index = ChainIndex::new(&backend.blockchain()?);
+                Ok((200, to_value(&index)?))
+            },
+            (Method::Get, path) if let Some(height) = block_height(path) => {
+                let blockchain = backend.blockchain()?;
+                let block = blockchain
+                    .block(height)
+                    .ok_or(ApiError::NotFound(format!("the chain has no block #{height}")))?;
+                Ok((200, to_value(block)?))
+            },
+            (Method::Post, "/requests") => {
+                if self.config.token.is_none() {
+                    return Err(ApiError::Forbidden(
+                        "this server takes no requests, it has no token set in its config".into(),
+                    ));
+                }
+                let submission = RequestSubmission::from_json(&read_body(request)?)
+                    .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+                let queued = backend.submit_request(submission)?;
+                Ok((201, to_value(&queued)?))
+            },
+            (_, "/health" | "/requests") => Err(ApiError::MethodNotAllowed),
+            _ => Err(ApiError::NotFound("no such endpoint".into())),
+        }
+    }
+
+    /// Checks the request's bearer token against the configured one, if any
+    fn authenticate(&self, request: &Request) -> Result<(), ApiError> {
+        let Some(token) = &self.config.token else {
+            return Ok(());
+        };
+        let sent = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Authorization"))
+            .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+            .ok_or(ApiError::Unauthorized("missing bearer token".into()))?;
+        if !bool::from(sent.as_bytes().ct_eq(token.as_bytes())) {
+            return Err(ApiError::Unauthorized("wrong bearer token".into()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tls")]
+pub use tls::server_config;
+
+#[cfg(feature = "tls")]
+mod tls {
+    use std::fmt::Display;
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::net::SocketAddr;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use rustls::RootCertStore;
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+    use rustls::server::WebPkiClientVerifier;
+    use tempfile::TempDir;
+    use tokio::net::TcpListener;
+    use tokio::runtime::Runtime;
+    use tokio_rustls::TlsAcceptor;
+
+    use super::{ServerError, TlsConfig};
+
+    /// Server side of the TLS the config sets up, requiring client certificates when it has a CA
+    /// to check them against
+    ///
+    /// # Errors
+    /// Returns [`ServerError::Tls`] if a file can't be read or holds no usable certificate or key.
+    pub fn server_config(config: &TlsConfig) -> Result<rustls::ServerConfig, ServerError> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let certificates = read_certificates(&config.certificate)?;
+        let key = PrivateKeyDer::from_pem_file(&config.key)
+            .map_err(|e| ServerError::Tls(format!("{}: {e}", config.key.display())))?;
+        let builder = rustls::ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .map_err(|e| ServerError::Tls(e.to_string()))?;
+        let builder = match &config.client_ca {
+            None => builder.with_no_client_auth(),
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for certificate in read_certificates(path)? {
+                    roots
+                        .add(certificate)
+                        .map_err(|e| ServerError::Tls(format!("{}: {e}", path.display())))?;
+                }
+                let verifier = WebPkiClientVerifier::builder_with_provider(roots.into(), provider)
+                    .build()
+                    .map_err(|e| ServerError::Tls(format!("{}: {e}", path.display())))?;
+                builder.with_client_cert_verifier(verifier)
+            },
+        };
+        builder.with_single_cert(certificates, key).map_err(|e| ServerError::Tls(e.to_string()))
+    }
+
+    fn read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>, ServerError> {
+        let error = |e: &dyn Display| ServerError::Tls(format!("{}: {e}", path.display()));
+        let reader = BufReader::new(File::open(path).map_err(|e| error(&e))?);
+        let certificates: Vec<_> = CertificateDer::pem_reader_iter(reader)
+            .collect::<Result<_, _>>()
+            .map_err(|e| error(&e))?;
+        if certificates.is_empty() {
+            return Err(error(&"no certificate found"));
+        }
+        Ok(certificates)
+    }
+
+    /// Accepts TLS connections on the address and forwards what they carry to a Unix socket in
+    /// a directory only this user can enter, which the HTTP server listens on
+    pub(super) struct Terminator {
+        runtime: Runtime,
+        listener: std::net::TcpListener,
+        acceptor: TlsAcceptor,
+        directory: TempDir,
+    }
+
+    impl Terminator {
+        pub(super) fn bind(address: &str, config: &TlsConfig) -> Result<Self, ServerError> {
+            let acceptor = TlsAcceptor::from(Arc::new(server_config(config)?));
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .enable_io()
+                .build()
+                .map_err(|e| ServerError::Transport(e.to_string()))?;
+            let listener = std::net::TcpListener::bind(address)
+                .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+                .map_err(|e| ServerError::Bind(e.to_string()))?;
+            let directory = tempfile::Builder::new()
+                .prefix("attestation-serve-")
+                .tempdir()
+                .map_err(|e| ServerError::Bind(e.to_string()))?;
+            Ok(Self { runtime, listener, acceptor, directory })
+        }
+
+        fn socket(&self) -> PathBuf { self.directory.path().join("http.sock") }
+
+        /// HTTP server on the socket connections are forwarded to
+        #[cfg(unix)]
+        pub(super) fn listen(
+            &self,
+        ) -> Result<tiny_http::Server, Box<dyn std::error::Error + Send + Sync>> {
+            tiny_http::Server::http_unix(&self.socket())
+        }
+
+        #[cfg(not(unix))]
+        pub(super) fn listen(
+            &self,
+        ) -> Result<tiny_http::Server, Box<dyn std::error::Error + Send + Sync>> {
+            Err("TLS is only served on Unix".into())
+        }
+
+        pub(super) fn local_addr(&self) -> Option<SocketAddr> { self.listener.local_addr().ok() }
+
+        /// Forwards connections in the background until the process ends
+        #[cfg(unix)]
+        pub(super) fn spawn(&self) -> Result<(), ServerError> {
+            let listener = self
+                .listener
+                .try_clone()
+                .and_then(|listener| {
+                    let _runtime = self.runtime.enter();
+                    TcpListener::from_std(listener)
+                })
+                .map_err(|e| ServerError::Transport(e.to_string()))?;
+            let (acceptor, socket) = (self.acceptor.clone(), self.socket());
+            self.runtime.spawn(async move {
+                loop {
+                    // Failing to accept one connection doesn't stop the others
+                    let Ok((stream, _)) = listener.accept().await else {
+                        continue;
+                    };
+                    tokio::spawn(forward(acceptor.clone(), stream, socket.clone()));
+                }
+            });
+            Ok(())
+        }
+
+        #[cfg(not(unix))]
+        pub(super) fn spawn(&self) -> Result<(), ServerError> {
+            Err(ServerError::Transport("TLS is only served on Unix".into()))
+        }
+    }
+
+    /// Forwards the connection once its handshake succeeds, which it doesn't for clients
+    /// without a certificate when one is required
+    #[cfg(unix)]
+    async fn forward(acceptor: TlsAcceptor, stream: tokio::net::TcpStream, socket: PathBuf) {
+        let Ok(mut stream) = acceptor.accept(stream).await else {
+            return;
+        };
+        let Ok(mut http) = tokio::net::UnixStream::connect(socket).await else {
+            return;
+        };
+        // Either side hanging up ends the connection
+        let _ = tokio::io::copy_bidirectional(&mut stream, &mut http).await;
+    }
+}
+
+/// Height of the block a `/blocks/<height>.json` path asks for
+fn block_height(path: &str) -> Option<u64> {
+    path.strip_prefix("/blocks/")?.strip_suffix(".json")?.parse().ok()
+}
+
+fn read_body(request: &mut Request) -> Result<Vec<u8>, ApiError> {
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_BODY + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| ApiError::BadRequest(format!("failed to read the body: {e}")))?;
+    if body.len() as u64 > MAX_BODY {
+        return Err(ApiError::PayloadTooLarge);
+    }
+    Ok(body)
+}
+
+fn to_value(value: &impl Serialize) -> Result<serde_json::Value, ApiError> {
+    serde_json::to_value(value).map_err(|e| ApiError::Internal(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::TcpStream;
+    use std::thread;
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::blockchain::{Block, GenesisBlock};
+    use crate::credential::Issuer;
+
+    /// Serves a chain of one block
+    struct Queue(Blockchain);
+
+    impl Backend for Queue {
+        fn blockchain(&self) -> Result<Blockchain, ApiError> { Ok(self.0.clone()) }
+
+        fn submit_request(
+            &self, submission: RequestSubmission,
+        ) -> Result<IssuanceRequest, ApiError> {
+            if submission.issuer != Uuid::from_u128(1) {
+                return Err(ApiError::BadRequest(format!("no issuer {}", submission.issuer)));
+            }
+            IssuanceRequest::new(submission).map_err(|e| ApiError::BadRequest(e.to_string()))
+        }
+    }
+
+    fn serve(token: Option<&str>) -> SocketAddr {
+        let config = ServerConfig { token: token.map(String::from), ..ServerConfig::default() };
+        let server = Server::bind("127.0.0.1:0", config).unwrap();
+        let address = server.local_addr().unwrap();
+        let genesis = GenesisBlock::new("Test".to_string(), None, None).unwrap();
+        let mut blockchain = Blockchain::with_genesis(genesis);
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        blockchain.add_block(Block::new(issuer), &signing).unwrap();
+        thread::spawn(move || server.run(&Queue(blockchain)));
+        address
+    }
+
+    fn send(
+        address: SocketAddr, method: &str, path: &str, token: Option<&str>, body: &str,
+    ) -> (u16, serde_json::Value) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        let authorization =
+            token.map_or(String::new(), |t| format!("Authorization: Bearer {t}\r\n"));
+        write!(
+            stream,
+            "{method} {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+             {authorization}Content-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    const REQUEST: &str = r#"{"issuer": "00000000-0000-0000-0000-000000000001",
+        "subject": {"name": "Alice", "surname": "Smith"},
+        "attributes": [{"name": "degree", "value": "MSc"}], "valid_from": "2024-01-01"}"#;
+
+    #[test]
+    fn test_submissions_need_the_token() {
+        let address = serve(Some("s3cret"));
+        assert_eq!(send(address, "GET", "/health", None, "").0, 200);
+        let (status, body) = send(address, "POST", "/requests", None, REQUEST);
+        assert_eq!(status, 401);
+        assert_eq!(body["error"], "unauthorized: missing bearer token");
+        assert_eq!(send(address, "POST", "/requests", Some("guess"), REQUEST).0, 401);
+
+        let (status, body) = send(address, "POST", "/requests", Some("s3cret"), REQUEST);
+        assert_eq!(status, 201);
+        assert_eq!(body["status"], "pending");
+        assert_eq!(body["attributes"][0]["value"], "MSc");
+
+        let unknown = REQUEST.replace("0001", "0002");
+        assert_eq!(send(address, "POST", "/requests", Some("s3cret"), &unknown).0, 400);
+        assert_eq!(send(address, "POST", "/requests", Some("s3cret"), "{").0, 400);
+        assert_eq!(send(address, "GET", "/requests", Some("s3cret"), "").0, 405);
+        assert_eq!(send(address, "GET", "/nothing", Some("s3cret"), "").0, 404);
+    }
+
+    #[test]
+    fn test_reads_need_the_token() {
+        let address = serve(Some("s3cret"));
+        for path in ["/head.json", "/genesis.json", "/index.json", "/blocks/0.json", "/nothing"] {
+            let (status, body) = send(address, "GET", path, None, "");
+            assert_eq!(status, 401);
+            assert_eq!(body["error"], "unauthorized: missing bearer token");
+            let (status, body) = send(address, "GET", path, Some("guess"), "");
+            assert_eq!(status, 401);
+            assert_eq!(body["error"], "unauthorized: wrong bearer token");
+        }
+        assert_eq!(send(address, "GET", "/head.json", Some("s3cret"), "").0, 200);
+        // Monitoring doesn't hold the token
+        assert_eq!(send(address, "GET", "/health", None, "").0, 200);
+        assert_eq!(send(address, "GET", "/metrics", None, "").0, 404);
+    }
+
+    #[test]
+    fn test_chain_is_served_as_files() {
+        let address = serve(None);
+        let (status, head) = send(address, "GET", "/head.json", None, "");
+        assert_eq!(status, 200);
+        assert_eq!(head["blocks"], 1);
+        let (status, block) = send(address, "GET", "/blocks/0.json", None, "");
+        assert_eq!(status, 200);
+        assert_eq!(block["hash"], head["head"]);
+        assert_eq!(send(address, "GET", "/genesis.json", None, "").0, 200);
+        let (status, index) = send(address, "GET", "/index.json", None, "");
+        assert_eq!(status, 200);
+        assert_eq!(index["blocks"]["0"], head["head"]);
+        let (status, error) = send(address, "GET", "/blocks/1.json", None, "");
+        assert_eq!(status, 404);
+        assert_eq!(error["error"], "the chain has no block #1");
+        assert_eq!(send(address, "GET", "/blocks/first.json", None, "").0, 404);
+    }
+
+    #[test]
+    fn test_submissions_refused_without_a_token() {
+        let address = serve(None);
+        assert_eq!(send(address, "POST", "/requests", Some("s3cret"), REQUEST).0, 403);
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_tls_config() {
+        use std::path::Path;
+
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tls");
+        let fixture = |name: &str| fixtures.join(name);
+        let tls = TlsConfig {
+            certificate: fixture("server.pem"),
+            key: fixture("server.key"),
+            client_ca: None,
+        };
+        server_config(&tls).unwrap();
+        let mutual = TlsConfig { client_ca: Some(fixture("ca.pem")), ..tls.clone() };
+        server_config(&mutual).unwrap();
+
+        let error = |config| server_config(&config).unwrap_err().to_string();
+        let missing = TlsConfig { client_ca: Some(fixture("none.pem")), ..tls.clone() };
+        assert!(error(missing).contains("none.pem"));
+        // A key isn't a certificate, nor the other way around
+        let not_a_ca = TlsConfig { client_ca: Some(fixture("client.key")), ..tls.clone() };
+        assert!(error(not_a_ca).ends_with("client.key: no certificate found"));
+        let not_a_key = TlsConfig { key: fixture("server.pem"), ..tls.clone() };
+        assert!(error(not_a_key).starts_with("failed to set up TLS: "));
+        let mismatched = TlsConfig { key: fixture("client.key"), ..tls };
+        assert!(server_config(&mismatched).is_err());
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use ed25519_dalek::Signature;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+/// Ed25519 signature, kept apart from [`Hash`](crate::hash::Hash) so that one can't be passed
+/// for the other, but written as the same hex string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sig(pub [u8; 64]);
+
+impl Default for Sig {
+    fn default() -> Self { Self([0; 64]) }
+}
+
+impl From<Signature> for Sig {
+    fn from(value: Signature) -> Self { Self(value.to_bytes()) }
+}
+
+impl From<&Sig> for Signature {
+    fn from(value: &Sig) -> Self { Signature::from_bytes(&value.0) }
+}
+
+impl From<Sig> for Signature {
+    fn from(value: Sig) -> Self { Self::from(&value) }
+}
+
+impl AsRef<[u8]> for Sig {
+    fn as_ref(&self) -> &[u8] { &self.0 }
+}
+
+impl Display for Sig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str(&hex::encode(self.0)) }
+}
+
+impl Serialize for Sig {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Sig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        let bytes = hex::decode(s).map_err(de::Error::custom)?;
+        let bytes = bytes.try_into().map_err(|b: Vec<u8>| {
+            de::Error::custom(format!("Signature must be 64 bytes, got {}", b.len()))
+        })?;
+        Ok(Self(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signature, Signer, SigningKey, Verifier};
+
+    use crate::signature::Sig;
+
+    #[test]
+    fn test_sig_round_trips_signature() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let signature = key.sign(b"hello world");
+        let sig = Sig::from(signature);
+        assert_eq!(sig.0, signature.to_bytes());
+        let restored: Signature = sig.clone().into();
+        assert!(key.verifying_key().verify(b"hello world", &restored).is_ok());
+
+        let json = serde_json::to_string(&sig).unwrap();
+        assert_eq!(json, format!("\"{}\"", hex::encode(signature.to_bytes())));
+        assert_eq!(serde_json::from_str::<Sig>(&json).unwrap(), sig);
+    }
+
+    #[test]
+    fn test_sig_deserialization_invalid_length() {
+        let error = serde_json::from_str::<Sig>("\"deadbeef\"").unwrap_err();
+        assert!(error.to_string().contains("Signature must be 64 bytes, got 4"));
+    }
+}
@@ -0,0 +1,206 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::Deserialize;
+
+use crate::keys::SecretKeyMaterial;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignError {
+    /// The signing program couldn't be run or didn't succeed
+    Command(String),
+    /// The signer answered with something that isn't a signature under its verifying key
+    InvalidSignature,
+}
+
+impl Display for SignError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Command(error) => write!(f, "Signing command failed: {error}"),
+            Self::InvalidSignature =>
+                f.write_str("Signer returned a signature that doesn't match its verifying key"),
+        }
+    }
+}
+
+impl Error for SignError {}
+
+/// Anything able to sign credentials and blocks on an issuer's behalf, be it a key in memory, a
+/// hardware token or a remote signing service
+pub trait BlockSigner {
+    fn verifying_key(&self) -> VerifyingKey;
+
+    /// Signs `message`, a hash computed by the chain or what an export format signs, e.g. a
+    /// JWT's header and payload
+    ///
+    /// # Errors
+    /// Fails if the signer can't produce a signature.
+    fn sign(&self, message: &[u8]) -> Result<Signature, SignError>;
+}
+
+impl BlockSigner for SigningKey {
+    fn verifying_key(&self) -> VerifyingKey { SigningKey::verifying_key(self) }
+
+    fn sign(&self, message: &[u8]) -> Result<Signature, SignError> {
+        Ok(Signer::sign(self, message))
+    }
+}
+
+impl BlockSigner for SecretKeyMaterial {
+    fn verifying_key(&self) -> VerifyingKey { self.signing().verifying_key() }
+
+    fn sign(&self, message: &[u8]) -> Result<Signature, SignError> {
+        BlockSigner::sign(self.signing(), message)
+    }
+}
+
+/// Program signing on an issuer's behalf, configured in `attestation.toml`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalSignerConfig {
+    pub command: PathBuf,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// Signs by running a program that reads the hex-encoded message on stdin and writes the
+/// hex-encoded signature on stdout. Signatures are checked against the issuer's verifying key
+/// before being used.
+#[derive(Debug, Clone)]
+pub struct ExternalSigner {
+    config: ExternalSignerConfig,
+    verifying: VerifyingKey,
+}
+
+impl ExternalSigner {
+    #[must_use]
+    pub fn new(config: ExternalSignerConfig, verifying: VerifyingKey) -> Self {
+        Self { config, verifying }
+    }
+}
+
+impl BlockSigner for ExternalSigner {
+    fn verifying_key(&self) -> VerifyingKey { self.verifying }
+
+    fn sign(&self, message: &[u8]) -> Result<Signature, SignError> {
+        let command_error =
+            |e: &dyn Display| SignError::Command(format!("{}: {e}", self.config.command.display()));
+        let mut child = Command::new(&self.config.command)
+            .args(&self.config.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| command_error(&e))?;
+        // Programs taking the message another way may exit without reading it, so only their exit
+        // status and output tell whether they signed
+        if let Some(mut stdin) = child.stdin.take()
+            && let Err(e) = writeln!(stdin, "{}", hex::encode(message))
+            && e.kind() != io::ErrorKind::BrokenPipe
+        {
+            return Err(command_error(&e));
+        }
+        let output = child.wait_with_output().map_err(|e| command_error(&e))?;
+        if !output.status.success() {
+            return Err(command_error(&output.status));
+        }
+        let mut bytes = [0; 64];
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        hex::decode_to_slice(stdout.trim(), &mut bytes).map_err(|_| SignError::InvalidSignature)?;
+        let signature = Signature::from_bytes(&bytes);
+        self.verifying.verify(message, &signature).map_err(|_| SignError::InvalidSignature)?;
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::blockchain::{Block, Blockchain};
+    use crate::credential::{Attribute, Credential, Issuer, Subject, ValidDuration};
+
+    /// Signs with a key in memory, remembering every message it was asked to sign
+    struct RecordingSigner {
+        key: SigningKey,
+        messages: RefCell<Vec<Vec<u8>>>,
+    }
+
+    impl BlockSigner for RecordingSigner {
+        fn verifying_key(&self) -> VerifyingKey { self.key.verifying_key() }
+
+        fn sign(&self, message: &[u8]) -> Result<Signature, SignError> {
+            self.messages.borrow_mut().push(message.to_vec());
+            BlockSigner::sign(&self.key, message)
+        }
+    }
+
+    #[test]
+    fn test_mock_signer_signs_credentials_and_blocks() {
+        let recorder =
+            RecordingSigner { key: SigningKey::from_bytes(&[7; 32]), messages: RefCell::default() };
+        let issuer = Issuer::from_signing_key("Registry".to_string(), &recorder.key);
+        let credential = Credential::new(
+            vec![Attribute::new("degree".to_string(), "PhD".to_string())],
+            issuer.clone(),
+            Subject::new("Alice".to_string(), "Smith".to_string()),
+            ValidDuration::starting(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
+        );
+        let mut chain = Blockchain::new();
+        let signed = credential.sign(&recorder, false, chain.format()).unwrap();
+        let mut block = Block::new(issuer);
+        block.add_credential(signed.clone(), false).unwrap();
+        chain.add_block(block, &recorder).unwrap();
+
+        let block = chain.block(0).unwrap();
+        assert_eq!(*recorder.messages.borrow(), [
+            signed.credential.bytes.to_vec(),
+            block.hash().bytes.to_vec()
+        ]);
+        assert_eq!(chain.validate(), Ok(()));
+        assert!(chain.check_credential(&credential).is_valid());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_external_signer() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let verifying = key.verifying_key();
+        let signer = |command: &str, args: &[&str]| {
+            let args = args.iter().map(ToString::to_string).collect();
+            ExternalSigner::new(ExternalSignerConfig { command: command.into(), args }, verifying)
+        };
+        // Ed25519 signatures are deterministic, so a canned answer works for a fixed message,
+        // given only once the program read that message in hex
+        let expected = Signer::sign(&key, b"message");
+        let canned = format!(
+            "read message; test \"$message\" = {} && echo {}",
+            hex::encode(b"message"),
+            hex::encode(expected.to_bytes())
+        );
+        assert_eq!(signer("sh", &["-c", &canned]).sign(b"message"), Ok(expected));
+        assert!(matches!(
+            signer("sh", &["-c", &canned]).sign(b"other message"),
+            Err(SignError::Command(_))
+        ));
+
+        assert!(matches!(signer("false", &[]).sign(b"message"), Err(SignError::Command(_))));
+        assert!(matches!(
+            signer("/nonexistent/signer", &[]).sign(b"message"),
+            Err(SignError::Command(_))
+        ));
+        let forged = format!("echo {}", "ab".repeat(64));
+        assert_eq!(
+            signer("sh", &["-c", &forged]).sign(b"message"),
+            Err(SignError::InvalidSignature)
+        );
+        assert_eq!(signer("cat", &[]).sign(b"message"), Err(SignError::InvalidSignature));
+    }
+}
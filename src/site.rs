@@ -0,0 +1,296 @@
+//! Static copy of the chain for a plain web host, laid out as `serve` answers so that the host
+//! acts as a read-only node for `blockchain sync` and `--remote` without running anything.
+//!
+//! The host isn't trusted: the head is anchored by a chain authority and the revocation list
+//! signed by it, and readers validate the blocks themselves. Blocks are only written again when
+//! the hash the index records for their height changes, so that re-exporting a grown chain only
+//! uploads the new blocks.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter, Write};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::anchor::AnchorError;
+use crate::blockchain::{Block, Blockchain, GenesisBlock};
+use crate::credential::Issuer;
+use crate::exchange::{ChainHead, ChainIndex};
+use crate::revocation_list::{RevocationList, RevocationListError};
+use crate::signer::BlockSigner;
+use crate::sync::{ChainSource, SyncError};
+
+#[derive(Debug)]
+pub enum SiteError {
+    /// A file of the site can't be written or removed
+    Io { path: PathBuf, error: io::Error },
+    /// The head can't be anchored
+    Anchor(AnchorError),
+    /// The revocation list can't be signed
+    RevocationList(RevocationListError),
+}
+
+impl Display for SiteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "failed to write {}: {error}", path.display()),
+            Self::Anchor(error) => write!(f, "failed to anchor the head: {error}"),
+            Self::RevocationList(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for SiteError {}
+
+/// Files an export wrote, left as they were and removed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SiteExport {
+    pub written: usize,
+    pub unchanged: usize,
+    /// Blocks left by an earlier export of a chain holding more blocks
+    pub removed: usize,
+}
+
+/// Directory holding a static copy of a chain
+pub struct Site {
+    dir: PathBuf,
+}
+
+impl Site {
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self { Self { dir: dir.into() } }
+
+    /// Whether the directory holds a site, rather than e.g. another node's working directory
+    #[must_use]
+    pub fn exists(dir: &Path) -> bool { dir.join("head.json").is_file() }
+
+    fn block_path(height: u64) -> String { format!("blocks/{height}.json") }
+
+    /// Reads the file at the path relative to the site, none if there is no such file
+    fn read(&self, path: &str) -> io::Result<Option<String>> {
+        match fs::read_to_string(self.dir.join(path)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes the file unless it already holds the contents
+    fn update(
+        &self, path: &str, contents: &str, export: &mut SiteExport,
+    ) -> Result<(), SiteError> {
+        if self.read(path).ok().flatten().as_deref() == Some(contents) {
+            export.unchanged += 1;
+            return Ok(());
+        }
+        let path = self.dir.join(path);
+        fs::write(&path, contents).map_err(|error| SiteError::Io { path, error })?;
+        export.written += 1;
+        Ok(())
+    }
+
+    /// Exports the chain, its head anchored and its revocations signed as the issuer, along with
+    /// an `index.html` listing the blocks if `html` is set. Files already holding what they
+    /// would be written with are left as they are, and the head and revocation list are only
+    /// signed again once the chain grew or another issuer signs them.
+    ///
+    /// # Errors
+    /// Fails if a file can't be written, or the head or revocation list can't be signed.
+    pub fn export(
+        &self, blockchain: &Blockchain, issuer: &Issuer, signer: &dyn BlockSigner, html: bool,
+    ) -> Result<SiteExport, SiteError> {
+        let blocks = self.dir.join("blocks");
+        fs::create_dir_all(&blocks).map_err(|error| SiteError::Io { path: blocks, error })?;
+        let mut export = SiteExport::default();
+        let index = ChainIndex::new(blockchain);
+        let previous = self.read("index.json").ok().flatten();
+        let previous = previous.and_then(|json| ChainIndex::from_json(&json).ok());
+        let exported = |block: &Block| {
+            let hash = previous.as_ref().and_then(|index| index.blocks.get(&block.height()));
+            hash == Some(block.hash()) && self.dir.join(Self::block_path(block.height())).exists()
+        };
+        for block in blockchain.blocks() {
+            if exported(block) {
+                export.unchanged += 1;
+            } else {
+                self.update(&Self::block_path(block.height()), &to_json(block), &mut export)?;
+            }
+        }
+        let stale = previous.iter().flat_map(|previous| previous.blocks.keys());
+        for &height in stale.filter(|height| !index.blocks.contains_key(height)) {
+            let path = self.dir.join(Self::block_path(height));
+            match fs::remove_file(&path) {
+                Ok(()) => export.removed += 1,
+                Err(error) if error.kind() == ErrorKind::NotFound => {},
+                Err(error) => return Err(SiteError::Io { path, error }),
+            }
+        }
+        if let Some(genesis) = blockchain.genesis() {
+            self.update("genesis.json", &to_json(genesis), &mut export)?;
+        }
+        let key = signer.verifying_key();
+        let head = match self.read("head.json").ok().flatten() {
+            Some(json)
+                if let Ok(head) = ChainHead::from_json(&json)
+                    && head.check(blockchain).is_ok()
+                    && head.anchor.as_ref().is_some_and(|anchor| anchor.signer_key == key) =>
+                head,
+            _ => ChainHead::signed(blockchain, issuer, signer).map_err(SiteError::Anchor)?,
+        };
+        self.update("head.json", &to_json(&head), &mut export)?;
+        let head_hash = blockchain.tip().map(Block::hash);
+        let revocations = match self.read("revocations.json").ok().flatten() {
+            Some(json)
+                if let Ok(list) = RevocationList::from_json(&json)
+                    && list.chain == blockchain.id()
+                    && list.head.as_ref() == head_hash
+                    && list.signer_key == key =>
+                list,
+            _ => RevocationList::new(blockchain, issuer, signer)
+                .map_err(SiteError::RevocationList)?,
+        };
+        self.update("revocations.json", &format!("{revocations}\n"), &mut export)?;
+        self.update("index.json", &to_json(&index), &mut export)?;
+        if html {
+            self.update("index.html", &index_html(blockchain, &head), &mut export)?;
+        }
+        Ok(export)
+    }
+
+    fn source_error(&self, path: &str, error: impl Display) -> SyncError {
+        SyncError::Source(format!("failed to read {}: {error}", self.dir.join(path).display()))
+    }
+}
+
+impl ChainSource for Site {
+    fn head(&self) -> Result<ChainHead, SyncError> {
+        let path = "head.json";
+        let json = self.read(path).map_err(|e| self.source_error(path, e))?;
+        let json = json.ok_or_else(|| self.source_error(path, "no such file"))?;
+        ChainHead::from_json(&json).map_err(|e| self.source_error(path, e))
+    }
+
+    fn genesis(&self) -> Result<Option<GenesisBlock>, SyncError> {
+        let path = "genesis.json";
+        let Some(json) = self.read(path).map_err(|e| self.source_error(path, e))? else {
+            return Ok(None);
+        };
+        serde_json::from_str(&json).map(Some).map_err(|e| self.source_error(path, e))
+    }
+
+    fn block(&self, height: u64) -> Result<Block, SyncError> {
+        let path = Self::block_path(height);
+        let json = self.read(&path).map_err(|e| self.source_error(&path, e))?;
+        let json = json.ok_or_else(|| self.source_error(&path, "no such file"))?;
+        serde_json::from_str(&json).map_err(|e| self.source_error(&path, e))
+    }
+}
+
+fn to_json(value: &impl Serialize) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default() + "\n"
+}
+
+/// Escapes text for HTML, issuer and chain names being chosen by whoever created them
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Page listing the blocks, newest first, with links to the files they're served as
+fn index_html(blockchain: &Blockchain, head: &ChainHead) -> String {
+    let name = blockchain.genesis().map_or("Chain", |genesis| &genesis.metadata().name);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{name}</title>\n</head>\n<body>\n<h1>{name}</h1>\n",
+        name = escape(name)
+    );
+    if let Some(anchor) = &head.anchor {
+        let _ = writeln!(
+            html,
+            "<p>{} blocks, the last one anchored by {} on {}.</p>",
+            head.blocks,
+            escape(&anchor.signer_name),
+            anchor.anchored_at.format("%Y-%m-%d %H:%M UTC")
+        );
+    }
+    html.push_str(
+        "<table>\n<tr><th>Height</th><th>Hash</th><th>Finalized</th><th>Signer</th></tr>\n",
+    );
+    for block in blockchain.blocks().rev() {
+        let _ = writeln!(
+            html,
+            "<tr><td><a href=\"blocks/{height}.json\">{height}</a></td><td><code>{}</code></td>\
+             <td>{}</td><td>{}</td></tr>",
+            block.hash(),
+            block.timestamp().format("%Y-%m-%d %H:%M UTC"),
+            escape(&block.signer().name),
+            height = block.height()
+        );
+    }
+    html.push_str(
+        "</table>\n<p><a href=\"head.json\">head.json</a> <a href=\"index.json\">index.json</a> \
+         <a href=\"revocations.json\">revocations.json</a></p>\n</body>\n</html>\n",
+    );
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::ChainPolicy;
+    use crate::sync::{self, Pull};
+
+    #[test]
+    fn test_export_and_sync_from_site() {
+        let dir = tempfile::tempdir().unwrap();
+        let genesis = GenesisBlock::new("Registry <test>".to_string(), None, None).unwrap();
+        let mut blockchain = Blockchain::with_genesis(genesis);
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        for _ in 0..3 {
+            blockchain.add_block(Block::new(issuer.clone()), &signing).unwrap();
+        }
+        let site = Site::new(dir.path());
+        let export = site.export(&blockchain, &issuer, &signing, true).unwrap();
+        // 3 blocks, the genesis block, head, revocations, index and page
+        assert_eq!(export, SiteExport { written: 8, unchanged: 0, removed: 0 });
+        assert!(Site::exists(dir.path()));
+        let page = fs::read_to_string(dir.path().join("index.html")).unwrap();
+        assert!(page.contains("<h1>Registry &lt;test&gt;</h1>"));
+
+        let head = site.head().unwrap();
+        assert_eq!(head.anchor.as_ref().map(|anchor| anchor.height), Some(2));
+        let Pull::Extended { blockchain: synced, fetched } =
+            sync::pull(None, &site, &ChainPolicy::default()).unwrap()
+        else {
+            panic!("expected the chain to be created");
+        };
+        assert_eq!(fetched, 3);
+        assert_eq!(synced.to_string(), blockchain.to_string());
+
+        // Nothing changes until the chain grows, and then only the new block and what describes
+        // the head are written
+        let export = site.export(&blockchain, &issuer, &signing, true).unwrap();
+        assert_eq!(export, SiteExport { written: 0, unchanged: 8, removed: 0 });
+        blockchain.add_block(Block::new(issuer.clone()), &signing).unwrap();
+        let export = site.export(&blockchain, &issuer, &signing, true).unwrap();
+        assert_eq!(export, SiteExport { written: 5, unchanged: 4, removed: 0 });
+    }
+
+    #[test]
+    fn test_tampered_site_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let genesis = GenesisBlock::new("Registry".to_string(), None, None).unwrap();
+        let mut blockchain = Blockchain::with_genesis(genesis);
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        blockchain.add_block(Block::new(issuer.clone()), &signing).unwrap();
+        let site = Site::new(dir.path());
+        site.export(&blockchain, &issuer, &signing, false).unwrap();
+
+        let path = dir.path().join("head.json");
+        let head = fs::read_to_string(&path).unwrap();
+        fs::write(&path, head.replace("\"blocks\": 1", "\"blocks\": 2")).unwrap();
+        let error = site.head().unwrap_err();
+        assert!(error.to_string().contains("anchor is of another head"), "{error}");
+    }
+}
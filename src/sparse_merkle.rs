@@ -0,0 +1,201 @@
+//! Sparse Merkle trees over sets of hashes, so that a hash can be proven *not* to be in a set with
+//! a path of hashes instead of the whole set.
+//!
+//! Every possible key has a slot: a hash's first [`DEPTH`] bits lead from the root to its leaf, so
+//! the root only depends on the set, not on the order the hashes were added in. Empty subtrees
+//! hash to fixed values that proofs leave out.
+
+use serde::{Deserialize, Serialize};
+
+use crate::hash::{Algorithm, Hash, HashFormat, HashVersion, Hasher, domain};
+
+/// Bits of a key leading to its leaf
+pub const DEPTH: usize = 256;
+
+/// Sibling of the node at `depth` on the path to a key, left out of proofs when it's empty
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SparseStep {
+    /// Bits of the key leading to the sibling, 1 for a child of the root
+    pub depth: u16,
+    pub sibling: Hash,
+}
+
+/// Non-empty siblings on the path from the root to a key's leaf, root first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SparseMerkleProof {
+    pub key: Hash,
+    pub siblings: Vec<SparseStep>,
+}
+
+impl SparseMerkleProof {
+    /// Whether the path leads from the key's empty leaf to `root`, proving the key isn't in the
+    /// tree. Siblings out of order, out of range or repeated make the proof fail.
+    #[must_use]
+    pub fn verify_absent(&self, root: &Hash) -> bool {
+        let empty = empty_subtrees(root.algorithm);
+        let mut siblings = self.siblings.iter().rev().peekable();
+        let mut node = empty[0].clone();
+        for depth in (1..=DEPTH).rev() {
+            let sibling = match siblings.next_if(|step| usize::from(step.depth) == depth) {
+                Some(step) => &step.sibling,
+                None => &empty[DEPTH - depth],
+            };
+            node = if bit(&self.key, depth - 1) {
+                parent(sibling, &node)
+            } else {
+                parent(&node, sibling)
+            };
+        }
+        siblings.next().is_none() && &node == root
+    }
+}
+
+fn hasher(algorithm: Algorithm) -> Hasher {
+    Hasher::new(HashFormat { version: HashVersion::V4, algorithm })
+}
+
+fn parent(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = hasher(left.algorithm);
+    hasher.domain(domain::SPARSE_MERKLE_NODE);
+    hasher.update(left.bytes);
+    hasher.update(right.bytes);
+    hasher.finalize()
+}
+
+/// Leaf of the keys sharing a slot, which takes more than one only if their first [`DEPTH`] bits
+/// collide
+fn leaf(keys: &[&Hash], algorithm: Algorithm) -> Hash {
+    let mut hasher = hasher(algorithm);
+    hasher.domain(domain::SPARSE_MERKLE_LEAF);
+    hasher.length(keys.len());
+    for key in keys {
+        hasher.update(key.bytes);
+    }
+    hasher.finalize()
+}
+
+/// Hashes of the empty subtrees by height, from an empty leaf up to an empty tree
+fn empty_subtrees(algorithm: Algorithm) -> Vec<Hash> {
+    let mut hasher = hasher(algorithm);
+    hasher.domain(domain::SPARSE_MERKLE_EMPTY);
+    let mut empty = vec![hasher.finalize()];
+    for height in 0..DEPTH {
+        empty.push(parent(&empty[height], &empty[height]));
+    }
+    empty
+}
+
+fn bit(key: &Hash, depth: usize) -> bool { key.bytes[depth / 8] & (0x80 >> (depth % 8)) != 0 }
+
+fn sorted(keys: &[Hash]) -> Vec<&Hash> {
+    let mut keys: Vec<_> = keys.iter().collect();
+    keys.sort_by_key(|key| key.bytes);
+    keys.dedup();
+    keys
+}
+
+/// Hash of the subtree at `depth` holding the given keys, which share their first `depth` bits
+/// and are sorted
+fn subtree(keys: &[&Hash], depth: usize, empty: &[Hash], algorithm: Algorithm) -> Hash {
+    if keys.is_empty() {
+        return empty[DEPTH - depth].clone();
+    }
+    if depth == DEPTH {
+        return leaf(keys, algorithm);
+    }
+    let split = keys.partition_point(|key| !bit(key, depth));
+    let left = subtree(&keys[..split], depth + 1, empty, algorithm);
+    let right = subtree(&keys[split..], depth + 1, empty, algorithm);
+    parent(&left, &right)
+}
+
+/// Root of the tree holding the keys
+#[must_use]
+pub fn root(keys: &[Hash], algorithm: Algorithm) -> Hash {
+    subtree(&sorted(keys), 0, &empty_subtrees(algorithm), algorithm)
+}
+
+/// Proof that `key` isn't in the tree holding the keys, none if it is
+#[must_use]
+pub fn prove_absent(keys: &[Hash], key: &Hash, algorithm: Algorithm) -> Option<SparseMerkleProof> {
+    let empty = empty_subtrees(algorithm);
+    let mut keys = sorted(keys);
+    let mut siblings = Vec::new();
+    for (depth, sibling_depth) in (0..DEPTH).zip(1..) {
+        if keys.is_empty() {
+            break;
+        }
+        let split = keys.partition_point(|k| !bit(k, depth));
+        let (left, right) = keys.split_at(split);
+        let (path, other) = if bit(key, depth) { (right, left) } else { (left, right) };
+        if !other.is_empty() {
+            let sibling = subtree(other, depth + 1, &empty, algorithm);
+            siblings.push(SparseStep { depth: sibling_depth, sibling });
+        }
+        keys = path.to_vec();
+    }
+    // Keys left at the leaf share the key's slot
+    keys.is_empty().then(|| SparseMerkleProof { key: key.clone(), siblings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(count: u8) -> Vec<Hash> {
+        (0..count)
+            .map(|i| {
+                let mut hasher = hasher(Algorithm::Sha512);
+                hasher.update([i]);
+                hasher.finalize()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_root_ignores_order() {
+        let keys = keys(6);
+        let mut reversed = keys.clone();
+        reversed.reverse();
+        assert_eq!(root(&keys, Algorithm::Sha512), root(&reversed, Algorithm::Sha512));
+        assert_ne!(root(&keys, Algorithm::Sha512), root(&keys[1..], Algorithm::Sha512));
+    }
+
+    #[test]
+    fn test_absence_proof_fails_once_the_key_is_added() {
+        let keys = keys(8);
+        let (present, absent) = keys.split_at(5);
+        let root = root(present, Algorithm::Sha512);
+        for key in absent {
+            let proof = prove_absent(present, key, Algorithm::Sha512).unwrap();
+            assert!(proof.verify_absent(&root));
+        }
+        assert!(prove_absent(present, &present[0], Algorithm::Sha512).is_none());
+
+        let proof = prove_absent(present, &absent[0], Algorithm::Sha512).unwrap();
+        let grown = super::root(&keys[..6], Algorithm::Sha512);
+        assert!(!proof.verify_absent(&grown));
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let keys = keys(1);
+        let root = root(&[], Algorithm::Sha512);
+        let proof = prove_absent(&[], &keys[0], Algorithm::Sha512).unwrap();
+        assert!(proof.siblings.is_empty());
+        assert!(proof.verify_absent(&root));
+    }
+
+    #[test]
+    fn test_tampered_siblings_fail() {
+        let keys = keys(4);
+        let root = root(&keys[1..], Algorithm::Sha512);
+        let mut proof = prove_absent(&keys[1..], &keys[0], Algorithm::Sha512).unwrap();
+        let step = proof.siblings[0].clone();
+        proof.siblings.insert(0, step);
+        assert!(!proof.verify_absent(&root));
+        proof.siblings.remove(0);
+        proof.siblings.pop();
+        assert!(!proof.verify_absent(&root));
+    }
+}
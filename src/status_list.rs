@@ -0,0 +1,334 @@
+//! Signed bitstrings of the revocation status of every credential issued on a chain, in the style
+//! of a W3C status list: each issuance gets a stable index, and a verifier holding the list and a
+//! credential's index checks a single bit instead of fetching the chain or a list of hashes.
+//!
+//! Indices follow [`INDEX_RULE`], so they only ever grow as blocks are appended. The bits are
+//! gzipped and base64url encoded, and the list signs the compact JSON of all its other fields like
+//! a [`crate::revocation_list::RevocationList`] does.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{Read, Write};
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::blockchain::{Blockchain, BlockchainError};
+use crate::credential::{Issuer, verifying_key_serde};
+use crate::exchange::{EXCHANGE_VERSION, ExchangeError, from_versioned_json};
+use crate::hash::Hash;
+use crate::signature::Sig;
+use crate::signer::{BlockSigner, SignError};
+
+/// How issuance hashes map to bit indices, recorded in every list
+pub const INDEX_RULE: &str = "blocks by height, then each block's new credentials followed by its \
+                              anchored ones, counting only the first issuance of a hash";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatusListError {
+    /// The file isn't a status list this version can read
+    Exchange(ExchangeError),
+    /// The list's bits aren't valid gzipped base64url, or don't match its size
+    Encoding,
+    /// The signature doesn't check against the key the list names
+    Signature,
+    /// The chain restricts block signers, and the issuer signing the list isn't one of them
+    NotAuthority,
+    /// The chain's authority set can't be followed through its blocks
+    Chain(BlockchainError),
+    Sign(SignError),
+}
+
+impl Display for StatusListError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exchange(error) => error.fmt(f),
+            Self::Encoding => f.write_str("the list's bits aren't a gzipped base64url bitstring"),
+            Self::Signature => f.write_str(
+                "the list's signature doesn't match its signer's key, potential forgery",
+            ),
+            Self::NotAuthority =>
+                f.write_str("only a chain authority can sign the chain's status list"),
+            Self::Chain(error) => write!(f, "the chain's authorities can't be followed: {error}"),
+            Self::Sign(error) => write!(f, "failed to sign the list: {error}"),
+        }
+    }
+}
+
+impl Error for StatusListError {}
+
+/// Index of every credential the chain issues, by issuance hash, following [`INDEX_RULE`]
+#[must_use]
+pub fn status_indices(blockchain: &Blockchain) -> HashMap<Hash, u64> {
+    let mut indices = HashMap::new();
+    for block in blockchain.blocks() {
+        for signed in block.issued_credentials() {
+            let next = indices.len() as u64;
+            indices.entry(signed.credential.clone()).or_insert(next);
+        }
+    }
+    indices
+}
+
+/// Index of the credential with the given issuance hash, if the chain issues it
+#[must_use]
+pub fn status_index(blockchain: &Blockchain, issuance: &Hash) -> Option<u64> {
+    status_indices(blockchain).get(issuance).copied()
+}
+
+/// Revocation bit of every credential issued on a chain as of its head, signed by a chain
+/// authority. Displayed as indented JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusList {
+    /// Format version, [`EXCHANGE_VERSION`] for files written by this version
+    pub version: u32,
+    pub generated_at: DateTime<Utc>,
+    /// UUID of the chain's genesis block, none for chains without one
+    pub chain: Option<Uuid>,
+    /// Hash of the chain's last block, none for an empty chain
+    pub head: Option<Hash>,
+    /// How the indices were assigned, [`INDEX_RULE`]
+    pub index_rule: String,
+    /// Number of indices assigned, the bitstring being padded to whole bytes
+    pub size: u64,
+    /// Gzipped bitstring, most significant bit first, base64url encoded without padding
+    pub encoded_list: String,
+    /// UUID and name of the issuer signing the list
+    pub signer: Uuid,
+    pub signer_name: String,
+    #[serde(with = "verifying_key_serde")]
+    pub signer_key: VerifyingKey,
+    pub signature: Sig,
+    #[serde(skip)]
+    bits: Vec<u8>,
+}
+
+/// The fields of a [`StatusList`] its signature covers
+#[derive(Serialize)]
+struct Unsigned<'a> {
+    version: u32,
+    generated_at: &'a DateTime<Utc>,
+    chain: &'a Option<Uuid>,
+    head: &'a Option<Hash>,
+    index_rule: &'a str,
+    size: u64,
+    encoded_list: &'a str,
+    signer: Uuid,
+    signer_name: &'a str,
+    #[serde(with = "verifying_key_serde")]
+    signer_key: &'a VerifyingKey,
+}
+
+impl StatusList {
+    /// Sets the bit of every credential among `credentials`, given as issuance and revocation
+    /// hash pairs, whose revocation the chain honors. The chain alone can't tell which issuance a
+    /// revocation hash belongs to, so the bits of credentials missing from `credentials` stay
+    /// clear.
+    ///
+    /// # Errors
+    /// Returns [`StatusListError::NotAuthority`] if the chain has authorities and the issuer isn't
+    /// one, and [`StatusListError::Sign`] if the signer fails or doesn't hold the issuer's key.
+    pub fn new(
+        blockchain: &Blockchain, credentials: &[(Hash, Hash)], issuer: &Issuer,
+        signer: &dyn BlockSigner,
+    ) -> Result<Self, StatusListError> {
+        if let Some(authorities) = blockchain.authorities().map_err(StatusListError::Chain)?
+            && !authorities.iter().any(|authority| authority.verifying == issuer.verifying)
+        {
+            return Err(StatusListError::NotAuthority);
+        }
+        let indices = status_indices(blockchain);
+        let revoked: HashSet<_> = blockchain
+            .revocations()
+            .into_iter()
+            .map(|(_, revocation)| &revocation.signed.credential)
+            .collect();
+        let size = indices.len() as u64;
+        let mut bits = vec![0; indices.len().div_ceil(8)];
+        for (issuance, revocation) in credentials {
+            if let Some(&index) = indices.get(issuance)
+                && revoked.contains(revocation)
+            {
+                set_bit(&mut bits, index);
+            }
+        }
+        let mut list = Self {
+            version: EXCHANGE_VERSION,
+            generated_at: Utc::now(),
+            chain: blockchain.id(),
+            head: blockchain.tip().map(|block| block.hash().clone()),
+            index_rule: INDEX_RULE.to_string(),
+            size,
+            encoded_list: encode(&bits),
+            signer: issuer.uuid,
+            signer_name: issuer.name.clone(),
+            signer_key: signer.verifying_key(),
+            signature: Sig::default(),
+            bits,
+        };
+        let signature = signer.sign(&list.message()).map_err(StatusListError::Sign)?;
+        if list.signer_key.verify(&list.message(), &signature).is_err() {
+            return Err(StatusListError::Sign(SignError::InvalidSignature));
+        }
+        list.signature = signature.into();
+        Ok(list)
+    }
+
+    fn message(&self) -> Vec<u8> {
+        let unsigned = Unsigned {
+            version: self.version,
+            generated_at: &self.generated_at,
+            chain: &self.chain,
+            head: &self.head,
+            index_rule: &self.index_rule,
+            size: self.size,
+            encoded_list: &self.encoded_list,
+            signer: self.signer,
+            signer_name: &self.signer_name,
+            signer_key: &self.signer_key,
+        };
+        serde_json::to_vec(&unsigned).unwrap_or_default()
+    }
+
+    /// Reads a list as written by its [`Display`] implementation, checks its signature against
+    /// the key it names, which the caller still has to trust, and decodes its bits
+    ///
+    /// # Errors
+    /// Returns [`StatusListError::Exchange`] if the JSON isn't a list in this format version,
+    /// [`StatusListError::Signature`] if the signature doesn't check, and
+    /// [`StatusListError::Encoding`] if the bits don't decode to the list's size.
+    pub fn from_json(json: &str) -> Result<Self, StatusListError> {
+        let mut list: Self = from_versioned_json(json).map_err(StatusListError::Exchange)?;
+        let signature = Signature::from(&list.signature);
+        if list.signer_key.verify(&list.message(), &signature).is_err() {
+            return Err(StatusListError::Signature);
+        }
+        list.bits = decode(&list.encoded_list).ok_or(StatusListError::Encoding)?;
+        if list.bits.len() as u64 != list.size.div_ceil(8) {
+            return Err(StatusListError::Encoding);
+        }
+        Ok(list)
+    }
+
+    /// Whether the credential at the given index is revoked, none past the end of the list
+    #[must_use]
+    pub fn is_revoked(&self, index: u64) -> Option<bool> {
+        if index >= self.size {
+            return None;
+        }
+        let byte = self.bits[usize::try_from(index / 8).ok()?];
+        Some(byte & (0x80 >> (index % 8)) != 0)
+    }
+
+    /// Number of bits set
+    #[must_use]
+    pub fn revoked(&self) -> u32 { self.bits.iter().map(|byte| byte.count_ones()).sum() }
+}
+
+impl Display for StatusList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(&self).unwrap())
+    }
+}
+
+fn set_bit(bits: &mut [u8], index: u64) {
+    if let Ok(byte) = usize::try_from(index / 8) {
+        bits[byte] |= 0x80 >> (index % 8);
+    }
+}
+
+fn encode(bits: &[u8]) -> String {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    // Writing to a vector doesn't fail
+    let _ = encoder.write_all(bits);
+    URL_SAFE_NO_PAD.encode(encoder.finish().unwrap_or_default())
+}
+
+fn decode(encoded: &str) -> Option<Vec<u8>> {
+    let compressed = URL_SAFE_NO_PAD.decode(encoded).ok()?;
+    let mut bits = Vec::new();
+    GzDecoder::new(compressed.as_slice()).read_to_end(&mut bits).ok()?;
+    Some(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::blockchain::{Block, GenesisBlock};
+    use crate::credential::{Attribute, Credential, Subject, ValidDuration};
+    use crate::hash::HashFormat;
+
+    fn credential(issuer: &Issuer, value: &str) -> Credential {
+        let valid = ValidDuration::starting(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        let attributes = vec![Attribute::new("degree".into(), value.into())];
+        let subject = Subject::new("Alice".into(), "Smith".into());
+        Credential::new(attributes, issuer.clone(), subject, valid)
+    }
+
+    fn pairs(credentials: &[Credential], format: HashFormat) -> Vec<(Hash, Hash)> {
+        credentials.iter().map(|c| (c.hash(false, format), c.hash(true, format))).collect()
+    }
+
+    #[test]
+    fn test_revocation_flips_exactly_one_bit() {
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let credentials: Vec<_> =
+            ["BSc", "MSc", "PhD"].iter().map(|value| credential(&issuer, value)).collect();
+        let mut blockchain =
+            Blockchain::with_genesis(GenesisBlock::new("Test".to_string(), None, None).unwrap());
+        let format = blockchain.format();
+        for credential in &credentials {
+            let mut block = Block::new(issuer.clone());
+            block.add_credential(credential.sign(&signing, false, format).unwrap(), false).unwrap();
+            blockchain.add_block(block, &signing).unwrap();
+        }
+        let pairs = pairs(&credentials, format);
+        let before = StatusList::new(&blockchain, &pairs, &issuer, &signing).unwrap();
+        assert_eq!(before.size, 3);
+        assert_eq!(before.revoked(), 0);
+        assert_eq!(status_index(&blockchain, &pairs[2].0), Some(2));
+
+        let mut block = Block::new(issuer.clone());
+        block.add_credential(credentials[1].sign(&signing, true, format).unwrap(), true).unwrap();
+        blockchain.add_block(block, &signing).unwrap();
+        let json = StatusList::new(&blockchain, &pairs, &issuer, &signing).unwrap().to_string();
+        let after = StatusList::from_json(&json).unwrap();
+        let flipped: Vec<_> =
+            (0..3).filter(|&i| before.is_revoked(i) != after.is_revoked(i)).collect();
+        assert_eq!(flipped, [1]);
+        assert_eq!(after.is_revoked(1), Some(true));
+        assert_eq!(after.is_revoked(3), None);
+
+        // Clearing the bits breaks the signature
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["encoded_list"] = before.encoded_list.clone().into();
+        assert_eq!(
+            StatusList::from_json(&value.to_string()).unwrap_err(),
+            StatusListError::Signature
+        );
+    }
+
+    #[test]
+    fn test_only_authorities_sign() {
+        let (authority, authority_signing) = Issuer::new("Registrar".to_string());
+        let (other, other_signing) = Issuer::new("Other".to_string());
+        let genesis =
+            GenesisBlock::new("Test".to_string(), None, Some(vec![authority.clone()])).unwrap();
+        let blockchain = Blockchain::with_genesis(genesis);
+        assert!(StatusList::new(&blockchain, &[], &authority, &authority_signing).is_ok());
+        assert_eq!(
+            StatusList::new(&blockchain, &[], &other, &other_signing).unwrap_err(),
+            StatusListError::NotAuthority
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Pulling the blocks another node holds beyond the local chain, read from its server or from a
+//! copy of its chain on a shared drive.
+//!
+//! Only the blocks past the common ancestor are read, and the extended chain is validated as a
+//! whole before it replaces the local one. A source whose chain holds other blocks than the
+//! local one at some height is refused: the chain only ever grows, so one of them was rewritten.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use uuid::Uuid;
+
+use crate::blockchain::{
+    Block, Blockchain, BlockchainError, ChainPolicy, DivergencePoint, GenesisBlock,
+};
+use crate::exchange::{ChainHead, ExchangeError};
+use crate::hash::Hash;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncError {
+    /// The source can't be read, with the reason
+    Source(String),
+    /// The source holds another chain
+    OtherChain { local: Option<Uuid>, remote: Option<Uuid> },
+    /// The source and the local chain hold different blocks from some height on
+    Diverged(Box<DivergencePoint>),
+    /// The blocks read don't make up the chain the source's head describes
+    Inconsistent(ExchangeError),
+    /// The extended chain doesn't validate
+    Invalid(BlockchainError),
+}
+
+impl Display for SyncError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Source(error) => f.write_str(error),
+            Self::OtherChain { local, remote } => {
+                let id = |uuid: &Option<Uuid>| uuid.map_or("none".into(), |uuid| uuid.to_string());
+                write!(f, "the source holds chain {}, not {}", id(remote), id(local))
+            },
+            Self::Diverged(divergence) => {
+                match divergence.last_common {
+                    Some(height) => write!(f, "the chains diverge after block {height}")?,
+                    None => f.write_str("the chains diverge from the first block")?,
+                }
+                write!(
+                    f,
+                    ", block {} is {} locally and {} at the source",
+                    divergence.height, divergence.local, divergence.other
+                )
+            },
+            Self::Inconsistent(error) => error.fmt(f),
+            Self::Invalid(error) => write!(f, "the synced chain doesn't validate: {error}"),
+        }
+    }
+}
+
+impl Error for SyncError {}
+
+/// Chain read block by block, e.g. from another node's server or a copy of its chain
+pub trait ChainSource {
+    /// The head of the chain the source holds
+    ///
+    /// # Errors
+    /// Returns [`SyncError::Source`] if the source can't be read.
+    fn head(&self) -> Result<ChainHead, SyncError>;
+
+    /// The chain's genesis block, none for chains without one
+    ///
+    /// # Errors
+    /// Returns [`SyncError::Source`] if the source can't be read.
+    fn genesis(&self) -> Result<Option<GenesisBlock>, SyncError>;
+
+    /// The block at the height
+    ///
+    /// # Errors
+    /// Returns [`SyncError::Source`] if the source can't be read or has no such block.
+    fn block(&self, height: u64) -> Result<Block, SyncError>;
+}
+
+/// A chain already read whole, e.g. the `blockchain.json` of another node on a shared drive
+impl ChainSource for Blockchain {
+    fn head(&self) -> Result<ChainHead, SyncError> { Ok(ChainHead::new(self)) }
+
+    fn genesis(&self) -> Result<Option<GenesisBlock>, SyncError> {
+        Ok(Blockchain::genesis(self).cloned())
+    }
+
+    fn block(&self, height: u64) -> Result<Block, SyncError> {
+        let missing = || SyncError::Source(format!("the source has no block #{height}"));
+        Blockchain::block(self, height).cloned().ok_or_else(missing)
+    }
+}
+
+/// What pulling from a source comes to
+#[derive(Debug)]
+pub enum Pull {
+    /// The local chain already holds every block the source does, and the given number more
+    UpToDate { ahead: u64 },
+    /// The local chain extended with the blocks the source held beyond it, validated
+    Extended { blockchain: Box<Blockchain>, fetched: u64 },
+}
+
+/// Reads the blocks the source holds beyond the local chain, or its whole chain without a local
+/// one, and validates the extended chain under the policy
+///
+/// # Errors
+/// Fails if the source can't be read, holds another chain or one diverging from the local chain,
+/// or the extended chain doesn't validate.
+pub fn pull(
+    local: Option<&Blockchain>, source: &dyn ChainSource, policy: &ChainPolicy,
+) -> Result<Pull, SyncError> {
+    let head = source.head()?;
+    let local = match local {
+        Some(local) => local.clone(),
+        None => Blockchain::from_blocks(source.genesis()?, Vec::new()),
+    };
+    if head.chain != local.id() {
+        return Err(SyncError::OtherChain { local: local.id(), remote: head.chain });
+    }
+    let local_blocks = local.len() as u64;
+    if let Some(divergence) = diverges_from(&local, &head, source)? {
+        return Err(SyncError::Diverged(Box::new(divergence)));
+    }
+    if head.blocks <= local_blocks {
+        return Ok(Pull::UpToDate { ahead: local_blocks - head.blocks });
+    }
+    let mut blocks: Vec<Block> = local.blocks().cloned().collect();
+    for height in local_blocks..head.blocks {
+        blocks.push(source.block(height)?);
+    }
+    let blockchain = Blockchain::from_blocks(local.genesis().cloned(), blocks);
+    head.check(&blockchain).map_err(SyncError::Inconsistent)?;
+    blockchain.validate_with(policy).map_err(SyncError::Invalid)?;
+    Ok(Pull::Extended { blockchain: Box::new(blockchain), fetched: head.blocks - local_blocks })
+}
+
+/// Finds the first height both chains hold a block at where the blocks differ. Blocks link to
+/// their predecessor's hash, so chains holding the same block at a height share every block
+/// below it, and the height is found in a logarithmic number of reads.
+fn diverges_from(
+    local: &Blockchain, head: &ChainHead, source: &dyn ChainSource,
+) -> Result<Option<DivergencePoint>, SyncError> {
+    let shared = head.blocks.min(local.len() as u64);
+    let local_hash = |height| local.block(height).map(|block| block.hash().clone());
+    let source_hash = |height: u64| -> Result<Option<Hash>, SyncError> {
+        // The source's last block is described by its head
+        if height + 1 == head.blocks {
+            return Ok(head.head.clone());
+        }
+        Ok(Some(source.block(height)?.hash().clone()))
+    };
+    let Some(last) = shared.checked_sub(1) else {
+        return Ok(None);
+    };
+    if source_hash(last)? == local_hash(last) {
+        return Ok(None);
+    }
+    // The blocks differ at `high` and are the same below `low`
+    let (mut low, mut high) = (0, last);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if source_hash(middle)? == local_hash(middle) {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    Ok(Some(DivergencePoint {
+        last_common: high.checked_sub(1),
+        height: high,
+        local: local_hash(high).unwrap_or_default(),
+        other: source_hash(high)?.unwrap_or_default(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credential::Issuer;
+    use crate::signer::BlockSigner;
+
+    fn grow(blockchain: &mut Blockchain, issuer: &Issuer, signing: &dyn BlockSigner, blocks: u64) {
+        for _ in 0..blocks {
+            blockchain.add_block(Block::new(issuer.clone()), signing).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_pull_missing_blocks() {
+        let genesis = GenesisBlock::new("Test".to_string(), None, None).unwrap();
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let mut local = Blockchain::with_genesis(genesis);
+        grow(&mut local, &issuer, &signing, 2);
+        let mut remote = local.clone();
+        grow(&mut remote, &issuer, &signing, 3);
+        let policy = ChainPolicy::default();
+
+        let Pull::Extended { blockchain, fetched } = pull(Some(&local), &remote, &policy).unwrap()
+        else {
+            panic!("expected the local chain to be extended");
+        };
+        assert_eq!(fetched, 3);
+        assert_eq!(blockchain.to_string(), remote.to_string());
+        let Pull::Extended { fetched, .. } = pull(None, &remote, &policy).unwrap() else {
+            panic!("expected the chain to be created");
+        };
+        assert_eq!(fetched, 5);
+        assert!(matches!(pull(Some(&remote), &local, &policy), Ok(Pull::UpToDate { ahead: 3 })));
+        assert!(matches!(pull(Some(&remote), &remote, &policy), Ok(Pull::UpToDate { ahead: 0 })));
+    }
+
+    #[test]
+    fn test_pull_refuses_other_chains() {
+        let genesis = GenesisBlock::new("Test".to_string(), None, None).unwrap();
+        let (issuer, signing) = Issuer::new("Registrar".to_string());
+        let (other_issuer, other_signing) = Issuer::new("Branch".to_string());
+        let mut remote = Blockchain::with_genesis(genesis);
+        grow(&mut remote, &issuer, &signing, 1);
+        // Block 0 is shared, the blocks after it were added apart
+        let mut forked = remote.clone();
+        grow(&mut forked, &other_issuer, &other_signing, 2);
+        grow(&mut remote, &issuer, &signing, 4);
+        let policy = ChainPolicy::default();
+
+        let Err(SyncError::Diverged(divergence)) = pull(Some(&forked), &remote, &policy) else {
+            panic!("expected the chains to diverge");
+        };
+        assert_eq!((divergence.last_common, divergence.height), (Some(0), 1));
+        let mut rebuilt = Blockchain::with_genesis(remote.genesis().unwrap().clone());
+        grow(&mut rebuilt, &other_issuer, &other_signing, 6);
+        let Err(SyncError::Diverged(divergence)) = pull(Some(&rebuilt), &remote, &policy) else {
+            panic!("expected the chains to diverge");
+        };
+        assert_eq!((divergence.last_common, divergence.height), (None, 0));
+
+        let genesis = GenesisBlock::new("Other".to_string(), None, None).unwrap();
+        let other = Blockchain::with_genesis(genesis);
+        assert!(matches!(pull(Some(&remote), &other, &policy), Err(SyncError::OtherChain { .. })));
+    }
+}
@@ -0,0 +1,144 @@
+/// Widest a column gets whatever its values, so that a single long value doesn't push the others
+/// off screen
+pub const MAX_COLUMN_WIDTH: usize = 40;
+
+/// Narrowest a column is shrunk to when fitting a table to the terminal
+pub const MIN_COLUMN_WIDTH: usize = 6;
+
+const SEPARATOR: &str = "  ";
+
+/// Rows of text laid out in aligned columns under a header, for people rather than scripts
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    #[must_use]
+    pub fn new<S: Into<String>>(header: impl IntoIterator<Item = S>) -> Self {
+        Self { header: header.into_iter().map(Into::into).collect(), rows: Vec::new() }
+    }
+
+    /// Adds a row, cells past the header's columns are dropped and missing ones left empty
+    pub fn row<S: Into<String>>(&mut self, cells: impl IntoIterator<Item = S>) {
+        let mut row: Vec<String> = cells.into_iter().map(Into::into).collect();
+        row.resize(self.header.len(), String::new());
+        self.rows.push(row);
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.rows.is_empty() }
+
+    /// Renders the header and rows, one line each, cutting values down to fit their column. The
+    /// columns are fitted into `width` characters if given, otherwise only capped at
+    /// [`MAX_COLUMN_WIDTH`].
+    #[must_use]
+    pub fn render(&self, width: Option<usize>) -> String {
+        let widths = self.widths(width);
+        let mut out = String::new();
+        for row in std::iter::once(&self.header).chain(&self.rows) {
+            let cells: Vec<_> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{:width$}", truncate(cell, width)))
+                .collect();
+            out.push_str(cells.join(SEPARATOR).trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    fn widths(&self, width: Option<usize>) -> Vec<usize> {
+        let mut widths: Vec<_> = (0..self.header.len())
+            .map(|column| {
+                std::iter::once(&self.header)
+                    .chain(&self.rows)
+                    .map(|row| cell_width(&row[column]))
+                    .max()
+                    .unwrap_or(0)
+                    .min(MAX_COLUMN_WIDTH)
+            })
+            .collect();
+        let Some(width) = width else { return widths };
+        let separators = SEPARATOR.len() * widths.len().saturating_sub(1);
+        // Narrows the widest column a character at a time, until the table fits or can't shrink
+        while widths.iter().sum::<usize>() + separators > width {
+            let Some(widest) = (0..widths.len()).max_by_key(|&column| widths[column]) else {
+                break;
+            };
+            if widths[widest] <= MIN_COLUMN_WIDTH {
+                break;
+            }
+            widths[widest] -= 1;
+        }
+        widths
+    }
+}
+
+fn cell_width(cell: &str) -> usize { cell.chars().count() }
+
+/// Cuts the value down to `width` characters, ending it with an ellipsis if anything was cut
+#[must_use]
+pub fn truncate(value: &str, width: usize) -> String {
+    if cell_width(value) <= width {
+        return value.to_string();
+    }
+    let mut cut: String = value.chars().take(width.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> Table {
+        let mut table = Table::new(["INDEX", "NAME", "STATUS"]);
+        table.row(["0", "Alice Smith", "valid"]);
+        table.row(["12", "Bob", "revoked"]);
+        table
+    }
+
+    #[test]
+    fn test_table_aligns_columns() {
+        assert_eq!(
+            table().render(None),
+            "INDEX  NAME         STATUS\n0      Alice Smith  valid\n12     Bob          revoked\n"
+        );
+    }
+
+    #[test]
+    fn test_table_truncates_long_values() {
+        assert_eq!(truncate("Alice", 5), "Alice");
+        assert_eq!(truncate("Alice Smith", 5), "Alic…");
+        assert_eq!(truncate("Zürich", 3), "Zü…");
+
+        let mut table = Table::new(["NAME"]);
+        table.row(["x".repeat(MAX_COLUMN_WIDTH + 10)]);
+        let rendered = table.render(None);
+        let row = rendered.lines().nth(1).unwrap();
+        assert_eq!(row.chars().count(), MAX_COLUMN_WIDTH);
+        assert!(row.ends_with('…'));
+    }
+
+    #[test]
+    fn test_table_fits_width() {
+        let rendered = table().render(Some(24));
+        for line in rendered.lines() {
+            assert!(line.chars().count() <= 24, "{line}");
+        }
+        assert!(rendered.contains("Alice S…"));
+        // Columns don't get narrower than the minimum, however narrow the terminal
+        let rendered = table().render(Some(1));
+        assert!(rendered.lines().nth(1).unwrap().starts_with("0      Alice…"));
+    }
+
+    #[test]
+    fn test_table_pads_short_rows() {
+        let mut table = Table::new(["A", "B"]);
+        table.row(["1"]);
+        table.row(["2", "x", "dropped"]);
+        assert_eq!(table.render(None), "A  B\n1\n2  x\n");
+    }
+}
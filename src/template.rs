@@ -0,0 +1,67 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::credential::Attribute;
+
+/// Kind of credential issued repeatedly, of which only the subject and dates change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialTemplate {
+    pub name: String,
+    /// UUID of the issuer issuing the credentials
+    pub issuer: Uuid,
+    pub attributes: Vec<Attribute>,
+    /// Years the credentials are valid for from their first day, indefinitely if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_years: Option<u32>,
+    /// Names of the schemas the attributes are validated against
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schemas: Vec<String>,
+}
+
+impl CredentialTemplate {
+    #[must_use]
+    pub fn new(name: String, issuer: Uuid, attributes: Vec<Attribute>) -> Self {
+        Self { name, issuer, attributes, valid_years: None, schemas: Vec::new() }
+    }
+
+    /// Template's attributes with those of the same name replaced by `overrides`, and the others
+    /// in `overrides` added after them
+    #[must_use]
+    pub fn attributes_with(&self, overrides: &[Attribute]) -> Vec<Attribute> {
+        let mut attributes = self.attributes.clone();
+        for attribute in overrides {
+            match attributes.iter_mut().find(|a| a.name == attribute.name) {
+                Some(existing) => existing.clone_from(attribute),
+                None => attributes.push(attribute.clone()),
+            }
+        }
+        attributes
+    }
+}
+
+impl Display for CredentialTemplate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string_pretty(self).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_attribute_overrides() {
+        let attribute = |name: &str, value: &str| Attribute::new(name.into(), value.into());
+        let template = CredentialTemplate::new("diploma".into(), Uuid::new_v4(), vec![
+            attribute("degree", "PhD"),
+            attribute("field", "Physics"),
+        ]);
+        let attributes = template
+            .attributes_with(&[attribute("field", "Chemistry"), attribute("honours", "yes")]);
+        let attributes: Vec<_> = attributes.iter().map(ToString::to_string).collect();
+        assert_eq!(attributes, ["degree: PhD", "field: Chemistry", "honours: yes"]);
+    }
+}
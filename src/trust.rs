@@ -0,0 +1,103 @@
+use ed25519_dalek::VerifyingKey;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::credential::verifying_key_serde;
+use crate::keys::fingerprint;
+
+/// Issuer key a verifier decided to trust, whichever chain it shows up on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedIssuer {
+    #[serde(with = "verifying_key_serde")]
+    pub verifying: VerifyingKey,
+    /// UUID of the issuer, if the key was trusted through a known issuer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Verifying keys of the issuers a verifier accepts credentials from. Unlike the chain's
+/// authorities, anyone can sign blocks, so this is what tells a verifier's own issuers apart from
+/// whoever else appended to the chain.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TrustStore {
+    issuers: Vec<TrustedIssuer>,
+}
+
+impl TrustStore {
+    /// Adds the issuer, returning false if its key was already trusted
+    pub fn trust(&mut self, issuer: TrustedIssuer) -> bool {
+        if self.trusts(&issuer.verifying) {
+            return false;
+        }
+        self.issuers.push(issuer);
+        true
+    }
+
+    /// Removes the keys trusted for the issuer with the given UUID or key fingerprint, returning
+    /// how many were removed
+    pub fn untrust(&mut self, query: &str) -> usize {
+        let uuid = Uuid::parse_str(query).ok();
+        let before = self.issuers.len();
+        self.issuers.retain(|issuer| {
+            let by_uuid = uuid.is_some() && issuer.uuid == uuid;
+            let by_fingerprint = fingerprint(&issuer.verifying).replace(' ', "")
+                == query.replace(' ', "").to_uppercase();
+            !by_uuid && !by_fingerprint
+        });
+        before - self.issuers.len()
+    }
+
+    #[must_use]
+    pub fn trusts(&self, key: &VerifyingKey) -> bool {
+        self.issuers.iter().any(|issuer| issuer.verifying == *key)
+    }
+
+    #[must_use]
+    pub fn issuers(&self) -> &[TrustedIssuer] { &self.issuers }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.issuers.is_empty() }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::SigningKey;
+
+    use super::*;
+
+    fn trusted(seed: u8, uuid: Option<Uuid>) -> TrustedIssuer {
+        let verifying = SigningKey::from_bytes(&[seed; 32]).verifying_key();
+        TrustedIssuer { verifying, uuid, name: None }
+    }
+
+    #[test]
+    fn test_trust_and_untrust() {
+        let mut store = TrustStore::default();
+        let uuid = Uuid::new_v4();
+        assert!(store.trust(trusted(1, Some(uuid))));
+        assert!(!store.trust(trusted(1, None)));
+        assert!(store.trust(trusted(2, None)));
+        assert!(store.trusts(&trusted(1, None).verifying));
+        assert!(!store.trusts(&trusted(3, None).verifying));
+
+        assert_eq!(store.untrust(&uuid.to_string()), 1);
+        assert!(!store.trusts(&trusted(1, None).verifying));
+        let second = fingerprint(&trusted(2, None).verifying).to_lowercase();
+        assert_eq!(store.untrust(&second), 1);
+        assert!(store.is_empty());
+        assert_eq!(store.untrust(&uuid.to_string()), 0);
+    }
+
+    #[test]
+    fn test_trust_store_roundtrip() {
+        let mut store = TrustStore::default();
+        store.trust(TrustedIssuer { name: Some("Uni".into()), ..trusted(1, Some(Uuid::new_v4())) });
+        store.trust(trusted(2, None));
+        let json = serde_json::to_string(&store).unwrap();
+        assert!(json.starts_with('['));
+        assert_eq!(serde_json::from_str::<TrustStore>(&json).unwrap(), store);
+    }
+}
@@ -0,0 +1,97 @@
+//! Styles of the terminal output, so that every command colors the same things the same way.
+//! Styled text has to be printed through [`anstream`], which drops the styles when the output
+//! isn't a terminal, `NO_COLOR` is set or `--color never` is given.
+
+use std::fmt::Display;
+
+use anstyle::{AnsiColor, Style};
+
+use crate::blockchain::{BlockSummary, VerificationStatus};
+
+pub const GOOD: Style = AnsiColor::Green.on_default();
+pub const BAD: Style = AnsiColor::Red.on_default();
+pub const WARNING: Style = AnsiColor::Yellow.on_default();
+pub const HEADING: Style = Style::new().bold();
+/// For hashes and other long identifiers, which are rarely read in full
+pub const DIM: Style = Style::new().dimmed();
+
+/// When to style the output, as given to `--color`
+pub use anstream::ColorChoice;
+
+/// Applies the choice to everything printed from then on
+pub fn set_color_choice(choice: ColorChoice) { choice.write_global(); }
+
+#[must_use]
+pub fn paint(style: Style, text: impl Display) -> String { format!("{style}{text}{style:#}") }
+
+#[must_use]
+pub fn heading(text: impl Display) -> String { paint(HEADING, text) }
+
+#[must_use]
+pub fn hash(text: impl Display) -> String { paint(DIM, text) }
+
+/// Green for valid credentials, red for revoked ones and broken signatures, yellow for those
+/// outside their validity period, no style otherwise
+#[must_use]
+pub fn status_style(status: &VerificationStatus) -> Style {
+    match status {
+        VerificationStatus::Valid { .. } => GOOD,
+        VerificationStatus::Revoked { .. } | VerificationStatus::SignatureInvalid { .. } => BAD,
+        VerificationStatus::Expired { .. } | VerificationStatus::NotYetValid { .. } => WARNING,
+        _ => Style::new(),
+    }
+}
+
+/// Renders the text in the status's style
+#[must_use]
+pub fn status(status: &VerificationStatus, text: impl Display) -> String {
+    paint(status_style(status), text)
+}
+
+/// The block summary as it displays, with a bold height and a dimmed hash
+#[must_use]
+pub fn summary(summary: &BlockSummary) -> String {
+    let plain = summary.to_string();
+    let height = format!("#{}", summary.height);
+    let short = summary.hash.short();
+    // The height and hash lead the summary, the rest is kept as it displays
+    let rest = &plain[height.len() + 1 + short.len()..];
+    format!("{} {}{rest}", heading(height), hash(short))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::hash::Hash;
+
+    #[test]
+    fn test_paint_resets_style() {
+        assert_eq!(paint(GOOD, "valid"), "\u{1b}[32mvalid\u{1b}[0m");
+        assert_eq!(paint(Style::new(), "plain"), "plain");
+    }
+
+    #[test]
+    fn test_status_styles() {
+        assert_eq!(status_style(&VerificationStatus::Valid { issued_in: 0 }), GOOD);
+        assert_eq!(status_style(&VerificationStatus::SignatureInvalid { block: 1 }), BAD);
+        assert_eq!(status_style(&VerificationStatus::NotFound), Style::new());
+        assert_eq!(status(&VerificationStatus::NotFound, "not found"), "not found");
+    }
+
+    #[test]
+    fn test_summary_keeps_plain_text() {
+        let summary = BlockSummary {
+            height: 12,
+            hash: Hash::default(),
+            timestamp: Utc::now(),
+            signer: "Registrar".into(),
+            new_credentials: 2,
+            revoked_credentials: 0,
+        };
+        let styled = self::summary(&summary);
+        assert!(styled.starts_with("\u{1b}[1m#12\u{1b}[0m \u{1b}[2m00000000\u{1b}[0m "));
+        assert_eq!(anstream::adapter::strip_str(&styled).to_string(), summary.to_string());
+    }
+}
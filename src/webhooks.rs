@@ -0,0 +1,312 @@
+//! Notifications posted to downstream systems, e.g. an HR tool, once a block is finalized, so that
+//! they learn of new and revoked credentials without watching the chain.
+//!
+//! Each delivery carries an HMAC-SHA256 of its body keyed with the webhook's secret, for the
+//! receiver to check it comes from us. Deliveries happen after the block is on the chain and
+//! their failures are only reported: a receiver being down never undoes a finalize.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::blockchain::Block;
+use crate::hash::Hash;
+
+/// Header carrying the signature, as `sha256=<hex digest>`
+pub const SIGNATURE_HEADER: &str = "X-Attestation-Signature";
+/// Header carrying the event, as in the payload
+pub const EVENT_HEADER: &str = "X-Attestation-Event";
+
+fn default_retries() -> u32 { 3 }
+
+/// Receiver of notifications, read from a `[[webhooks]]` entry of the config
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Events to deliver, every event if none is given
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    /// Key of the HMAC signing the deliveries, shared with the receiver
+    pub secret: String,
+    /// How many times a failed delivery is tried again
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+impl WebhookConfig {
+    #[must_use]
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A block was added to the chain
+    BlockFinalized,
+    /// A block added to the chain revokes credentials
+    CredentialRevoked,
+}
+
+impl Display for WebhookEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::BlockFinalized => "block_finalized",
+            Self::CredentialRevoked => "credential_revoked",
+        })
+    }
+}
+
+/// Payload of a delivery
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notification {
+    pub event: WebhookEvent,
+    /// UUID of the chain's genesis block, none for chains without one
+    pub chain: Option<Uuid>,
+    pub height: u64,
+    pub hash: Hash,
+    pub timestamp: DateTime<Utc>,
+    /// UUID of the issuer signing the block
+    pub signer: Uuid,
+    /// Hashes the block issues credentials under, empty for `credential_revoked`
+    pub issued: Vec<Hash>,
+    /// Hashes the block revokes credentials under, which differ from the ones they were issued
+    /// under
+    pub revoked: Vec<Hash>,
+}
+
+impl Notification {
+    /// Notifications of a finalized block, `credential_revoked` only if it revokes credentials
+    #[must_use]
+    pub fn for_block(chain: Option<Uuid>, block: &Block) -> Vec<Self> {
+        let revoked: Vec<Hash> =
+            block.revoked_credentials().iter().map(|r| r.signed.credential.clone()).collect();
+        let finalized = Self {
+            event: WebhookEvent::BlockFinalized,
+            chain,
+            height: block.height(),
+            hash: block.hash().clone(),
+            timestamp: block.timestamp(),
+            signer: block.signer().uuid,
+            issued: block.issued_credentials().map(|signed| signed.credential.clone()).collect(),
+            revoked,
+        };
+        if finalized.revoked.is_empty() {
+            return vec![finalized];
+        }
+        let event = WebhookEvent::CredentialRevoked;
+        let revocation = Self { event, issued: Vec::new(), ..finalized.clone() };
+        vec![finalized, revocation]
+    }
+
+    /// The body as delivered
+    #[must_use]
+    pub fn body(&self) -> String { serde_json::to_string(self).unwrap_or_default() }
+}
+
+/// Value of the signature header for the body, keyed with the secret
+#[must_use]
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        // HMAC takes keys of any length
+        return String::new();
+    };
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Delivery that failed every time it was tried
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryError {
+    pub url: String,
+    pub event: WebhookEvent,
+    /// Why each try failed, in order
+    pub attempts: Vec<String>,
+}
+
+impl Display for DeliveryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to deliver {} to {} after {} attempts: {}",
+            self.event,
+            self.url,
+            self.attempts.len(),
+            self.attempts.last().map_or("", String::as_str)
+        )
+    }
+}
+
+impl Error for DeliveryError {}
+
+#[cfg(feature = "client")]
+pub use delivery::Webhooks;
+
+#[cfg(feature = "client")]
+mod delivery {
+    use std::thread;
+    use std::time::Duration;
+
+    use reqwest::blocking::Client;
+
+    use super::{DeliveryError, EVENT_HEADER, Notification, SIGNATURE_HEADER, WebhookConfig};
+
+    /// Client delivering notifications
+    pub struct Webhooks {
+        client: Client,
+        retry_delay: Duration,
+    }
+
+    impl Webhooks {
+        /// Client waiting 10 seconds at most for each delivery, and a second before the first
+        /// retry, doubling the wait for each further one
+        ///
+        /// # Errors
+        /// Fails if the client can't be set up.
+        pub fn new() -> Result<Self, String> {
+            let client = Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .map_err(|e| e.to_string())?;
+            Ok(Self { client, retry_delay: Duration::from_secs(1) })
+        }
+
+        #[must_use]
+        pub fn with_retry_delay(self, retry_delay: Duration) -> Self {
+            Self { retry_delay, ..self }
+        }
+
+        /// Delivers each notification to the webhooks that want its event, trying failed
+        /// deliveries again as many times as the webhook allows
+        ///
+        /// # Errors
+        /// Returns the deliveries that failed every try.
+        pub fn deliver(
+            &self, webhooks: &[WebhookConfig], notifications: &[Notification],
+        ) -> Result<(), Vec<DeliveryError>> {
+            let mut errors = Vec::new();
+            for notification in notifications {
+                let body = notification.body();
+                for webhook in webhooks.iter().filter(|webhook| webhook.wants(notification.event)) {
+                    if let Err(attempts) = self.post(webhook, notification, &body) {
+                        let (url, event) = (webhook.url.clone(), notification.event);
+                        errors.push(DeliveryError { url, event, attempts });
+                    }
+                }
+            }
+            if errors.is_empty() { Ok(()) } else { Err(errors) }
+        }
+
+        fn post(
+            &self, webhook: &WebhookConfig, notification: &Notification, body: &str,
+        ) -> Result<(), Vec<String>> {
+            let mut attempts = Vec::new();
+            let mut delay = self.retry_delay;
+            for attempt in 0..=webhook.retries {
+                if attempt > 0 {
+                    thread::sleep(delay);
+                    delay *= 2;
+                }
+                let response = self
+                    .client
+                    .post(&webhook.url)
+                    .header("Content-Type", "application/json")
+                    .header(EVENT_HEADER, notification.event.to_string())
+                    .header(SIGNATURE_HEADER, super::signature(&webhook.secret, body.as_bytes()))
+                    .body(body.to_string())
+                    .send();
+                match response {
+                    Ok(response) if response.status().is_success() => return Ok(()),
+                    Ok(response) => attempts.push(format!("answered {}", response.status())),
+                    Err(e) => attempts.push(e.without_url().to_string()),
+                }
+            }
+            Err(attempts)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credential::Issuer;
+
+    #[test]
+    fn test_config_and_signature() {
+        let webhook: WebhookConfig = toml::from_str(
+            "url = \"https://hr.example.org/hook\"\nevents = [\"credential_revoked\"]\n\
+             secret = \"s3cret\"",
+        )
+        .unwrap();
+        assert_eq!(webhook.retries, 3);
+        assert!(webhook.wants(WebhookEvent::CredentialRevoked));
+        assert!(!webhook.wants(WebhookEvent::BlockFinalized));
+        assert!(toml::from_str::<WebhookConfig>("url = \"x\"\nsecret = \"s\"\nevents = [\"x\"]")
+            .is_err());
+
+        // RFC 4231 test case 2
+        assert_eq!(
+            signature("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_notifications_for_block() {
+        let (issuer, _) = Issuer::new("Registrar".to_string());
+        let notifications = Notification::for_block(None, &Block::new(issuer));
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].event, WebhookEvent::BlockFinalized);
+        let body: serde_json::Value = serde_json::from_str(&notifications[0].body()).unwrap();
+        assert_eq!(body["event"], "block_finalized");
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_delivery_and_retries() {
+        use std::thread;
+        use std::time::Duration;
+
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", server.server_addr().to_ip().unwrap());
+        let receiver = thread::spawn(move || {
+            let mut deliveries = Vec::new();
+            // The first try fails, the retry succeeds
+            for status in [503, 204] {
+                let mut request = server.recv().unwrap();
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let header = |name: &'static str| {
+                    let header = request.headers().iter().find(|h| h.field.equiv(name));
+                    header.map(|h| h.value.to_string()).unwrap_or_default()
+                };
+                deliveries.push((header(SIGNATURE_HEADER), header(EVENT_HEADER), body));
+                request.respond(tiny_http::Response::empty(status)).unwrap();
+            }
+            deliveries
+        });
+        let (issuer, _) = Issuer::new("Registrar".to_string());
+        let notifications = Notification::for_block(None, &Block::new(issuer));
+        let webhook =
+            WebhookConfig { url, events: Vec::new(), secret: "s3cret".into(), retries: 1 };
+        let webhooks = Webhooks::new().unwrap().with_retry_delay(Duration::from_millis(10));
+        webhooks.deliver(std::slice::from_ref(&webhook), &notifications).unwrap();
+
+        let deliveries = receiver.join().unwrap();
+        let (signature_header, event, body) = &deliveries[1];
+        assert_eq!(signature_header, &signature("s3cret", body.as_bytes()));
+        assert_eq!(event, "block_finalized");
+        assert_eq!(serde_json::from_str::<Notification>(body).unwrap(), notifications[0]);
+
+        let unreachable = WebhookConfig { url: "http://127.0.0.1:1/hook".into(), ..webhook };
+        let errors = webhooks.deliver(&[unreachable], &notifications).unwrap_err();
+        assert_eq!(errors[0].attempts.len(), 2);
+    }
+}